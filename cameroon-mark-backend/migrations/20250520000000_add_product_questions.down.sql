@@ -0,0 +1,6 @@
+ALTER TABLE reported_items
+DROP COLUMN question_id;
+
+DROP TABLE IF EXISTS product_answer_votes;
+DROP TABLE IF EXISTS product_answers;
+DROP TABLE IF EXISTS product_questions;
@@ -0,0 +1,50 @@
+-- Public product questions
+CREATE TABLE IF NOT EXISTS product_questions (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    product_id UUID NOT NULL REFERENCES products(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    question TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_product_questions_product_id ON product_questions(product_id);
+
+-- Answers from the seller or other buyers
+CREATE TABLE IF NOT EXISTS product_answers (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    question_id UUID NOT NULL REFERENCES product_questions(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    answer TEXT NOT NULL,
+    is_seller BOOLEAN NOT NULL DEFAULT FALSE,
+    upvotes INTEGER NOT NULL DEFAULT 0,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_product_answers_question_id ON product_answers(question_id);
+
+-- One upvote per user and answer
+CREATE TABLE IF NOT EXISTS product_answer_votes (
+    answer_id UUID NOT NULL REFERENCES product_answers(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (answer_id, user_id)
+);
+
+CREATE TRIGGER update_product_questions_updated_at
+BEFORE UPDATE ON product_questions
+FOR EACH ROW
+EXECUTE FUNCTION update_updated_at_column();
+
+CREATE TRIGGER update_product_answers_updated_at
+BEFORE UPDATE ON product_answers
+FOR EACH ROW
+EXECUTE FUNCTION update_updated_at_column();
+
+-- Reports can now target a single question instead of the whole product.
+-- A report goes with its question, so it can never fall back to being about the product.
+ALTER TABLE reported_items
+ADD COLUMN question_id UUID REFERENCES product_questions(id) ON DELETE CASCADE;
+
+CREATE INDEX idx_reported_items_question_id ON reported_items(question_id);
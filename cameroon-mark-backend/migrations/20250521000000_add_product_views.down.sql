@@ -0,0 +1,4 @@
+ALTER TABLE products
+DROP COLUMN view_count;
+
+DROP TABLE IF EXISTS product_views;
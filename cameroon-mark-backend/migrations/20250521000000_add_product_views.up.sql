@@ -0,0 +1,21 @@
+-- Product views, one per user or session, product and 30 minute window
+CREATE TABLE IF NOT EXISTS product_views (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    product_id UUID NOT NULL REFERENCES products(id) ON DELETE CASCADE,
+    user_id UUID REFERENCES users(id) ON DELETE CASCADE,
+    session_id VARCHAR(64),
+    -- Product, viewer and window the view was counted for
+    dedupe_key VARCHAR(128) NOT NULL,
+    viewed_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CHECK (user_id IS NOT NULL OR session_id IS NOT NULL)
+);
+
+CREATE UNIQUE INDEX idx_product_views_dedupe_key ON product_views(dedupe_key);
+
+CREATE INDEX idx_product_views_product_id ON product_views(product_id, viewed_at);
+CREATE INDEX idx_product_views_user_id ON product_views(user_id, viewed_at);
+CREATE INDEX idx_product_views_session_id ON product_views(session_id, viewed_at);
+
+-- Running total of counted views
+ALTER TABLE products
+ADD COLUMN view_count BIGINT NOT NULL DEFAULT 0;
@@ -0,0 +1,12 @@
+ALTER TABLE notifications DROP COLUMN IF EXISTS channels;
+
+DROP TRIGGER IF EXISTS update_notification_preferences_updated_at ON notification_preferences;
+DROP TABLE IF EXISTS notification_preferences;
+
+DROP INDEX IF EXISTS idx_saved_items_alerts;
+
+ALTER TABLE saved_items
+DROP COLUMN IF EXISTS last_alerted_at,
+DROP COLUMN IF EXISTS last_alerted_price,
+DROP COLUMN IF EXISTS alerts_enabled,
+DROP COLUMN IF EXISTS price_at_save;
@@ -0,0 +1,35 @@
+-- Price and stock alerts on saved items
+ALTER TABLE saved_items
+ADD COLUMN price_at_save DECIMAL(10,2),
+ADD COLUMN alerts_enabled BOOLEAN NOT NULL DEFAULT FALSE,
+ADD COLUMN last_alerted_price DECIMAL(10,2),
+ADD COLUMN last_alerted_at TIMESTAMPTZ;
+
+-- Existing saved items start from the current price
+UPDATE saved_items si
+SET price_at_save = p.price
+FROM products p
+WHERE si.product_id = p.id;
+
+CREATE INDEX idx_saved_items_alerts ON saved_items(product_id) WHERE alerts_enabled;
+
+-- Per-user notification channel preferences
+CREATE TABLE IF NOT EXISTS notification_preferences (
+    user_id UUID PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    email_notifications BOOLEAN NOT NULL DEFAULT TRUE,
+    push_notifications BOOLEAN NOT NULL DEFAULT TRUE,
+    notification_frequency VARCHAR(20) NOT NULL DEFAULT 'immediate',
+    quiet_hours_start INTEGER CHECK (quiet_hours_start BETWEEN 0 AND 23),
+    quiet_hours_end INTEGER CHECK (quiet_hours_end BETWEEN 0 AND 23),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TRIGGER update_notification_preferences_updated_at
+BEFORE UPDATE ON notification_preferences
+FOR EACH ROW
+EXECUTE FUNCTION update_updated_at_column();
+
+-- Channels each notification is delivered on besides the in-app feed
+ALTER TABLE notifications
+ADD COLUMN channels TEXT[] NOT NULL DEFAULT '{in_app}';
@@ -0,0 +1,17 @@
+ALTER TABLE saved_items
+DROP CONSTRAINT IF EXISTS saved_items_wishlist_id_product_id_key;
+
+-- Collapse lists back into one entry per product
+DELETE FROM saved_items a
+USING saved_items b
+WHERE a.user_id = b.user_id
+  AND a.product_id = b.product_id
+  AND a.created_at > b.created_at;
+
+ALTER TABLE saved_items
+ADD CONSTRAINT saved_items_user_id_product_id_key UNIQUE (user_id, product_id);
+
+ALTER TABLE saved_items DROP COLUMN IF EXISTS wishlist_id;
+
+DROP TRIGGER IF EXISTS update_wishlists_updated_at ON wishlists;
+DROP TABLE IF EXISTS wishlists;
@@ -0,0 +1,43 @@
+-- Named wishlists; saved items now belong to a list
+CREATE TABLE IF NOT EXISTS wishlists (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    name VARCHAR(100) NOT NULL,
+    is_public BOOLEAN NOT NULL DEFAULT FALSE,
+    is_default BOOLEAN NOT NULL DEFAULT FALSE,
+    share_slug VARCHAR(32) NOT NULL UNIQUE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE(user_id, name)
+);
+
+CREATE INDEX idx_wishlists_user_id ON wishlists(user_id);
+CREATE UNIQUE INDEX idx_wishlists_default ON wishlists(user_id) WHERE is_default;
+
+CREATE TRIGGER update_wishlists_updated_at
+BEFORE UPDATE ON wishlists
+FOR EACH ROW
+EXECUTE FUNCTION update_updated_at_column();
+
+-- Every user with saved items gets a default list holding them
+INSERT INTO wishlists (user_id, name, is_default, share_slug)
+SELECT DISTINCT user_id, 'Saved items', TRUE, substr(md5(random()::text || user_id::text), 1, 12)
+FROM saved_items;
+
+ALTER TABLE saved_items
+ADD COLUMN wishlist_id UUID REFERENCES wishlists(id) ON DELETE CASCADE;
+
+UPDATE saved_items si
+SET wishlist_id = w.id
+FROM wishlists w
+WHERE w.user_id = si.user_id AND w.is_default;
+
+ALTER TABLE saved_items
+ALTER COLUMN wishlist_id SET NOT NULL;
+
+-- The same product may now sit in several lists, but only once per list
+ALTER TABLE saved_items
+DROP CONSTRAINT IF EXISTS saved_items_user_id_product_id_key;
+
+ALTER TABLE saved_items
+ADD CONSTRAINT saved_items_wishlist_id_product_id_key UNIQUE (wishlist_id, product_id);
@@ -0,0 +1,3 @@
+ALTER TABLE cart_items DROP COLUMN IF EXISTS price_at_add;
+
+ALTER TABLE products DROP COLUMN IF EXISTS status;
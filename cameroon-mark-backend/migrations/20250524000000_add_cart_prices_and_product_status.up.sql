@@ -0,0 +1,16 @@
+-- Sellers can take a listing off sale without deleting it
+ALTER TABLE products
+ADD COLUMN status VARCHAR(20) NOT NULL DEFAULT 'active'
+CHECK (status IN ('active', 'inactive'));
+
+-- Price shown to the buyer when the item went into the cart
+ALTER TABLE cart_items
+ADD COLUMN price_at_add DECIMAL(10,2);
+
+UPDATE cart_items ci
+SET price_at_add = p.price
+FROM products p
+WHERE ci.product_id = p.id;
+
+ALTER TABLE cart_items
+ALTER COLUMN price_at_add SET NOT NULL;
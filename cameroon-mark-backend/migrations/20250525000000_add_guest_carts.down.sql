@@ -0,0 +1,11 @@
+DELETE FROM cart_items WHERE guest_cart_id IS NOT NULL;
+
+ALTER TABLE cart_items DROP CONSTRAINT IF EXISTS cart_items_guest_cart_id_product_id_key;
+ALTER TABLE cart_items DROP CONSTRAINT IF EXISTS cart_items_owner_check;
+ALTER TABLE cart_items DROP COLUMN IF EXISTS guest_cart_id;
+
+ALTER TABLE cart_items
+ALTER COLUMN user_id SET NOT NULL;
+
+DROP TRIGGER IF EXISTS update_guest_carts_updated_at ON guest_carts;
+DROP TABLE IF EXISTS guest_carts;
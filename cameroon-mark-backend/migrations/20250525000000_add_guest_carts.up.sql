@@ -0,0 +1,27 @@
+-- Anonymous carts, identified by a signed cart token on the client
+CREATE TABLE IF NOT EXISTS guest_carts (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    expires_at TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_guest_carts_expires_at ON guest_carts(expires_at);
+
+CREATE TRIGGER update_guest_carts_updated_at
+BEFORE UPDATE ON guest_carts
+FOR EACH ROW
+EXECUTE FUNCTION update_updated_at_column();
+
+-- A cart line belongs either to a user or to a guest cart
+ALTER TABLE cart_items
+ALTER COLUMN user_id DROP NOT NULL;
+
+ALTER TABLE cart_items
+ADD COLUMN guest_cart_id UUID REFERENCES guest_carts(id) ON DELETE CASCADE;
+
+ALTER TABLE cart_items
+ADD CONSTRAINT cart_items_owner_check CHECK ((user_id IS NULL) <> (guest_cart_id IS NULL));
+
+ALTER TABLE cart_items
+ADD CONSTRAINT cart_items_guest_cart_id_product_id_key UNIQUE (guest_cart_id, product_id);
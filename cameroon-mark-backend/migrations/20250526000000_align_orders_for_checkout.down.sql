@@ -0,0 +1,58 @@
+DROP TRIGGER IF EXISTS update_order_items_updated_at ON order_items;
+DROP INDEX IF EXISTS idx_order_items_seller_id;
+DROP INDEX IF EXISTS idx_order_items_order_id;
+
+ALTER TABLE order_items
+DROP COLUMN IF EXISTS updated_at,
+DROP COLUMN IF EXISTS product_title;
+
+DROP INDEX IF EXISTS idx_orders_user_id;
+DROP INDEX IF EXISTS idx_orders_idempotency_key;
+
+ALTER TABLE orders
+DROP COLUMN IF EXISTS idempotency_key,
+DROP COLUMN IF EXISTS discount_code_id,
+DROP COLUMN IF EXISTS tax_amount,
+DROP COLUMN IF EXISTS shipping_amount,
+DROP COLUMN IF EXISTS discount_amount,
+DROP COLUMN IF EXISTS subtotal;
+
+ALTER TABLE orders
+ADD COLUMN shipping_name VARCHAR(255),
+ADD COLUMN shipping_address_1 VARCHAR(255),
+ADD COLUMN shipping_address_2 VARCHAR(255),
+ADD COLUMN shipping_city VARCHAR(255),
+ADD COLUMN shipping_postal_code VARCHAR(50),
+ADD COLUMN shipping_country VARCHAR(255),
+ADD COLUMN shipping_phone VARCHAR(50);
+
+UPDATE orders
+SET shipping_name = shipping_address->>'name',
+    shipping_address_1 = shipping_address->>'address_1',
+    shipping_address_2 = shipping_address->>'address_2',
+    shipping_city = shipping_address->>'city',
+    shipping_postal_code = shipping_address->>'postal_code',
+    shipping_country = shipping_address->>'country',
+    shipping_phone = shipping_address->>'phone';
+
+ALTER TABLE orders
+ALTER COLUMN shipping_name SET NOT NULL,
+ALTER COLUMN shipping_address_1 SET NOT NULL,
+ALTER COLUMN shipping_city SET NOT NULL,
+ALTER COLUMN shipping_country SET NOT NULL,
+DROP COLUMN shipping_address;
+
+ALTER TABLE orders
+ALTER COLUMN status DROP DEFAULT,
+ALTER COLUMN payment_status DROP DEFAULT;
+
+ALTER TABLE orders
+ALTER COLUMN status TYPE order_status USING status::order_status,
+ALTER COLUMN payment_status TYPE payment_status USING payment_status::payment_status,
+ALTER COLUMN payment_method TYPE payment_method USING payment_method::payment_method;
+
+ALTER TABLE orders
+ALTER COLUMN status SET DEFAULT 'pending',
+ALTER COLUMN payment_status SET DEFAULT 'pending';
+
+ALTER TABLE orders RENAME COLUMN user_id TO buyer_id;
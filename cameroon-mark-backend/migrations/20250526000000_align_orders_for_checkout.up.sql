@@ -0,0 +1,81 @@
+-- Bring the orders table in line with the order entity
+ALTER TABLE orders RENAME COLUMN buyer_id TO user_id;
+
+ALTER TABLE orders
+ALTER COLUMN status DROP DEFAULT,
+ALTER COLUMN payment_status DROP DEFAULT;
+
+ALTER TABLE orders
+ALTER COLUMN status TYPE VARCHAR(20) USING status::text,
+ALTER COLUMN payment_status TYPE VARCHAR(20) USING payment_status::text,
+ALTER COLUMN payment_method TYPE VARCHAR(20) USING payment_method::text;
+
+ALTER TABLE orders
+ALTER COLUMN status SET DEFAULT 'pending',
+ALTER COLUMN payment_status SET DEFAULT 'pending';
+
+-- Shipping details are kept together as one document
+ALTER TABLE orders
+ADD COLUMN shipping_address JSONB;
+
+UPDATE orders
+SET shipping_address = jsonb_build_object(
+    'name', shipping_name,
+    'address_1', shipping_address_1,
+    'address_2', shipping_address_2,
+    'city', shipping_city,
+    'postal_code', shipping_postal_code,
+    'country', shipping_country,
+    'phone', shipping_phone
+);
+
+ALTER TABLE orders
+ALTER COLUMN shipping_address SET NOT NULL,
+DROP COLUMN shipping_name,
+DROP COLUMN shipping_address_1,
+DROP COLUMN shipping_address_2,
+DROP COLUMN shipping_city,
+DROP COLUMN shipping_postal_code,
+DROP COLUMN shipping_country,
+DROP COLUMN shipping_phone;
+
+-- Price breakdown computed at checkout
+ALTER TABLE orders
+ADD COLUMN subtotal DECIMAL(12,2) NOT NULL DEFAULT 0,
+ADD COLUMN discount_amount DECIMAL(12,2) NOT NULL DEFAULT 0,
+ADD COLUMN shipping_amount DECIMAL(12,2) NOT NULL DEFAULT 0,
+ADD COLUMN tax_amount DECIMAL(12,2) NOT NULL DEFAULT 0,
+ADD COLUMN discount_code_id UUID,
+ADD COLUMN idempotency_key VARCHAR(100);
+
+UPDATE orders SET subtotal = total_amount;
+
+ALTER TABLE orders
+ALTER COLUMN total_amount TYPE DECIMAL(12,2);
+
+-- A retried checkout with the same key returns the first order
+CREATE UNIQUE INDEX idx_orders_idempotency_key ON orders(user_id, idempotency_key)
+WHERE idempotency_key IS NOT NULL;
+
+CREATE INDEX IF NOT EXISTS idx_orders_user_id ON orders(user_id);
+
+-- Order lines keep what the buyer saw at checkout
+ALTER TABLE order_items
+ADD COLUMN product_title VARCHAR(255),
+ADD COLUMN updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP;
+
+UPDATE order_items oi
+SET product_title = p.title
+FROM products p
+WHERE oi.product_id = p.id;
+
+ALTER TABLE order_items
+ALTER COLUMN product_title SET NOT NULL;
+
+CREATE INDEX IF NOT EXISTS idx_order_items_order_id ON order_items(order_id);
+CREATE INDEX IF NOT EXISTS idx_order_items_seller_id ON order_items(seller_id);
+
+CREATE TRIGGER update_order_items_updated_at
+BEFORE UPDATE ON order_items
+FOR EACH ROW
+EXECUTE FUNCTION update_updated_at_column();
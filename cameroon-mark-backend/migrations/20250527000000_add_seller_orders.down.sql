@@ -0,0 +1,6 @@
+DROP INDEX IF EXISTS idx_order_items_seller_order_id;
+
+ALTER TABLE order_items
+DROP COLUMN IF EXISTS seller_order_id;
+
+DROP TABLE IF EXISTS seller_orders;
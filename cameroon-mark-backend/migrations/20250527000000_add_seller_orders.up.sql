@@ -0,0 +1,52 @@
+-- One sub-order per seller inside a buyer's order
+CREATE TABLE seller_orders (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    order_id UUID NOT NULL REFERENCES orders(id) ON DELETE CASCADE,
+    seller_id UUID NOT NULL REFERENCES users(id),
+    status VARCHAR(20) NOT NULL DEFAULT 'pending',
+    subtotal DECIMAL(12,2) NOT NULL DEFAULT 0,
+    discount_amount DECIMAL(12,2) NOT NULL DEFAULT 0,
+    shipping_amount DECIMAL(12,2) NOT NULL DEFAULT 0,
+    tax_amount DECIMAL(12,2) NOT NULL DEFAULT 0,
+    total_amount DECIMAL(12,2) NOT NULL DEFAULT 0,
+    fulfillment_status VARCHAR(20) NOT NULL DEFAULT 'unfulfilled',
+    tracking_number VARCHAR(100),
+    shipping_provider VARCHAR(100),
+    shipped_at TIMESTAMPTZ,
+    delivered_at TIMESTAMPTZ,
+    payout_status VARCHAR(20) NOT NULL DEFAULT 'pending',
+    payout_amount DECIMAL(12,2) NOT NULL DEFAULT 0,
+    paid_out_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE(order_id, seller_id)
+);
+
+CREATE INDEX idx_seller_orders_seller_id ON seller_orders(seller_id);
+CREATE INDEX idx_seller_orders_status ON seller_orders(status);
+
+CREATE TRIGGER update_seller_orders_updated_at
+BEFORE UPDATE ON seller_orders
+FOR EACH ROW
+EXECUTE FUNCTION update_updated_at_column();
+
+-- Split existing orders by seller
+INSERT INTO seller_orders (order_id, seller_id, status, subtotal, total_amount, payout_amount, created_at, updated_at)
+SELECT o.id, oi.seller_id, o.status, SUM(oi.quantity * oi.unit_price), SUM(oi.quantity * oi.unit_price),
+       SUM(oi.quantity * oi.unit_price), o.created_at, o.updated_at
+FROM orders o
+JOIN order_items oi ON oi.order_id = o.id
+GROUP BY o.id, oi.seller_id;
+
+ALTER TABLE order_items
+ADD COLUMN seller_order_id UUID REFERENCES seller_orders(id) ON DELETE CASCADE;
+
+UPDATE order_items oi
+SET seller_order_id = so.id
+FROM seller_orders so
+WHERE so.order_id = oi.order_id AND so.seller_id = oi.seller_id;
+
+ALTER TABLE order_items
+ALTER COLUMN seller_order_id SET NOT NULL;
+
+CREATE INDEX idx_order_items_seller_order_id ON order_items(seller_order_id);
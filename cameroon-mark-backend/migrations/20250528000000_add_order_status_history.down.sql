@@ -0,0 +1,4 @@
+DROP TABLE IF EXISTS order_status_history;
+
+ALTER TABLE seller_orders DROP CONSTRAINT IF EXISTS seller_orders_status_check;
+ALTER TABLE orders DROP CONSTRAINT IF EXISTS orders_status_check;
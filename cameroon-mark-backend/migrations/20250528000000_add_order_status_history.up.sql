@@ -0,0 +1,28 @@
+-- Only statuses known to the order state machine may be stored
+ALTER TABLE orders
+ADD CONSTRAINT orders_status_check
+CHECK (status IN ('pending', 'processing', 'shipped', 'delivered', 'canceled'));
+
+ALTER TABLE seller_orders
+ADD CONSTRAINT seller_orders_status_check
+CHECK (status IN ('pending', 'processing', 'shipped', 'delivered', 'canceled'));
+
+-- Audit trail of every status change on an order or one of its sub-orders
+CREATE TABLE order_status_history (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    order_id UUID NOT NULL REFERENCES orders(id) ON DELETE CASCADE,
+    seller_order_id UUID REFERENCES seller_orders(id) ON DELETE CASCADE,
+    from_status VARCHAR(20),
+    to_status VARCHAR(20) NOT NULL,
+    actor_id UUID REFERENCES users(id) ON DELETE SET NULL,
+    actor_role VARCHAR(20) NOT NULL,
+    note TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_order_status_history_order_id ON order_status_history(order_id, created_at);
+
+-- Existing orders start their history at their current status
+INSERT INTO order_status_history (order_id, to_status, actor_id, actor_role, note, created_at)
+SELECT id, status, user_id, 'buyer', 'Order placed', created_at
+FROM orders;
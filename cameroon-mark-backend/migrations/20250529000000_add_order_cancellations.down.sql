@@ -0,0 +1,7 @@
+DROP INDEX IF EXISTS idx_seller_orders_seller_created;
+
+ALTER TABLE seller_orders
+DROP COLUMN IF EXISTS canceled_at,
+DROP COLUMN IF EXISTS canceled_by,
+DROP COLUMN IF EXISTS cancellation_note,
+DROP COLUMN IF EXISTS cancellation_reason;
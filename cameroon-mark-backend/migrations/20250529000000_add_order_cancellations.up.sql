@@ -0,0 +1,9 @@
+-- Why and by whom a seller's part of an order was canceled
+ALTER TABLE seller_orders
+ADD COLUMN cancellation_reason VARCHAR(40),
+ADD COLUMN cancellation_note TEXT,
+ADD COLUMN canceled_by VARCHAR(20),
+ADD COLUMN canceled_at TIMESTAMPTZ;
+
+-- Seller cancellation rates are computed per seller over a date range
+CREATE INDEX idx_seller_orders_seller_created ON seller_orders(seller_id, created_at);
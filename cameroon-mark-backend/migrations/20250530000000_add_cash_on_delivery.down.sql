@@ -0,0 +1,7 @@
+ALTER TABLE seller_orders
+DROP COLUMN IF EXISTS cod_collected_at,
+DROP COLUMN IF EXISTS cod_collected_amount,
+DROP COLUMN IF EXISTS delivery_code_attempts,
+DROP COLUMN IF EXISTS delivery_code;
+
+DROP TABLE IF EXISTS seller_payment_settings;
@@ -0,0 +1,20 @@
+-- Sellers choose whether they accept cash on delivery, and up to what amount
+CREATE TABLE seller_payment_settings (
+    seller_id UUID PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    cod_enabled BOOLEAN NOT NULL DEFAULT FALSE,
+    cod_max_order_value DECIMAL(12,2),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TRIGGER update_seller_payment_settings_updated_at
+BEFORE UPDATE ON seller_payment_settings
+FOR EACH ROW
+EXECUTE FUNCTION update_updated_at_column();
+
+-- The buyer hands the courier a one-time code in exchange for the parcel
+ALTER TABLE seller_orders
+ADD COLUMN delivery_code VARCHAR(10),
+ADD COLUMN delivery_code_attempts INTEGER NOT NULL DEFAULT 0,
+ADD COLUMN cod_collected_amount DECIMAL(12,2),
+ADD COLUMN cod_collected_at TIMESTAMPTZ;
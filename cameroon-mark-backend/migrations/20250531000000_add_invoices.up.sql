@@ -0,0 +1,45 @@
+-- Tax identity printed on a seller's invoices, and the counter their invoice numbers come from
+CREATE TABLE seller_tax_profiles (
+    seller_id UUID PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    legal_name VARCHAR(255),
+    niu VARCHAR(20),
+    next_invoice_number INTEGER NOT NULL DEFAULT 1,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TRIGGER update_seller_tax_profiles_updated_at
+BEFORE UPDATE ON seller_tax_profiles
+FOR EACH ROW
+EXECUTE FUNCTION update_updated_at_column();
+
+-- One invoice per sub-order, numbered in sequence for each seller.
+-- Amounts and the seller's NIU are copied at issue time so the invoice never changes underneath the buyer.
+CREATE TABLE invoices (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    invoice_number VARCHAR(40) NOT NULL UNIQUE,
+    seller_id UUID NOT NULL REFERENCES users(id),
+    sequence INTEGER NOT NULL,
+    order_id UUID NOT NULL REFERENCES orders(id) ON DELETE CASCADE,
+    seller_order_id UUID NOT NULL UNIQUE REFERENCES seller_orders(id) ON DELETE CASCADE,
+    seller_niu VARCHAR(20),
+    subtotal DECIMAL(12,2) NOT NULL,
+    discount_amount DECIMAL(12,2) NOT NULL,
+    shipping_amount DECIMAL(12,2) NOT NULL,
+    tax_rate DECIMAL(6,4) NOT NULL,
+    tax_amount DECIMAL(12,2) NOT NULL,
+    total_amount DECIMAL(12,2) NOT NULL,
+    -- Set once the sub-order has been paid and the document doubles as a receipt
+    paid_at TIMESTAMPTZ,
+    storage_key VARCHAR(255) NOT NULL,
+    issued_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (seller_id, sequence)
+);
+
+CREATE INDEX idx_invoices_order_id ON invoices(order_id);
+
+CREATE TRIGGER update_invoices_updated_at
+BEFORE UPDATE ON invoices
+FOR EACH ROW
+EXECUTE FUNCTION update_updated_at_column();
@@ -0,0 +1,48 @@
+-- One row per attempt to collect an order's payment through a provider
+CREATE TABLE payments (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    order_id UUID NOT NULL REFERENCES orders(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(id),
+    amount DECIMAL(12,2) NOT NULL,
+    currency VARCHAR(3) NOT NULL DEFAULT 'XAF',
+    processor VARCHAR(30) NOT NULL,
+    -- The provider's id for the payment request, and for the money movement once it settles
+    processor_payment_id VARCHAR(100),
+    processor_transaction_id VARCHAR(100),
+    status VARCHAR(20) NOT NULL DEFAULT 'pending'
+        CHECK (status IN ('pending', 'processing', 'completed', 'failed', 'refunded', 'partially_refunded', 'cancelled')),
+    payment_method VARCHAR(50) NOT NULL,
+    metadata JSONB,
+    -- Secret the provider echoes back in its callback
+    callback_token VARCHAR(100) UNIQUE,
+    failure_reason TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    completed_at TIMESTAMPTZ,
+    UNIQUE (processor, processor_payment_id)
+);
+
+CREATE INDEX idx_payments_order_id ON payments(order_id);
+CREATE INDEX idx_payments_status_created_at ON payments(status, created_at);
+
+CREATE TRIGGER update_payments_updated_at
+BEFORE UPDATE ON payments
+FOR EACH ROW
+EXECUTE FUNCTION update_updated_at_column();
+
+-- Every callback a provider sends, exactly as received, for audits and disputes
+CREATE TABLE payment_callbacks (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    processor VARCHAR(30) NOT NULL,
+    payment_id UUID REFERENCES payments(id) ON DELETE SET NULL,
+    headers JSONB NOT NULL,
+    raw_body TEXT NOT NULL,
+    verified BOOLEAN NOT NULL DEFAULT FALSE,
+    outcome VARCHAR(20) NOT NULL
+        CHECK (outcome IN ('applied', 'duplicate', 'ignored', 'rejected')),
+    error TEXT,
+    received_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_payment_callbacks_payment_id ON payment_callbacks(payment_id);
+CREATE INDEX idx_payment_callbacks_received_at ON payment_callbacks(received_at);
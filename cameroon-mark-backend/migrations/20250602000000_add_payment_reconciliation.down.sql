@@ -0,0 +1,7 @@
+DROP TABLE IF EXISTS payment_reconciliation_reports;
+DROP TABLE IF EXISTS provider_settlements;
+
+UPDATE payments SET status = 'cancelled' WHERE status = 'expired';
+ALTER TABLE payments DROP CONSTRAINT payments_status_check;
+ALTER TABLE payments ADD CONSTRAINT payments_status_check
+    CHECK (status IN ('pending', 'processing', 'completed', 'failed', 'refunded', 'partially_refunded', 'cancelled'));
@@ -0,0 +1,36 @@
+-- Attempts the payer never completed are expired by the reconciliation job
+ALTER TABLE payments DROP CONSTRAINT payments_status_check;
+ALTER TABLE payments ADD CONSTRAINT payments_status_check
+    CHECK (status IN ('pending', 'processing', 'completed', 'failed', 'refunded', 'partially_refunded', 'cancelled', 'expired'));
+
+-- Lines of the settlement reports providers send us, one per transaction they settled
+CREATE TABLE provider_settlements (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    processor VARCHAR(30) NOT NULL,
+    -- The provider's id for the payment request, matched against payments.processor_payment_id
+    provider_reference VARCHAR(100) NOT NULL,
+    transaction_id VARCHAR(100),
+    amount DECIMAL(12,2) NOT NULL,
+    settled_at TIMESTAMPTZ NOT NULL,
+    imported_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (processor, provider_reference)
+);
+
+CREATE INDEX idx_provider_settlements_settled_at ON provider_settlements(settled_at);
+
+-- What our completed payments and the provider's settlements disagreed on, per day and provider
+CREATE TABLE payment_reconciliation_reports (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    report_date DATE NOT NULL,
+    processor VARCHAR(30) NOT NULL,
+    payments_count INTEGER NOT NULL,
+    payments_total DECIMAL(14,2) NOT NULL,
+    settlements_count INTEGER NOT NULL,
+    settlements_total DECIMAL(14,2) NOT NULL,
+    missing_at_provider JSONB NOT NULL DEFAULT '[]',
+    missing_locally JSONB NOT NULL DEFAULT '[]',
+    amount_mismatches JSONB NOT NULL DEFAULT '[]',
+    status VARCHAR(20) NOT NULL CHECK (status IN ('matched', 'discrepancies')),
+    generated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (report_date, processor)
+);
@@ -0,0 +1,20 @@
+-- Business expenses sellers and admins track against their revenue
+CREATE TABLE expenses (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    amount DECIMAL(12,2) NOT NULL CHECK (amount > 0),
+    currency VARCHAR(3) NOT NULL DEFAULT 'XAF',
+    category VARCHAR(100) NOT NULL,
+    description TEXT NOT NULL,
+    date DATE NOT NULL,
+    receipt_url TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_expenses_user_id_date ON expenses(user_id, date);
+
+CREATE TRIGGER update_expenses_updated_at
+BEFORE UPDATE ON expenses
+FOR EACH ROW
+EXECUTE FUNCTION update_updated_at_column();
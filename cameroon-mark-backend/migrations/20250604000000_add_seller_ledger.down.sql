@@ -0,0 +1,6 @@
+DROP TABLE IF EXISTS ledger_entries;
+DROP TABLE IF EXISTS ledger_transactions;
+DROP TABLE IF EXISTS withdrawals;
+DROP TABLE IF EXISTS ledger_accounts;
+
+ALTER TABLE categories DROP COLUMN IF EXISTS commission_rate;
@@ -0,0 +1,89 @@
+-- Share of each sale the marketplace keeps, set per category
+ALTER TABLE categories
+ADD COLUMN commission_rate DECIMAL(5,4) NOT NULL DEFAULT 0.1000 CHECK (commission_rate >= 0 AND commission_rate < 1);
+
+-- Double-entry ledger of what buyers paid, what the marketplace keeps and what it owes each seller.
+-- Sellers own an escrow account (paid for, not yet delivered), a wallet (free to withdraw) and a
+-- withdrawals account (asked to be paid out). The marketplace owns its cash and its commission.
+CREATE TABLE ledger_accounts (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    owner_id UUID REFERENCES users(id) ON DELETE RESTRICT,
+    kind VARCHAR(30) NOT NULL CHECK (kind IN ('seller_escrow', 'seller_wallet', 'seller_withdrawals', 'platform_cash', 'platform_commission')),
+    currency VARCHAR(3) NOT NULL DEFAULT 'XAF',
+    -- Sum of the account's entries, kept up to date as entries are posted
+    balance DECIMAL(14,2) NOT NULL DEFAULT 0,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CHECK ((owner_id IS NULL) = (kind IN ('platform_cash', 'platform_commission')))
+);
+
+CREATE UNIQUE INDEX idx_ledger_accounts_owner_kind ON ledger_accounts(owner_id, kind) WHERE owner_id IS NOT NULL;
+CREATE UNIQUE INDEX idx_ledger_accounts_platform_kind ON ledger_accounts(kind) WHERE owner_id IS NULL;
+
+CREATE TRIGGER update_ledger_accounts_updated_at
+BEFORE UPDATE ON ledger_accounts
+FOR EACH ROW
+EXECUTE FUNCTION update_updated_at_column();
+
+INSERT INTO ledger_accounts (kind) VALUES ('platform_cash'), ('platform_commission');
+
+-- Sellers' requests to be paid their wallet balance on MTN or Orange Money
+CREATE TABLE withdrawals (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    seller_id UUID NOT NULL REFERENCES users(id) ON DELETE RESTRICT,
+    amount DECIMAL(12,2) NOT NULL CHECK (amount > 0),
+    currency VARCHAR(3) NOT NULL DEFAULT 'XAF',
+    method VARCHAR(20) NOT NULL CHECK (method IN ('mtn', 'orange')),
+    phone_number VARCHAR(20) NOT NULL,
+    status VARCHAR(20) NOT NULL DEFAULT 'pending'
+        CHECK (status IN ('pending', 'approved', 'processing', 'paid', 'rejected', 'failed')),
+    provider_reference VARCHAR(100),
+    failure_reason TEXT,
+    reviewed_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    reviewed_at TIMESTAMPTZ,
+    paid_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_withdrawals_seller_id_created_at ON withdrawals(seller_id, created_at);
+CREATE INDEX idx_withdrawals_status ON withdrawals(status);
+
+CREATE TRIGGER update_withdrawals_updated_at
+BEFORE UPDATE ON withdrawals
+FOR EACH ROW
+EXECUTE FUNCTION update_updated_at_column();
+
+-- One movement of money, made of entries that add up to zero
+CREATE TABLE ledger_transactions (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    kind VARCHAR(30) NOT NULL CHECK (kind IN (
+        'payment_captured', 'delivery_released', 'cash_collected', 'refund',
+        'withdrawal_requested', 'withdrawal_paid', 'withdrawal_returned'
+    )),
+    order_id UUID REFERENCES orders(id) ON DELETE RESTRICT,
+    seller_order_id UUID REFERENCES seller_orders(id) ON DELETE RESTRICT,
+    withdrawal_id UUID REFERENCES withdrawals(id) ON DELETE RESTRICT,
+    description TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- A sub-order is captured and released at most once
+CREATE UNIQUE INDEX idx_ledger_transactions_seller_order_kind ON ledger_transactions(seller_order_id, kind)
+    WHERE kind IN ('payment_captured', 'delivery_released', 'cash_collected');
+CREATE INDEX idx_ledger_transactions_order_id ON ledger_transactions(order_id);
+
+-- Credits are positive and debits negative, so an account's balance is the sum of its entries:
+-- seller accounts are positive while the marketplace owes the seller, platform cash is negative
+-- while it holds buyers' money.
+CREATE TABLE ledger_entries (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    transaction_id UUID NOT NULL REFERENCES ledger_transactions(id) ON DELETE RESTRICT,
+    account_id UUID NOT NULL REFERENCES ledger_accounts(id) ON DELETE RESTRICT,
+    amount DECIMAL(14,2) NOT NULL CHECK (amount <> 0),
+    balance_after DECIMAL(14,2) NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_ledger_entries_account_id_created_at ON ledger_entries(account_id, created_at);
+CREATE INDEX idx_ledger_entries_transaction_id ON ledger_entries(transaction_id);
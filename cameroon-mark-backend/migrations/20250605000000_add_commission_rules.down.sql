@@ -0,0 +1,9 @@
+ALTER TABLE order_items
+DROP COLUMN IF EXISTS processing_fee,
+DROP COLUMN IF EXISTS commission_amount,
+DROP COLUMN IF EXISTS commission_rate,
+DROP COLUMN IF EXISTS commission_rule_id;
+
+DROP TABLE IF EXISTS commission_rules;
+
+ALTER TABLE seller_payment_settings DROP COLUMN IF EXISTS seller_tier;
@@ -0,0 +1,48 @@
+-- Admins place sellers in a tier that commission rules can target
+ALTER TABLE seller_payment_settings
+ADD COLUMN seller_tier VARCHAR(20) NOT NULL DEFAULT 'standard' CHECK (seller_tier IN ('standard', 'verified', 'premium'));
+
+-- Marketplace commission: a share of each item's price after discounts and/or a fixed fee per unit.
+-- The most specific rule in force when an order is placed applies: promotions first, then rules for
+-- a category and a tier, then a category, then a tier, then everyone. Without a rule, the
+-- category's commission_rate applies.
+CREATE TABLE commission_rules (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    name VARCHAR(100) NOT NULL,
+    category_id UUID REFERENCES categories(id) ON DELETE CASCADE,
+    seller_tier VARCHAR(20) CHECK (seller_tier IN ('standard', 'verified', 'premium')),
+    is_promotion BOOLEAN NOT NULL DEFAULT FALSE,
+    percentage DECIMAL(5,4) NOT NULL DEFAULT 0 CHECK (percentage >= 0 AND percentage < 1),
+    fixed_fee DECIMAL(12,2) NOT NULL DEFAULT 0 CHECK (fixed_fee >= 0),
+    starts_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    ends_at TIMESTAMPTZ,
+    created_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CHECK (ends_at IS NULL OR ends_at > starts_at),
+    -- Promotions are always temporary
+    CHECK (NOT is_promotion OR ends_at IS NOT NULL)
+);
+
+CREATE INDEX idx_commission_rules_starts_at ON commission_rules(starts_at);
+
+CREATE TRIGGER update_commission_rules_updated_at
+BEFORE UPDATE ON commission_rules
+FOR EACH ROW
+EXECUTE FUNCTION update_updated_at_column();
+
+-- Fees worked out at checkout, kept so reports and payouts do not change when rules do.
+-- The processing fee is the item's share of what the payment provider charges on the sub-order.
+ALTER TABLE order_items
+ADD COLUMN commission_rule_id UUID REFERENCES commission_rules(id) ON DELETE SET NULL,
+ADD COLUMN commission_rate DECIMAL(5,4) NOT NULL DEFAULT 0,
+ADD COLUMN commission_amount DECIMAL(12,2) NOT NULL DEFAULT 0,
+ADD COLUMN processing_fee DECIMAL(12,2) NOT NULL DEFAULT 0;
+
+-- Items sold before fees were recorded pay their category's rate on the undiscounted price
+UPDATE order_items oi
+SET commission_rate = c.commission_rate,
+    commission_amount = ROUND(oi.unit_price * oi.quantity * c.commission_rate, 0)
+FROM products p
+JOIN categories c ON c.id = p.category_id
+WHERE p.id = oi.product_id;
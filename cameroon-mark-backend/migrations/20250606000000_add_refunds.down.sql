@@ -0,0 +1,7 @@
+DROP INDEX IF EXISTS idx_ledger_transactions_refund_seller_order;
+ALTER TABLE ledger_transactions DROP COLUMN IF EXISTS refund_id;
+
+DROP TABLE IF EXISTS refund_items;
+DROP TABLE IF EXISTS refunds;
+
+ALTER TABLE payments DROP COLUMN IF EXISTS refunded_amount;
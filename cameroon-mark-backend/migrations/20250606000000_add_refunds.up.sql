@@ -0,0 +1,64 @@
+-- How much of each payment has been given back, so refunds can never add up to more than was paid
+ALTER TABLE payments
+ADD COLUMN refunded_amount DECIMAL(12,2) NOT NULL DEFAULT 0 CHECK (refunded_amount >= 0 AND refunded_amount <= amount);
+
+-- Earlier refunds were only recorded in the ledger
+UPDATE payments SET refunded_amount = amount WHERE status = 'refunded';
+UPDATE payments p
+SET refunded_amount = LEAST(p.amount, (
+    SELECT COALESCE(SUM(e.amount), 0)
+    FROM ledger_transactions t
+    JOIN ledger_entries e ON e.transaction_id = t.id
+    JOIN ledger_accounts a ON a.id = e.account_id AND a.kind = 'platform_cash'
+    WHERE t.order_id = p.order_id AND t.kind = 'refund'
+))
+WHERE p.status = 'partially_refunded';
+
+-- Money given back to a buyer on a payment, sent through the provider when it supports refunds
+-- and paid by hand otherwise
+CREATE TABLE refunds (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    payment_id UUID NOT NULL REFERENCES payments(id) ON DELETE RESTRICT,
+    order_id UUID NOT NULL REFERENCES orders(id) ON DELETE RESTRICT,
+    amount DECIMAL(12,2) NOT NULL CHECK (amount > 0),
+    currency VARCHAR(3) NOT NULL DEFAULT 'XAF',
+    reason TEXT NOT NULL,
+    status VARCHAR(20) NOT NULL DEFAULT 'pending'
+        CHECK (status IN ('pending', 'processing', 'completed', 'failed')),
+    -- Reference of the latest attempt with the provider, or of the manual payment
+    provider_reference VARCHAR(100),
+    failure_reason TEXT,
+    requested_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    completed_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_refunds_payment_id ON refunds(payment_id);
+CREATE INDEX idx_refunds_order_id ON refunds(order_id);
+CREATE INDEX idx_refunds_status ON refunds(status);
+
+CREATE TRIGGER update_refunds_updated_at
+BEFORE UPDATE ON refunds
+FOR EACH ROW
+EXECUTE FUNCTION update_updated_at_column();
+
+-- The part of a refund taken from each order item
+CREATE TABLE refund_items (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    refund_id UUID NOT NULL REFERENCES refunds(id) ON DELETE CASCADE,
+    order_item_id UUID NOT NULL REFERENCES order_items(id) ON DELETE RESTRICT,
+    seller_order_id UUID NOT NULL REFERENCES seller_orders(id) ON DELETE RESTRICT,
+    amount DECIMAL(12,2) NOT NULL CHECK (amount > 0),
+    UNIQUE (refund_id, order_item_id)
+);
+
+CREATE INDEX idx_refund_items_order_item_id ON refund_items(order_item_id);
+CREATE INDEX idx_refund_items_seller_order_id ON refund_items(seller_order_id);
+
+-- Each refund is taken back from a sub-order once
+ALTER TABLE ledger_transactions
+ADD COLUMN refund_id UUID REFERENCES refunds(id) ON DELETE RESTRICT;
+
+CREATE UNIQUE INDEX idx_ledger_transactions_refund_seller_order ON ledger_transactions(refund_id, seller_order_id)
+    WHERE refund_id IS NOT NULL;
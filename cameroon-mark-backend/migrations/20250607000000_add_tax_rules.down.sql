@@ -0,0 +1,13 @@
+ALTER TABLE invoices DROP COLUMN IF EXISTS vat_registered;
+UPDATE invoices SET tax_rate = 0.1925 WHERE tax_rate IS NULL;
+ALTER TABLE invoices ALTER COLUMN tax_rate SET NOT NULL;
+
+ALTER TABLE order_items
+DROP COLUMN IF EXISTS tax_amount,
+DROP COLUMN IF EXISTS taxable_amount,
+DROP COLUMN IF EXISTS tax_rate,
+DROP COLUMN IF EXISTS tax_rule_id;
+
+ALTER TABLE seller_tax_profiles DROP COLUMN IF EXISTS vat_registered;
+
+DROP TABLE IF EXISTS tax_rules;
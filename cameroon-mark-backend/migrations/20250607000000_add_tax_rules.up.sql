@@ -0,0 +1,70 @@
+-- VAT charged on goods. The most specific rule in force when an order is placed applies: a rule
+-- for the item's category, then the default rule. A rate of 0 exempts the category, e.g. basic
+-- foodstuffs. When prices include tax the VAT is taken out of the price instead of added to it.
+CREATE TABLE tax_rules (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    name VARCHAR(100) NOT NULL,
+    category_id UUID REFERENCES categories(id) ON DELETE CASCADE,
+    rate DECIMAL(5,4) NOT NULL CHECK (rate >= 0 AND rate < 1),
+    prices_include_tax BOOLEAN NOT NULL DEFAULT FALSE,
+    starts_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    ends_at TIMESTAMPTZ,
+    created_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CHECK (ends_at IS NULL OR ends_at > starts_at)
+);
+
+CREATE INDEX idx_tax_rules_starts_at ON tax_rules(starts_at);
+
+CREATE TRIGGER update_tax_rules_updated_at
+BEFORE UPDATE ON tax_rules
+FOR EACH ROW
+EXECUTE FUNCTION update_updated_at_column();
+
+-- Standard Cameroon VAT (17.5% plus the 10% communal surcharge), as charged until now
+INSERT INTO tax_rules (name, rate, starts_at)
+VALUES ('Cameroon VAT', 0.1925, '2000-01-01');
+
+-- Sellers below the VAT threshold do not charge VAT. Existing sellers keep charging it until they say otherwise.
+ALTER TABLE seller_tax_profiles
+ADD COLUMN vat_registered BOOLEAN NOT NULL DEFAULT TRUE;
+
+-- VAT worked out at checkout for each item, on its price after its share of the seller's discount
+ALTER TABLE order_items
+ADD COLUMN tax_rule_id UUID REFERENCES tax_rules(id) ON DELETE SET NULL,
+ADD COLUMN tax_rate DECIMAL(5,4) NOT NULL DEFAULT 0,
+ADD COLUMN taxable_amount DECIMAL(12,2) NOT NULL DEFAULT 0,
+ADD COLUMN tax_amount DECIMAL(12,2) NOT NULL DEFAULT 0;
+
+-- Earlier items share their sub-order's discount and VAT by price, the rounding going to the largest item
+WITH shares AS (
+    SELECT oi.id,
+           so.subtotal - so.discount_amount AS so_taxable,
+           so.tax_amount AS so_tax,
+           ROUND((so.subtotal - so.discount_amount) * oi.unit_price * oi.quantity / so.subtotal, 0) AS taxable,
+           ROUND(so.tax_amount * oi.unit_price * oi.quantity / so.subtotal, 0) AS tax,
+           ROW_NUMBER() OVER (PARTITION BY so.id ORDER BY oi.unit_price * oi.quantity DESC, oi.id) AS position,
+           so.id AS seller_order_id
+    FROM order_items oi
+    JOIN seller_orders so ON so.id = oi.seller_order_id
+    WHERE so.subtotal > 0
+),
+totals AS (
+    SELECT shares.*,
+           SUM(taxable) OVER (PARTITION BY seller_order_id) AS taxable_total,
+           SUM(tax) OVER (PARTITION BY seller_order_id) AS tax_total
+    FROM shares
+)
+UPDATE order_items oi
+SET taxable_amount = t.taxable + CASE WHEN t.position = 1 THEN t.so_taxable - t.taxable_total ELSE 0 END,
+    tax_amount = t.tax + CASE WHEN t.position = 1 THEN t.so_tax - t.tax_total ELSE 0 END,
+    tax_rate = CASE WHEN t.so_tax > 0 THEN 0.1925 ELSE 0 END,
+    tax_rule_id = CASE WHEN t.so_tax > 0 THEN (SELECT id FROM tax_rules WHERE name = 'Cameroon VAT') END
+FROM totals t
+WHERE t.id = oi.id;
+
+-- Invoices can mix rates, each line showing its own, and record whether the seller charged VAT
+ALTER TABLE invoices
+ALTER COLUMN tax_rate DROP NOT NULL,
+ADD COLUMN vat_registered BOOLEAN NOT NULL DEFAULT TRUE;
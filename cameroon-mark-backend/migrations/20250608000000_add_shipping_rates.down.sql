@@ -0,0 +1,9 @@
+ALTER TABLE seller_orders
+DROP COLUMN IF EXISTS shipping_rate_id,
+DROP COLUMN IF EXISTS shipping_zone,
+DROP COLUMN IF EXISTS shipping_method;
+
+DROP TABLE IF EXISTS shipping_rates;
+DROP TABLE IF EXISTS seller_shipping_settings;
+
+ALTER TABLE products DROP COLUMN IF EXISTS weight_grams;
@@ -0,0 +1,61 @@
+-- Shipping weight of one unit, used to pick a weight bracket
+ALTER TABLE products
+ADD COLUMN weight_grams INTEGER NOT NULL DEFAULT 1000 CHECK (weight_grams > 0);
+
+-- Where a seller ships from and whether buyers can collect their order instead
+CREATE TABLE seller_shipping_settings (
+    seller_id UUID PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    ship_from_city VARCHAR(100),
+    pickup_enabled BOOLEAN NOT NULL DEFAULT FALSE,
+    pickup_address TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CHECK (NOT pickup_enabled OR pickup_address IS NOT NULL)
+);
+
+CREATE TRIGGER update_seller_shipping_settings_updated_at
+BEFORE UPDATE ON seller_shipping_settings
+FOR EACH ROW
+EXECUTE FUNCTION update_updated_at_column();
+
+-- Delivery prices by zone and weight bracket. Sellers set their own; rates without a seller are the
+-- marketplace's defaults, used for zones where the seller has none. Sub-orders whose goods come to
+-- free_above or more ship free.
+CREATE TABLE shipping_rates (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    seller_id UUID REFERENCES users(id) ON DELETE CASCADE,
+    name VARCHAR(100) NOT NULL,
+    carrier VARCHAR(30) NOT NULL DEFAULT 'manual',
+    zone VARCHAR(20) NOT NULL CHECK (zone IN ('same_city', 'douala_yaounde', 'other_regions', 'international')),
+    min_weight_grams INTEGER NOT NULL DEFAULT 0 CHECK (min_weight_grams >= 0),
+    max_weight_grams INTEGER,
+    price DECIMAL(12,2) NOT NULL CHECK (price >= 0),
+    free_above DECIMAL(12,2) CHECK (free_above IS NULL OR free_above > 0),
+    delivery_days INTEGER CHECK (delivery_days IS NULL OR delivery_days >= 0),
+    is_active BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CHECK (max_weight_grams IS NULL OR max_weight_grams > min_weight_grams)
+);
+
+CREATE INDEX idx_shipping_rates_seller_id_zone ON shipping_rates(seller_id, zone);
+
+CREATE TRIGGER update_shipping_rates_updated_at
+BEFORE UPDATE ON shipping_rates
+FOR EACH ROW
+EXECUTE FUNCTION update_updated_at_column();
+
+-- Marketplace defaults. International delivery is left to sellers who offer it.
+INSERT INTO shipping_rates (name, zone, min_weight_grams, max_weight_grams, price, delivery_days) VALUES
+    ('Standard delivery', 'same_city', 0, 5000, 1000, 1),
+    ('Standard delivery', 'same_city', 5000, NULL, 2500, 2),
+    ('Standard delivery', 'douala_yaounde', 0, 5000, 2500, 2),
+    ('Standard delivery', 'douala_yaounde', 5000, NULL, 5000, 3),
+    ('Standard delivery', 'other_regions', 0, 5000, 3500, 4),
+    ('Standard delivery', 'other_regions', 5000, NULL, 7000, 5);
+
+-- How each sub-order reaches the buyer, fixed at checkout
+ALTER TABLE seller_orders
+ADD COLUMN shipping_method VARCHAR(20) NOT NULL DEFAULT 'delivery' CHECK (shipping_method IN ('delivery', 'pickup')),
+ADD COLUMN shipping_zone VARCHAR(20) CHECK (shipping_zone IN ('same_city', 'douala_yaounde', 'other_regions', 'international')),
+ADD COLUMN shipping_rate_id UUID REFERENCES shipping_rates(id) ON DELETE SET NULL;
@@ -0,0 +1,44 @@
+-- Parcels handed to a carrier for a seller's sub-order. A sub-order has at most one shipment
+-- that is not canceled; carriers push tracking events to callback_url?token=callback_token.
+CREATE TABLE shipments (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    seller_order_id UUID NOT NULL REFERENCES seller_orders(id) ON DELETE CASCADE,
+    carrier VARCHAR(30) NOT NULL,
+    tracking_number VARCHAR(100) NOT NULL,
+    carrier_reference VARCHAR(100),
+    status VARCHAR(20) NOT NULL DEFAULT 'created' CHECK (status IN ('created', 'in_transit', 'out_for_delivery', 'delivered', 'failed', 'returned', 'canceled')),
+    weight_grams INTEGER NOT NULL CHECK (weight_grams > 0),
+    label_storage_key VARCHAR(255) NOT NULL,
+    callback_token VARCHAR(64) NOT NULL,
+    created_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    last_event_at TIMESTAMPTZ,
+    last_checked_at TIMESTAMPTZ,
+    canceled_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE UNIQUE INDEX idx_shipments_open_seller_order ON shipments(seller_order_id) WHERE status <> 'canceled';
+CREATE UNIQUE INDEX idx_shipments_carrier_tracking_number ON shipments(carrier, tracking_number) WHERE status <> 'canceled';
+CREATE INDEX idx_shipments_status ON shipments(status);
+
+CREATE TRIGGER update_shipments_updated_at
+BEFORE UPDATE ON shipments
+FOR EACH ROW
+EXECUTE FUNCTION update_updated_at_column();
+
+-- Tracking history as reported by the carrier, pushed to the webhook or pulled by the tracking job
+CREATE TABLE shipment_events (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    shipment_id UUID NOT NULL REFERENCES shipments(id) ON DELETE CASCADE,
+    status VARCHAR(20) NOT NULL CHECK (status IN ('created', 'in_transit', 'out_for_delivery', 'delivered', 'failed', 'returned', 'canceled')),
+    description TEXT,
+    location VARCHAR(100),
+    occurred_at TIMESTAMPTZ NOT NULL,
+    source VARCHAR(20) NOT NULL CHECK (source IN ('carrier', 'webhook', 'tracking_job')),
+    raw_payload JSONB,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (shipment_id, status, occurred_at)
+);
+
+CREATE INDEX idx_shipment_events_shipment_id ON shipment_events(shipment_id, occurred_at);
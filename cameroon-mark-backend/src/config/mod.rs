@@ -12,6 +12,7 @@ pub struct Config {
     pub cors: CorsConfig,
     pub password_reset: PasswordResetConfig,
     pub payment: PaymentConfig,
+    pub shipping: ShippingConfig,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -57,10 +58,35 @@ pub struct PasswordResetConfig {
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct PaymentConfig {
+    // Use the providers' sandboxes instead of live money
+    pub sandbox: bool,
+    // MTN MoMo Collections: API user id, API key and product subscription key
     pub mtn_api_key: String,
     pub mtn_api_secret: String,
+    pub mtn_subscription_key: String,
+    // MTN MoMo Disbursements, used to pay sellers. Withdrawals are paid by hand when left empty.
+    pub mtn_disbursement_api_key: String,
+    pub mtn_disbursement_api_secret: String,
+    pub mtn_disbursement_subscription_key: String,
+    // Orange Money Web Payment: OAuth client id and secret, and merchant key
     pub orange_api_key: String,
     pub orange_api_secret: String,
+    pub orange_merchant_key: String,
+    // Share of each payment the providers keep, e.g. 0.01 for 1%. Passed on to sellers as processing fees.
+    pub mtn_processing_fee_rate: f64,
+    pub orange_processing_fee_rate: f64,
+    // Public URL of this API, where providers send payment callbacks
+    pub callback_base_url: String,
+    // Page buyers return to after paying on the provider's site
+    pub return_url: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ShippingConfig {
+    // Offer the in-memory mock carrier, for local development
+    pub mock_carrier: bool,
+    // Public URL of this API, where carriers push tracking events
+    pub callback_base_url: String,
 }
 
 static CONFIG: OnceLock<Config> = OnceLock::new();
@@ -120,10 +146,32 @@ pub fn get_config() -> &'static Config {
                     .expect("PASSWORD_RESET_EXPIRATION must be a number"),
             },
             payment: PaymentConfig {
+                sandbox: env::var("PAYMENT_ENVIRONMENT").unwrap_or_else(|_| "sandbox".to_string()) != "production",
                 mtn_api_key: env::var("MTN_API_KEY").unwrap_or_default(),
                 mtn_api_secret: env::var("MTN_API_SECRET").unwrap_or_default(),
+                mtn_subscription_key: env::var("MTN_SUBSCRIPTION_KEY").unwrap_or_default(),
+                mtn_disbursement_api_key: env::var("MTN_DISBURSEMENT_API_KEY").unwrap_or_default(),
+                mtn_disbursement_api_secret: env::var("MTN_DISBURSEMENT_API_SECRET").unwrap_or_default(),
+                mtn_disbursement_subscription_key: env::var("MTN_DISBURSEMENT_SUBSCRIPTION_KEY").unwrap_or_default(),
                 orange_api_key: env::var("ORANGE_API_KEY").unwrap_or_default(),
                 orange_api_secret: env::var("ORANGE_API_SECRET").unwrap_or_default(),
+                orange_merchant_key: env::var("ORANGE_MERCHANT_KEY").unwrap_or_default(),
+                mtn_processing_fee_rate: env::var("MTN_PROCESSING_FEE_RATE")
+                    .unwrap_or_else(|_| "0".to_string())
+                    .parse()
+                    .expect("MTN_PROCESSING_FEE_RATE must be a number"),
+                orange_processing_fee_rate: env::var("ORANGE_PROCESSING_FEE_RATE")
+                    .unwrap_or_else(|_| "0".to_string())
+                    .parse()
+                    .expect("ORANGE_PROCESSING_FEE_RATE must be a number"),
+                callback_base_url: env::var("PAYMENT_CALLBACK_BASE_URL").unwrap_or_else(|_| "http://localhost:8080".to_string()),
+                return_url: env::var("PAYMENT_RETURN_URL").unwrap_or_else(|_| "http://localhost:3000/orders".to_string()),
+            },
+            shipping: ShippingConfig {
+                mock_carrier: env::var("SHIPPING_MOCK_CARRIER").is_ok_and(|value| value == "true"),
+                callback_base_url: env::var("SHIPPING_CALLBACK_BASE_URL")
+                    .or_else(|_| env::var("PAYMENT_CALLBACK_BASE_URL"))
+                    .unwrap_or_else(|_| "http://localhost:8080".to_string()),
             },
         }
     })
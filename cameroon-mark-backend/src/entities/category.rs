@@ -2,6 +2,7 @@ use sea_orm::entity::prelude::*;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 use chrono::{DateTime, Utc};
+use bigdecimal::BigDecimal;
 
 #[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
 #[sea_orm(table_name = "categories")]
@@ -12,6 +13,8 @@ pub struct Model {
     pub description: Option<String>,
     pub image_url: Option<String>,
     pub slug: String,
+    // Share of each sale the marketplace keeps, e.g. 0.1 for 10%
+    pub commission_rate: BigDecimal,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
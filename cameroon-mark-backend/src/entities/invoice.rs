@@ -0,0 +1,62 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use bigdecimal::BigDecimal;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "invoices")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: Uuid,
+    pub invoice_number: String,
+    pub seller_id: Uuid,
+    pub sequence: i32,
+    pub order_id: Uuid,
+    pub seller_order_id: Uuid,
+    pub seller_niu: Option<String>,
+    pub subtotal: BigDecimal,
+    pub discount_amount: BigDecimal,
+    pub shipping_amount: BigDecimal,
+    // Empty when the lines are taxed at different rates
+    pub tax_rate: Option<BigDecimal>,
+    pub tax_amount: BigDecimal,
+    pub total_amount: BigDecimal,
+    // Whether the seller charged VAT when the invoice was issued
+    pub vat_registered: bool,
+    pub paid_at: Option<DateTime<Utc>>,
+    pub storage_key: String,
+    pub issued_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::order::Entity",
+        from = "Column::OrderId",
+        to = "super::order::Column::Id",
+        on_delete = "Cascade"
+    )]
+    Order,
+    #[sea_orm(
+        belongs_to = "super::seller_order::Entity",
+        from = "Column::SellerOrderId",
+        to = "super::seller_order::Column::Id",
+        on_delete = "Cascade"
+    )]
+    SellerOrder,
+}
+
+impl Related<super::order::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Order.def()
+    }
+}
+
+impl Related<super::seller_order::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::SellerOrder.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
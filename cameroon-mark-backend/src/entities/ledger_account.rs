@@ -0,0 +1,32 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use bigdecimal::BigDecimal;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "ledger_accounts")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: Uuid,
+    // The seller the account belongs to, none for the marketplace's own accounts
+    pub owner_id: Option<Uuid>,
+    pub kind: String,
+    pub currency: String,
+    pub balance: BigDecimal,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(has_many = "super::ledger_entry::Entity")]
+    LedgerEntry,
+}
+
+impl Related<super::ledger_entry::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::LedgerEntry.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
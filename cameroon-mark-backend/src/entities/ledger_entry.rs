@@ -0,0 +1,47 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use bigdecimal::BigDecimal;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "ledger_entries")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: Uuid,
+    pub transaction_id: Uuid,
+    pub account_id: Uuid,
+    // Positive for a credit, negative for a debit
+    pub amount: BigDecimal,
+    pub balance_after: BigDecimal,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::ledger_transaction::Entity",
+        from = "Column::TransactionId",
+        to = "super::ledger_transaction::Column::Id"
+    )]
+    LedgerTransaction,
+    #[sea_orm(
+        belongs_to = "super::ledger_account::Entity",
+        from = "Column::AccountId",
+        to = "super::ledger_account::Column::Id"
+    )]
+    LedgerAccount,
+}
+
+impl Related<super::ledger_transaction::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::LedgerTransaction.def()
+    }
+}
+
+impl Related<super::ledger_account::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::LedgerAccount.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -8,3 +8,34 @@ pub mod reported_item;
 pub mod campaign;
 pub mod discount_code;
 pub mod email_campaign;
+pub mod notification;
+pub mod product_question;
+pub mod product_answer;
+pub mod product_answer_vote;
+pub mod product_view;
+pub mod notification_preferences;
+pub mod wishlist;
+pub mod cart_item;
+pub mod guest_cart;
+pub mod seller_order;
+pub mod order_status_history;
+pub mod seller_payment_settings;
+pub mod seller_tax_profile;
+pub mod invoice;
+pub mod payment;
+pub mod payment_callback;
+pub mod provider_settlement;
+pub mod payment_reconciliation_report;
+pub mod expense;
+pub mod ledger_account;
+pub mod ledger_transaction;
+pub mod ledger_entry;
+pub mod withdrawal;
+pub mod commission_rule;
+pub mod refund;
+pub mod refund_item;
+pub mod tax_rule;
+pub mod shipping_rate;
+pub mod seller_shipping_setting;
+pub mod shipment;
+pub mod shipment_event;
@@ -9,7 +9,12 @@ pub struct Model {
     #[sea_orm(primary_key)]
     pub id: Uuid,
     pub user_id: Uuid,
+    pub subtotal: BigDecimal,
+    pub discount_amount: BigDecimal,
+    pub shipping_amount: BigDecimal,
+    pub tax_amount: BigDecimal,
     pub total_amount: BigDecimal,
+    pub discount_code_id: Option<Uuid>,
     #[sea_orm(column_type = "String(StringLen::N(20))", nullable)]
     pub status: String,
     #[sea_orm(column_type = "String(StringLen::N(20))", nullable)]
@@ -17,6 +22,8 @@ pub struct Model {
     #[sea_orm(column_type = "String(StringLen::N(20))", nullable)]
     pub payment_method: String,
     pub shipping_address: Json,
+    #[sea_orm(column_type = "String(StringLen::N(100))", nullable)]
+    pub idempotency_key: Option<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -29,6 +36,8 @@ pub enum Relation {
         to = "super::order_item::Column::OrderId"
     )]
     OrderItems,
+    #[sea_orm(has_many = "super::seller_order::Entity")]
+    SellerOrders,
     #[sea_orm(
         belongs_to = "super::user::Entity",
         from = "Column::UserId",
@@ -43,6 +52,12 @@ impl Related<super::order_item::Entity> for Entity {
     }
 }
 
+impl Related<super::seller_order::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::SellerOrders.def()
+    }
+}
+
 impl Related<super::user::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::User.def()
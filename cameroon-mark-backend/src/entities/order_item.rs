@@ -9,9 +9,22 @@ pub struct Model {
     #[sea_orm(primary_key)]
     pub id: Uuid,
     pub order_id: Uuid,
+    pub seller_order_id: Uuid,
     pub product_id: Uuid,
+    pub seller_id: Uuid,
+    pub product_title: String,
     pub quantity: i32,
     pub unit_price: BigDecimal,
+    // Fees fixed at checkout
+    pub commission_rule_id: Option<Uuid>,
+    pub commission_rate: BigDecimal,
+    pub commission_amount: BigDecimal,
+    pub processing_fee: BigDecimal,
+    // VAT fixed at checkout, on the price after discount and net of any VAT it included
+    pub tax_rule_id: Option<Uuid>,
+    pub tax_rate: BigDecimal,
+    pub taxable_amount: BigDecimal,
+    pub tax_amount: BigDecimal,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -30,6 +43,12 @@ pub enum Relation {
         to = "super::product::Column::Id"
     )]
     Product,
+    #[sea_orm(
+        belongs_to = "super::seller_order::Entity",
+        from = "Column::SellerOrderId",
+        to = "super::seller_order::Column::Id"
+    )]
+    SellerOrder,
 }
 
 impl Related<super::order::Entity> for Entity {
@@ -44,4 +63,10 @@ impl Related<super::product::Entity> for Entity {
     }
 }
 
+impl Related<super::seller_order::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::SellerOrder.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {} 
\ No newline at end of file
@@ -0,0 +1,62 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use bigdecimal::BigDecimal;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "payments")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: Uuid,
+    pub order_id: Uuid,
+    pub user_id: Uuid,
+    pub amount: BigDecimal,
+    pub refunded_amount: BigDecimal,
+    pub currency: String,
+    pub processor: String,
+    pub processor_payment_id: Option<String>,
+    pub processor_transaction_id: Option<String>,
+    #[sea_orm(column_type = "String(StringLen::N(20))")]
+    pub status: String,
+    pub payment_method: String,
+    pub metadata: Option<Json>,
+    #[serde(skip_serializing)]
+    pub callback_token: Option<String>,
+    pub failure_reason: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::order::Entity",
+        from = "Column::OrderId",
+        to = "super::order::Column::Id",
+        on_delete = "Cascade"
+    )]
+    Order,
+    #[sea_orm(
+        belongs_to = "super::user::Entity",
+        from = "Column::UserId",
+        to = "super::user::Column::Id"
+    )]
+    User,
+    #[sea_orm(has_many = "super::payment_callback::Entity")]
+    Callbacks,
+}
+
+impl Related<super::order::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Order.def()
+    }
+}
+
+impl Related<super::payment_callback::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Callbacks.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,28 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, NaiveDate, Utc};
+use bigdecimal::BigDecimal;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "payment_reconciliation_reports")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: Uuid,
+    pub report_date: NaiveDate,
+    pub processor: String,
+    pub payments_count: i32,
+    pub payments_total: BigDecimal,
+    pub settlements_count: i32,
+    pub settlements_total: BigDecimal,
+    pub missing_at_provider: Json,
+    pub missing_locally: Json,
+    pub amount_mismatches: Json,
+    #[sea_orm(column_type = "String(StringLen::N(20))")]
+    pub status: String,
+    pub generated_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -18,8 +18,13 @@ pub struct Model {
     pub stock: i32,
     pub images: ImageArray,
     pub location: String,
+    // Shipping weight of one unit
+    pub weight_grams: i32,
     pub featured: bool,
     pub rating: Option<f64>,
+    pub view_count: i64,
+    #[sea_orm(column_type = "String(StringLen::N(20))")]
+    pub status: String,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
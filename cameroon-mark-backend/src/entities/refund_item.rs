@@ -0,0 +1,45 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use bigdecimal::BigDecimal;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "refund_items")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: Uuid,
+    pub refund_id: Uuid,
+    pub order_item_id: Uuid,
+    pub seller_order_id: Uuid,
+    pub amount: BigDecimal,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::refund::Entity",
+        from = "Column::RefundId",
+        to = "super::refund::Column::Id",
+        on_delete = "Cascade"
+    )]
+    Refund,
+    #[sea_orm(
+        belongs_to = "super::order_item::Entity",
+        from = "Column::OrderItemId",
+        to = "super::order_item::Column::Id"
+    )]
+    OrderItem,
+}
+
+impl Related<super::refund::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Refund.def()
+    }
+}
+
+impl Related<super::order_item::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::OrderItem.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
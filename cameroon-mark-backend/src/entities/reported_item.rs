@@ -9,6 +9,7 @@ pub struct Model {
     pub id: Uuid,
     pub user_id: Uuid,
     pub product_id: Uuid,
+    pub question_id: Option<Uuid>,
     pub reason: String,
     pub status: String,
     pub created_at: DateTimeWithTimeZone,
@@ -29,6 +30,12 @@ pub enum Relation {
         to = "super::product::Column::Id"
     )]
     Product,
+    #[sea_orm(
+        belongs_to = "super::product_question::Entity",
+        from = "Column::QuestionId",
+        to = "super::product_question::Column::Id"
+    )]
+    Question,
 }
 
 impl Related<super::user::Entity> for Entity {
@@ -43,4 +50,10 @@ impl Related<super::product::Entity> for Entity {
     }
 }
 
+impl Related<super::product_question::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Question.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
@@ -1,6 +1,7 @@
 use sea_orm::entity::prelude::*;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
+use bigdecimal::BigDecimal;
 use chrono::{DateTime, Utc};
 
 #[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
@@ -10,6 +11,11 @@ pub struct Model {
     pub id: Uuid,
     pub user_id: Uuid,
     pub product_id: Uuid,
+    pub wishlist_id: Uuid,
+    pub price_at_save: Option<BigDecimal>,
+    pub alerts_enabled: bool,
+    pub last_alerted_price: Option<BigDecimal>,
+    pub last_alerted_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
 }
 
@@ -19,6 +25,8 @@ pub enum Relation {
     User,
     #[sea_orm(belongs_to = "super::product::Entity", from = "Column::ProductId", to = "super::product::Column::Id")]
     Product,
+    #[sea_orm(belongs_to = "super::wishlist::Entity", from = "Column::WishlistId", to = "super::wishlist::Column::Id")]
+    Wishlist,
 }
 
 impl Related<super::user::Entity> for Entity {
@@ -33,4 +41,10 @@ impl Related<super::product::Entity> for Entity {
     }
 }
 
+impl Related<super::wishlist::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Wishlist.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
\ No newline at end of file
@@ -0,0 +1,85 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use bigdecimal::BigDecimal;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "seller_orders")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+    pub order_id: Uuid,
+    pub seller_id: Uuid,
+    #[sea_orm(column_type = "String(StringLen::N(20))")]
+    pub status: String,
+    pub subtotal: BigDecimal,
+    pub discount_amount: BigDecimal,
+    pub shipping_amount: BigDecimal,
+    pub tax_amount: BigDecimal,
+    pub total_amount: BigDecimal,
+    #[sea_orm(column_type = "String(StringLen::N(20))")]
+    pub fulfillment_status: String,
+    // Delivery or pickup in store
+    #[sea_orm(column_type = "String(StringLen::N(20))")]
+    pub shipping_method: String,
+    pub shipping_zone: Option<String>,
+    pub shipping_rate_id: Option<Uuid>,
+    pub tracking_number: Option<String>,
+    pub shipping_provider: Option<String>,
+    pub shipped_at: Option<DateTime<Utc>>,
+    pub delivered_at: Option<DateTime<Utc>>,
+    #[sea_orm(column_type = "String(StringLen::N(20))")]
+    pub payout_status: String,
+    pub payout_amount: BigDecimal,
+    pub paid_out_at: Option<DateTime<Utc>>,
+    pub cancellation_reason: Option<String>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub cancellation_note: Option<String>,
+    pub canceled_by: Option<String>,
+    pub canceled_at: Option<DateTime<Utc>>,
+    pub delivery_code: Option<String>,
+    pub delivery_code_attempts: i32,
+    pub cod_collected_amount: Option<BigDecimal>,
+    pub cod_collected_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::order::Entity",
+        from = "Column::OrderId",
+        to = "super::order::Column::Id",
+        on_delete = "Cascade"
+    )]
+    Order,
+    #[sea_orm(
+        belongs_to = "super::user::Entity",
+        from = "Column::SellerId",
+        to = "super::user::Column::Id"
+    )]
+    Seller,
+    #[sea_orm(has_many = "super::order_item::Entity")]
+    OrderItems,
+}
+
+impl Related<super::order::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Order.def()
+    }
+}
+
+impl Related<super::user::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Seller.def()
+    }
+}
+
+impl Related<super::order_item::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::OrderItems.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,35 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use bigdecimal::BigDecimal;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "seller_payment_settings")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub seller_id: Uuid,
+    pub cod_enabled: bool,
+    pub cod_max_order_value: Option<BigDecimal>,
+    pub seller_tier: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::user::Entity",
+        from = "Column::SellerId",
+        to = "super::user::Column::Id",
+        on_delete = "Cascade"
+    )]
+    Seller,
+}
+
+impl Related<super::user::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Seller.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,56 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "shipments")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: Uuid,
+    pub seller_order_id: Uuid,
+    #[sea_orm(column_type = "String(StringLen::N(30))")]
+    pub carrier: String,
+    pub tracking_number: String,
+    // Id the carrier knows the shipment by, when it differs from the tracking number
+    pub carrier_reference: Option<String>,
+    #[sea_orm(column_type = "String(StringLen::N(20))")]
+    pub status: String,
+    pub weight_grams: i32,
+    pub label_storage_key: String,
+    // Secret the carrier sends back with tracking events for this shipment
+    #[serde(skip_serializing)]
+    pub callback_token: String,
+    pub created_by: Option<Uuid>,
+    pub last_event_at: Option<DateTime<Utc>>,
+    pub last_checked_at: Option<DateTime<Utc>>,
+    pub canceled_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::seller_order::Entity",
+        from = "Column::SellerOrderId",
+        to = "super::seller_order::Column::Id",
+        on_delete = "Cascade"
+    )]
+    SellerOrder,
+    #[sea_orm(has_many = "super::shipment_event::Entity")]
+    Events,
+}
+
+impl Related<super::seller_order::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::SellerOrder.def()
+    }
+}
+
+impl Related<super::shipment_event::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Events.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,41 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "shipment_events")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: Uuid,
+    pub shipment_id: Uuid,
+    #[sea_orm(column_type = "String(StringLen::N(20))")]
+    pub status: String,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub description: Option<String>,
+    pub location: Option<String>,
+    pub occurred_at: DateTime<Utc>,
+    // carrier, webhook or tracking_job
+    #[sea_orm(column_type = "String(StringLen::N(20))")]
+    pub source: String,
+    pub raw_payload: Option<Json>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::shipment::Entity",
+        from = "Column::ShipmentId",
+        to = "super::shipment::Column::Id",
+        on_delete = "Cascade"
+    )]
+    Shipment,
+}
+
+impl Related<super::shipment::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Shipment.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
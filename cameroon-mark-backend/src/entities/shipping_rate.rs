@@ -0,0 +1,47 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use bigdecimal::BigDecimal;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "shipping_rates")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: Uuid,
+    // A marketplace default when empty
+    pub seller_id: Option<Uuid>,
+    pub name: String,
+    #[sea_orm(column_type = "String(StringLen::N(30))")]
+    pub carrier: String,
+    #[sea_orm(column_type = "String(StringLen::N(20))")]
+    pub zone: String,
+    pub min_weight_grams: i32,
+    // No upper limit when empty
+    pub max_weight_grams: Option<i32>,
+    pub price: BigDecimal,
+    // Sub-orders whose goods come to this much ship free
+    pub free_above: Option<BigDecimal>,
+    pub delivery_days: Option<i32>,
+    pub is_active: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::user::Entity",
+        from = "Column::SellerId",
+        to = "super::user::Column::Id",
+        on_delete = "Cascade"
+    )]
+    Seller,
+}
+
+impl Related<super::user::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Seller.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -1,16 +1,33 @@
 use axum::{
     extract::{Json, State},
-    http::StatusCode,
+    http::{HeaderMap, StatusCode},
     response::{IntoResponse, Response},
 };
 use std::sync::Arc;
 
 use crate::errors::{AppError, ApiResponse, Result};
 use crate::models::user::{LoginRequest, RegisterRequest, PasswordResetRequest, UserProfile};
-use crate::services::auth;
-use crate::utils::validation;
+use crate::services::{auth, cart};
+use crate::utils::{jwt, validation};
 use crate::AppState;
 
+// Bring a guest cart sent along with the request into the user's cart.
+// A bad or expired cart token must not stop the user from signing in.
+async fn merge_guest_cart(state: &AppState, headers: &HeaderMap, user_id: uuid::Uuid) {
+    let Some(cart_token) = headers.get("X-Cart-Token").and_then(|value| value.to_str().ok()) else {
+        return;
+    };
+
+    let guest_cart_id = match jwt::verify_cart_token(cart_token) {
+        Ok(guest_cart_id) => guest_cart_id,
+        Err(_) => return,
+    };
+
+    if let Err(e) = cart::merge_guest_cart(&state.db, guest_cart_id, user_id).await {
+        tracing::warn!("Failed to merge guest cart {} into user {}: {:?}", guest_cart_id, user_id, e);
+    }
+}
+
 #[derive(serde::Serialize)]
 struct AuthResponse {
     user: UserProfile,
@@ -19,6 +36,7 @@ struct AuthResponse {
 
 pub async fn register(
     State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
     Json(payload): Json<RegisterRequest>,
 ) -> Result<Response> {
     // Validate request payload
@@ -27,6 +45,9 @@ pub async fn register(
     // Register the user
     let (user, token) = auth::register(&state.db, payload).await?;
     
+    // Keep whatever the visitor put in their cart before registering
+    merge_guest_cart(&state, &headers, user.id).await;
+    
     Ok((
         StatusCode::CREATED,
         Json(AuthResponse { user: UserProfile::from(user), token }),
@@ -36,6 +57,7 @@ pub async fn register(
 
 pub async fn login(
     State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
     Json(payload): Json<LoginRequest>,
 ) -> Result<Response> {
     // Validate request payload
@@ -44,6 +66,9 @@ pub async fn login(
     // Authenticate the user
     let (user, token) = auth::login(&state.db, payload).await?;
     
+    // Keep whatever the visitor put in their cart before signing in
+    merge_guest_cart(&state, &headers, user.id).await;
+    
     Ok((
         StatusCode::OK,
         Json(AuthResponse { user: UserProfile::from(user), token }),
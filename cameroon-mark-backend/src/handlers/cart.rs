@@ -1,5 +1,5 @@
 use axum::{
-    extract::{Json, Path, State},
+    extract::{Json, Path, Query, State},
     http::StatusCode,
     response::IntoResponse,
 };
@@ -7,38 +7,58 @@ use std::sync::Arc;
 use uuid::Uuid;
 
 use crate::errors::{AppError, ApiResponse, Result};
-use crate::middlewares::auth::ExtractUserId;
+use crate::middlewares::auth::ExtractCartOwner;
 use crate::models::cart::{AddToCartRequest, UpdateCartItemRequest};
+use crate::models::shipping::ShippingQuoteQuery;
 use crate::services::cart;
 use crate::utils::validation;
 
 // AppState is defined in main.rs
 use crate::AppState;
 
+// Start a cart for a visitor who is not signed in
+pub async fn create_guest_cart(
+    State(state): State<Arc<AppState>>,
+) -> Result<impl IntoResponse> {
+    let guest_cart = cart::create_guest_cart(&state.db).await?;
+    
+    Ok((StatusCode::CREATED, Json(ApiResponse::success(guest_cart))))
+}
+
 pub async fn get_cart(
     State(state): State<Arc<AppState>>,
-    user_id: ExtractUserId,
+    ExtractCartOwner(owner): ExtractCartOwner,
 ) -> Result<impl IntoResponse> {
     // Get user's cart
-    let cart_response = cart::get_cart(&state.db, user_id.0).await?;
+    let cart_response = cart::get_cart(&state.db, owner).await?;
     
     // Return success response with cart
     Ok(Json(ApiResponse::success(cart_response)))
 }
 
+// Shipping options for each seller's items, to `city` and `country`
+pub async fn get_shipping_quotes(
+    State(state): State<Arc<AppState>>,
+    ExtractCartOwner(owner): ExtractCartOwner,
+    Query(query): Query<ShippingQuoteQuery>,
+) -> Result<impl IntoResponse> {
+    let quotes = cart::get_shipping_quotes(&state.db, owner, query).await?;
+    Ok(Json(ApiResponse::success(quotes)))
+}
+
 pub async fn add_to_cart(
     State(state): State<Arc<AppState>>,
-    user_id: ExtractUserId,
+    ExtractCartOwner(owner): ExtractCartOwner,
     Json(payload): Json<AddToCartRequest>,
 ) -> Result<impl IntoResponse> {
     // Validate request payload
     validation::validate(&payload)?;
     
     // Add item to cart
-    cart::add_to_cart(&state.db, user_id.0, payload).await?;
+    cart::add_to_cart(&state.db, owner, payload).await?;
     
     // Get updated cart
-    let cart_response = cart::get_cart(&state.db, user_id.0).await?;
+    let cart_response = cart::get_cart(&state.db, owner).await?;
     
     // Return success response with updated cart
     Ok(Json(ApiResponse::success_with_message(
@@ -49,7 +69,7 @@ pub async fn add_to_cart(
 
 pub async fn update_cart_item(
     State(state): State<Arc<AppState>>,
-    user_id: ExtractUserId,
+    ExtractCartOwner(owner): ExtractCartOwner,
     Path(product_id): Path<Uuid>,
     Json(payload): Json<UpdateCartItemRequest>,
 ) -> Result<impl IntoResponse> {
@@ -57,10 +77,10 @@ pub async fn update_cart_item(
     validation::validate(&payload)?;
     
     // Update cart item
-    cart::update_cart_item(&state.db, user_id.0, product_id, payload.quantity).await?;
+    cart::update_cart_item(&state.db, owner, product_id, payload.quantity).await?;
     
     // Get updated cart
-    let cart_response = cart::get_cart(&state.db, user_id.0).await?;
+    let cart_response = cart::get_cart(&state.db, owner).await?;
     
     // Return success response with updated cart
     Ok(Json(ApiResponse::success_with_message(
@@ -71,14 +91,14 @@ pub async fn update_cart_item(
 
 pub async fn remove_from_cart(
     State(state): State<Arc<AppState>>,
-    user_id: ExtractUserId,
+    ExtractCartOwner(owner): ExtractCartOwner,
     Path(product_id): Path<Uuid>,
 ) -> Result<impl IntoResponse> {
     // Remove item from cart
-    cart::remove_from_cart(&state.db, user_id.0, product_id).await?;
+    cart::remove_from_cart(&state.db, owner, product_id).await?;
     
     // Get updated cart
-    let cart_response = cart::get_cart(&state.db, user_id.0).await?;
+    let cart_response = cart::get_cart(&state.db, owner).await?;
     
     // Return success response with updated cart
     Ok(Json(ApiResponse::success_with_message(
@@ -89,14 +109,17 @@ pub async fn remove_from_cart(
 
 pub async fn clear_cart(
     State(state): State<Arc<AppState>>,
-    user_id: ExtractUserId,
+    ExtractCartOwner(owner): ExtractCartOwner,
 ) -> Result<impl IntoResponse> {
     // Clear cart
-    cart::clear_cart(&state.db, user_id.0).await?;
+    cart::clear_cart(&state.db, owner).await?;
+    
+    // Get the now empty cart
+    let cart_response = cart::get_cart(&state.db, owner).await?;
     
     // Return success response
     Ok(Json(ApiResponse::success_with_message(
-        serde_json::json!({ "items": [], "total_items": 0, "total_price": 0 }),
+        cart_response,
         "Cart cleared",
     )))
 }
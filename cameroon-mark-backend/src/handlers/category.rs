@@ -10,7 +10,17 @@ use uuid::Uuid;
 use crate::errors::{AppError, ApiResponse, Result};
 use crate::services::category;
 use crate::models::category::{CreateCategoryRequest, UpdateCategoryRequest};
-use crate::middlewares::auth::ExtractUserId;
+use crate::middlewares::auth::{ExtractUserId, ExtractUserRole};
+use crate::models::user::UserRole;
+use crate::utils::validation;
+
+// Commission is platform revenue, so only admins may change it
+fn ensure_can_set_commission(role: &UserRole, commission_rate: Option<f64>) -> Result<()> {
+    if commission_rate.is_some() && *role != UserRole::Admin {
+        return Err(AppError::forbidden("Only admins can change a category's commission rate"));
+    }
+    Ok(())
+}
 
 // AppState is defined in main.rs
 use crate::AppState;
@@ -36,8 +46,12 @@ pub async fn get_category(
 pub async fn create_category(
     State(state): State<Arc<AppState>>,
     _user_id: ExtractUserId, // Ensure user is authenticated
+    ExtractUserRole(role): ExtractUserRole,
     Json(payload): Json<CreateCategoryRequest>,
 ) -> Result<impl IntoResponse> {
+    validation::validate(&payload)?;
+    ensure_can_set_commission(&role, payload.commission_rate)?;
+
     let category = category::create_category(&state.db, payload).await?;
     Ok((StatusCode::CREATED, Json(ApiResponse::success(category))))
 }
@@ -45,9 +59,13 @@ pub async fn create_category(
 pub async fn update_category(
     State(state): State<Arc<AppState>>,
     _user_id: ExtractUserId, // Ensure user is authenticated
+    ExtractUserRole(role): ExtractUserRole,
     Path(category_id): Path<Uuid>,
     Json(payload): Json<UpdateCategoryRequest>,
 ) -> Result<impl IntoResponse> {
+    validation::validate(&payload)?;
+    ensure_can_set_commission(&role, payload.commission_rate)?;
+
     let category = category::update_category(&state.db, category_id, payload).await?;
     Ok(Json(ApiResponse::success(category)))
 }
@@ -0,0 +1,77 @@
+use axum::{
+    extract::{Json, Path, State},
+    response::IntoResponse,
+};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::errors::{AppError, ApiResponse, Result};
+use crate::middlewares::auth::{ExtractUserId, ExtractUserRole};
+use crate::models::cod::{ConfirmDeliveryRequest, UpdateCodSettingsRequest};
+use crate::models::user::UserRole;
+use crate::services::{cod, order};
+use crate::utils::validation;
+use crate::AppState;
+
+fn ensure_seller(role: &UserRole) -> Result<()> {
+    if *role != UserRole::Seller {
+        return Err(AppError::forbidden("Only sellers can manage cash on delivery"));
+    }
+    Ok(())
+}
+
+// Get the seller's cash on delivery settings
+pub async fn get_settings(
+    State(state): State<Arc<AppState>>,
+    ExtractUserId(user_id): ExtractUserId,
+    ExtractUserRole(role): ExtractUserRole,
+) -> Result<impl IntoResponse> {
+    ensure_seller(&role)?;
+
+    let settings = cod::get_settings(&state.db, user_id).await?;
+    Ok(Json(ApiResponse::success(settings)))
+}
+
+// Opt in or out of cash on delivery
+pub async fn update_settings(
+    State(state): State<Arc<AppState>>,
+    ExtractUserId(user_id): ExtractUserId,
+    ExtractUserRole(role): ExtractUserRole,
+    Json(payload): Json<UpdateCodSettingsRequest>,
+) -> Result<impl IntoResponse> {
+    ensure_seller(&role)?;
+    validation::validate(&payload)?;
+
+    let settings = cod::update_settings(&state.db, user_id, payload).await?;
+    Ok(Json(ApiResponse::success_with_message(settings, "Cash on delivery settings updated")))
+}
+
+// Confirm a cash on delivery hand-over with the buyer's code
+pub async fn confirm_delivery(
+    State(state): State<Arc<AppState>>,
+    ExtractUserId(user_id): ExtractUserId,
+    ExtractUserRole(role): ExtractUserRole,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<ConfirmDeliveryRequest>,
+) -> Result<impl IntoResponse> {
+    validation::validate(&payload)?;
+
+    cod::confirm_delivery(&state.db, id, user_id, &role, payload).await?;
+
+    let mut order = order::get_order_by_id(&state.db, id).await?;
+    order::present_for(&mut order, user_id, &role);
+
+    Ok(Json(ApiResponse::success_with_message(order, "Delivery confirmed and cash collected")))
+}
+
+// Cash on delivery money collected and still outstanding for the seller
+pub async fn get_summary(
+    State(state): State<Arc<AppState>>,
+    ExtractUserId(user_id): ExtractUserId,
+    ExtractUserRole(role): ExtractUserRole,
+) -> Result<impl IntoResponse> {
+    ensure_seller(&role)?;
+
+    let summary = cod::get_summary(&state.db, user_id).await?;
+    Ok(Json(ApiResponse::success(summary)))
+}
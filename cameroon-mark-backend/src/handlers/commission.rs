@@ -0,0 +1,68 @@
+use axum::{
+    extract::{Json, Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::errors::{ApiResponse, Result};
+use crate::middlewares::auth::{ExtractUserId, RequireAdmin};
+use crate::models::commission::{
+    CommissionRuleQuery, CreateCommissionRuleRequest, UpdateCommissionRuleRequest, UpdateSellerTierRequest,
+};
+use crate::services::commission;
+use crate::AppState;
+
+// Commission rules, filter by `category_id` or `active` (admin only)
+pub async fn get_rules(
+    State(state): State<Arc<AppState>>,
+    _: RequireAdmin,
+    Query(query): Query<CommissionRuleQuery>,
+) -> Result<impl IntoResponse> {
+    let rules = commission::get_rules(&state.db, query).await?;
+    Ok(Json(ApiResponse::success(rules)))
+}
+
+// Add a commission rule (admin only)
+pub async fn create_rule(
+    State(state): State<Arc<AppState>>,
+    _: RequireAdmin,
+    ExtractUserId(admin_id): ExtractUserId,
+    Json(payload): Json<CreateCommissionRuleRequest>,
+) -> Result<impl IntoResponse> {
+    let rule = commission::create_rule(&state.db, admin_id, payload).await?;
+    Ok((StatusCode::CREATED, Json(ApiResponse::success(rule))))
+}
+
+// Change a rule's rates or dates for orders placed from now on (admin only)
+pub async fn update_rule(
+    State(state): State<Arc<AppState>>,
+    _: RequireAdmin,
+    Path(rule_id): Path<Uuid>,
+    Json(payload): Json<UpdateCommissionRuleRequest>,
+) -> Result<impl IntoResponse> {
+    let rule = commission::update_rule(&state.db, rule_id, payload).await?;
+    Ok(Json(ApiResponse::success(rule)))
+}
+
+// Stop applying a rule (admin only)
+pub async fn end_rule(
+    State(state): State<Arc<AppState>>,
+    _: RequireAdmin,
+    Path(rule_id): Path<Uuid>,
+) -> Result<impl IntoResponse> {
+    commission::end_rule(&state.db, rule_id).await?;
+    Ok(Json(ApiResponse::success_with_message((), "Commission rule ended")))
+}
+
+// Place a seller in a tier (admin only)
+pub async fn set_seller_tier(
+    State(state): State<Arc<AppState>>,
+    _: RequireAdmin,
+    Path(seller_id): Path<Uuid>,
+    Json(payload): Json<UpdateSellerTierRequest>,
+) -> Result<impl IntoResponse> {
+    let tier = commission::set_seller_tier(&state.db, seller_id, payload.tier).await?;
+    Ok(Json(ApiResponse::success(tier)))
+}
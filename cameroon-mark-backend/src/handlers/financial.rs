@@ -0,0 +1,185 @@
+use axum::{
+    extract::{Json, Path, Query, State},
+    http::header,
+    response::{IntoResponse, Response},
+};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::errors::{AppError, ApiResponse, Result};
+use crate::middlewares::auth::{ExtractUserId, ExtractUserRole, RequireAdmin};
+use crate::models::financial::{
+    CalculateTaxRequest, CreateExpenseRequest, ExpenseQuery, MarkRefundPaidRequest, ProcessPaymentRequest, RefundQuery, RefundRequest,
+    RevenueReportQuery, StatementFormat, StatementQuery,
+};
+use crate::models::user::UserRole;
+use crate::services::{financial, refund, statement};
+use crate::AppState;
+
+// Expenses and revenue are bookkeeping for sellers and the marketplace itself
+fn ensure_seller_or_admin(role: &UserRole) -> Result<()> {
+    match role {
+        UserRole::Seller | UserRole::Admin => Ok(()),
+        _ => Err(AppError::forbidden("Only sellers and admins can access financial records")),
+    }
+}
+
+// Start paying an order through a mobile money provider
+pub async fn process_payment(
+    State(state): State<Arc<AppState>>,
+    ExtractUserId(user_id): ExtractUserId,
+    Json(payload): Json<ProcessPaymentRequest>,
+) -> Result<impl IntoResponse> {
+    let payment = financial::process_payment(&state.db, &state.payment_gateways, user_id, payload).await?;
+
+    Ok(Json(ApiResponse::success(payment)))
+}
+
+// Refund a completed payment, in full or in part (admin only)
+pub async fn process_refund(
+    State(state): State<Arc<AppState>>,
+    _: RequireAdmin,
+    ExtractUserId(admin_id): ExtractUserId,
+    Json(payload): Json<RefundRequest>,
+) -> Result<impl IntoResponse> {
+    let refund = refund::process_refund(&state.db, &state.payment_gateways, admin_id, payload).await?;
+
+    Ok(Json(ApiResponse::success(refund)))
+}
+
+// VAT and delivery on a basket, as checkout would charge them
+pub async fn calculate_taxes(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<CalculateTaxRequest>,
+) -> Result<impl IntoResponse> {
+    let calculation = financial::calculate_taxes(&state.db, payload).await?;
+
+    Ok(Json(ApiResponse::success(calculation)))
+}
+
+// Admins see every refund, buyers the refunds on their own payments
+pub async fn get_refunds(
+    State(state): State<Arc<AppState>>,
+    ExtractUserId(user_id): ExtractUserId,
+    ExtractUserRole(role): ExtractUserRole,
+    Query(query): Query<RefundQuery>,
+) -> Result<impl IntoResponse> {
+    let page = query.page.unwrap_or(1).max(1);
+    let per_page = query.per_page.unwrap_or(20).clamp(1, 50);
+    let buyer_id = (role != UserRole::Admin).then_some(user_id);
+    let (refunds, total) = refund::get_refunds(&state.db, buyer_id, query).await?;
+
+    Ok(Json(ApiResponse::success_with_pagination(refunds, total, page, per_page)))
+}
+
+// Send a failed refund to the provider again (admin only)
+pub async fn retry_refund(
+    State(state): State<Arc<AppState>>,
+    _: RequireAdmin,
+    Path(refund_id): Path<Uuid>,
+) -> Result<impl IntoResponse> {
+    let refund = refund::retry_refund(&state.db, &state.payment_gateways, refund_id).await?;
+
+    Ok(Json(ApiResponse::success(refund)))
+}
+
+// Record a refund paid by hand (admin only)
+pub async fn mark_refund_paid(
+    State(state): State<Arc<AppState>>,
+    _: RequireAdmin,
+    Path(refund_id): Path<Uuid>,
+    Json(payload): Json<MarkRefundPaidRequest>,
+) -> Result<impl IntoResponse> {
+    let refund = refund::mark_refund_paid(&state.db, refund_id, payload).await?;
+
+    Ok(Json(ApiResponse::success_with_message(refund, "Refund marked paid")))
+}
+
+pub async fn track_expense(
+    State(state): State<Arc<AppState>>,
+    ExtractUserId(user_id): ExtractUserId,
+    ExtractUserRole(role): ExtractUserRole,
+    Json(payload): Json<CreateExpenseRequest>,
+) -> Result<impl IntoResponse> {
+    ensure_seller_or_admin(&role)?;
+
+    let expense = financial::track_expense(&state.db, user_id, payload).await?;
+
+    Ok(Json(ApiResponse::success_with_message(expense, "Expense recorded")))
+}
+
+pub async fn get_expenses(
+    State(state): State<Arc<AppState>>,
+    ExtractUserId(user_id): ExtractUserId,
+    ExtractUserRole(role): ExtractUserRole,
+    Query(query): Query<ExpenseQuery>,
+) -> Result<impl IntoResponse> {
+    ensure_seller_or_admin(&role)?;
+
+    let expenses = financial::get_expenses(&state.db, user_id, query.from, query.to, query.category).await?;
+
+    Ok(Json(ApiResponse::success(expenses)))
+}
+
+// Sellers see their own sales, admins the whole marketplace
+pub async fn get_revenue_report(
+    State(state): State<Arc<AppState>>,
+    ExtractUserId(user_id): ExtractUserId,
+    ExtractUserRole(role): ExtractUserRole,
+    Query(query): Query<RevenueReportQuery>,
+) -> Result<impl IntoResponse> {
+    ensure_seller_or_admin(&role)?;
+
+    let seller_id = (role == UserRole::Seller).then_some(user_id);
+    let report = financial::get_revenue_report(&state.db, seller_id, query.from, query.to).await?;
+
+    Ok(Json(ApiResponse::success(report)))
+}
+
+// Sellers see the margin they keep after fees, admins the marketplace's take
+pub async fn get_profit_margins(
+    State(state): State<Arc<AppState>>,
+    ExtractUserId(user_id): ExtractUserId,
+    ExtractUserRole(role): ExtractUserRole,
+    Query(query): Query<RevenueReportQuery>,
+) -> Result<impl IntoResponse> {
+    ensure_seller_or_admin(&role)?;
+
+    let seller_id = (role == UserRole::Seller).then_some(user_id);
+    let margins = financial::get_profit_margins(&state.db, seller_id, query.from, query.to).await?;
+
+    Ok(Json(ApiResponse::success(margins)))
+}
+
+// A seller's income statement, balance sheet and cash flow as JSON, or `format=csv` or `pdf` to download.
+// Sellers get their own, admins name the seller.
+pub async fn get_financial_statement(
+    State(state): State<Arc<AppState>>,
+    ExtractUserId(user_id): ExtractUserId,
+    ExtractUserRole(role): ExtractUserRole,
+    Query(query): Query<StatementQuery>,
+) -> Result<Response> {
+    let seller_id = statement::statement_seller(user_id, &role, query.seller_id)?;
+    let report = statement::generate_financial_statement(&state.db, seller_id, query.from, query.to).await?;
+
+    let filename = format!("statement-{}-{}", query.from, query.to);
+    Ok(match query.format {
+        StatementFormat::Json => Json(ApiResponse::success(report)).into_response(),
+        StatementFormat::Csv => (
+            [
+                (header::CONTENT_TYPE, "text/csv; charset=utf-8".to_string()),
+                (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}.csv\"", filename)),
+            ],
+            statement::statement_csv(&report)?,
+        )
+            .into_response(),
+        StatementFormat::Pdf => (
+            [
+                (header::CONTENT_TYPE, "application/pdf".to_string()),
+                (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}.pdf\"", filename)),
+            ],
+            statement::statement_pdf(&report)?,
+        )
+            .into_response(),
+    })
+}
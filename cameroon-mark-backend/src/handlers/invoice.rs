@@ -0,0 +1,79 @@
+use axum::{
+    extract::{Json, Path, Query, State},
+    http::header,
+    response::IntoResponse,
+};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::errors::{AppError, ApiResponse, Result};
+use crate::middlewares::auth::{ExtractUserId, ExtractUserRole};
+use crate::models::invoice::{InvoiceQuery, UpdateTaxProfileRequest};
+use crate::models::user::UserRole;
+use crate::services::invoice;
+use crate::utils::validation;
+use crate::AppState;
+
+fn ensure_seller(role: &UserRole) -> Result<()> {
+    if *role != UserRole::Seller {
+        return Err(AppError::forbidden("Only sellers have a tax profile"));
+    }
+    Ok(())
+}
+
+// Get the legal name and NIU printed on the seller's invoices
+pub async fn get_tax_profile(
+    State(state): State<Arc<AppState>>,
+    ExtractUserId(user_id): ExtractUserId,
+    ExtractUserRole(role): ExtractUserRole,
+) -> Result<impl IntoResponse> {
+    ensure_seller(&role)?;
+
+    let profile = invoice::get_tax_profile(&state.db, user_id).await?;
+    Ok(Json(ApiResponse::success(profile)))
+}
+
+// Set the legal name and NIU printed on the seller's invoices
+pub async fn update_tax_profile(
+    State(state): State<Arc<AppState>>,
+    ExtractUserId(user_id): ExtractUserId,
+    ExtractUserRole(role): ExtractUserRole,
+    Json(payload): Json<UpdateTaxProfileRequest>,
+) -> Result<impl IntoResponse> {
+    ensure_seller(&role)?;
+    validation::validate(&payload)?;
+
+    let profile = invoice::update_tax_profile(&state.db, user_id, payload).await?;
+    Ok(Json(ApiResponse::success_with_message(profile, "Tax profile updated")))
+}
+
+// Download the PDF invoice for an order, or for one seller's part of it
+pub async fn get_invoice(
+    State(state): State<Arc<AppState>>,
+    ExtractUserId(user_id): ExtractUserId,
+    ExtractUserRole(role): ExtractUserRole,
+    Path(id): Path<Uuid>,
+    Query(query): Query<InvoiceQuery>,
+) -> Result<impl IntoResponse> {
+    let (invoice_number, pdf) = invoice::get_invoice_pdf(
+        &state.db,
+        &state.s3_client,
+        &state.config.minio.bucket,
+        id,
+        user_id,
+        &role,
+        query.seller_order_id,
+    )
+    .await?;
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/pdf".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}.pdf\"", invoice_number),
+            ),
+        ],
+        pdf,
+    ))
+}
@@ -9,6 +9,7 @@ use serde::Deserialize;
 
 use crate::{
     errors::{Result, ApiResponse},
+    models::money::Money,
     models::marketing::{
         CreateCampaignRequest, 
         CreateDiscountCodeRequest, 
@@ -30,7 +31,8 @@ pub struct GenerateDiscountCodeQuery {
 #[derive(Deserialize)]
 pub struct ValidateDiscountCodeQuery {
     code: String,
-    subtotal: f64,
+    // In francs
+    subtotal: i64,
     #[serde(default)]
     products: Vec<Uuid>,
 }
@@ -101,10 +103,10 @@ pub async fn validate_discount_code(
     Query(query): Query<ValidateDiscountCodeQuery>,
 ) -> Result<impl IntoResponse> {
     let code = marketing::validate_discount_code(
-        &state.db, 
+        state.db.as_ref(),
         user_id, 
         &query.code, 
-        query.subtotal, 
+        Money::xaf(query.subtotal),
         query.products
     ).await?;
     Ok(Json(ApiResponse::success(code)))
@@ -10,3 +10,15 @@ pub mod saved_item;
 pub mod analytics;
 pub mod admin;
 pub mod marketing;
+pub mod notification;
+pub mod product_question;
+pub mod wishlist;
+pub mod cod;
+pub mod invoice;
+pub mod payment;
+pub mod financial;
+pub mod wallet;
+pub mod commission;
+pub mod tax;
+pub mod shipping;
+pub mod shipment;
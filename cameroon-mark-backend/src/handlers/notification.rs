@@ -0,0 +1,64 @@
+use axum::{
+    extract::{Json, Path, Query, State},
+    response::IntoResponse,
+};
+use serde::Deserialize;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::errors::{ApiResponse, Result};
+use crate::middlewares::auth::ExtractUserId;
+use crate::models::message_enhancement::UpdateNotificationPreferencesRequest;
+use crate::services::notification;
+use crate::utils::validation;
+use crate::AppState;
+
+#[derive(Deserialize)]
+pub struct NotificationQuery {
+    unread_only: Option<bool>,
+}
+
+// Get notifications for the current user
+pub async fn get_notifications(
+    State(state): State<Arc<AppState>>,
+    ExtractUserId(user_id): ExtractUserId,
+    Query(query): Query<NotificationQuery>,
+) -> Result<impl IntoResponse> {
+    let notifications = notification::get_notifications(
+        &state.db,
+        user_id,
+        query.unread_only.unwrap_or(false),
+    ).await?;
+    Ok(Json(ApiResponse::success(notifications)))
+}
+
+// Mark a notification as read
+pub async fn mark_as_read(
+    State(state): State<Arc<AppState>>,
+    ExtractUserId(user_id): ExtractUserId,
+    Path(notification_id): Path<Uuid>,
+) -> Result<impl IntoResponse> {
+    notification::mark_as_read(&state.db, user_id, notification_id).await?;
+    Ok(Json(ApiResponse::success_with_message((), "Notification marked as read")))
+}
+
+// Get notification channel preferences for the current user
+pub async fn get_preferences(
+    State(state): State<Arc<AppState>>,
+    ExtractUserId(user_id): ExtractUserId,
+) -> Result<impl IntoResponse> {
+    let preferences = notification::get_preferences(state.db.as_ref(), user_id).await?;
+    Ok(Json(ApiResponse::success(preferences)))
+}
+
+// Update notification channel preferences for the current user
+pub async fn update_preferences(
+    State(state): State<Arc<AppState>>,
+    ExtractUserId(user_id): ExtractUserId,
+    Json(payload): Json<UpdateNotificationPreferencesRequest>,
+) -> Result<impl IntoResponse> {
+    validation::validate(&payload)?;
+
+    let preferences = notification::update_preferences(&state.db, user_id, payload).await?;
+    Ok(Json(ApiResponse::success_with_message(preferences, "Notification preferences updated")))
+}
@@ -1,6 +1,6 @@
 use axum::{
-    extract::{Json, Path, State},
-    http::StatusCode,
+    extract::{Json, Path, Query, State},
+    http::{HeaderMap, StatusCode},
     response::IntoResponse,
 };
 use std::sync::Arc;
@@ -8,24 +8,43 @@ use uuid::Uuid;
 
 use crate::errors::{AppError, ApiResponse, Result};
 use crate::middlewares::auth::{ExtractUserId, ExtractUserRole};
-use crate::models::order::{CreateOrderRequest, UpdateOrderStatusRequest};
+use crate::models::order::{BuyAgainQuery, CancelOrderRequest, ConfirmReceiptQuery, CreateOrderRequest, OrderListOptions, OrderResponse, UpdateOrderStatusRequest};
 use crate::models::user::UserRole;
-use crate::services::order;
+use crate::services::{order, order_status, reorder};
 use crate::utils::validation;
 
 // AppState is defined in main.rs
 use crate::AppState;
 
+// Longest Idempotency-Key header value we store
+const MAX_IDEMPOTENCY_KEY_LEN: usize = 100;
+
 pub async fn create_order(
     State(state): State<Arc<AppState>>,
     user_id: ExtractUserId,
+    headers: HeaderMap,
     Json(payload): Json<CreateOrderRequest>,
 ) -> Result<impl IntoResponse> {
     // Validate request payload
     validation::validate(&payload)?;
+
+    // Clients send the same key when retrying so a double tap does not place two orders
+    let idempotency_key = match headers.get("idempotency-key") {
+        Some(value) => {
+            let key = value
+                .to_str()
+                .map_err(|_| AppError::bad_request("Invalid Idempotency-Key header"))?
+                .trim();
+            if key.is_empty() || key.len() > MAX_IDEMPOTENCY_KEY_LEN {
+                return Err(AppError::bad_request("Idempotency-Key must be between 1 and 100 characters"));
+            }
+            Some(key.to_string())
+        }
+        None => None,
+    };
     
     // Create order from cart items
-    let order = order::create_order(&state.db, user_id.0, payload).await?;
+    let order = order::create_order(&state.db, &state.payment_gateways, user_id.0, payload, idempotency_key).await?;
     
     // Return success response with created order
     Ok((StatusCode::CREATED, Json(ApiResponse::success_with_message(
@@ -38,21 +57,25 @@ pub async fn get_orders(
     State(state): State<Arc<AppState>>,
     user_id: ExtractUserId,
     user_role: ExtractUserRole,
+    Query(options): Query<OrderListOptions>,
 ) -> Result<impl IntoResponse> {
+    let page = options.page.unwrap_or(1).max(1);
+    let per_page = options.per_page.unwrap_or(10).clamp(1, 50);
+
     // Get orders based on user role
-    let orders = match user_role.0 {
+    let (orders, total) = match user_role.0 {
         // For customers, get their orders
         UserRole::Customer => {
-            order::get_buyer_orders(&state.db, user_id.0).await?
+            order::get_buyer_orders(&state.db, user_id.0, options).await?
         },
         // For sellers, get orders for their products
         UserRole::Seller => {
-            order::get_seller_orders(&state.db, user_id.0).await?
+            order::get_seller_orders(&state.db, user_id.0, options).await?
         },
         // Admin can see all orders
         UserRole::Admin => {
             // Admins can view all orders in the system
-            order::get_all_orders(&state.db).await?
+            order::get_all_orders(&state.db, options).await?
         },
         // Pending sellers don't have access to orders yet
         UserRole::PendingSeller => {
@@ -61,29 +84,21 @@ pub async fn get_orders(
     };
     
     // Return success response with orders
-    Ok(Json(ApiResponse::success(orders)))
+    Ok(Json(ApiResponse::success_with_pagination(orders, total, page, per_page)))
 }
 
-pub async fn get_order(
-    State(state): State<Arc<AppState>>,
-    user_id: ExtractUserId,
-    user_role: ExtractUserRole,
-    Path(id): Path<Uuid>,
-) -> Result<impl IntoResponse> {
-    // Get order by ID
-    let order = order::get_order_by_id(&state.db, id).await?;
-    
-    // Check if user is authorized to view this order
-    match user_role.0 {
+// Check that the user may see an order
+fn ensure_can_view(order: &OrderResponse, user_id: Uuid, role: &UserRole) -> Result<()> {
+    match role {
         UserRole::Customer => {
             // Customers can only view their own orders
-            if order.buyer_id != user_id.0 {
+            if order.buyer_id != user_id {
                 return Err(AppError::forbidden("You are not authorized to view this order"));
             }
         },
         UserRole::Seller => {
             // Sellers can only view orders that contain their products
-            let is_seller_order = order.items.iter().any(|item| item.seller_id == user_id.0);
+            let is_seller_order = order.items.iter().any(|item| item.seller_id == user_id);
             if !is_seller_order {
                 return Err(AppError::forbidden("You are not authorized to view this order"));
             }
@@ -97,28 +112,66 @@ pub async fn get_order(
             return Err(AppError::forbidden("Pending sellers cannot view orders"));
         },
     }
+
+    Ok(())
+}
+
+pub async fn get_order(
+    State(state): State<Arc<AppState>>,
+    user_id: ExtractUserId,
+    user_role: ExtractUserRole,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse> {
+    // Get order by ID
+    let mut order = order::get_order_by_id(&state.db, id).await?;
+    
+    // Check if user is authorized to view this order
+    ensure_can_view(&order, user_id.0, &user_role.0)?;
+
+    // Sellers only see their own part of the order
+    order::present_for(&mut order, user_id.0, &user_role.0);
     
     // Return success response with order
     Ok(Json(ApiResponse::success(order)))
 }
 
-pub async fn update_order_status(
+pub async fn get_order_history(
     State(state): State<Arc<AppState>>,
     user_id: ExtractUserId,
+    user_role: ExtractUserRole,
     Path(id): Path<Uuid>,
-    Json(payload): Json<UpdateOrderStatusRequest>,
 ) -> Result<impl IntoResponse> {
-    // Get order by ID
     let order = order::get_order_by_id(&state.db, id).await?;
-    
-    // Check if user is authorized to update this order
-    let is_seller_order = order.items.iter().any(|item| item.seller_id == user_id.0);
-    if !is_seller_order {
-        return Err(AppError::forbidden("You are not authorized to update this order"));
+    ensure_can_view(&order, user_id.0, &user_role.0)?;
+
+    let mut history = order_status::get_history(&state.db, id).await?;
+
+    // Sellers only see the order-level entries and their own sub-order
+    if user_role.0 == UserRole::Seller {
+        let own: Vec<Uuid> = order
+            .seller_orders
+            .iter()
+            .filter(|so| so.seller_id == user_id.0)
+            .map(|so| so.id)
+            .collect();
+        history.retain(|entry| entry.seller_order_id.is_none_or(|id| own.contains(&id)));
     }
-    
-    // Update order status
-    let updated_order = order::update_order_status(&state.db, id, payload.status).await?;
+
+    Ok(Json(ApiResponse::success(history)))
+}
+
+pub async fn update_order_status(
+    State(state): State<Arc<AppState>>,
+    user_id: ExtractUserId,
+    user_role: ExtractUserRole,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<UpdateOrderStatusRequest>,
+) -> Result<impl IntoResponse> {
+    validation::validate(&payload)?;
+
+    // Sellers only update their own part of the order
+    let mut updated_order = order::update_order_status(&state.db, id, user_id.0, &user_role.0, payload).await?;
+    order::present_for(&mut updated_order, user_id.0, &user_role.0);
     
     // Return success response with updated order
     Ok(Json(ApiResponse::success_with_message(
@@ -126,3 +179,60 @@ pub async fn update_order_status(
         "Order status updated successfully",
     )))
 }
+
+// The buyer confirms their parcels arrived
+pub async fn confirm_receipt(
+    State(state): State<Arc<AppState>>,
+    user_id: ExtractUserId,
+    Path(id): Path<Uuid>,
+    Query(query): Query<ConfirmReceiptQuery>,
+) -> Result<impl IntoResponse> {
+    let mut order = order::confirm_receipt(&state.db, id, user_id.0, query).await?;
+    order::present_for(&mut order, user_id.0, &UserRole::Customer);
+
+    Ok(Json(ApiResponse::success_with_message(order, "Delivery confirmed")))
+}
+
+pub async fn cancel_order(
+    State(state): State<Arc<AppState>>,
+    user_id: ExtractUserId,
+    user_role: ExtractUserRole,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<CancelOrderRequest>,
+) -> Result<impl IntoResponse> {
+    validation::validate(&payload)?;
+
+    let mut order = order::cancel_order(&state.db, id, user_id.0, &user_role.0, payload).await?;
+    order::present_for(&mut order, user_id.0, &user_role.0);
+
+    Ok(Json(ApiResponse::success_with_message(order, "Order canceled")))
+}
+
+// Copy the items of a past order back into the cart at current prices
+pub async fn reorder(
+    State(state): State<Arc<AppState>>,
+    user_id: ExtractUserId,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse> {
+    let result = reorder::reorder(&state.db, id, user_id.0).await?;
+
+    let message = if result.added_items == 0 {
+        "None of the items from this order can be bought right now"
+    } else {
+        "Items added to your cart"
+    };
+    Ok(Json(ApiResponse::success_with_message(result, message)))
+}
+
+// Products the buyer has ordered before
+pub async fn get_buy_again(
+    State(state): State<Arc<AppState>>,
+    user_id: ExtractUserId,
+    Query(query): Query<BuyAgainQuery>,
+) -> Result<impl IntoResponse> {
+    let page = query.page.unwrap_or(1).max(1);
+    let per_page = query.per_page.unwrap_or(20).clamp(1, 50);
+
+    let (items, total) = reorder::get_buy_again(&state.db, user_id.0, page, per_page).await?;
+    Ok(Json(ApiResponse::success_with_pagination(items, total, page, per_page)))
+}
@@ -0,0 +1,110 @@
+use axum::{
+    extract::{Json, Query, State},
+    http::HeaderMap,
+    response::IntoResponse,
+};
+use chrono::{Duration, Utc};
+use std::sync::Arc;
+
+use crate::errors::{ApiResponse, Result};
+use crate::middlewares::auth::RequireAdmin;
+use crate::models::financial::{
+    ImportSettlementsRequest, PaymentProcessor, ReconciliationQuery, WebhookAck, WebhookQuery,
+};
+use crate::models::order::PaymentMethod;
+use crate::services::payment_reconciliation;
+use crate::services::payment_webhook::{self, RawCallback};
+use crate::utils::validation;
+use crate::AppState;
+
+fn headers_to_json(headers: &HeaderMap) -> serde_json::Value {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            (
+                name.to_string(),
+                serde_json::Value::String(value.to_str().unwrap_or_default().to_string()),
+            )
+        })
+        .collect::<serde_json::Map<_, _>>()
+        .into()
+}
+
+async fn receive(
+    state: &AppState,
+    method: PaymentMethod,
+    processor: PaymentProcessor,
+    url_token: Option<String>,
+    headers: HeaderMap,
+    body: String,
+) -> Result<impl IntoResponse> {
+    let gateway = state.payment_gateways.for_method(&method)?;
+
+    let callback = RawCallback {
+        processor,
+        url_token,
+        headers: headers_to_json(&headers),
+        body,
+    };
+    let outcome = payment_webhook::handle_callback(&state.db, gateway.as_ref(), callback).await?;
+
+    Ok(Json(ApiResponse::success(WebhookAck { outcome })))
+}
+
+// Request-to-pay result from MTN MoMo
+pub async fn mtn_webhook(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<WebhookQuery>,
+    headers: HeaderMap,
+    body: String,
+) -> Result<impl IntoResponse> {
+    receive(&state, PaymentMethod::Mtn, PaymentProcessor::MtnMobileMoney, query.token, headers, body).await
+}
+
+// Web payment notification from Orange Money
+pub async fn orange_webhook(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    body: String,
+) -> Result<impl IntoResponse> {
+    receive(&state, PaymentMethod::Orange, PaymentProcessor::OrangeMoney, None, headers, body).await
+}
+
+// Upload a provider's settlement report for reconciliation
+pub async fn import_settlements(
+    State(state): State<Arc<AppState>>,
+    _: RequireAdmin,
+    Json(payload): Json<ImportSettlementsRequest>,
+) -> Result<impl IntoResponse> {
+    validation::validate(&payload)?;
+
+    let result = payment_reconciliation::import_settlements(&state.db, payload).await?;
+
+    Ok(Json(ApiResponse::success(result)))
+}
+
+// Reconciliation reports for a day, yesterday by default
+pub async fn get_reconciliation_reports(
+    State(state): State<Arc<AppState>>,
+    _: RequireAdmin,
+    Query(query): Query<ReconciliationQuery>,
+) -> Result<impl IntoResponse> {
+    let date = query.date.unwrap_or_else(|| (Utc::now() - Duration::days(1)).date_naive());
+
+    let reports = payment_reconciliation::get_reports(&state.db, date).await?;
+
+    Ok(Json(ApiResponse::success(reports)))
+}
+
+// Rebuild a day's reports, e.g. after a late settlement report was uploaded
+pub async fn run_reconciliation(
+    State(state): State<Arc<AppState>>,
+    _: RequireAdmin,
+    Query(query): Query<ReconciliationQuery>,
+) -> Result<impl IntoResponse> {
+    let date = query.date.unwrap_or_else(|| (Utc::now() - Duration::days(1)).date_naive());
+
+    let reports = payment_reconciliation::build_daily_report(&state.db, date).await?;
+
+    Ok(Json(ApiResponse::success(reports)))
+}
@@ -1,6 +1,6 @@
 use axum::{
     extract::{Path, Query, State},
-    http::StatusCode,
+    http::{HeaderMap, StatusCode},
     Json,
 };
 use std::sync::Arc;
@@ -8,9 +8,9 @@ use uuid::Uuid;
 
 use crate::{
     errors::{Result, ApiResponse},
-    models::product::{CreateProductRequest, UpdateProductRequest, ProductFilterOptions, ProductResponse},
-    services::product,
-    middlewares::auth::ExtractUserId,
+    models::product::{CreateProductRequest, UpdateProductRequest, ProductFilterOptions, ProductResponse, RecentlyViewedQuery},
+    services::{product, product_view},
+    middlewares::auth::{ExtractUserId, MaybeUserId},
     utils::validation,
     AppState,
 };
@@ -27,12 +27,44 @@ pub async fn get_products(
 
 pub async fn get_product(
     State(state): State<Arc<AppState>>,
+    MaybeUserId(user_id): MaybeUserId,
+    headers: HeaderMap,
     Path(product_id): Path<Uuid>,
 ) -> Result<Json<ProductResponse>> {
     let product = product::get_product_by_id(&state.db, product_id).await?;
+
+    // Anonymous visitors are identified by the session id the client sends, a UUID it keeps
+    let session_id = headers
+        .get("X-Session-Id")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| Uuid::parse_str(value.trim()).ok())
+        .map(|id| id.to_string());
+
+    // Sellers looking at their own listings are not counted
+    if user_id != Some(product.seller_id) {
+        let db = state.db.clone();
+        tokio::spawn(async move {
+            if let Err(e) = product_view::record_view(&db, product_id, user_id, session_id).await {
+                tracing::warn!("Failed to record view for product {}: {:?}", product_id, e);
+            }
+        });
+    }
+
     Ok(Json(ProductResponse::from(product)))
 }
 
+pub async fn get_recently_viewed(
+    State(state): State<Arc<AppState>>,
+    ExtractUserId(user_id): ExtractUserId,
+    Query(params): Query<RecentlyViewedQuery>,
+) -> Result<Json<ApiResponse<Vec<ProductResponse>>>> {
+    let limit = params.limit.unwrap_or(20).clamp(1, 50);
+    let products = product_view::get_recently_viewed(&state.db, user_id, limit).await?;
+    Ok(Json(ApiResponse::success(
+        products.into_iter().map(ProductResponse::from).collect()
+    )))
+}
+
 pub async fn create_product(
     State(state): State<Arc<AppState>>,
     ExtractUserId(seller_id): ExtractUserId,
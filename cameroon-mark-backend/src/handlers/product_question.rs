@@ -0,0 +1,88 @@
+use axum::{
+    extract::{Json, Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::errors::{ApiResponse, Result};
+use crate::middlewares::auth::ExtractUserId;
+use crate::models::product_question::{AnswerQuestionRequest, AskQuestionRequest, QuestionListOptions};
+use crate::services::product_question;
+use crate::utils::validation;
+use crate::AppState;
+
+// Get public questions and answers for a product
+pub async fn get_product_questions(
+    State(state): State<Arc<AppState>>,
+    Path(product_id): Path<Uuid>,
+    Query(options): Query<QuestionListOptions>,
+) -> Result<impl IntoResponse> {
+    let page = options.page.unwrap_or(1).max(1);
+    let per_page = options.per_page.unwrap_or(10).clamp(1, 50);
+    let (questions, total) = product_question::get_product_questions(&state.db, product_id, options).await?;
+    Ok(Json(ApiResponse::success_with_pagination(questions, total, page, per_page)))
+}
+
+// Ask a question about a product
+pub async fn ask_question(
+    State(state): State<Arc<AppState>>,
+    ExtractUserId(user_id): ExtractUserId,
+    Path(product_id): Path<Uuid>,
+    Json(payload): Json<AskQuestionRequest>,
+) -> Result<impl IntoResponse> {
+    validation::validate(&payload)?;
+
+    let question = product_question::ask_question(&state.db, user_id, product_id, payload).await?;
+    Ok((StatusCode::CREATED, Json(ApiResponse::success_with_message(
+        question,
+        "Question posted",
+    ))))
+}
+
+// Answer a question
+pub async fn answer_question(
+    State(state): State<Arc<AppState>>,
+    ExtractUserId(user_id): ExtractUserId,
+    Path(question_id): Path<Uuid>,
+    Json(payload): Json<AnswerQuestionRequest>,
+) -> Result<impl IntoResponse> {
+    validation::validate(&payload)?;
+
+    let answer = product_question::answer_question(&state.db, user_id, question_id, payload).await?;
+    Ok((StatusCode::CREATED, Json(ApiResponse::success_with_message(
+        answer,
+        "Answer posted",
+    ))))
+}
+
+// Delete one of your own questions
+pub async fn delete_question(
+    State(state): State<Arc<AppState>>,
+    ExtractUserId(user_id): ExtractUserId,
+    Path(question_id): Path<Uuid>,
+) -> Result<impl IntoResponse> {
+    product_question::delete_question(&state.db, user_id, question_id).await?;
+    Ok(Json(ApiResponse::success_with_message((), "Question deleted")))
+}
+
+// Upvote an answer
+pub async fn upvote_answer(
+    State(state): State<Arc<AppState>>,
+    ExtractUserId(user_id): ExtractUserId,
+    Path(answer_id): Path<Uuid>,
+) -> Result<impl IntoResponse> {
+    product_question::upvote_answer(&state.db, user_id, answer_id).await?;
+    Ok(Json(ApiResponse::success_with_message((), "Answer upvoted")))
+}
+
+// Remove an upvote from an answer
+pub async fn remove_upvote(
+    State(state): State<Arc<AppState>>,
+    ExtractUserId(user_id): ExtractUserId,
+    Path(answer_id): Path<Uuid>,
+) -> Result<impl IntoResponse> {
+    product_question::remove_upvote(&state.db, user_id, answer_id).await?;
+    Ok(Json(ApiResponse::success_with_message((), "Upvote removed")))
+}
@@ -8,7 +8,7 @@ use uuid::Uuid;
 
 use crate::errors::{AppError, ApiResponse, Result};
 use crate::middlewares::auth::ExtractUserId;
-use crate::models::saved_item::AddSavedItemRequest;
+use crate::models::saved_item::{AddSavedItemRequest, UpdateSavedItemAlertsRequest};
 use crate::services::saved_item;
 use crate::utils::validation;
 use crate::AppState;
@@ -45,4 +45,17 @@ pub async fn remove_saved_item(
 ) -> Result<impl IntoResponse> {
     saved_item::remove_saved_item(&state.db, user_id, product_id).await?;
     Ok(Json(ApiResponse::success_with_message((), "Product removed from saved items")))
-}
\ No newline at end of file
+}
+
+// Turn price and stock alerts on or off for a saved product
+pub async fn update_alerts(
+    State(state): State<Arc<AppState>>,
+    ExtractUserId(user_id): ExtractUserId,
+    Path(product_id): Path<Uuid>,
+    Json(payload): Json<UpdateSavedItemAlertsRequest>,
+) -> Result<impl IntoResponse> {
+    validation::validate(&payload)?;
+
+    saved_item::update_alerts(&state.db, user_id, product_id, payload).await?;
+    Ok(Json(ApiResponse::success_with_message((), "Saved item alerts updated")))
+}
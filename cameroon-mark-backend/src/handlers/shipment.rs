@@ -0,0 +1,118 @@
+use axum::{
+    extract::{Json, Path, Query, State},
+    http::{header, StatusCode},
+    response::IntoResponse,
+};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::errors::{ApiResponse, Result};
+use crate::middlewares::auth::{ExtractUserId, ExtractUserRole};
+use crate::models::shipment::{CreateShipmentRequest, ShipmentQuery, TrackingWebhookQuery};
+use crate::services::shipment;
+use crate::AppState;
+
+// Book the seller's part of an order with a carrier and print its label (sellers and admins)
+pub async fn create_shipment(
+    State(state): State<Arc<AppState>>,
+    ExtractUserId(user_id): ExtractUserId,
+    ExtractUserRole(role): ExtractUserRole,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<CreateShipmentRequest>,
+) -> Result<impl IntoResponse> {
+    let shipment = shipment::create_shipment(
+        &state.db,
+        &state.s3_client,
+        &state.config.minio.bucket,
+        &state.shipping_carriers,
+        id,
+        user_id,
+        &role,
+        payload,
+    )
+    .await?;
+    Ok((StatusCode::CREATED, Json(ApiResponse::success(shipment))))
+}
+
+// Where the parcel for an order is, with its tracking history
+pub async fn get_shipment(
+    State(state): State<Arc<AppState>>,
+    ExtractUserId(user_id): ExtractUserId,
+    ExtractUserRole(role): ExtractUserRole,
+    Path(id): Path<Uuid>,
+    Query(query): Query<ShipmentQuery>,
+) -> Result<impl IntoResponse> {
+    let shipment = shipment::get_shipment(
+        &state.db,
+        &state.shipping_carriers,
+        id,
+        user_id,
+        &role,
+        query.seller_order_id,
+    )
+    .await?;
+    Ok(Json(ApiResponse::success(shipment)))
+}
+
+// Call off a parcel the carrier has not picked up yet (sellers and admins)
+pub async fn cancel_shipment(
+    State(state): State<Arc<AppState>>,
+    ExtractUserId(user_id): ExtractUserId,
+    ExtractUserRole(role): ExtractUserRole,
+    Path(id): Path<Uuid>,
+    Query(query): Query<ShipmentQuery>,
+) -> Result<impl IntoResponse> {
+    shipment::cancel_shipment(
+        &state.db,
+        &state.shipping_carriers,
+        id,
+        user_id,
+        &role,
+        query.seller_order_id,
+    )
+    .await?;
+    Ok(Json(ApiResponse::success_with_message((), "Shipment canceled")))
+}
+
+// Download the shipping label as a PDF (sellers and admins)
+pub async fn get_label(
+    State(state): State<Arc<AppState>>,
+    ExtractUserId(user_id): ExtractUserId,
+    ExtractUserRole(role): ExtractUserRole,
+    Path(id): Path<Uuid>,
+    Query(query): Query<ShipmentQuery>,
+) -> Result<impl IntoResponse> {
+    let (tracking_number, pdf) = shipment::get_label_pdf(
+        &state.db,
+        &state.s3_client,
+        &state.config.minio.bucket,
+        &state.shipping_carriers,
+        id,
+        user_id,
+        &role,
+        query.seller_order_id,
+    )
+    .await?;
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/pdf".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"label-{}.pdf\"", tracking_number),
+            ),
+        ],
+        pdf,
+    ))
+}
+
+// Tracking events pushed by a carrier
+pub async fn tracking_webhook(
+    State(state): State<Arc<AppState>>,
+    Path(carrier): Path<String>,
+    Query(query): Query<TrackingWebhookQuery>,
+    Json(payload): Json<serde_json::Value>,
+) -> Result<impl IntoResponse> {
+    let ack = shipment::handle_webhook(&state.db, &state.shipping_carriers, &carrier, query.token, payload).await?;
+    Ok(Json(ApiResponse::success(ack)))
+}
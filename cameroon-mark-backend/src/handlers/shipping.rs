@@ -0,0 +1,86 @@
+use axum::{
+    extract::{Json, Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::errors::{ApiResponse, AppError, Result};
+use crate::middlewares::auth::{ExtractUserId, ExtractUserRole};
+use crate::models::shipping::{
+    CreateShippingRateRequest, ShippingRateQuery, UpdateShippingRateRequest, UpdateShippingSettingsRequest,
+};
+use crate::services::shipping;
+use crate::AppState;
+
+// Shipping rates: a seller's own, or the marketplace defaults for admins and with `defaults=true`
+pub async fn get_rates(
+    State(state): State<Arc<AppState>>,
+    ExtractUserId(user_id): ExtractUserId,
+    ExtractUserRole(role): ExtractUserRole,
+    Query(query): Query<ShippingRateQuery>,
+) -> Result<impl IntoResponse> {
+    let owner = shipping::rate_owner(user_id, &role)?;
+    let rates = shipping::get_rates(&state.db, owner, query).await?;
+    Ok(Json(ApiResponse::success(rates)))
+}
+
+// Add a rate for a zone and weight bracket (sellers and admins)
+pub async fn create_rate(
+    State(state): State<Arc<AppState>>,
+    ExtractUserId(user_id): ExtractUserId,
+    ExtractUserRole(role): ExtractUserRole,
+    Json(payload): Json<CreateShippingRateRequest>,
+) -> Result<impl IntoResponse> {
+    let owner = shipping::rate_owner(user_id, &role)?;
+    let rate = shipping::create_rate(&state.db, owner, payload).await?;
+    Ok((StatusCode::CREATED, Json(ApiResponse::success(rate))))
+}
+
+pub async fn update_rate(
+    State(state): State<Arc<AppState>>,
+    ExtractUserId(user_id): ExtractUserId,
+    ExtractUserRole(role): ExtractUserRole,
+    Path(rate_id): Path<Uuid>,
+    Json(payload): Json<UpdateShippingRateRequest>,
+) -> Result<impl IntoResponse> {
+    let owner = shipping::rate_owner(user_id, &role)?;
+    let rate = shipping::update_rate(&state.db, owner, rate_id, payload).await?;
+    Ok(Json(ApiResponse::success(rate)))
+}
+
+pub async fn remove_rate(
+    State(state): State<Arc<AppState>>,
+    ExtractUserId(user_id): ExtractUserId,
+    ExtractUserRole(role): ExtractUserRole,
+    Path(rate_id): Path<Uuid>,
+) -> Result<impl IntoResponse> {
+    let owner = shipping::rate_owner(user_id, &role)?;
+    shipping::remove_rate(&state.db, owner, rate_id).await?;
+    Ok(Json(ApiResponse::success_with_message((), "Shipping rate removed")))
+}
+
+// Where the seller ships from and whether buyers can collect in store (sellers only)
+pub async fn get_settings(
+    State(state): State<Arc<AppState>>,
+    ExtractUserId(user_id): ExtractUserId,
+    ExtractUserRole(role): ExtractUserRole,
+) -> Result<impl IntoResponse> {
+    let seller_id = shipping::rate_owner(user_id, &role)?
+        .ok_or_else(|| AppError::forbidden("Only sellers have shipping settings"))?;
+    let settings = shipping::get_settings(&state.db, seller_id).await?;
+    Ok(Json(ApiResponse::success(settings)))
+}
+
+pub async fn update_settings(
+    State(state): State<Arc<AppState>>,
+    ExtractUserId(user_id): ExtractUserId,
+    ExtractUserRole(role): ExtractUserRole,
+    Json(payload): Json<UpdateShippingSettingsRequest>,
+) -> Result<impl IntoResponse> {
+    let seller_id = shipping::rate_owner(user_id, &role)?
+        .ok_or_else(|| AppError::forbidden("Only sellers have shipping settings"))?;
+    let settings = shipping::update_settings(&state.db, seller_id, payload).await?;
+    Ok(Json(ApiResponse::success(settings)))
+}
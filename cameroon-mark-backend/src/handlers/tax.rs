@@ -0,0 +1,55 @@
+use axum::{
+    extract::{Json, Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::errors::{ApiResponse, Result};
+use crate::middlewares::auth::{ExtractUserId, RequireAdmin};
+use crate::models::tax::{CreateTaxRuleRequest, TaxRuleQuery, UpdateTaxRuleRequest};
+use crate::services::tax;
+use crate::AppState;
+
+// VAT rules, filter by `category_id` or `active` (admin only)
+pub async fn get_rules(
+    State(state): State<Arc<AppState>>,
+    _: RequireAdmin,
+    Query(query): Query<TaxRuleQuery>,
+) -> Result<impl IntoResponse> {
+    let rules = tax::get_rules(&state.db, query).await?;
+    Ok(Json(ApiResponse::success(rules)))
+}
+
+// Add a VAT rule or a category exemption (admin only)
+pub async fn create_rule(
+    State(state): State<Arc<AppState>>,
+    _: RequireAdmin,
+    ExtractUserId(admin_id): ExtractUserId,
+    Json(payload): Json<CreateTaxRuleRequest>,
+) -> Result<impl IntoResponse> {
+    let rule = tax::create_rule(&state.db, admin_id, payload).await?;
+    Ok((StatusCode::CREATED, Json(ApiResponse::success(rule))))
+}
+
+// Change a rule for orders placed from now on (admin only)
+pub async fn update_rule(
+    State(state): State<Arc<AppState>>,
+    _: RequireAdmin,
+    Path(rule_id): Path<Uuid>,
+    Json(payload): Json<UpdateTaxRuleRequest>,
+) -> Result<impl IntoResponse> {
+    let rule = tax::update_rule(&state.db, rule_id, payload).await?;
+    Ok(Json(ApiResponse::success(rule)))
+}
+
+// Stop applying a rule (admin only)
+pub async fn end_rule(
+    State(state): State<Arc<AppState>>,
+    _: RequireAdmin,
+    Path(rule_id): Path<Uuid>,
+) -> Result<impl IntoResponse> {
+    tax::end_rule(&state.db, rule_id).await?;
+    Ok(Json(ApiResponse::success_with_message((), "Tax rule ended")))
+}
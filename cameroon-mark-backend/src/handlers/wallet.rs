@@ -0,0 +1,130 @@
+use axum::{
+    extract::{Json, Path, Query, State},
+    response::IntoResponse,
+};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::errors::{AppError, ApiResponse, Result};
+use crate::middlewares::auth::{ExtractUserId, ExtractUserRole, RequireAdmin};
+use crate::models::ledger::{
+    CreateWithdrawalRequest, MarkWithdrawalPaidRequest, RejectWithdrawalRequest, WalletHistoryQuery, WithdrawalQuery,
+};
+use crate::models::user::UserRole;
+use crate::services::{ledger, withdrawal};
+use crate::AppState;
+
+fn ensure_seller(role: &UserRole) -> Result<()> {
+    if *role != UserRole::Seller {
+        return Err(AppError::forbidden("Only sellers have a wallet"));
+    }
+    Ok(())
+}
+
+// What the marketplace holds for the seller: available, in escrow and being withdrawn
+pub async fn get_balance(
+    State(state): State<Arc<AppState>>,
+    ExtractUserId(user_id): ExtractUserId,
+    ExtractUserRole(role): ExtractUserRole,
+) -> Result<impl IntoResponse> {
+    ensure_seller(&role)?;
+
+    let balance = ledger::get_wallet_balance(&state.db, user_id).await?;
+    Ok(Json(ApiResponse::success(balance)))
+}
+
+// Movements on the seller's accounts, newest first
+pub async fn get_transactions(
+    State(state): State<Arc<AppState>>,
+    ExtractUserId(user_id): ExtractUserId,
+    ExtractUserRole(role): ExtractUserRole,
+    Query(query): Query<WalletHistoryQuery>,
+) -> Result<impl IntoResponse> {
+    ensure_seller(&role)?;
+
+    let page = query.page.unwrap_or(1).max(1);
+    let per_page = query.per_page.unwrap_or(20).clamp(1, 50);
+    let (entries, total) = ledger::get_wallet_history(&state.db, user_id, query).await?;
+
+    Ok(Json(ApiResponse::success_with_pagination(entries, total, page, per_page)))
+}
+
+// The seller's withdrawals, newest first
+pub async fn get_withdrawals(
+    State(state): State<Arc<AppState>>,
+    ExtractUserId(user_id): ExtractUserId,
+    ExtractUserRole(role): ExtractUserRole,
+    Query(query): Query<WithdrawalQuery>,
+) -> Result<impl IntoResponse> {
+    ensure_seller(&role)?;
+
+    let page = query.page.unwrap_or(1).max(1);
+    let per_page = query.per_page.unwrap_or(20).clamp(1, 50);
+    let (withdrawals, total) = withdrawal::get_withdrawals(&state.db, Some(user_id), query).await?;
+
+    Ok(Json(ApiResponse::success_with_pagination(withdrawals, total, page, per_page)))
+}
+
+// Ask for wallet money to be sent to a Mobile Money account
+pub async fn request_withdrawal(
+    State(state): State<Arc<AppState>>,
+    ExtractUserId(user_id): ExtractUserId,
+    ExtractUserRole(role): ExtractUserRole,
+    Json(payload): Json<CreateWithdrawalRequest>,
+) -> Result<impl IntoResponse> {
+    ensure_seller(&role)?;
+
+    let withdrawal = withdrawal::request_withdrawal(&state.db, user_id, payload).await?;
+    Ok(Json(ApiResponse::success_with_message(
+        withdrawal,
+        "Withdrawal requested, it will be paid once approved",
+    )))
+}
+
+// Every seller's withdrawals (admin only)
+pub async fn list_withdrawals(
+    State(state): State<Arc<AppState>>,
+    _: RequireAdmin,
+    Query(query): Query<WithdrawalQuery>,
+) -> Result<impl IntoResponse> {
+    let page = query.page.unwrap_or(1).max(1);
+    let per_page = query.per_page.unwrap_or(20).clamp(1, 50);
+    let (withdrawals, total) = withdrawal::get_withdrawals(&state.db, None, query).await?;
+
+    Ok(Json(ApiResponse::success_with_pagination(withdrawals, total, page, per_page)))
+}
+
+// Approve a withdrawal, sending it through the provider when it supports transfers (admin only)
+pub async fn approve_withdrawal(
+    State(state): State<Arc<AppState>>,
+    _: RequireAdmin,
+    ExtractUserId(admin_id): ExtractUserId,
+    Path(withdrawal_id): Path<Uuid>,
+) -> Result<impl IntoResponse> {
+    let withdrawal =
+        withdrawal::approve_withdrawal(&state.db, &state.payment_gateways, admin_id, withdrawal_id).await?;
+    Ok(Json(ApiResponse::success(withdrawal)))
+}
+
+// Turn a withdrawal down, returning the money to the seller's wallet (admin only)
+pub async fn reject_withdrawal(
+    State(state): State<Arc<AppState>>,
+    _: RequireAdmin,
+    ExtractUserId(admin_id): ExtractUserId,
+    Path(withdrawal_id): Path<Uuid>,
+    Json(payload): Json<RejectWithdrawalRequest>,
+) -> Result<impl IntoResponse> {
+    let withdrawal = withdrawal::reject_withdrawal(&state.db, admin_id, withdrawal_id, payload).await?;
+    Ok(Json(ApiResponse::success(withdrawal)))
+}
+
+// Record a withdrawal paid by hand (admin only)
+pub async fn mark_withdrawal_paid(
+    State(state): State<Arc<AppState>>,
+    _: RequireAdmin,
+    Path(withdrawal_id): Path<Uuid>,
+    Json(payload): Json<MarkWithdrawalPaidRequest>,
+) -> Result<impl IntoResponse> {
+    let withdrawal = withdrawal::mark_withdrawal_paid(&state.db, withdrawal_id, payload).await?;
+    Ok(Json(ApiResponse::success(withdrawal)))
+}
@@ -0,0 +1,145 @@
+use axum::{
+    extract::{Json, Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::errors::{ApiResponse, Result};
+use crate::middlewares::auth::ExtractUserId;
+use crate::models::wishlist::{
+    AddWishlistItemRequest, CreateWishlistRequest, MoveWishlistItemRequest, UpdateWishlistRequest,
+    WishlistToCartRequest,
+};
+use crate::services::wishlist;
+use crate::utils::validation;
+use crate::AppState;
+
+// Get all wishlists of the current user
+pub async fn get_wishlists(
+    State(state): State<Arc<AppState>>,
+    ExtractUserId(user_id): ExtractUserId,
+) -> Result<impl IntoResponse> {
+    let wishlists = wishlist::get_wishlists(&state.db, user_id).await?;
+    Ok(Json(ApiResponse::success(wishlists)))
+}
+
+// Create a named wishlist
+pub async fn create_wishlist(
+    State(state): State<Arc<AppState>>,
+    ExtractUserId(user_id): ExtractUserId,
+    Json(payload): Json<CreateWishlistRequest>,
+) -> Result<impl IntoResponse> {
+    validation::validate(&payload)?;
+
+    let wishlist = wishlist::create_wishlist(&state.db, user_id, payload).await?;
+    Ok((StatusCode::CREATED, Json(ApiResponse::success_with_message(
+        wishlist,
+        "Wishlist created",
+    ))))
+}
+
+// Get a wishlist with its items
+pub async fn get_wishlist(
+    State(state): State<Arc<AppState>>,
+    ExtractUserId(user_id): ExtractUserId,
+    Path(wishlist_id): Path<Uuid>,
+) -> Result<impl IntoResponse> {
+    let wishlist = wishlist::get_wishlist(&state.db, user_id, wishlist_id).await?;
+    Ok(Json(ApiResponse::success(wishlist)))
+}
+
+// Rename a wishlist or change its visibility
+pub async fn update_wishlist(
+    State(state): State<Arc<AppState>>,
+    ExtractUserId(user_id): ExtractUserId,
+    Path(wishlist_id): Path<Uuid>,
+    Json(payload): Json<UpdateWishlistRequest>,
+) -> Result<impl IntoResponse> {
+    validation::validate(&payload)?;
+
+    let wishlist = wishlist::update_wishlist(&state.db, user_id, wishlist_id, payload).await?;
+    Ok(Json(ApiResponse::success_with_message(wishlist, "Wishlist updated")))
+}
+
+// Delete a wishlist
+pub async fn delete_wishlist(
+    State(state): State<Arc<AppState>>,
+    ExtractUserId(user_id): ExtractUserId,
+    Path(wishlist_id): Path<Uuid>,
+) -> Result<impl IntoResponse> {
+    wishlist::delete_wishlist(&state.db, user_id, wishlist_id).await?;
+    Ok(Json(ApiResponse::success_with_message((), "Wishlist deleted")))
+}
+
+// Generate a new share link for a wishlist
+pub async fn regenerate_share_link(
+    State(state): State<Arc<AppState>>,
+    ExtractUserId(user_id): ExtractUserId,
+    Path(wishlist_id): Path<Uuid>,
+) -> Result<impl IntoResponse> {
+    let wishlist = wishlist::regenerate_share_link(&state.db, user_id, wishlist_id).await?;
+    Ok(Json(ApiResponse::success_with_message(wishlist, "Share link regenerated")))
+}
+
+// Add a product to a wishlist
+pub async fn add_item(
+    State(state): State<Arc<AppState>>,
+    ExtractUserId(user_id): ExtractUserId,
+    Path(wishlist_id): Path<Uuid>,
+    Json(payload): Json<AddWishlistItemRequest>,
+) -> Result<impl IntoResponse> {
+    validation::validate(&payload)?;
+
+    let item = wishlist::add_item(&state.db, user_id, wishlist_id, payload).await?;
+    Ok((StatusCode::CREATED, Json(ApiResponse::success_with_message(
+        item,
+        "Product added to wishlist",
+    ))))
+}
+
+// Remove a product from a wishlist
+pub async fn remove_item(
+    State(state): State<Arc<AppState>>,
+    ExtractUserId(user_id): ExtractUserId,
+    Path((wishlist_id, product_id)): Path<(Uuid, Uuid)>,
+) -> Result<impl IntoResponse> {
+    wishlist::remove_item(&state.db, user_id, wishlist_id, product_id).await?;
+    Ok(Json(ApiResponse::success_with_message((), "Product removed from wishlist")))
+}
+
+// Move a product to another wishlist
+pub async fn move_item(
+    State(state): State<Arc<AppState>>,
+    ExtractUserId(user_id): ExtractUserId,
+    Path((wishlist_id, product_id)): Path<(Uuid, Uuid)>,
+    Json(payload): Json<MoveWishlistItemRequest>,
+) -> Result<impl IntoResponse> {
+    validation::validate(&payload)?;
+
+    let item = wishlist::move_item(&state.db, user_id, wishlist_id, product_id, payload).await?;
+    Ok(Json(ApiResponse::success_with_message(item, "Product moved")))
+}
+
+// Add a product from a wishlist to the cart
+pub async fn add_item_to_cart(
+    State(state): State<Arc<AppState>>,
+    ExtractUserId(user_id): ExtractUserId,
+    Path((wishlist_id, product_id)): Path<(Uuid, Uuid)>,
+    Json(payload): Json<WishlistToCartRequest>,
+) -> Result<impl IntoResponse> {
+    validation::validate(&payload)?;
+
+    wishlist::add_item_to_cart(&state.db, user_id, wishlist_id, product_id, payload).await?;
+    Ok(Json(ApiResponse::success_with_message((), "Product added to cart")))
+}
+
+// Open a public wishlist from its share link
+pub async fn get_shared_wishlist(
+    State(state): State<Arc<AppState>>,
+    Path(share_slug): Path<String>,
+) -> Result<impl IntoResponse> {
+    let wishlist = wishlist::get_shared_wishlist(&state.db, &share_slug).await?;
+    Ok(Json(ApiResponse::success(wishlist)))
+}
@@ -0,0 +1,131 @@
+use chrono::{Duration as ChronoDuration, Utc};
+use sea_orm::DatabaseConnection;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::services::payment_gateway::PaymentGateways;
+use crate::services::shipping_carrier::ShippingCarriers;
+use crate::services::{cart, payment_reconciliation, refund, shipment, withdrawal};
+
+// How often abandoned guest carts are cleaned up
+const GUEST_CART_CLEANUP_INTERVAL: Duration = Duration::from_secs(60 * 60);
+// How often payments stuck waiting on a provider are looked up
+const PAYMENT_RECONCILIATION_INTERVAL: Duration = Duration::from_secs(10 * 60);
+// How often payouts sent to a provider are looked up
+const WITHDRAWAL_CHECK_INTERVAL: Duration = Duration::from_secs(10 * 60);
+// How often refunds are sent to providers and looked up
+const REFUND_CHECK_INTERVAL: Duration = Duration::from_secs(5 * 60);
+// The previous day's report is rebuilt this often, so settlement reports uploaded late are picked up
+const RECONCILIATION_REPORT_INTERVAL: Duration = Duration::from_secs(6 * 60 * 60);
+// How often carriers are asked about parcels on the way
+const SHIPMENT_TRACKING_INTERVAL: Duration = Duration::from_secs(15 * 60);
+
+// Start the background jobs that run alongside the API server
+pub fn spawn(
+    db: Arc<DatabaseConnection>,
+    payment_gateways: Arc<PaymentGateways>,
+    shipping_carriers: Arc<ShippingCarriers>,
+) {
+    tokio::spawn(expire_guest_carts(db.clone()));
+    tokio::spawn(reconcile_payments(db.clone(), payment_gateways.clone()));
+    tokio::spawn(check_withdrawals(db.clone(), payment_gateways.clone()));
+    tokio::spawn(check_refunds(db.clone(), payment_gateways));
+    tokio::spawn(track_shipments(db.clone(), shipping_carriers));
+    tokio::spawn(build_reconciliation_reports(db));
+}
+
+async fn expire_guest_carts(db: Arc<DatabaseConnection>) {
+    let mut interval = tokio::time::interval(GUEST_CART_CLEANUP_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        match cart::expire_guest_carts(&db).await {
+            Ok(0) => {}
+            Ok(count) => tracing::info!("Removed {} expired guest carts", count),
+            Err(e) => tracing::error!("Failed to remove expired guest carts: {:?}", e),
+        }
+    }
+}
+
+async fn reconcile_payments(db: Arc<DatabaseConnection>, payment_gateways: Arc<PaymentGateways>) {
+    let mut interval = tokio::time::interval(PAYMENT_RECONCILIATION_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        match payment_reconciliation::reconcile_pending_payments(&db, &payment_gateways).await {
+            Ok(summary) if summary.checked == 0 => {}
+            Ok(summary) => tracing::info!(
+                "Reconciled {} stuck payments: {} settled, {} expired, {} orders canceled, {} lookups failed",
+                summary.checked,
+                summary.settled,
+                summary.expired,
+                summary.orders_canceled,
+                summary.failed_lookups
+            ),
+            Err(e) => tracing::error!("Failed to reconcile pending payments: {:?}", e),
+        }
+
+        match payment_reconciliation::release_unpaid_orders(&db).await {
+            Ok(0) => {}
+            Ok(count) => tracing::info!("Canceled {} orders nobody paid for", count),
+            Err(e) => tracing::error!("Failed to cancel unpaid orders: {:?}", e),
+        }
+    }
+}
+
+async fn check_withdrawals(db: Arc<DatabaseConnection>, payment_gateways: Arc<PaymentGateways>) {
+    let mut interval = tokio::time::interval(WITHDRAWAL_CHECK_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        match withdrawal::check_processing_withdrawals(&db, &payment_gateways).await {
+            Ok(0) => {}
+            Ok(count) => tracing::info!("Settled {} withdrawals sent to providers", count),
+            Err(e) => tracing::error!("Failed to check withdrawals: {:?}", e),
+        }
+    }
+}
+
+async fn check_refunds(db: Arc<DatabaseConnection>, payment_gateways: Arc<PaymentGateways>) {
+    let mut interval = tokio::time::interval(REFUND_CHECK_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        match refund::check_refunds(&db, &payment_gateways).await {
+            Ok(0) => {}
+            Ok(count) => tracing::info!("Settled {} refunds with providers", count),
+            Err(e) => tracing::error!("Failed to check refunds: {:?}", e),
+        }
+    }
+}
+
+async fn track_shipments(db: Arc<DatabaseConnection>, shipping_carriers: Arc<ShippingCarriers>) {
+    let mut interval = tokio::time::interval(SHIPMENT_TRACKING_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        match shipment::track_shipments(&db, &shipping_carriers).await {
+            Ok(0) => {}
+            Ok(count) => tracing::info!("Recorded {} tracking events from carriers", count),
+            Err(e) => tracing::error!("Failed to track shipments: {:?}", e),
+        }
+    }
+}
+
+async fn build_reconciliation_reports(db: Arc<DatabaseConnection>) {
+    let mut interval = tokio::time::interval(RECONCILIATION_REPORT_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        let yesterday = (Utc::now() - ChronoDuration::days(1)).date_naive();
+        if let Err(e) = payment_reconciliation::build_daily_report(&db, yesterday).await {
+            tracing::error!("Failed to build the payment reconciliation report for {}: {:?}", yesterday, e);
+        }
+    }
+}
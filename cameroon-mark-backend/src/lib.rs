@@ -7,6 +7,7 @@ pub mod routes;
 pub mod services;
 pub mod utils;
 pub mod entities;
+pub mod jobs;
 // Re-export the config module for use with AppState
 pub use crate::config::Config;
 
@@ -16,12 +17,16 @@ pub mod app_state {
     use std::sync::Arc;
     use aws_sdk_s3::Client as S3Client;
     use crate::config::Config;
+    use crate::services::payment_gateway::PaymentGateways;
+    use crate::services::shipping_carrier::ShippingCarriers;
 
     // Application state that will be shared across handlers
     pub struct AppState {
         pub db: Arc<DatabaseConnection>,
         pub s3_client: Arc<S3Client>,
         pub config: Config,
+        pub payment_gateways: Arc<PaymentGateways>,
+        pub shipping_carriers: Arc<ShippingCarriers>,
     }
 }
 
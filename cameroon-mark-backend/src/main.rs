@@ -10,7 +10,9 @@ use aws_sdk_s3::config::{Region, Credentials};
 use aws_config::meta::region::RegionProviderChain;
 
 // Import the AppState and routes from lib.rs
-use cameroon_mark_backend::{AppState, routes};
+use cameroon_mark_backend::{AppState, jobs, routes};
+use cameroon_mark_backend::services::payment_gateway::PaymentGateways;
+use cameroon_mark_backend::services::shipping_carrier::ShippingCarriers;
 
 use axum::{
     routing::get,
@@ -87,6 +89,9 @@ async fn main() -> anyhow::Result<()> {
             HeaderName::from_static("origin"),
             HeaderName::from_static("x-csrftoken"),
             HeaderName::from_static("x-xsrf-token"),
+            HeaderName::from_static("x-cart-token"),
+            HeaderName::from_static("x-session-id"),
+            HeaderName::from_static("idempotency-key"),
         ])
         .allow_credentials(true)
         .max_age(Duration::from_secs(3600))
@@ -129,20 +134,51 @@ async fn main() -> anyhow::Result<()> {
             expiration: config.password_reset.expiration,
         },
         payment: cameroon_mark_backend::config::PaymentConfig {
+            sandbox: config.payment.sandbox,
             mtn_api_key: config.payment.mtn_api_key.clone(),
             mtn_api_secret: config.payment.mtn_api_secret.clone(),
+            mtn_subscription_key: config.payment.mtn_subscription_key.clone(),
+            mtn_disbursement_api_key: config.payment.mtn_disbursement_api_key.clone(),
+            mtn_disbursement_api_secret: config.payment.mtn_disbursement_api_secret.clone(),
+            mtn_disbursement_subscription_key: config.payment.mtn_disbursement_subscription_key.clone(),
             orange_api_key: config.payment.orange_api_key.clone(),
             orange_api_secret: config.payment.orange_api_secret.clone(),
+            orange_merchant_key: config.payment.orange_merchant_key.clone(),
+            mtn_processing_fee_rate: config.payment.mtn_processing_fee_rate,
+            orange_processing_fee_rate: config.payment.orange_processing_fee_rate,
+            callback_base_url: config.payment.callback_base_url.clone(),
+            return_url: config.payment.return_url.clone(),
+        },
+        shipping: cameroon_mark_backend::config::ShippingConfig {
+            mock_carrier: config.shipping.mock_carrier,
+            callback_base_url: config.shipping.callback_base_url.clone(),
         },
     };
 
+    let payment_gateways = Arc::new(PaymentGateways::from_config(&lib_config.payment));
+    tracing::info!(
+        "Payment gateways running against {}",
+        if lib_config.payment.sandbox { "sandbox" } else { "production" }
+    );
+
+    let shipping_carriers = Arc::new(ShippingCarriers::from_config(&lib_config.shipping));
+
     // Set up application state
     let app_state = Arc::new(AppState {
         db: Arc::new(db),
         s3_client: Arc::new(s3_client),
         config: lib_config,
+        payment_gateways,
+        shipping_carriers,
     });
 
+    // Start background jobs
+    jobs::spawn(
+        app_state.db.clone(),
+        app_state.payment_gateways.clone(),
+        app_state.shipping_carriers.clone(),
+    );
+
     // Set up API routes
     let app = Router::new()
         .route("/", get(health_check))
@@ -181,7 +217,14 @@ fn api_routes(app_state: Arc<AppState>) -> Router<Arc<AppState>> {
         .nest("/cart", routes::cart::routes())
         .nest("/orders", routes::order::routes())
         .nest("/saved-items", routes::saved_item::routes())
+        .nest("/wishlists", routes::wishlist::routes())
         .nest("/admin", routes::admin::routes())
         .nest("/analytics", routes::analytics::routes())
         .nest("/marketing", routes::marketing::routes(app_state.clone()))
+        .nest("/questions", routes::product_question::routes())
+        .nest("/notifications", routes::notification::routes())
+        .nest("/payments", routes::payment::routes())
+        .nest("/finance", routes::financial::routes())
+        .nest("/wallet", routes::wallet::routes())
+        .nest("/shipping", routes::shipping::routes())
 }
@@ -11,6 +11,7 @@ use uuid::Uuid;
 
 use crate::{
     errors::AppError,
+    models::cart::CartOwner,
     models::user::UserRole,
     utils::jwt,
     AppState,
@@ -23,6 +24,10 @@ pub struct ExtractUserId(pub Uuid);
 #[derive(Debug)]
 pub struct ExtractUserRole(pub UserRole);
 
+// Optional authentication for public routes that behave differently for signed-in users
+#[derive(Debug)]
+pub struct MaybeUserId(pub Option<Uuid>);
+
 #[async_trait]
 impl<'a ,S> FromRequestParts<S> for ExtractUserId
 where
@@ -70,6 +75,55 @@ where
     }
 }
 
+#[async_trait]
+impl<S> FromRequestParts<S> for MaybeUserId
+where
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let user_id = parts
+            .headers
+            .get("Authorization")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .and_then(|token| jwt::verify_token(token).ok())
+            .map(|claims| claims.sub);
+
+        Ok(MaybeUserId(user_id))
+    }
+}
+
+// Signed-in user's cart, or a guest cart identified by the X-Cart-Token header
+#[derive(Debug)]
+pub struct ExtractCartOwner(pub CartOwner);
+
+#[async_trait]
+impl<S> FromRequestParts<S> for ExtractCartOwner
+where
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        if parts.headers.contains_key("Authorization") {
+            let ExtractUserId(user_id) = ExtractUserId::from_request_parts(parts, state).await?;
+            return Ok(ExtractCartOwner(CartOwner::User(user_id)));
+        }
+
+        let cart_token = parts
+            .headers
+            .get("X-Cart-Token")
+            .ok_or_else(|| AppError::auth("Missing authorization header or cart token"))?
+            .to_str()
+            .map_err(|_| AppError::auth("Invalid cart token"))?;
+
+        let guest_cart_id = jwt::verify_cart_token(cart_token)?;
+        Ok(ExtractCartOwner(CartOwner::Guest(guest_cart_id)))
+    }
+}
+
 pub async fn require_auth(ExtractUserId(user_id): ExtractUserId) -> Result<Uuid, Response> {
     Ok(user_id)
 }
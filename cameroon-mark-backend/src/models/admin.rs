@@ -38,6 +38,8 @@ pub struct ReportedItem {
     pub id: Uuid,
     pub product_id: Uuid,
     pub product_name: String,
+    pub question_id: Option<Uuid>,
+    pub question: Option<String>,
     pub seller_id: Uuid,
     pub seller_name: String,
     pub reporter_id: Uuid,
@@ -49,6 +51,8 @@ pub struct ReportedItem {
 #[derive(Debug, Deserialize, Validate)]
 pub struct ReportItemRequest {
     pub product_id: Uuid,
+    // Set when reporting a single question on the product rather than the product itself
+    pub question_id: Option<Uuid>,
     #[validate(length(min = 5, max = 500, message = "Reason must be between 5 and 500 characters"))]
     pub reason: String,
 }
@@ -33,15 +33,27 @@ pub struct ProductPerformance {
     pub total_quantity: i64,
     pub total_revenue: BigDecimal,
     pub average_rating: Option<f64>,
+    pub view_count: i64,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SellerAnalytics {
     pub summary: SalesSummary,
+    pub cancellations: CancellationStats,
     pub monthly_sales: Vec<MonthlySales>,
     pub top_products: Vec<ProductPerformance>,
 }
 
+// How often a seller's orders end up canceled, and by whom
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CancellationStats {
+    pub total_orders: i64,
+    pub canceled_by_seller: i64,
+    pub canceled_by_buyer: i64,
+    // Share of orders the seller canceled, in percent
+    pub seller_cancellation_rate: f64,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct AnalyticsTimeRange {
     #[serde(with = "date_format")]
@@ -4,6 +4,8 @@ use sqlx::FromRow;
 use uuid::Uuid;
 use validator::Validate;
 
+use crate::models::money::Money;
+
 #[derive(Debug, Serialize, Deserialize, FromRow)]
 pub struct CartItem {
     pub id: Uuid,
@@ -14,6 +16,20 @@ pub struct CartItem {
     pub updated_at: DateTime<Utc>,
 }
 
+// Whose cart an operation applies to
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CartOwner {
+    User(Uuid),
+    Guest(Uuid),
+}
+
+// Token handed to an anonymous visitor for their cart
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GuestCartResponse {
+    pub cart_token: String,
+    pub expires_at: DateTime<Utc>,
+}
+
 #[derive(Debug, Serialize, Deserialize, Validate)]
 pub struct AddToCartRequest {
     pub product_id: Uuid,
@@ -28,20 +44,52 @@ pub struct UpdateCartItemRequest {
     pub quantity: i32,
 }
 
+// Something the buyer should know about a cart line before checking out
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum CartWarning {
+    PriceChanged { previous_price: Money, current_price: Money },
+    InsufficientStock { available: i32 },
+    OutOfStock,
+    Unavailable,
+}
+
+impl CartWarning {
+    // Lines with a blocking warning cannot be checked out as they are
+    pub fn is_blocking(&self) -> bool {
+        !matches!(self, CartWarning::PriceChanged { .. })
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CartItemResponse {
     pub id: Uuid,
     pub product_id: Uuid,
     pub product_title: String,
     pub product_image: String,
-    pub product_price: f64,
+    pub product_price: Money,
+    pub price_at_add: Money,
     pub quantity: i32,
-    pub total_price: f64,
+    pub available_stock: i32,
+    pub total_price: Money,
+    pub seller_id: Uuid,
+    pub seller_name: String,
+    pub warnings: Vec<CartWarning>,
 }
 
+// Cart lines sold by the same seller
 #[derive(Debug, Serialize, Deserialize)]
-pub struct CartResponse {
+pub struct CartSellerGroup {
+    pub seller_id: Uuid,
+    pub seller_name: String,
     pub items: Vec<CartItemResponse>,
+    pub subtotal: Money,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CartResponse {
+    pub sellers: Vec<CartSellerGroup>,
     pub total_items: usize,
-    pub total_price: f64,
+    pub total_price: Money,
+    pub has_warnings: bool,
 }
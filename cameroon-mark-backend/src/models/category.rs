@@ -24,6 +24,9 @@ pub struct CreateCategoryRequest {
     pub image_url: Option<String>,
     #[validate(length(min = 1, max = 100, message = "Slug must be between 1 and 100 characters"))]
     pub slug: String,
+    // Share of each sale the marketplace keeps, 10% when not given
+    #[validate(range(min = 0.0, max = 0.99, message = "Commission rate must be between 0 and 0.99"))]
+    pub commission_rate: Option<f64>,
 }
 
 #[derive(Debug, Deserialize, Validate)]
@@ -35,6 +38,8 @@ pub struct UpdateCategoryRequest {
     pub image_url: Option<String>,
     #[validate(length(min = 1, max = 100, message = "Slug must be between 1 and 100 characters"))]
     pub slug: Option<String>,
+    #[validate(range(min = 0.0, max = 0.99, message = "Commission rate must be between 0 and 0.99"))]
+    pub commission_rate: Option<f64>,
 }
 
 #[derive(Debug, Serialize)]
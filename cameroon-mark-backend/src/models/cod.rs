@@ -0,0 +1,37 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+use crate::models::money::Money;
+
+// A seller's cash on delivery terms
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CodSettings {
+    pub cod_enabled: bool,
+    // Largest sub-order total the seller accepts cash for. No limit when empty.
+    pub cod_max_order_value: Option<Money>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct UpdateCodSettingsRequest {
+    pub cod_enabled: bool,
+
+    pub cod_max_order_value: Option<Money>,
+}
+
+// Entered by the courier when the buyer pays and receives the parcel
+#[derive(Debug, Deserialize, Validate)]
+pub struct ConfirmDeliveryRequest {
+    #[validate(length(min = 6, max = 6, message = "Confirmation code must be 6 digits"))]
+    pub code: String,
+}
+
+// Cash a seller has collected on delivery and cash still out with couriers
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CodSummary {
+    pub orders_awaiting_collection: i64,
+    pub amount_awaiting_collection: Money,
+    pub orders_collected: i64,
+    pub amount_collected: Money,
+}
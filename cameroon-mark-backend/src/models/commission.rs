@@ -0,0 +1,99 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::models::money::Money;
+
+// Set by admins; commission rules can target a tier
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum SellerTier {
+    #[default]
+    Standard,
+    Verified,
+    Premium,
+}
+
+impl std::fmt::Display for SellerTier {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SellerTier::Standard => write!(f, "standard"),
+            SellerTier::Verified => write!(f, "verified"),
+            SellerTier::Premium => write!(f, "premium"),
+        }
+    }
+}
+
+impl FromStr for SellerTier {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "standard" => Ok(SellerTier::Standard),
+            "verified" => Ok(SellerTier::Verified),
+            "premium" => Ok(SellerTier::Premium),
+            _ => Err(format!("Unknown seller tier: {}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct CommissionRule {
+    pub id: Uuid,
+    pub name: String,
+    pub category_id: Option<Uuid>,
+    pub seller_tier: Option<SellerTier>,
+    pub is_promotion: bool,
+    // e.g. 0.08 for 8% of the item's price after discounts
+    pub percentage: f64,
+    // Per unit sold
+    pub fixed_fee: Money,
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateCommissionRuleRequest {
+    #[validate(length(min = 1, max = 100, message = "Name must be between 1 and 100 characters"))]
+    pub name: String,
+    // Every category when empty
+    pub category_id: Option<Uuid>,
+    // Every tier when empty
+    pub seller_tier: Option<SellerTier>,
+    #[serde(default)]
+    pub is_promotion: bool,
+    #[validate(range(min = 0.0, max = 0.99, message = "Percentage must be between 0 and 0.99"))]
+    pub percentage: Option<f64>,
+    pub fixed_fee: Option<Money>,
+    // Now when empty
+    pub starts_at: Option<DateTime<Utc>>,
+    // Required for promotions
+    pub ends_at: Option<DateTime<Utc>>,
+}
+
+// Rates can be changed freely: orders keep the fees worked out when they were placed
+#[derive(Debug, Deserialize, Validate)]
+pub struct UpdateCommissionRuleRequest {
+    #[validate(length(min = 1, max = 100, message = "Name must be between 1 and 100 characters"))]
+    pub name: Option<String>,
+    #[validate(range(min = 0.0, max = 0.99, message = "Percentage must be between 0 and 0.99"))]
+    pub percentage: Option<f64>,
+    pub fixed_fee: Option<Money>,
+    pub starts_at: Option<DateTime<Utc>>,
+    pub ends_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CommissionRuleQuery {
+    pub category_id: Option<Uuid>,
+    // Only rules in force now
+    pub active: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateSellerTierRequest {
+    pub tier: SellerTier,
+}
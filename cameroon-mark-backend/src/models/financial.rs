@@ -3,6 +3,9 @@ use chrono::{DateTime, Utc, NaiveDate};
 use uuid::Uuid;
 use validator::Validate;
 use std::collections::HashMap;
+use std::str::FromStr;
+
+use crate::models::money::Money;
 
 // Payment processor types
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
@@ -24,6 +27,75 @@ pub enum PaymentStatus {
     Refunded,
     PartiallyRefunded,
     Cancelled,
+    // The payer never completed the attempt
+    Expired,
+}
+
+impl std::fmt::Display for PaymentProcessor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PaymentProcessor::MtnMobileMoney => write!(f, "mtn_mobile_money"),
+            PaymentProcessor::OrangeMoney => write!(f, "orange_money"),
+            PaymentProcessor::CreditCard => write!(f, "credit_card"),
+            PaymentProcessor::BankTransfer => write!(f, "bank_transfer"),
+            PaymentProcessor::CashOnDelivery => write!(f, "cash_on_delivery"),
+        }
+    }
+}
+
+impl FromStr for PaymentProcessor {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "mtn_mobile_money" => Ok(PaymentProcessor::MtnMobileMoney),
+            "orange_money" => Ok(PaymentProcessor::OrangeMoney),
+            "credit_card" => Ok(PaymentProcessor::CreditCard),
+            "bank_transfer" => Ok(PaymentProcessor::BankTransfer),
+            "cash_on_delivery" => Ok(PaymentProcessor::CashOnDelivery),
+            _ => Err(format!("Unknown payment processor: {}", s)),
+        }
+    }
+}
+
+impl std::fmt::Display for PaymentStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PaymentStatus::Pending => write!(f, "pending"),
+            PaymentStatus::Processing => write!(f, "processing"),
+            PaymentStatus::Completed => write!(f, "completed"),
+            PaymentStatus::Failed => write!(f, "failed"),
+            PaymentStatus::Refunded => write!(f, "refunded"),
+            PaymentStatus::PartiallyRefunded => write!(f, "partially_refunded"),
+            PaymentStatus::Cancelled => write!(f, "cancelled"),
+            PaymentStatus::Expired => write!(f, "expired"),
+        }
+    }
+}
+
+impl FromStr for PaymentStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pending" => Ok(PaymentStatus::Pending),
+            "processing" => Ok(PaymentStatus::Processing),
+            "completed" => Ok(PaymentStatus::Completed),
+            "failed" => Ok(PaymentStatus::Failed),
+            "refunded" => Ok(PaymentStatus::Refunded),
+            "partially_refunded" => Ok(PaymentStatus::PartiallyRefunded),
+            "cancelled" => Ok(PaymentStatus::Cancelled),
+            "expired" => Ok(PaymentStatus::Expired),
+            _ => Err(format!("Unknown payment status: {}", s)),
+        }
+    }
+}
+
+impl PaymentStatus {
+    // The provider has settled the payment one way or the other
+    pub fn is_final(&self) -> bool {
+        !matches!(self, PaymentStatus::Pending | PaymentStatus::Processing)
+    }
 }
 
 // Payment details
@@ -32,8 +104,7 @@ pub struct Payment {
     pub id: Uuid,
     pub order_id: Uuid,
     pub user_id: Uuid,
-    pub amount: f64,
-    pub currency: String,
+    pub amount: Money,
     pub processor: PaymentProcessor,
     pub processor_payment_id: Option<String>,
     pub status: PaymentStatus,
@@ -48,8 +119,7 @@ pub struct Payment {
 #[derive(Debug, Deserialize, Validate)]
 pub struct ProcessPaymentRequest {
     pub order_id: Uuid,
-    pub amount: f64,
-    pub currency: String,
+    pub amount: Money,
     pub processor: PaymentProcessor,
     pub payment_method: String,
     pub phone_number: Option<String>, // For mobile money
@@ -67,12 +137,96 @@ pub struct PaymentResponse {
     pub message: String,
 }
 
-// Refund request
+// Refund request. Without items the amount is shared across the order's items by what is left to
+// refund on each; without either, everything left on the payment is refunded.
 #[derive(Debug, Deserialize, Validate)]
 pub struct RefundRequest {
     pub payment_id: Uuid,
-    pub amount: Option<f64>, // If not provided, full refund
+    pub amount: Option<Money>,
+    #[validate(length(min = 1, max = 500, message = "Reason must be between 1 and 500 characters"))]
+    pub reason: String,
+    pub items: Option<Vec<RefundItemRequest>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RefundItemRequest {
+    pub order_item_id: Uuid,
+    // Everything left to refund on the item when empty
+    pub amount: Option<Money>,
+}
+
+// pending -> processing -> completed, or failed at the provider and retried or paid by hand
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum RefundStatus {
+    // Waiting to be sent to the provider, or to be paid by hand
+    Pending,
+    // Sent to the provider, waiting for it to complete
+    Processing,
+    Completed,
+    Failed,
+}
+
+impl std::fmt::Display for RefundStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RefundStatus::Pending => write!(f, "pending"),
+            RefundStatus::Processing => write!(f, "processing"),
+            RefundStatus::Completed => write!(f, "completed"),
+            RefundStatus::Failed => write!(f, "failed"),
+        }
+    }
+}
+
+impl FromStr for RefundStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pending" => Ok(RefundStatus::Pending),
+            "processing" => Ok(RefundStatus::Processing),
+            "completed" => Ok(RefundStatus::Completed),
+            "failed" => Ok(RefundStatus::Failed),
+            _ => Err(format!("Unknown refund status: {}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct Refund {
+    pub id: Uuid,
+    pub payment_id: Uuid,
+    pub order_id: Uuid,
+    pub amount: Money,
     pub reason: String,
+    pub status: RefundStatus,
+    pub provider_reference: Option<String>,
+    pub failure_reason: Option<String>,
+    pub items: Vec<RefundItem>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RefundItem {
+    pub order_item_id: Uuid,
+    pub seller_order_id: Uuid,
+    pub amount: Money,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RefundQuery {
+    pub order_id: Option<Uuid>,
+    pub payment_id: Option<Uuid>,
+    pub status: Option<RefundStatus>,
+    pub page: Option<u64>,
+    pub per_page: Option<u64>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct MarkRefundPaidRequest {
+    #[validate(length(min = 1, max = 100, message = "Provider reference must be between 1 and 100 characters"))]
+    pub provider_reference: String,
 }
 
 // Revenue report
@@ -80,15 +234,28 @@ pub struct RefundRequest {
 pub struct RevenueReport {
     pub total_revenue: f64,
     pub net_revenue: f64,
+    // Providers' fees on online payments, passed on to sellers
     pub processing_fees: f64,
+    // Marketplace commission on the items kept
+    pub commission: f64,
     pub refunds: f64,
     pub taxes_collected: f64,
+    // Sales kept without VAT, and what sellers receive after commission and processing fees
+    pub seller_earnings: Earnings,
+    // Commission and processing fees charged, and what the marketplace keeps after paying the providers
+    pub platform_earnings: Earnings,
     pub revenue_by_day: HashMap<String, f64>,
     pub revenue_by_payment_method: HashMap<String, f64>,
     pub revenue_by_category: Vec<CategoryRevenue>,
     pub top_products: Vec<ProductRevenue>,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Earnings {
+    pub gross: f64,
+    pub net: f64,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CategoryRevenue {
     pub category_id: Uuid,
@@ -105,49 +272,44 @@ pub struct ProductRevenue {
     pub units_sold: i32,
 }
 
-// Tax calculation
+// VAT on a basket, worked out the way checkout does
 #[derive(Debug, Serialize, Deserialize)]
 pub struct TaxCalculation {
-    pub order_id: Option<Uuid>,
-    pub subtotal: f64,
-    pub shipping_cost: f64,
+    pub subtotal: Money,
+    pub shipping_cost: Money,
     pub taxes: Vec<TaxItem>,
-    pub total_tax: f64,
-    pub grand_total: f64,
+    pub total_tax: Money,
+    pub grand_total: Money,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct TaxItem {
+    pub product_id: Uuid,
+    // The rule applied, or why there is no VAT
     pub name: String,
-    pub rate: f32,
-    pub amount: f64,
+    pub rate: f64,
+    pub prices_include_tax: bool,
+    pub taxable_amount: Money,
+    pub amount: Money,
 }
 
 // Tax calculation request
 #[derive(Debug, Deserialize, Validate)]
 pub struct CalculateTaxRequest {
-    pub order_id: Option<Uuid>,
-    pub subtotal: f64,
-    pub shipping_cost: f64,
-    pub shipping_address: Option<TaxAddress>,
+    #[validate(length(min = 1, message = "Add at least one item"))]
     pub items: Vec<TaxableItem>,
-}
-
-#[derive(Debug, Serialize, Deserialize, Validate)]
-pub struct TaxAddress {
-    pub country: String,
-    pub state_province: Option<String>,
-    pub city: Option<String>,
-    pub postal_code: Option<String>,
+    // Delivery is quoted to this city, and left out when it is empty
+    #[validate(length(min = 2, max = 100, message = "Shipping city must be between 2 and 100 characters"))]
+    pub shipping_city: Option<String>,
+    // Cameroon when empty
+    pub shipping_country: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Validate)]
 pub struct TaxableItem {
     pub product_id: Uuid,
+    #[validate(range(min = 1, message = "Quantity must be at least 1"))]
     pub quantity: i32,
-    pub unit_price: f64,
-    pub product_type: Option<String>,
-    pub tax_code: Option<String>,
 }
 
 // Expense tracking
@@ -155,8 +317,7 @@ pub struct TaxableItem {
 pub struct Expense {
     pub id: Uuid,
     pub user_id: Uuid,
-    pub amount: f64,
-    pub currency: String,
+    pub amount: Money,
     pub category: String,
     pub description: String,
     pub date: NaiveDate,
@@ -168,8 +329,7 @@ pub struct Expense {
 // Create expense request
 #[derive(Debug, Deserialize, Validate)]
 pub struct CreateExpenseRequest {
-    pub amount: f64,
-    pub currency: String,
+    pub amount: Money,
     
     #[validate(length(min = 1, max = 100, message = "Category must be between 1 and 100 characters"))]
     pub category: String,
@@ -178,12 +338,70 @@ pub struct CreateExpenseRequest {
     pub description: String,
     
     pub date: NaiveDate,
+    #[validate(url(message = "Receipt must be a valid URL"))]
     pub receipt_url: Option<String>,
 }
 
-// Financial statement
+#[derive(Debug, Deserialize)]
+pub struct ExpenseQuery {
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+    pub category: Option<String>,
+}
+
+// Both ends are included
+#[derive(Debug, Deserialize)]
+pub struct RevenueReportQuery {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+}
+
+// Share of item sales kept after fees: by the seller for sellers, by the marketplace for admins
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ProfitMargins {
+    pub sales: f64,
+    pub gross_margin: f32, // Percentage, before processing fees
+    pub net_margin: f32, // Percentage, after processing fees
+    pub product_margins: Vec<ProductMargin>,
+    pub category_margins: Vec<CategoryMargin>,
+    pub cost_breakdown: CostBreakdown,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ProductMargin {
+    pub id: Uuid,
+    pub name: String,
+    pub revenue: f64,
+    pub cost: f64,
+    pub profit: f64,
+    pub margin: f32, // Percentage
+    pub units_sold: i32,
+    pub profit_per_unit: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CategoryMargin {
+    pub id: Uuid,
+    pub name: String,
+    pub revenue: f64,
+    pub cost: f64,
+    pub profit: f64,
+    pub margin: f32, // Percentage
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CostBreakdown {
+    pub commission: f64,
+    pub processing_fees: f64,
+    // Given back to buyers, in full on canceled sub-orders or in part
+    pub refunds: f64,
+}
+
+// A seller's financial statements for a period, for their accountant
 #[derive(Debug, Serialize, Deserialize)]
 pub struct FinancialStatement {
+    pub seller_id: Uuid,
+    pub seller_name: String,
     pub period_start: NaiveDate,
     pub period_end: NaiveDate,
     pub income_statement: IncomeStatement,
@@ -191,42 +409,98 @@ pub struct FinancialStatement {
     pub cash_flow: CashFlow,
 }
 
+// One line of a statement. Amounts we have no data for are empty and explained in `note`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StatementLine {
+    pub label: String,
+    pub amount: Option<Money>,
+    pub note: Option<String>,
+}
+
+impl StatementLine {
+    pub fn new(label: impl Into<String>, amount: Money) -> Self {
+        Self {
+            label: label.into(),
+            amount: Some(amount),
+            note: None,
+        }
+    }
+
+    pub fn unavailable(label: impl Into<String>, note: impl Into<String>) -> Self {
+        Self {
+            label: label.into(),
+            amount: None,
+            note: Some(note.into()),
+        }
+    }
+}
+
+// Sub-orders delivered in the period, refunds recorded in it and expenses dated in it.
+// Sales, delivery and refunds are before VAT, which is owed to the tax administration.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct IncomeStatement {
-    pub revenue: f64,
-    pub cost_of_goods_sold: f64,
-    pub gross_profit: f64,
-    pub gross_margin: f32,
-    pub operating_expenses: HashMap<String, f64>,
-    pub total_operating_expenses: f64,
-    pub operating_income: f64,
-    pub other_income: f64,
-    pub other_expenses: f64,
-    pub net_income: f64,
-    pub net_margin: f32,
+    pub orders_delivered: i64,
+    pub sales: Money,
+    pub delivery: Money,
+    pub refunds: Money,
+    pub revenue: Money,
+    // Commission and processing fees taken by the marketplace, less what refunds gave back
+    pub marketplace_fees: Money,
+    pub gross_profit: Money,
+    pub gross_margin: f64,
+    // By expense category
+    pub operating_expenses: Vec<StatementLine>,
+    pub total_operating_expenses: Money,
+    pub net_income: Money,
+    pub net_margin: f64,
+    pub vat_collected: Money,
 }
 
+// At the end of the period. Only what the marketplace holds for the seller is known; the totals
+// add up the lines that have an amount.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct BalanceSheet {
-    pub assets: HashMap<String, f64>,
-    pub total_assets: f64,
-    pub liabilities: HashMap<String, f64>,
-    pub total_liabilities: f64,
-    pub equity: HashMap<String, f64>,
-    pub total_equity: f64,
+    pub as_of: NaiveDate,
+    pub assets: Vec<StatementLine>,
+    pub total_assets: Money,
+    pub liabilities: Vec<StatementLine>,
+    pub total_liabilities: Money,
+    pub equity: Vec<StatementLine>,
 }
 
+// Money the marketplace holds for the seller (escrow, wallet and payouts in progress) moves with
+// payments, fees, refunds and payouts. Cash on delivery and expenses go through the seller's hands.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CashFlow {
-    pub operating_activities: HashMap<String, f64>,
-    pub net_cash_from_operating: f64,
-    pub investing_activities: HashMap<String, f64>,
-    pub net_cash_from_investing: f64,
-    pub financing_activities: HashMap<String, f64>,
-    pub net_cash_from_financing: f64,
-    pub net_change_in_cash: f64,
-    pub beginning_cash_balance: f64,
-    pub ending_cash_balance: f64,
+    pub opening_balance: Money,
+    pub payments_received: Money,
+    pub fees_paid: Money,
+    pub refunds_paid: Money,
+    pub payouts: Money,
+    pub closing_balance: Money,
+    pub cash_on_delivery_collected: Money,
+    pub expenses_paid: Money,
+    // Payments and cash collected, less fees, refunds and expenses
+    pub net_cash_flow: Money,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum StatementFormat {
+    #[default]
+    Json,
+    Csv,
+    Pdf,
+}
+
+// Both ends are included. Admins name the seller.
+#[derive(Debug, Deserialize)]
+pub struct StatementQuery {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub seller_id: Option<Uuid>,
+    #[serde(default)]
+    pub format: StatementFormat,
 }
 
 // Financial report request
@@ -237,3 +511,127 @@ pub struct FinancialReportRequest {
     pub period_end: NaiveDate,
     pub compare_to_previous: Option<bool>,
 }
+
+// What happened to a provider callback
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum CallbackOutcome {
+    // The payment moved to the status in the callback
+    Applied,
+    // The payment had already settled, so nothing changed
+    Duplicate,
+    // The callback reported no final status
+    Ignored,
+    // The callback failed verification
+    Rejected,
+}
+
+impl std::fmt::Display for CallbackOutcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CallbackOutcome::Applied => write!(f, "applied"),
+            CallbackOutcome::Duplicate => write!(f, "duplicate"),
+            CallbackOutcome::Ignored => write!(f, "ignored"),
+            CallbackOutcome::Rejected => write!(f, "rejected"),
+        }
+    }
+}
+
+// MTN MoMo does not sign callbacks, so the payment's secret travels in the callback URL
+#[derive(Debug, Deserialize)]
+pub struct WebhookQuery {
+    pub token: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct WebhookAck {
+    pub outcome: CallbackOutcome,
+}
+
+// One line of a provider's settlement report
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct SettlementLine {
+    #[validate(length(min = 1, max = 100, message = "Provider reference must be between 1 and 100 characters"))]
+    pub provider_reference: String,
+    pub transaction_id: Option<String>,
+    pub amount: Money,
+    pub settled_at: DateTime<Utc>,
+}
+
+// A provider settlement report, as uploaded by an admin
+#[derive(Debug, Deserialize, Validate)]
+pub struct ImportSettlementsRequest {
+    pub processor: PaymentProcessor,
+    #[validate(length(min = 1, message = "At least one settlement line is required"))]
+    #[validate]
+    pub settlements: Vec<SettlementLine>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImportSettlementsResponse {
+    pub imported: u64,
+    // Lines already imported from an earlier report
+    pub skipped: u64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReconciliationQuery {
+    // Defaults to yesterday
+    pub date: Option<NaiveDate>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ReconciliationStatus {
+    Matched,
+    Discrepancies,
+}
+
+impl std::fmt::Display for ReconciliationStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReconciliationStatus::Matched => write!(f, "matched"),
+            ReconciliationStatus::Discrepancies => write!(f, "discrepancies"),
+        }
+    }
+}
+
+impl FromStr for ReconciliationStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "matched" => Ok(ReconciliationStatus::Matched),
+            "discrepancies" => Ok(ReconciliationStatus::Discrepancies),
+            _ => Err(format!("Unknown reconciliation status: {}", s)),
+        }
+    }
+}
+
+// A payment or settlement found on only one side, or with different amounts on each
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ReconciliationEntry {
+    pub provider_reference: String,
+    pub payment_id: Option<Uuid>,
+    pub order_id: Option<Uuid>,
+    pub recorded_amount: Option<Money>,
+    pub settled_amount: Option<Money>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReconciliationReport {
+    pub id: Uuid,
+    pub report_date: NaiveDate,
+    pub processor: PaymentProcessor,
+    pub payments_count: i32,
+    pub payments_total: Money,
+    pub settlements_count: i32,
+    pub settlements_total: Money,
+    // Completed on our side, absent from the provider's report
+    pub missing_at_provider: Vec<ReconciliationEntry>,
+    // Settled by the provider, not completed on our side
+    pub missing_locally: Vec<ReconciliationEntry>,
+    pub amount_mismatches: Vec<ReconciliationEntry>,
+    pub status: ReconciliationStatus,
+    pub generated_at: DateTime<Utc>,
+}
@@ -0,0 +1,34 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use validator::Validate;
+
+// The business details a seller prints on their invoices
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TaxProfile {
+    pub legal_name: Option<String>,
+    // Numéro d'Identifiant Unique issued by the tax administration
+    pub niu: Option<String>,
+    // Sellers who are not registered for VAT sell without it
+    pub vat_registered: bool,
+    pub invoices_issued: i32,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct UpdateTaxProfileRequest {
+    #[validate(length(min = 2, max = 255, message = "Legal name must be between 2 and 255 characters"))]
+    pub legal_name: Option<String>,
+
+    #[validate(length(min = 14, max = 14, message = "NIU must be 14 characters"))]
+    pub niu: Option<String>,
+
+    // Unchanged when empty
+    pub vat_registered: Option<bool>,
+}
+
+// Picks which seller's invoice to download when an order has several sellers
+#[derive(Debug, Deserialize)]
+pub struct InvoiceQuery {
+    pub seller_order_id: Option<Uuid>,
+}
@@ -0,0 +1,231 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::models::money::Money;
+use crate::models::order::PaymentMethod;
+
+// Accounts of the double-entry ledger. Sellers have one of each seller account.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum LedgerAccountKind {
+    // Paid for by the buyer, held until the sub-order is delivered
+    SellerEscrow,
+    // Free for the seller to withdraw
+    SellerWallet,
+    // Asked to be paid out, waiting for approval or the provider
+    SellerWithdrawals,
+    // Money the marketplace holds at the providers
+    PlatformCash,
+    // The marketplace's share of sales: commission and the processing fees passed on to sellers
+    PlatformCommission,
+}
+
+impl LedgerAccountKind {
+    pub fn is_seller_account(self) -> bool {
+        matches!(
+            self,
+            LedgerAccountKind::SellerEscrow | LedgerAccountKind::SellerWallet | LedgerAccountKind::SellerWithdrawals
+        )
+    }
+}
+
+impl std::fmt::Display for LedgerAccountKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LedgerAccountKind::SellerEscrow => write!(f, "seller_escrow"),
+            LedgerAccountKind::SellerWallet => write!(f, "seller_wallet"),
+            LedgerAccountKind::SellerWithdrawals => write!(f, "seller_withdrawals"),
+            LedgerAccountKind::PlatformCash => write!(f, "platform_cash"),
+            LedgerAccountKind::PlatformCommission => write!(f, "platform_commission"),
+        }
+    }
+}
+
+impl FromStr for LedgerAccountKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "seller_escrow" => Ok(LedgerAccountKind::SellerEscrow),
+            "seller_wallet" => Ok(LedgerAccountKind::SellerWallet),
+            "seller_withdrawals" => Ok(LedgerAccountKind::SellerWithdrawals),
+            "platform_cash" => Ok(LedgerAccountKind::PlatformCash),
+            "platform_commission" => Ok(LedgerAccountKind::PlatformCommission),
+            _ => Err(format!("Unknown ledger account: {}", s)),
+        }
+    }
+}
+
+// What a ledger transaction records
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum LedgerTransactionKind {
+    // The buyer paid online; the seller's part goes into escrow
+    PaymentCaptured,
+    // Delivered: escrow moves to the seller's wallet less commission
+    DeliveryReleased,
+    // Delivered and paid in cash to the seller's courier; the seller owes the commission
+    CashCollected,
+    // Money returned to the buyer, taken back from escrow or the wallet
+    Refund,
+    WithdrawalRequested,
+    WithdrawalPaid,
+    // A rejected or failed withdrawal goes back to the wallet
+    WithdrawalReturned,
+}
+
+impl std::fmt::Display for LedgerTransactionKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LedgerTransactionKind::PaymentCaptured => write!(f, "payment_captured"),
+            LedgerTransactionKind::DeliveryReleased => write!(f, "delivery_released"),
+            LedgerTransactionKind::CashCollected => write!(f, "cash_collected"),
+            LedgerTransactionKind::Refund => write!(f, "refund"),
+            LedgerTransactionKind::WithdrawalRequested => write!(f, "withdrawal_requested"),
+            LedgerTransactionKind::WithdrawalPaid => write!(f, "withdrawal_paid"),
+            LedgerTransactionKind::WithdrawalReturned => write!(f, "withdrawal_returned"),
+        }
+    }
+}
+
+impl FromStr for LedgerTransactionKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "payment_captured" => Ok(LedgerTransactionKind::PaymentCaptured),
+            "delivery_released" => Ok(LedgerTransactionKind::DeliveryReleased),
+            "cash_collected" => Ok(LedgerTransactionKind::CashCollected),
+            "refund" => Ok(LedgerTransactionKind::Refund),
+            "withdrawal_requested" => Ok(LedgerTransactionKind::WithdrawalRequested),
+            "withdrawal_paid" => Ok(LedgerTransactionKind::WithdrawalPaid),
+            "withdrawal_returned" => Ok(LedgerTransactionKind::WithdrawalReturned),
+            _ => Err(format!("Unknown ledger transaction: {}", s)),
+        }
+    }
+}
+
+// pending -> approved -> processing -> paid, or rejected by an admin, or failed at the provider
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum WithdrawalStatus {
+    Pending,
+    // Approved, to be paid out by hand from the provider's merchant portal
+    Approved,
+    // Sent to the provider, waiting for the transfer to complete
+    Processing,
+    Paid,
+    Rejected,
+    Failed,
+}
+
+impl std::fmt::Display for WithdrawalStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WithdrawalStatus::Pending => write!(f, "pending"),
+            WithdrawalStatus::Approved => write!(f, "approved"),
+            WithdrawalStatus::Processing => write!(f, "processing"),
+            WithdrawalStatus::Paid => write!(f, "paid"),
+            WithdrawalStatus::Rejected => write!(f, "rejected"),
+            WithdrawalStatus::Failed => write!(f, "failed"),
+        }
+    }
+}
+
+impl FromStr for WithdrawalStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pending" => Ok(WithdrawalStatus::Pending),
+            "approved" => Ok(WithdrawalStatus::Approved),
+            "processing" => Ok(WithdrawalStatus::Processing),
+            "paid" => Ok(WithdrawalStatus::Paid),
+            "rejected" => Ok(WithdrawalStatus::Rejected),
+            "failed" => Ok(WithdrawalStatus::Failed),
+            _ => Err(format!("Unknown withdrawal status: {}", s)),
+        }
+    }
+}
+
+// What the marketplace owes a seller
+#[derive(Debug, Serialize)]
+pub struct WalletBalance {
+    // Can be withdrawn now. Negative when refunds or cash on delivery commission exceed sales.
+    pub available: Money,
+    // Paid for, released once delivered
+    pub in_escrow: Money,
+    // Asked to be paid out and not yet sent
+    pub pending_withdrawal: Money,
+}
+
+// One movement on one of the seller's accounts
+#[derive(Debug, Serialize)]
+pub struct WalletTransaction {
+    pub id: Uuid,
+    pub kind: LedgerTransactionKind,
+    pub account: LedgerAccountKind,
+    // Positive when money came in
+    pub amount: Money,
+    pub balance_after: Money,
+    pub order_id: Option<Uuid>,
+    pub seller_order_id: Option<Uuid>,
+    pub withdrawal_id: Option<Uuid>,
+    pub description: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct WalletHistoryQuery {
+    // All of the seller's accounts when empty
+    pub account: Option<LedgerAccountKind>,
+    pub page: Option<u64>,
+    pub per_page: Option<u64>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateWithdrawalRequest {
+    pub amount: Money,
+    // Mtn or Orange
+    pub method: PaymentMethod,
+    #[validate(length(min = 9, max = 20, message = "Phone number must be between 9 and 20 characters"))]
+    pub phone_number: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Withdrawal {
+    pub id: Uuid,
+    pub seller_id: Uuid,
+    pub amount: Money,
+    pub method: PaymentMethod,
+    pub phone_number: String,
+    pub status: WithdrawalStatus,
+    pub provider_reference: Option<String>,
+    pub failure_reason: Option<String>,
+    pub reviewed_at: Option<DateTime<Utc>>,
+    pub paid_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct WithdrawalQuery {
+    pub status: Option<WithdrawalStatus>,
+    pub page: Option<u64>,
+    pub per_page: Option<u64>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct RejectWithdrawalRequest {
+    #[validate(length(min = 1, max = 500, message = "Reason must be between 1 and 500 characters"))]
+    pub reason: String,
+}
+
+// Recorded by an admin after paying a withdrawal by hand
+#[derive(Debug, Deserialize, Validate)]
+pub struct MarkWithdrawalPaidRequest {
+    #[validate(length(min = 1, max = 100, message = "Provider reference must be between 1 and 100 characters"))]
+    pub provider_reference: String,
+}
@@ -1,9 +1,12 @@
+use bigdecimal::BigDecimal;
 use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc, NaiveDate, NaiveDateTime};
 use uuid::Uuid;
 use validator::Validate;
 use std::collections::HashMap;
 
+use crate::models::money::Money;
+
 // Campaign types
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub enum CampaignType {
@@ -85,9 +88,10 @@ pub struct DiscountCode {
     pub campaign_id: Option<Uuid>,
     pub code: String,
     pub discount_type: DiscountType,
-    pub discount_value: f64,
-    pub min_purchase_amount: Option<f64>,
-    pub max_discount_amount: Option<f64>,
+    // Percent off for percentage codes, whole currency units for fixed amounts
+    pub discount_value: BigDecimal,
+    pub min_purchase_amount: Option<Money>,
+    pub max_discount_amount: Option<Money>,
     pub usage_limit: Option<i32>,
     pub usage_count: i32,
     pub products: Option<Vec<Uuid>>,
@@ -109,9 +113,9 @@ pub struct CreateDiscountCodeRequest {
     
     pub discount_value: f64,
     
-    pub min_purchase_amount: Option<f64>,
+    pub min_purchase_amount: Option<Money>,
     
-    pub max_discount_amount: Option<f64>,
+    pub max_discount_amount: Option<Money>,
     
     pub usage_limit: Option<i32>,
     
@@ -186,7 +190,7 @@ pub struct ProductRecommendation {
     pub product_id: Uuid,
     pub name: String,
     pub image: String,
-    pub price: f64,
+    pub price: Money,
     pub relevance_score: f32,
     pub recommendation_type: String, // "similar", "frequently_bought_together", "customers_also_viewed", "trending"
 }
@@ -3,6 +3,8 @@ use uuid::Uuid;
 use validator::Validate;
 use chrono::{DateTime, Utc};
 
+use crate::models::notification::NotificationFrequency;
+
 // Message template
 #[derive(Debug, Serialize, Deserialize)]
 pub struct MessageTemplate {
@@ -101,7 +103,7 @@ pub struct MessageNotificationPreferences {
     pub user_id: Uuid,
     pub email_notifications: bool,
     pub push_notifications: bool,
-    pub notification_frequency: String, // "immediate", "hourly", "daily", "off"
+    pub notification_frequency: String, // "immediate", "daily", "off"
     pub quiet_hours_start: Option<i32>, // 0-23
     pub quiet_hours_end: Option<i32>, // 0-23
     pub updated_at: DateTime<Utc>,
@@ -122,9 +124,9 @@ pub struct UpdateNotificationPreferencesRequest {
 
 // Validator function for notification frequency
 fn validate_notification_frequency(frequency: &str) -> Result<(), validator::ValidationError> {
-    match frequency {
-        "immediate" | "hourly" | "daily" | "off" => Ok(()),
-        _ => Err(validator::ValidationError::new("invalid_frequency")),
+    match frequency.parse::<NotificationFrequency>() {
+        Ok(_) => Ok(()),
+        Err(_) => Err(validator::ValidationError::new("invalid_frequency")),
     }
 }
 
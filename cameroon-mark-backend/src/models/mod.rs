@@ -9,3 +9,16 @@ pub mod saved_item;
 pub mod analytics;
 pub mod admin;
 pub mod marketing;
+pub mod notification;
+pub mod product_question;
+pub mod message_enhancement;
+pub mod wishlist;
+pub mod cod;
+pub mod invoice;
+pub mod financial;
+pub mod money;
+pub mod ledger;
+pub mod commission;
+pub mod tax;
+pub mod shipping;
+pub mod shipment;
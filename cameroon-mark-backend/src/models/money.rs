@@ -0,0 +1,372 @@
+use bigdecimal::{BigDecimal, RoundingMode, ToPrimitive};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::str::FromStr;
+
+use crate::errors::AppError;
+
+// Currencies amounts can be held in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Currency {
+    // Central African CFA franc, the marketplace currency
+    #[default]
+    XAF,
+    EUR,
+    USD,
+}
+
+impl Currency {
+    // Digits after the decimal point. The CFA franc has no subunit in use.
+    pub fn minor_digits(self) -> u32 {
+        match self {
+            Currency::XAF => 0,
+            Currency::EUR | Currency::USD => 2,
+        }
+    }
+}
+
+impl std::fmt::Display for Currency {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Currency::XAF => write!(f, "XAF"),
+            Currency::EUR => write!(f, "EUR"),
+            Currency::USD => write!(f, "USD"),
+        }
+    }
+}
+
+impl FromStr for Currency {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_uppercase().as_str() {
+            "XAF" => Ok(Currency::XAF),
+            "EUR" => Ok(Currency::EUR),
+            "USD" => Ok(Currency::USD),
+            _ => Err(format!("Unknown currency: {}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MoneyError {
+    CurrencyMismatch(Currency, Currency),
+    Overflow,
+    InvalidAmount(String),
+}
+
+impl std::fmt::Display for MoneyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MoneyError::CurrencyMismatch(a, b) => write!(f, "Cannot combine amounts in {} and {}", a, b),
+            MoneyError::Overflow => write!(f, "Amount is too large"),
+            MoneyError::InvalidAmount(value) => write!(f, "Invalid amount: {}", value),
+        }
+    }
+}
+
+impl std::error::Error for MoneyError {}
+
+impl From<MoneyError> for AppError {
+    fn from(e: MoneyError) -> Self {
+        AppError::bad_request(e.to_string())
+    }
+}
+
+// An amount of money in the smallest unit of its currency: francs for XAF, cents for EUR and USD.
+// Serialized as {"amount": 12500, "currency": "XAF"}, with the amount in that smallest unit.
+//
+// Rounding: anything finer than the currency's smallest unit, from rates, percentages or
+// decimal input, is rounded half away from zero. Arithmetic is checked, and amounts in
+// different currencies never combine.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Money {
+    amount: i64,
+    #[serde(default)]
+    currency: Currency,
+}
+
+impl Money {
+    pub const fn new(amount: i64, currency: Currency) -> Self {
+        Self { amount, currency }
+    }
+
+    pub const fn xaf(francs: i64) -> Self {
+        Self::new(francs, Currency::XAF)
+    }
+
+    pub const fn zero(currency: Currency) -> Self {
+        Self::new(0, currency)
+    }
+
+    // In the currency's smallest unit
+    pub fn amount(&self) -> i64 {
+        self.amount
+    }
+
+    pub fn currency(&self) -> Currency {
+        self.currency
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.amount == 0
+    }
+
+    pub fn is_positive(&self) -> bool {
+        self.amount > 0
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.amount < 0
+    }
+
+    fn minor_per_major(currency: Currency) -> i64 {
+        10_i64.pow(currency.minor_digits())
+    }
+
+    // From a stored DECIMAL amount in whole currency units
+    pub fn from_decimal(value: &BigDecimal, currency: Currency) -> Result<Self, MoneyError> {
+        let minor = (value * BigDecimal::from(Self::minor_per_major(currency)))
+            .with_scale_round(0, RoundingMode::HalfUp)
+            .to_i64()
+            .ok_or(MoneyError::Overflow)?;
+
+        Ok(Self::new(minor, currency))
+    }
+
+    // From an amount in whole currency units sent as a JSON number or by a provider
+    pub fn from_major(value: f64, currency: Currency) -> Result<Self, MoneyError> {
+        if !value.is_finite() {
+            return Err(MoneyError::InvalidAmount(value.to_string()));
+        }
+        // The shortest decimal form is what the sender meant, unlike the float's exact binary value
+        let decimal = BigDecimal::from_str(&value.to_string()).map_err(|_| MoneyError::InvalidAmount(value.to_string()))?;
+
+        Self::from_decimal(&decimal, currency)
+    }
+
+    // In whole currency units, for DECIMAL columns
+    pub fn to_decimal(&self) -> BigDecimal {
+        BigDecimal::new(self.amount.into(), self.currency.minor_digits() as i64)
+    }
+
+    // Rounded to whole currency units, for providers that take no subunits
+    pub fn whole_units(&self) -> i64 {
+        self.to_decimal().with_scale_round(0, RoundingMode::HalfUp).to_i64().unwrap_or_default()
+    }
+
+    fn same_currency(&self, other: &Money) -> Result<(), MoneyError> {
+        if self.currency != other.currency {
+            return Err(MoneyError::CurrencyMismatch(self.currency, other.currency));
+        }
+        Ok(())
+    }
+
+    pub fn checked_add(self, other: Money) -> Result<Money, MoneyError> {
+        self.same_currency(&other)?;
+        let amount = self.amount.checked_add(other.amount).ok_or(MoneyError::Overflow)?;
+        Ok(Self::new(amount, self.currency))
+    }
+
+    pub fn checked_sub(self, other: Money) -> Result<Money, MoneyError> {
+        self.same_currency(&other)?;
+        let amount = self.amount.checked_sub(other.amount).ok_or(MoneyError::Overflow)?;
+        Ok(Self::new(amount, self.currency))
+    }
+
+    pub fn checked_mul(self, quantity: i64) -> Result<Money, MoneyError> {
+        let amount = self.amount.checked_mul(quantity).ok_or(MoneyError::Overflow)?;
+        Ok(Self::new(amount, self.currency))
+    }
+
+    // Multiply by a rate such as 0.1925, rounding to the smallest unit
+    pub fn mul_rate(self, rate: &BigDecimal) -> Result<Money, MoneyError> {
+        let amount = (BigDecimal::from(self.amount) * rate)
+            .with_scale_round(0, RoundingMode::HalfUp)
+            .to_i64()
+            .ok_or(MoneyError::Overflow)?;
+        Ok(Self::new(amount, self.currency))
+    }
+
+    // A percentage such as 15 for 15%, rounding to the smallest unit
+    pub fn percent(self, percent: &BigDecimal) -> Result<Money, MoneyError> {
+        self.mul_rate(&(percent / BigDecimal::from(100)))
+    }
+
+    pub fn min(self, other: Money) -> Result<Money, MoneyError> {
+        self.same_currency(&other)?;
+        Ok(if other.amount < self.amount { other } else { self })
+    }
+
+    pub fn max(self, other: Money) -> Result<Money, MoneyError> {
+        self.same_currency(&other)?;
+        Ok(if other.amount > self.amount { other } else { self })
+    }
+
+    // Add up amounts that all share the given currency
+    pub fn sum<I: IntoIterator<Item = Money>>(amounts: I, currency: Currency) -> Result<Money, MoneyError> {
+        amounts.into_iter().try_fold(Money::zero(currency), Money::checked_add)
+    }
+
+    // Split this amount in proportion to the weights. The parts always add up to the whole:
+    // units lost to rounding go to the parts with the largest remainders, earliest first.
+    // Weights must not be negative and at least one must be positive.
+    pub fn allocate(self, weights: &[Money]) -> Result<Vec<Money>, MoneyError> {
+        let mut total: i128 = 0;
+        for weight in weights {
+            self.same_currency(weight)?;
+            if weight.is_negative() {
+                return Err(MoneyError::InvalidAmount(format!("negative weight {}", weight)));
+            }
+            total += weight.amount as i128;
+        }
+        if weights.is_empty() || total <= 0 {
+            return Err(MoneyError::InvalidAmount("nothing to allocate against".to_string()));
+        }
+
+        let mut parts = Vec::with_capacity(weights.len());
+        let mut remainders = Vec::with_capacity(weights.len());
+        let mut allocated: i128 = 0;
+        for (index, weight) in weights.iter().enumerate() {
+            let share = self.amount as i128 * weight.amount as i128;
+            parts.push(share / total);
+            remainders.push(((share % total).abs(), index));
+            allocated += share / total;
+        }
+
+        remainders.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+        let step = if self.amount >= 0 { 1 } else { -1 };
+        for (_, index) in remainders.iter().cycle().take((self.amount as i128 - allocated).unsigned_abs() as usize) {
+            parts[*index] += step;
+        }
+
+        Ok(parts
+            .into_iter()
+            .map(|amount| Self::new(amount as i64, self.currency))
+            .collect())
+    }
+}
+
+// Amounts in different currencies are not comparable
+impl PartialOrd for Money {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        (self.currency == other.currency).then(|| self.amount.cmp(&other.amount))
+    }
+}
+
+// Grouped with spaces as is usual in Cameroon, e.g. "12 500 XAF" or "1 234.50 EUR"
+impl std::fmt::Display for Money {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let digits = self.currency.minor_digits() as usize;
+        let scale = Self::minor_per_major(self.currency) as u64;
+        let whole = (self.amount.unsigned_abs() / scale).to_string();
+
+        let mut grouped = String::new();
+        for (i, c) in whole.chars().enumerate() {
+            if i > 0 && (whole.len() - i).is_multiple_of(3) {
+                grouped.push(' ');
+            }
+            grouped.push(c);
+        }
+
+        let sign = if self.amount < 0 { "-" } else { "" };
+        if digits > 0 {
+            let fraction = self.amount.unsigned_abs() % scale;
+            write!(f, "{}{}.{:0width$} {}", sign, grouped, fraction, self.currency, width = digits)
+        } else {
+            write!(f, "{}{} {}", sign, grouped, self.currency)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rate(value: &str) -> BigDecimal {
+        BigDecimal::from_str(value).unwrap()
+    }
+
+    #[test]
+    fn mul_rate_rounds_half_away_from_zero() {
+        assert_eq!(Money::xaf(10).mul_rate(&rate("0.25")).unwrap(), Money::xaf(3));
+        assert_eq!(Money::xaf(10).mul_rate(&rate("0.24")).unwrap(), Money::xaf(2));
+        assert_eq!(Money::xaf(-10).mul_rate(&rate("0.25")).unwrap(), Money::xaf(-3));
+        assert_eq!(Money::xaf(10_000).mul_rate(&rate("0.1925")).unwrap(), Money::xaf(1925));
+        assert_eq!(Money::xaf(2_599).percent(&rate("15")).unwrap(), Money::xaf(390));
+    }
+
+    #[test]
+    fn mul_rate_overflow_is_an_error() {
+        assert_eq!(Money::xaf(i64::MAX).mul_rate(&rate("2")), Err(MoneyError::Overflow));
+    }
+
+    #[test]
+    fn allocate_gives_remainders_to_the_largest_fractions() {
+        let parts = Money::xaf(100).allocate(&[Money::xaf(1), Money::xaf(1), Money::xaf(1)]).unwrap();
+        assert_eq!(parts, vec![Money::xaf(34), Money::xaf(33), Money::xaf(33)]);
+
+        let parts = Money::xaf(10).allocate(&[Money::xaf(1), Money::xaf(2), Money::xaf(4)]).unwrap();
+        assert_eq!(parts, vec![Money::xaf(1), Money::xaf(3), Money::xaf(6)]);
+        assert_eq!(Money::sum(parts, Currency::XAF).unwrap(), Money::xaf(10));
+    }
+
+    #[test]
+    fn allocate_negative_amounts_adds_up() {
+        let parts = Money::xaf(-100).allocate(&[Money::xaf(1), Money::xaf(1), Money::xaf(1)]).unwrap();
+        assert_eq!(parts, vec![Money::xaf(-34), Money::xaf(-33), Money::xaf(-33)]);
+    }
+
+    #[test]
+    fn allocate_keeps_zero_weights_at_zero() {
+        let parts = Money::xaf(7).allocate(&[Money::xaf(0), Money::xaf(3)]).unwrap();
+        assert_eq!(parts, vec![Money::xaf(0), Money::xaf(7)]);
+    }
+
+    #[test]
+    fn allocate_rejects_bad_weights() {
+        assert!(Money::xaf(100).allocate(&[]).is_err());
+        assert!(Money::xaf(100).allocate(&[Money::xaf(0), Money::xaf(0)]).is_err());
+        assert!(Money::xaf(100).allocate(&[Money::xaf(-5), Money::xaf(10)]).is_err());
+        assert_eq!(
+            Money::xaf(100).allocate(&[Money::new(1, Currency::EUR)]),
+            Err(MoneyError::CurrencyMismatch(Currency::XAF, Currency::EUR))
+        );
+    }
+
+    #[test]
+    fn from_major_uses_the_decimal_the_sender_meant() {
+        assert_eq!(Money::from_major(12500.0, Currency::XAF).unwrap(), Money::xaf(12500));
+        assert_eq!(Money::from_major(12499.5, Currency::XAF).unwrap(), Money::xaf(12500));
+        assert_eq!(Money::from_major(0.29, Currency::EUR).unwrap(), Money::new(29, Currency::EUR));
+        assert_eq!(Money::from_major(1.005, Currency::USD).unwrap(), Money::new(101, Currency::USD));
+        assert!(Money::from_major(f64::NAN, Currency::XAF).is_err());
+        assert!(Money::from_major(f64::INFINITY, Currency::XAF).is_err());
+    }
+
+    #[test]
+    fn displays_grouped_amounts() {
+        assert_eq!(Money::xaf(0).to_string(), "0 XAF");
+        assert_eq!(Money::xaf(500).to_string(), "500 XAF");
+        assert_eq!(Money::xaf(12_500).to_string(), "12 500 XAF");
+        assert_eq!(Money::xaf(-1_234_567).to_string(), "-1 234 567 XAF");
+        assert_eq!(Money::new(123_450, Currency::EUR).to_string(), "1 234.50 EUR");
+        assert_eq!(Money::new(-5, Currency::USD).to_string(), "-0.05 USD");
+    }
+
+    #[test]
+    fn checked_ops_refuse_overflow_and_mixed_currencies() {
+        assert_eq!(Money::xaf(2).checked_add(Money::xaf(3)).unwrap(), Money::xaf(5));
+        assert_eq!(Money::xaf(2).checked_sub(Money::xaf(3)).unwrap(), Money::xaf(-1));
+        assert_eq!(Money::xaf(250).checked_mul(4).unwrap(), Money::xaf(1000));
+
+        assert_eq!(Money::xaf(i64::MAX).checked_add(Money::xaf(1)), Err(MoneyError::Overflow));
+        assert_eq!(Money::xaf(i64::MIN).checked_sub(Money::xaf(1)), Err(MoneyError::Overflow));
+        assert_eq!(Money::xaf(i64::MAX).checked_mul(2), Err(MoneyError::Overflow));
+        assert_eq!(
+            Money::xaf(1).checked_add(Money::new(1, Currency::EUR)),
+            Err(MoneyError::CurrencyMismatch(Currency::XAF, Currency::EUR))
+        );
+        assert!(Money::xaf(1).min(Money::new(1, Currency::USD)).is_err());
+    }
+}
@@ -0,0 +1,94 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::entities::notification;
+
+// Kinds of notifications the platform sends
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationKind {
+    ProductQuestion,
+    QuestionAnswered,
+    PriceDrop,
+    BackInStock,
+    OrderCanceled,
+    WithdrawalUpdate,
+    RefundUpdate,
+}
+
+impl std::fmt::Display for NotificationKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NotificationKind::ProductQuestion => write!(f, "product_question"),
+            NotificationKind::QuestionAnswered => write!(f, "question_answered"),
+            NotificationKind::PriceDrop => write!(f, "price_drop"),
+            NotificationKind::BackInStock => write!(f, "back_in_stock"),
+            NotificationKind::OrderCanceled => write!(f, "order_canceled"),
+            NotificationKind::WithdrawalUpdate => write!(f, "withdrawal_update"),
+            NotificationKind::RefundUpdate => write!(f, "refund_update"),
+        }
+    }
+}
+
+// How often notifications go out beyond the in-app feed
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationFrequency {
+    // Each one by email and push as it happens
+    Immediate,
+    // Gathered into one email a day, with no push
+    Daily,
+    // In the app only
+    Off,
+}
+
+impl std::fmt::Display for NotificationFrequency {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NotificationFrequency::Immediate => write!(f, "immediate"),
+            NotificationFrequency::Daily => write!(f, "daily"),
+            NotificationFrequency::Off => write!(f, "off"),
+        }
+    }
+}
+
+impl std::str::FromStr for NotificationFrequency {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "immediate" => Ok(NotificationFrequency::Immediate),
+            "daily" => Ok(NotificationFrequency::Daily),
+            "off" => Ok(NotificationFrequency::Off),
+            _ => Err(format!("Unknown notification frequency: {}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Notification {
+    pub id: Uuid,
+    pub kind: String,
+    pub title: String,
+    pub body: String,
+    pub link: Option<String>,
+    pub read: bool,
+    pub channels: Vec<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<notification::Model> for Notification {
+    fn from(model: notification::Model) -> Self {
+        Self {
+            id: model.id,
+            kind: model.kind,
+            title: model.title,
+            body: model.body,
+            link: model.link,
+            read: model.read,
+            channels: model.channels,
+            created_at: model.created_at,
+        }
+    }
+}
@@ -1,9 +1,14 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::{postgres::PgRow, FromRow, Row};
+use std::str::FromStr;
 use uuid::Uuid;
 use validator::Validate;
 
+use crate::models::cart::CartResponse;
+use crate::models::money::Money;
+use crate::models::shipping::{ShippingChoice, ShippingMethod, ShippingZone};
+
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, sqlx::Type)]
 #[sqlx(type_name = "order_status", rename_all = "lowercase")]
 pub enum OrderStatus {
@@ -26,6 +31,35 @@ impl std::fmt::Display for OrderStatus {
     }
 }
 
+impl OrderStatus {
+    // Delivered and canceled orders never change again
+    pub fn is_terminal(&self) -> bool {
+        matches!(self, OrderStatus::Delivered | OrderStatus::Canceled)
+    }
+
+    // Who may move an order from this status to `next`. Empty means the move is not allowed at all.
+    pub fn allowed_actors(&self, next: &OrderStatus) -> &'static [OrderActor] {
+        use OrderActor::*;
+
+        match (self, next) {
+            (OrderStatus::Pending, OrderStatus::Processing) => &[Seller, Admin, System],
+            (OrderStatus::Pending, OrderStatus::Canceled) => &[Buyer, Seller, Admin, System],
+            (OrderStatus::Processing, OrderStatus::Shipped) => &[Seller, Admin, System],
+            (OrderStatus::Processing, OrderStatus::Canceled) => &[Buyer, Seller, Admin, System],
+            // Delivery releases escrow, so the seller's word is not enough: the buyer confirms it,
+            // a carrier reports it or an admin steps in
+            (OrderStatus::Shipped, OrderStatus::Delivered) => &[Buyer, Admin, System],
+            // A parcel lost in transit can only be written off by an admin
+            (OrderStatus::Shipped, OrderStatus::Canceled) => &[Admin],
+            _ => &[],
+        }
+    }
+
+    pub fn can_transition_to(&self, next: &OrderStatus, actor: OrderActor) -> bool {
+        self.allowed_actors(next).contains(&actor)
+    }
+}
+
 impl FromRow<'_, PgRow> for OrderStatus {
     fn from_row(row: &PgRow) -> Result<Self, sqlx::Error> {
         let status: String = row.try_get(0)?;
@@ -46,12 +80,29 @@ impl FromRow<'_, PgRow> for OrderStatus {
     }
 }
 
+impl FromStr for OrderStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pending" => Ok(OrderStatus::Pending),
+            "processing" => Ok(OrderStatus::Processing),
+            "shipped" => Ok(OrderStatus::Shipped),
+            "delivered" => Ok(OrderStatus::Delivered),
+            "canceled" => Ok(OrderStatus::Canceled),
+            _ => Err(format!("Unknown order status: {}", s)),
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, sqlx::Type)]
 #[sqlx(type_name = "payment_status", rename_all = "lowercase")]
 pub enum PaymentStatus {
     Pending,
     Paid,
     Failed,
+    Refunded,
+    PartiallyRefunded,
 }
 
 impl std::fmt::Display for PaymentStatus {
@@ -60,6 +111,8 @@ impl std::fmt::Display for PaymentStatus {
             PaymentStatus::Pending => write!(f, "pending"),
             PaymentStatus::Paid => write!(f, "paid"),
             PaymentStatus::Failed => write!(f, "failed"),
+            PaymentStatus::Refunded => write!(f, "refunded"),
+            PaymentStatus::PartiallyRefunded => write!(f, "partially_refunded"),
         }
     }
 }
@@ -71,6 +124,8 @@ impl FromRow<'_, PgRow> for PaymentStatus {
             "pending" => Ok(PaymentStatus::Pending),
             "paid" => Ok(PaymentStatus::Paid),
             "failed" => Ok(PaymentStatus::Failed),
+            "refunded" => Ok(PaymentStatus::Refunded),
+            "partially_refunded" => Ok(PaymentStatus::PartiallyRefunded),
             _ => Err(sqlx::Error::ColumnDecode {
                 index: "payment_status".to_string(),
                 source: Box::new(std::io::Error::new(
@@ -82,11 +137,27 @@ impl FromRow<'_, PgRow> for PaymentStatus {
     }
 }
 
+impl FromStr for PaymentStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pending" => Ok(PaymentStatus::Pending),
+            "paid" => Ok(PaymentStatus::Paid),
+            "failed" => Ok(PaymentStatus::Failed),
+            "refunded" => Ok(PaymentStatus::Refunded),
+            "partially_refunded" => Ok(PaymentStatus::PartiallyRefunded),
+            _ => Err(format!("Unknown payment status: {}", s)),
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, sqlx::Type)]
 #[sqlx(type_name = "payment_method", rename_all = "lowercase")]
 pub enum PaymentMethod {
     Mtn,
     Orange,
+    CashOnDelivery,
     Other,
 }
 
@@ -95,6 +166,7 @@ impl std::fmt::Display for PaymentMethod {
         match self {
             PaymentMethod::Mtn => write!(f, "mtn"),
             PaymentMethod::Orange => write!(f, "orange"),
+            PaymentMethod::CashOnDelivery => write!(f, "cash_on_delivery"),
             PaymentMethod::Other => write!(f, "other"),
         }
     }
@@ -106,6 +178,7 @@ impl FromRow<'_, PgRow> for PaymentMethod {
         match method.as_str() {
             "mtn" => Ok(PaymentMethod::Mtn),
             "orange" => Ok(PaymentMethod::Orange),
+            "cash_on_delivery" => Ok(PaymentMethod::CashOnDelivery),
             "other" => Ok(PaymentMethod::Other),
             _ => Err(sqlx::Error::ColumnDecode {
                 index: "payment_method".to_string(),
@@ -118,6 +191,206 @@ impl FromRow<'_, PgRow> for PaymentMethod {
     }
 }
 
+impl FromStr for PaymentMethod {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "mtn" => Ok(PaymentMethod::Mtn),
+            "orange" => Ok(PaymentMethod::Orange),
+            "cash_on_delivery" => Ok(PaymentMethod::CashOnDelivery),
+            "other" => Ok(PaymentMethod::Other),
+            _ => Err(format!("Unknown payment method: {}", s)),
+        }
+    }
+}
+
+// Who triggered an order status change
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum OrderActor {
+    Buyer,
+    Seller,
+    Admin,
+    System,
+}
+
+impl std::fmt::Display for OrderActor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OrderActor::Buyer => write!(f, "buyer"),
+            OrderActor::Seller => write!(f, "seller"),
+            OrderActor::Admin => write!(f, "admin"),
+            OrderActor::System => write!(f, "system"),
+        }
+    }
+}
+
+impl FromStr for OrderActor {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "buyer" => Ok(OrderActor::Buyer),
+            "seller" => Ok(OrderActor::Seller),
+            "admin" => Ok(OrderActor::Admin),
+            "system" => Ok(OrderActor::System),
+            _ => Err(format!("Unknown order actor: {}", s)),
+        }
+    }
+}
+
+// One entry in an order's status history
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OrderStatusHistoryEntry {
+    pub id: Uuid,
+    pub seller_order_id: Option<Uuid>,
+    pub from_status: Option<OrderStatus>,
+    pub to_status: OrderStatus,
+    pub actor_id: Option<Uuid>,
+    pub actor_role: OrderActor,
+    pub note: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+// Why an order was canceled
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum CancellationReason {
+    ChangedMind,
+    OrderedByMistake,
+    FoundBetterPrice,
+    DeliveryTooSlow,
+    OutOfStock,
+    UnableToDeliver,
+    PricingError,
+    PaymentNotReceived,
+    Other,
+}
+
+impl CancellationReason {
+    // Wording shown to the other party
+    pub fn label(&self) -> &'static str {
+        match self {
+            CancellationReason::ChangedMind => "Changed their mind",
+            CancellationReason::OrderedByMistake => "Ordered by mistake",
+            CancellationReason::FoundBetterPrice => "Found a better price",
+            CancellationReason::DeliveryTooSlow => "Delivery takes too long",
+            CancellationReason::OutOfStock => "Item out of stock",
+            CancellationReason::UnableToDeliver => "Unable to deliver to this address",
+            CancellationReason::PricingError => "Pricing error",
+            CancellationReason::PaymentNotReceived => "Payment was not completed",
+            CancellationReason::Other => "Other",
+        }
+    }
+}
+
+impl std::fmt::Display for CancellationReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CancellationReason::ChangedMind => write!(f, "changed_mind"),
+            CancellationReason::OrderedByMistake => write!(f, "ordered_by_mistake"),
+            CancellationReason::FoundBetterPrice => write!(f, "found_better_price"),
+            CancellationReason::DeliveryTooSlow => write!(f, "delivery_too_slow"),
+            CancellationReason::OutOfStock => write!(f, "out_of_stock"),
+            CancellationReason::UnableToDeliver => write!(f, "unable_to_deliver"),
+            CancellationReason::PricingError => write!(f, "pricing_error"),
+            CancellationReason::PaymentNotReceived => write!(f, "payment_not_received"),
+            CancellationReason::Other => write!(f, "other"),
+        }
+    }
+}
+
+impl FromStr for CancellationReason {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "changed_mind" => Ok(CancellationReason::ChangedMind),
+            "ordered_by_mistake" => Ok(CancellationReason::OrderedByMistake),
+            "found_better_price" => Ok(CancellationReason::FoundBetterPrice),
+            "delivery_too_slow" => Ok(CancellationReason::DeliveryTooSlow),
+            "out_of_stock" => Ok(CancellationReason::OutOfStock),
+            "unable_to_deliver" => Ok(CancellationReason::UnableToDeliver),
+            "pricing_error" => Ok(CancellationReason::PricingError),
+            "payment_not_received" => Ok(CancellationReason::PaymentNotReceived),
+            "other" => Ok(CancellationReason::Other),
+            _ => Err(format!("Unknown cancellation reason: {}", s)),
+        }
+    }
+}
+
+// Delivery progress of a seller's part of an order
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum FulfillmentStatus {
+    Unfulfilled,
+    Shipped,
+    Delivered,
+    Canceled,
+}
+
+impl std::fmt::Display for FulfillmentStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FulfillmentStatus::Unfulfilled => write!(f, "unfulfilled"),
+            FulfillmentStatus::Shipped => write!(f, "shipped"),
+            FulfillmentStatus::Delivered => write!(f, "delivered"),
+            FulfillmentStatus::Canceled => write!(f, "canceled"),
+        }
+    }
+}
+
+impl FromStr for FulfillmentStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "unfulfilled" => Ok(FulfillmentStatus::Unfulfilled),
+            "shipped" => Ok(FulfillmentStatus::Shipped),
+            "delivered" => Ok(FulfillmentStatus::Delivered),
+            "canceled" => Ok(FulfillmentStatus::Canceled),
+            _ => Err(format!("Unknown fulfillment status: {}", s)),
+        }
+    }
+}
+
+// Where the money owed to a seller for a sub-order stands.
+// Funds are held until delivery, then released for payout.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum PayoutStatus {
+    Pending,
+    Released,
+    Paid,
+    Canceled,
+}
+
+impl std::fmt::Display for PayoutStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PayoutStatus::Pending => write!(f, "pending"),
+            PayoutStatus::Released => write!(f, "released"),
+            PayoutStatus::Paid => write!(f, "paid"),
+            PayoutStatus::Canceled => write!(f, "canceled"),
+        }
+    }
+}
+
+impl FromStr for PayoutStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pending" => Ok(PayoutStatus::Pending),
+            "released" => Ok(PayoutStatus::Released),
+            "paid" => Ok(PayoutStatus::Paid),
+            "canceled" => Ok(PayoutStatus::Canceled),
+            _ => Err(format!("Unknown payout status: {}", s)),
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, FromRow)]
 pub struct Order {
     pub id: Uuid,
@@ -169,11 +442,82 @@ pub struct CreateOrderRequest {
     pub shipping_phone: Option<String>,
     
     pub payment_method: PaymentMethod,
+
+    #[validate(length(min = 1, max = 50, message = "Discount code must be between 1 and 50 characters"))]
+    pub discount_code: Option<String>,
+
+    // How each seller's items should reach the buyer, the cheapest delivery for sellers not listed
+    #[serde(default)]
+    pub shipping: Vec<ShippingChoice>,
+}
+
+impl CreateOrderRequest {
+    pub fn shipping_address(&self) -> ShippingAddress {
+        ShippingAddress {
+            name: self.shipping_name.trim().to_string(),
+            address_1: self.shipping_address_1.trim().to_string(),
+            address_2: self.shipping_address_2.as_ref().map(|a| a.trim().to_string()),
+            city: self.shipping_city.trim().to_string(),
+            postal_code: self.shipping_postal_code.as_ref().map(|c| c.trim().to_string()),
+            country: self.shipping_country.trim().to_string(),
+            phone: self.shipping_phone.as_ref().map(|p| p.trim().to_string()),
+        }
+    }
+}
+
+// Delivery details stored with the order as a JSON document
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShippingAddress {
+    pub name: String,
+    pub address_1: String,
+    pub address_2: Option<String>,
+    pub city: String,
+    pub postal_code: Option<String>,
+    pub country: String,
+    pub phone: Option<String>,
+}
+
+// Which part of the order arrived, when the buyer confirms them one at a time
+#[derive(Debug, Deserialize)]
+pub struct ConfirmReceiptQuery {
+    pub seller_order_id: Option<Uuid>,
+}
+
+// Filters and paging for order lists
+#[derive(Debug, Deserialize)]
+pub struct OrderListOptions {
+    pub page: Option<u64>,
+    pub per_page: Option<u64>,
+    pub status: Option<String>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    // Order id (or the start of it) or the buyer's phone number
+    pub search: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Validate)]
 pub struct UpdateOrderStatusRequest {
     pub status: OrderStatus,
+
+    // Needed by admins on orders with several sellers
+    pub seller_order_id: Option<Uuid>,
+
+    #[validate(length(min = 1, max = 100, message = "Tracking number must be between 1 and 100 characters"))]
+    pub tracking_number: Option<String>,
+
+    #[validate(length(min = 1, max = 100, message = "Shipping provider must be between 1 and 100 characters"))]
+    pub shipping_provider: Option<String>,
+
+    #[validate(length(max = 500, message = "Note must be at most 500 characters"))]
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct CancelOrderRequest {
+    pub reason: CancellationReason,
+
+    #[validate(length(max = 500, message = "Note must be at most 500 characters"))]
+    pub note: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -181,30 +525,112 @@ pub struct OrderResponse {
     pub id: Uuid,
     pub buyer_id: Uuid,
     pub buyer_name: String,
-    pub total_amount: f64,
+    pub subtotal: Money,
+    pub discount_amount: Money,
+    pub shipping_amount: Money,
+    pub tax_amount: Money,
+    pub total_amount: Money,
+    pub discount_code_id: Option<Uuid>,
     pub status: OrderStatus,
     pub payment_status: PaymentStatus,
-    pub shipping_name: String,
-    pub shipping_address_1: String,
-    pub shipping_address_2: Option<String>,
-    pub shipping_city: String,
-    pub shipping_postal_code: Option<String>,
-    pub shipping_country: String,
-    pub shipping_phone: Option<String>,
+    pub shipping_address: ShippingAddress,
     pub payment_method: PaymentMethod,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    pub seller_orders: Vec<SellerOrderResponse>,
     pub items: Vec<OrderItemResponse>,
 }
 
+// One seller's share of an order, shipped and paid out on its own
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SellerOrderResponse {
+    pub id: Uuid,
+    pub seller_id: Uuid,
+    pub seller_name: String,
+    pub status: OrderStatus,
+    pub subtotal: Money,
+    pub discount_amount: Money,
+    pub shipping_amount: Money,
+    pub tax_amount: Money,
+    pub total_amount: Money,
+    pub fulfillment_status: FulfillmentStatus,
+    pub shipping_method: ShippingMethod,
+    // Empty for pickup in store
+    pub shipping_zone: Option<ShippingZone>,
+    pub tracking_number: Option<String>,
+    pub shipping_provider: Option<String>,
+    pub shipped_at: Option<DateTime<Utc>>,
+    pub delivered_at: Option<DateTime<Utc>>,
+    pub payout_status: PayoutStatus,
+    pub payout_amount: Money,
+    pub paid_out_at: Option<DateTime<Utc>>,
+    pub cancellation_reason: Option<CancellationReason>,
+    pub cancellation_note: Option<String>,
+    pub canceled_by: Option<OrderActor>,
+    pub canceled_at: Option<DateTime<Utc>>,
+    // Cash on delivery only, and only shown to the buyer
+    pub delivery_code: Option<String>,
+    pub cod_collected_amount: Option<Money>,
+    pub cod_collected_at: Option<DateTime<Utc>>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct OrderItemResponse {
     pub id: Uuid,
+    pub seller_order_id: Uuid,
     pub product_id: Uuid,
     pub product_title: String,
     pub product_image: String,
     pub quantity: i32,
-    pub unit_price: f64,
+    pub unit_price: Money,
     pub seller_id: Uuid,
     pub seller_name: String,
 }
+
+// Why a line from a past order did not go back into the cart as it was
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ReorderIssue {
+    Unavailable,
+    OutOfStock,
+    QuantityReduced { available: i32 },
+    PriceChanged { previous_price: Money, current_price: Money },
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReorderItemResult {
+    pub product_id: Uuid,
+    pub product_title: String,
+    pub ordered_quantity: i32,
+    pub added_quantity: i32,
+    pub issues: Vec<ReorderIssue>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReorderResponse {
+    pub items: Vec<ReorderItemResult>,
+    pub added_items: usize,
+    pub cart: CartResponse,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BuyAgainQuery {
+    pub page: Option<u64>,
+    pub per_page: Option<u64>,
+}
+
+// A product the buyer has ordered before, with what it costs now
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BuyAgainItem {
+    pub product_id: Uuid,
+    pub product_title: String,
+    pub product_image: String,
+    pub seller_id: Uuid,
+    pub last_price: Money,
+    pub current_price: Money,
+    pub times_ordered: i64,
+    pub last_quantity: i32,
+    pub last_ordered_at: DateTime<Utc>,
+    pub available: bool,
+    pub available_stock: i32,
+}
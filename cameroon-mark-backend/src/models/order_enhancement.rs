@@ -80,25 +80,6 @@ pub struct OrderFulfillmentRequest {
     pub notes: Option<String>,
 }
 
-// Shipping label generation request
-#[derive(Debug, Deserialize, Validate)]
-pub struct ShippingLabelRequest {
-    pub order_id: Uuid,
-    pub shipping_provider: String,
-    pub service_type: String, // e.g., "standard", "express"
-    
-    #[validate]
-    pub recipient_address: ShippingAddress,
-    
-    #[validate]
-    pub sender_address: ShippingAddress,
-    
-    pub package_weight: f64,
-    pub package_dimensions: Option<String>,
-    pub insurance_amount: Option<f64>,
-    pub signature_required: bool,
-}
-
 // Shipping address for label generation
 #[derive(Debug, Serialize, Deserialize, Validate)]
 pub struct ShippingAddress {
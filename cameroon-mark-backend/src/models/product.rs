@@ -26,6 +26,23 @@ fn validate_optional_price(value: &BigDecimal) -> Result<(), ValidationError> {
     Ok(())
 }
 
+// Whether a listing can currently be bought
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ProductStatus {
+    Active,
+    Inactive,
+}
+
+impl std::fmt::Display for ProductStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProductStatus::Active => write!(f, "active"),
+            ProductStatus::Inactive => write!(f, "inactive"),
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ImageArray(pub Vec<String>);
 
@@ -110,8 +127,11 @@ pub struct Product {
     pub seller_id: Uuid,
     pub stock: i32,
     pub location: String,
+    pub weight_grams: i32,
     pub featured: bool,
     pub rating: Option<f64>,
+    pub view_count: i64,
+    pub status: String,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -128,8 +148,11 @@ impl From<product::Model> for Product {
             seller_id: model.seller_id,
             stock: model.stock,
             location: model.location,
+            weight_grams: model.weight_grams,
             featured: model.featured,
             rating: model.rating.map(|r| r as f64),
+            view_count: model.view_count,
+            status: model.status,
             created_at: model.created_at,
             updated_at: model.updated_at,
         }
@@ -156,6 +179,10 @@ pub struct CreateProductRequest {
     pub images: ImageArray,
     
     pub location: Option<String>,
+
+    // Shipping weight of one unit, 1 kg when not given
+    #[validate(range(min = 1, max = 1000000, message = "Weight must be between 1 g and 1000 kg"))]
+    pub weight_grams: Option<i32>,
     
     pub featured: Option<bool>,
 }
@@ -186,8 +213,13 @@ pub struct UpdateProductRequest {
     pub images: Option<ImageArray>,
     
     pub location: Option<String>,
+
+    #[validate(range(min = 1, max = 1000000, message = "Weight must be between 1 g and 1000 kg"))]
+    pub weight_grams: Option<i32>,
     
     pub featured: Option<bool>,
+
+    pub status: Option<ProductStatus>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -201,8 +233,11 @@ pub struct ProductResponse {
     pub stock: i32,
     pub images: Vec<String>,
     pub location: String,
+    pub weight_grams: i32,
     pub featured: bool,
     pub rating: Option<f64>,
+    pub view_count: i64,
+    pub status: String,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -219,8 +254,11 @@ impl From<Product> for ProductResponse {
             stock: product.stock,
             images: product.images.0,
             location: product.location,
+            weight_grams: product.weight_grams,
             featured: product.featured,
             rating: product.rating,
+            view_count: product.view_count,
+            status: product.status,
             created_at: product.created_at,
             updated_at: product.updated_at,
         }
@@ -252,6 +290,11 @@ impl Default for ProductFilterOptions {
     }
 }
 
+#[derive(Debug, Deserialize)]
+pub struct RecentlyViewedQuery {
+    pub limit: Option<u64>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProductSummary {
     pub id: Uuid,
@@ -0,0 +1,45 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use validator::Validate;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ProductQuestion {
+    pub id: Uuid,
+    pub product_id: Uuid,
+    pub user_id: Uuid,
+    pub user_name: String,
+    pub question: String,
+    pub answers: Vec<ProductAnswer>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ProductAnswer {
+    pub id: Uuid,
+    pub question_id: Uuid,
+    pub user_id: Uuid,
+    pub user_name: String,
+    pub answer: String,
+    pub is_seller: bool,
+    pub upvotes: i32,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct AskQuestionRequest {
+    #[validate(length(min = 5, max = 500, message = "Question must be between 5 and 500 characters"))]
+    pub question: String,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct AnswerQuestionRequest {
+    #[validate(length(min = 1, max = 1000, message = "Answer must be between 1 and 1000 characters"))]
+    pub answer: String,
+}
+
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct QuestionListOptions {
+    pub page: Option<u64>,
+    pub per_page: Option<u64>,
+}
@@ -1,3 +1,4 @@
+use bigdecimal::BigDecimal;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
@@ -11,19 +12,31 @@ pub struct SavedItem {
     pub id: Uuid,
     pub user_id: Uuid,
     pub product_id: Uuid,
+    pub wishlist_id: Uuid,
+    pub price_at_save: Option<BigDecimal>,
+    pub alerts_enabled: bool,
     pub created_at: DateTime<Utc>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SavedItemWithProduct {
     pub id: Uuid,
+    pub wishlist_id: Uuid,
     pub product: ProductSummary,
+    pub price_at_save: Option<BigDecimal>,
+    pub alerts_enabled: bool,
     pub created_at: DateTime<Utc>,
 }
 
 #[derive(Debug, Deserialize, Validate)]
 pub struct AddSavedItemRequest {
     pub product_id: Uuid,
+    pub alerts_enabled: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct UpdateSavedItemAlertsRequest {
+    pub alerts_enabled: bool,
 }
 
 impl From<saved_item::Model> for SavedItem {
@@ -32,6 +45,9 @@ impl From<saved_item::Model> for SavedItem {
             id: model.id,
             user_id: model.user_id,
             product_id: model.product_id,
+            wishlist_id: model.wishlist_id,
+            price_at_save: model.price_at_save,
+            alerts_enabled: model.alerts_enabled,
             created_at: model.created_at,
         }
     }
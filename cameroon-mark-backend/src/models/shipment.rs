@@ -0,0 +1,133 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use uuid::Uuid;
+use validator::Validate;
+
+// Where a parcel is according to its carrier
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ShipmentStatus {
+    Created,
+    InTransit,
+    OutForDelivery,
+    Delivered,
+    // A delivery attempt failed; the carrier usually tries again
+    Failed,
+    Returned,
+    Canceled,
+}
+
+impl ShipmentStatus {
+    // Parcels in these states are no longer tracked
+    pub fn is_final(&self) -> bool {
+        matches!(self, ShipmentStatus::Delivered | ShipmentStatus::Returned | ShipmentStatus::Canceled)
+    }
+}
+
+impl std::fmt::Display for ShipmentStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ShipmentStatus::Created => write!(f, "created"),
+            ShipmentStatus::InTransit => write!(f, "in_transit"),
+            ShipmentStatus::OutForDelivery => write!(f, "out_for_delivery"),
+            ShipmentStatus::Delivered => write!(f, "delivered"),
+            ShipmentStatus::Failed => write!(f, "failed"),
+            ShipmentStatus::Returned => write!(f, "returned"),
+            ShipmentStatus::Canceled => write!(f, "canceled"),
+        }
+    }
+}
+
+impl FromStr for ShipmentStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "created" => Ok(ShipmentStatus::Created),
+            "in_transit" => Ok(ShipmentStatus::InTransit),
+            "out_for_delivery" => Ok(ShipmentStatus::OutForDelivery),
+            "delivered" => Ok(ShipmentStatus::Delivered),
+            "failed" => Ok(ShipmentStatus::Failed),
+            "returned" => Ok(ShipmentStatus::Returned),
+            "canceled" => Ok(ShipmentStatus::Canceled),
+            _ => Err(format!("Unknown shipment status: {}", s)),
+        }
+    }
+}
+
+// Where a tracking event came from
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum TrackingSource {
+    // Returned when the shipment was created
+    Carrier,
+    Webhook,
+    TrackingJob,
+}
+
+impl std::fmt::Display for TrackingSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TrackingSource::Carrier => write!(f, "carrier"),
+            TrackingSource::Webhook => write!(f, "webhook"),
+            TrackingSource::TrackingJob => write!(f, "tracking_job"),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ShipmentEvent {
+    pub status: ShipmentStatus,
+    pub description: Option<String>,
+    pub location: Option<String>,
+    pub occurred_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Shipment {
+    pub id: Uuid,
+    pub order_id: Uuid,
+    pub seller_order_id: Uuid,
+    pub carrier: String,
+    pub carrier_name: String,
+    pub tracking_number: String,
+    pub status: ShipmentStatus,
+    pub weight_grams: i32,
+    pub created_at: DateTime<Utc>,
+    pub canceled_at: Option<DateTime<Utc>>,
+    // Oldest first
+    pub events: Vec<ShipmentEvent>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateShipmentRequest {
+    // Needed by admins, and on orders the seller shares with others it is inferred
+    pub seller_order_id: Option<Uuid>,
+    // The carrier of the rate the buyer chose when empty
+    #[validate(length(min = 1, max = 30, message = "Carrier must be between 1 and 30 characters"))]
+    pub carrier: Option<String>,
+    // For sellers' own couriers; other carriers issue their own
+    #[validate(length(min = 1, max = 100, message = "Tracking number must be between 1 and 100 characters"))]
+    pub tracking_number: Option<String>,
+    // The products' weight when empty
+    #[validate(range(min = 1, max = 1000000, message = "Weight must be between 1 g and 1000 kg"))]
+    pub weight_grams: Option<i32>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ShipmentQuery {
+    pub seller_order_id: Option<Uuid>,
+}
+
+// Token the carrier was given for a shipment, sent back on the webhook URL
+#[derive(Debug, Deserialize)]
+pub struct TrackingWebhookQuery {
+    pub token: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TrackingWebhookAck {
+    // Events we did not have yet
+    pub recorded: usize,
+}
@@ -0,0 +1,191 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::models::money::Money;
+
+// Where a parcel goes relative to where the seller ships from
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ShippingZone {
+    SameCity,
+    DoualaYaounde,
+    OtherRegions,
+    International,
+}
+
+impl std::fmt::Display for ShippingZone {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ShippingZone::SameCity => write!(f, "same_city"),
+            ShippingZone::DoualaYaounde => write!(f, "douala_yaounde"),
+            ShippingZone::OtherRegions => write!(f, "other_regions"),
+            ShippingZone::International => write!(f, "international"),
+        }
+    }
+}
+
+impl FromStr for ShippingZone {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "same_city" => Ok(ShippingZone::SameCity),
+            "douala_yaounde" => Ok(ShippingZone::DoualaYaounde),
+            "other_regions" => Ok(ShippingZone::OtherRegions),
+            "international" => Ok(ShippingZone::International),
+            _ => Err(format!("Unknown shipping zone: {}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ShippingMethod {
+    Delivery,
+    Pickup,
+}
+
+impl std::fmt::Display for ShippingMethod {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ShippingMethod::Delivery => write!(f, "delivery"),
+            ShippingMethod::Pickup => write!(f, "pickup"),
+        }
+    }
+}
+
+impl FromStr for ShippingMethod {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "delivery" => Ok(ShippingMethod::Delivery),
+            "pickup" => Ok(ShippingMethod::Pickup),
+            _ => Err(format!("Unknown shipping method: {}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ShippingRate {
+    pub id: Uuid,
+    // A marketplace default when empty
+    pub seller_id: Option<Uuid>,
+    pub name: String,
+    pub carrier: String,
+    pub zone: ShippingZone,
+    pub min_weight_grams: i32,
+    pub max_weight_grams: Option<i32>,
+    pub price: Money,
+    pub free_above: Option<Money>,
+    pub delivery_days: Option<i32>,
+    pub is_active: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateShippingRateRequest {
+    #[validate(length(min = 1, max = 100, message = "Name must be between 1 and 100 characters"))]
+    pub name: String,
+    // The seller's own couriers when empty
+    #[validate(length(min = 1, max = 30, message = "Carrier must be between 1 and 30 characters"))]
+    pub carrier: Option<String>,
+    pub zone: ShippingZone,
+    #[validate(range(min = 0, message = "Weights cannot be negative"))]
+    pub min_weight_grams: Option<i32>,
+    // No upper limit when empty
+    #[validate(range(min = 1, message = "Weights must be positive"))]
+    pub max_weight_grams: Option<i32>,
+    pub price: Money,
+    pub free_above: Option<Money>,
+    #[validate(range(min = 0, max = 90, message = "Delivery time must be between 0 and 90 days"))]
+    pub delivery_days: Option<i32>,
+}
+
+// Orders keep the shipping price worked out when they were placed
+#[derive(Debug, Deserialize, Validate)]
+pub struct UpdateShippingRateRequest {
+    #[validate(length(min = 1, max = 100, message = "Name must be between 1 and 100 characters"))]
+    pub name: Option<String>,
+    #[validate(length(min = 1, max = 30, message = "Carrier must be between 1 and 30 characters"))]
+    pub carrier: Option<String>,
+    #[validate(range(min = 0, message = "Weights cannot be negative"))]
+    pub min_weight_grams: Option<i32>,
+    #[validate(range(min = 1, message = "Weights must be positive"))]
+    pub max_weight_grams: Option<i32>,
+    pub price: Option<Money>,
+    pub free_above: Option<Money>,
+    #[validate(range(min = 0, max = 90, message = "Delivery time must be between 0 and 90 days"))]
+    pub delivery_days: Option<i32>,
+    pub is_active: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ShippingRateQuery {
+    pub zone: Option<ShippingZone>,
+    // The marketplace defaults rather than the seller's own rates
+    pub defaults: Option<bool>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ShippingSettings {
+    pub seller_id: Uuid,
+    pub ship_from_city: Option<String>,
+    pub pickup_enabled: bool,
+    pub pickup_address: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct UpdateShippingSettingsRequest {
+    #[validate(length(min = 2, max = 100, message = "City must be between 2 and 100 characters"))]
+    pub ship_from_city: Option<String>,
+    pub pickup_enabled: Option<bool>,
+    #[validate(length(min = 5, max = 500, message = "Pickup address must be between 5 and 500 characters"))]
+    pub pickup_address: Option<String>,
+}
+
+// Where the buyer wants their order delivered
+#[derive(Debug, Deserialize, Validate)]
+pub struct ShippingQuoteQuery {
+    #[validate(length(min = 2, max = 100, message = "City must be between 2 and 100 characters"))]
+    pub city: String,
+    // Cameroon when empty
+    pub country: Option<String>,
+}
+
+// One way a seller's items can reach the buyer
+#[derive(Debug, Serialize, Clone)]
+pub struct ShippingOption {
+    pub method: ShippingMethod,
+    // Empty for pickup
+    pub rate_id: Option<Uuid>,
+    pub name: String,
+    pub carrier: Option<String>,
+    pub price: Money,
+    // Free because the goods reached the rate's threshold
+    pub free_shipping: bool,
+    pub delivery_days: Option<i32>,
+    pub pickup_address: Option<String>,
+}
+
+// Shipping options for one seller's sub-order, cheapest first
+#[derive(Debug, Serialize)]
+pub struct SellerShippingQuote {
+    pub seller_id: Uuid,
+    pub zone: ShippingZone,
+    pub weight_grams: i64,
+    pub options: Vec<ShippingOption>,
+}
+
+// The buyer's pick for one seller at checkout
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ShippingChoice {
+    pub seller_id: Uuid,
+    // The cheapest delivery option when empty
+    pub rate_id: Option<Uuid>,
+    #[serde(default)]
+    pub pickup: bool,
+}
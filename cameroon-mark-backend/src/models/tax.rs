@@ -0,0 +1,51 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use validator::Validate;
+
+#[derive(Debug, Serialize)]
+pub struct TaxRule {
+    pub id: Uuid,
+    pub name: String,
+    pub category_id: Option<Uuid>,
+    // e.g. 0.1925 for 19.25%, 0 for exempt categories
+    pub rate: f64,
+    pub prices_include_tax: bool,
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateTaxRuleRequest {
+    #[validate(length(min = 1, max = 100, message = "Name must be between 1 and 100 characters"))]
+    pub name: String,
+    // Every category when empty
+    pub category_id: Option<Uuid>,
+    #[validate(range(min = 0.0, max = 0.99, message = "Rate must be between 0 and 0.99"))]
+    pub rate: f64,
+    #[serde(default)]
+    pub prices_include_tax: bool,
+    // Now when empty
+    pub starts_at: Option<DateTime<Utc>>,
+    pub ends_at: Option<DateTime<Utc>>,
+}
+
+// Orders keep the VAT worked out when they were placed
+#[derive(Debug, Deserialize, Validate)]
+pub struct UpdateTaxRuleRequest {
+    #[validate(length(min = 1, max = 100, message = "Name must be between 1 and 100 characters"))]
+    pub name: Option<String>,
+    #[validate(range(min = 0.0, max = 0.99, message = "Rate must be between 0 and 0.99"))]
+    pub rate: Option<f64>,
+    pub prices_include_tax: Option<bool>,
+    pub starts_at: Option<DateTime<Utc>>,
+    pub ends_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TaxRuleQuery {
+    pub category_id: Option<Uuid>,
+    // Only rules in force now
+    pub active: Option<bool>,
+}
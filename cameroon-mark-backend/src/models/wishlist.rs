@@ -0,0 +1,83 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::entities::wishlist;
+use crate::models::saved_item::SavedItemWithProduct;
+
+// A named list of saved products owned by a buyer
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Wishlist {
+    pub id: Uuid,
+    pub name: String,
+    pub is_public: bool,
+    pub is_default: bool,
+    pub share_slug: String,
+    pub item_count: u64,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl Wishlist {
+    pub fn from_model(model: wishlist::Model, item_count: u64) -> Self {
+        Self {
+            id: model.id,
+            name: model.name,
+            is_public: model.is_public,
+            is_default: model.is_default,
+            share_slug: model.share_slug,
+            item_count,
+            created_at: model.created_at,
+            updated_at: model.updated_at,
+        }
+    }
+}
+
+// A wishlist together with its items
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WishlistWithItems {
+    #[serde(flatten)]
+    pub wishlist: Wishlist,
+    pub items: Vec<SavedItemWithProduct>,
+}
+
+// Read-only view of a public wishlist opened through its share link
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SharedWishlist {
+    pub name: String,
+    pub owner_name: String,
+    pub items: Vec<SavedItemWithProduct>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateWishlistRequest {
+    #[validate(length(min = 1, max = 100, message = "Name must be between 1 and 100 characters"))]
+    pub name: String,
+    pub is_public: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct UpdateWishlistRequest {
+    #[validate(length(min = 1, max = 100, message = "Name must be between 1 and 100 characters"))]
+    pub name: Option<String>,
+    pub is_public: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct AddWishlistItemRequest {
+    pub product_id: Uuid,
+    pub alerts_enabled: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct MoveWishlistItemRequest {
+    pub target_wishlist_id: Uuid,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct WishlistToCartRequest {
+    #[validate(range(min = 1, message = "Quantity must be at least 1"))]
+    pub quantity: Option<i32>,
+}
@@ -1,10 +1,11 @@
 use axum::{
-    routing::{get, post},
+    routing::{get, post, put},
     Router,
 };
 use std::sync::Arc;
 
 use crate::handlers::admin::*;  // Using glob import to include all admin handlers
+use crate::handlers::{commission, payment, tax, wallet};
 use crate::middlewares::auth::{ExtractUserId, RequireAdmin};
 use crate::app_state::AppState;
 
@@ -15,9 +16,23 @@ pub fn routes() -> Router<Arc<AppState>> {
         .route("/sellers/pending", get(get_pending_sellers))
         .route("/sellers/:seller_id/approve", post(approve_seller))
         .route("/sellers/:seller_id/reject", post(reject_seller))
+        .route("/sellers/:seller_id/tier", put(commission::set_seller_tier))
         .route("/reports", get(get_reported_items))
         .route("/reports/:report_id/delete", post(delete_reported_item))
         .route("/reports/:report_id/ignore", post(ignore_reported_item))
+        .route("/payments/settlements", post(payment::import_settlements))
+        .route(
+            "/payments/reconciliation",
+            get(payment::get_reconciliation_reports).post(payment::run_reconciliation),
+        )
+        .route("/commission-rules", get(commission::get_rules).post(commission::create_rule))
+        .route("/commission-rules/:id", put(commission::update_rule).delete(commission::end_rule))
+        .route("/tax-rules", get(tax::get_rules).post(tax::create_rule))
+        .route("/tax-rules/:id", put(tax::update_rule).delete(tax::end_rule))
+        .route("/withdrawals", get(wallet::list_withdrawals))
+        .route("/withdrawals/:id/approve", post(wallet::approve_withdrawal))
+        .route("/withdrawals/:id/reject", post(wallet::reject_withdrawal))
+        .route("/withdrawals/:id/mark-paid", post(wallet::mark_withdrawal_paid))
         .route_layer(axum::middleware::from_extractor::<RequireAdmin>());
 
     // User routes - for reporting items
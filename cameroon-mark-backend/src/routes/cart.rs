@@ -5,15 +5,21 @@ use axum::{
 use std::sync::Arc;
 
 use crate::handlers::cart;
-use crate::middlewares::auth::ExtractUserId;
+use crate::middlewares::auth::ExtractCartOwner;
 use crate::AppState;
 
 pub fn routes() -> Router<Arc<AppState>> {
-    Router::new()
+    // Signed-in users use their account cart, guests send X-Cart-Token
+    let cart_routes = Router::new()
         .route("/", get(cart::get_cart))
+        .route("/shipping", get(cart::get_shipping_quotes))
         .route("/items", post(cart::add_to_cart))
         .route("/items/:id", put(cart::update_cart_item))
         .route("/items/:id", delete(cart::remove_from_cart))
         .route("/", delete(cart::clear_cart))
-        .route_layer(axum::middleware::from_extractor::<ExtractUserId>())
+        .route_layer(axum::middleware::from_extractor::<ExtractCartOwner>());
+
+    Router::new()
+        .route("/guest", post(cart::create_guest_cart))
+        .merge(cart_routes)
 }
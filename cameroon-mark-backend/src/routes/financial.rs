@@ -0,0 +1,23 @@
+use axum::{
+    routing::{get, post},
+    Router,
+};
+use std::sync::Arc;
+
+use crate::handlers::financial;
+use crate::middlewares::auth::ExtractUserId;
+use crate::AppState;
+
+pub fn routes() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/payments", post(financial::process_payment))
+        .route("/refunds", get(financial::get_refunds).post(financial::process_refund))
+        .route("/refunds/:id/retry", post(financial::retry_refund))
+        .route("/refunds/:id/mark-paid", post(financial::mark_refund_paid))
+        .route("/taxes/calculate", post(financial::calculate_taxes))
+        .route("/expenses", get(financial::get_expenses).post(financial::track_expense))
+        .route("/revenue", get(financial::get_revenue_report))
+        .route("/profit-margins", get(financial::get_profit_margins))
+        .route("/statements", get(financial::get_financial_statement))
+        .route_layer(axum::middleware::from_extractor::<ExtractUserId>())
+}
@@ -10,6 +10,13 @@ pub mod saved_item;
 pub mod analytics;
 pub mod admin;
 pub mod marketing;
+pub mod notification;
+pub mod product_question;
+pub mod wishlist;
+pub mod payment;
+pub mod financial;
+pub mod wallet;
+pub mod shipping;
 
 use axum::{
     routing::{get, post, put, delete},
@@ -0,0 +1,17 @@
+use axum::{
+    routing::{get, put},
+    Router,
+};
+use std::sync::Arc;
+
+use crate::handlers::notification;
+use crate::middlewares::auth::ExtractUserId;
+use crate::AppState;
+
+pub fn routes() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/", get(notification::get_notifications))
+        .route("/preferences", get(notification::get_preferences).put(notification::update_preferences))
+        .route("/:id/read", put(notification::mark_as_read))
+        .route_layer(axum::middleware::from_extractor::<ExtractUserId>())
+}
@@ -4,19 +4,34 @@ use axum::{
 };
 use std::sync::Arc;
 
-use crate::handlers::order;
+use crate::handlers::{cod, invoice, order, shipment};
 use crate::middlewares::auth::{ExtractUserId, ExtractUserRole};
 use crate::AppState;
 
 pub fn routes() -> Router<Arc<AppState>> {
     let seller_routes = Router::new()
         .route("/:id/status", put(order::update_order_status))
+        .route("/:id/confirm-delivery", post(cod::confirm_delivery))
+        .route("/cod/summary", get(cod::get_summary))
         .route_layer(axum::middleware::from_extractor::<ExtractUserRole>());
         
     Router::new()
         .route("/", post(order::create_order))
         .route("/", get(order::get_orders))
+        .route("/buy-again", get(order::get_buy_again))
         .route("/:id", get(order::get_order))
+        .route("/:id/history", get(order::get_order_history))
+        .route("/:id/cancel", post(order::cancel_order))
+        .route("/:id/confirm-receipt", post(order::confirm_receipt))
+        .route("/:id/invoice", get(invoice::get_invoice))
+        .route(
+            "/:id/shipment",
+            get(shipment::get_shipment)
+                .post(shipment::create_shipment)
+                .delete(shipment::cancel_shipment),
+        )
+        .route("/:id/shipment/label", get(shipment::get_label))
+        .route("/:id/reorder", post(order::reorder))
         .merge(seller_routes)
         .route_layer(axum::middleware::from_extractor::<ExtractUserId>())
 }
@@ -0,0 +1,15 @@
+use axum::{
+    routing::post,
+    Router,
+};
+use std::sync::Arc;
+
+use crate::handlers::payment;
+use crate::AppState;
+
+// Provider callbacks carry their own proof of origin instead of a user token
+pub fn routes() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/webhooks/mtn", post(payment::mtn_webhook).put(payment::mtn_webhook))
+        .route("/webhooks/orange", post(payment::orange_webhook))
+}
@@ -4,7 +4,7 @@ use axum::{
 };
 use std::sync::Arc;
 
-use crate::handlers::product;
+use crate::handlers::{product, product_question};
 use crate::middlewares::auth::{ExtractUserId, ExtractUserRole, require_seller};
 use crate::AppState;
 
@@ -18,5 +18,6 @@ pub fn routes() -> Router<Arc<AppState>> {
     Router::new()
         .route("/", get(product::get_products))
         .route("/:id", get(product::get_product))
+        .route("/:id/questions", get(product_question::get_product_questions).post(product_question::ask_question))
         .merge(seller_routes)
 }
@@ -0,0 +1,18 @@
+use axum::{
+    routing::{delete, post},
+    Router,
+};
+use std::sync::Arc;
+
+use crate::handlers::product_question;
+use crate::middlewares::auth::ExtractUserId;
+use crate::AppState;
+
+pub fn routes() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/:id", delete(product_question::delete_question))
+        .route("/:id/answers", post(product_question::answer_question))
+        .route("/answers/:answer_id/upvote", post(product_question::upvote_answer))
+        .route("/answers/:answer_id/upvote", delete(product_question::remove_upvote))
+        .route_layer(axum::middleware::from_extractor::<ExtractUserId>())
+}
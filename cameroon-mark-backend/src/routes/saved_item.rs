@@ -1,5 +1,5 @@
 use axum::{
-    routing::{get, post, delete},
+    routing::{get, post, put, delete},
     Router,
 };
 use std::sync::Arc;
@@ -13,5 +13,6 @@ pub fn routes() -> Router<Arc<AppState>> {
         .route("/", get(saved_item::get_saved_items))
         .route("/", post(saved_item::add_saved_item))
         .route("/:product_id", delete(saved_item::remove_saved_item))
+        .route("/:product_id/alerts", put(saved_item::update_alerts))
         .route_layer(axum::middleware::from_extractor::<ExtractUserId>())
 }
\ No newline at end of file
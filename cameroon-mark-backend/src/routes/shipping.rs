@@ -0,0 +1,21 @@
+use axum::{
+    routing::{get, post, put},
+    Router,
+};
+use std::sync::Arc;
+
+use crate::handlers::{shipment, shipping};
+use crate::middlewares::auth::ExtractUserId;
+use crate::AppState;
+
+pub fn routes() -> Router<Arc<AppState>> {
+    // Carriers authenticate with the token they were given for each shipment
+    let webhook_routes = Router::new().route("/webhooks/:carrier", post(shipment::tracking_webhook));
+
+    Router::new()
+        .route("/rates", get(shipping::get_rates).post(shipping::create_rate))
+        .route("/rates/:id", put(shipping::update_rate).delete(shipping::remove_rate))
+        .route("/settings", get(shipping::get_settings).put(shipping::update_settings))
+        .route_layer(axum::middleware::from_extractor::<ExtractUserId>())
+        .merge(webhook_routes)
+}
@@ -4,7 +4,7 @@ use axum::{
 };
 use std::sync::Arc;
 
-use crate::handlers::user;
+use crate::handlers::{cod, invoice, product, user};
 use crate::middlewares::auth::ExtractUserId;
 use crate::AppState;
 
@@ -12,6 +12,9 @@ pub fn routes() -> Router<Arc<AppState>> {
     Router::new()
         .route("/profile", get(user::get_profile).put(user::update_profile))
         .route("/me/password", put(user::change_password))
+        .route("/me/recently-viewed", get(product::get_recently_viewed))
+        .route("/me/cod-settings", get(cod::get_settings).put(cod::update_settings))
+        .route("/me/tax-profile", get(invoice::get_tax_profile).put(invoice::update_tax_profile))
         .route("/me/address", get(user::get_user_address_handler).put(user::update_user_address_handler))
         .route_layer(axum::middleware::from_extractor::<ExtractUserId>())
 }
@@ -0,0 +1,17 @@
+use axum::{
+    routing::get,
+    Router,
+};
+use std::sync::Arc;
+
+use crate::handlers::wallet;
+use crate::middlewares::auth::ExtractUserId;
+use crate::AppState;
+
+pub fn routes() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/", get(wallet::get_balance))
+        .route("/transactions", get(wallet::get_transactions))
+        .route("/withdrawals", get(wallet::get_withdrawals).post(wallet::request_withdrawal))
+        .route_layer(axum::middleware::from_extractor::<ExtractUserId>())
+}
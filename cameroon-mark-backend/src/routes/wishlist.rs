@@ -0,0 +1,31 @@
+use axum::{
+    routing::{get, post, delete},
+    Router,
+};
+use std::sync::Arc;
+
+use crate::handlers::wishlist;
+use crate::middlewares::auth::ExtractUserId;
+use crate::AppState;
+
+pub fn routes() -> Router<Arc<AppState>> {
+    let owner_routes = Router::new()
+        .route("/", get(wishlist::get_wishlists).post(wishlist::create_wishlist))
+        .route(
+            "/:id",
+            get(wishlist::get_wishlist)
+                .put(wishlist::update_wishlist)
+                .delete(wishlist::delete_wishlist),
+        )
+        .route("/:id/share-link", post(wishlist::regenerate_share_link))
+        .route("/:id/items", post(wishlist::add_item))
+        .route("/:id/items/:product_id", delete(wishlist::remove_item))
+        .route("/:id/items/:product_id/move", post(wishlist::move_item))
+        .route("/:id/items/:product_id/cart", post(wishlist::add_item_to_cart))
+        .route_layer(axum::middleware::from_extractor::<ExtractUserId>());
+
+    // Anyone with the link can read a public list
+    Router::new()
+        .route("/shared/:slug", get(wishlist::get_shared_wishlist))
+        .merge(owner_routes)
+}
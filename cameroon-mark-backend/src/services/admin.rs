@@ -6,7 +6,7 @@ use uuid::Uuid;
 use chrono::Utc;
 
 use crate::{
-    entities::{user, product, reported_item},
+    entities::{user, product, product_question, reported_item},
     errors::{AppError, Result},
     models::admin::{DashboardStats, PendingSeller, ReportedItem, ReportItemRequest, SellerActionRequest, ReportActionRequest},
     models::user::{User, UserRole},
@@ -153,15 +153,17 @@ pub async fn get_reported_items(db: &DatabaseConnection) -> Result<Vec<ReportedI
         sea_orm::DatabaseBackend::Postgres,
         r#"
         SELECT 
-            r.id, r.product_id, p.name as product_name, 
-            p.user_id as seller_id, s.name as seller_name,
+            r.id, r.product_id, p.title as product_name, 
+            r.question_id, q.question,
+            p.seller_id as seller_id, s.name as seller_name,
             r.user_id as reporter_id, u.name as reporter_name,
             r.reason, r.created_at
         FROM 
             reported_items r
             JOIN products p ON r.product_id = p.id
-            JOIN users s ON p.user_id = s.id
+            JOIN users s ON p.seller_id = s.id
             JOIN users u ON r.user_id = u.id
+            LEFT JOIN product_questions q ON r.question_id = q.id
         WHERE 
             r.status = 'pending'
         ORDER BY 
@@ -180,6 +182,8 @@ pub async fn get_reported_items(db: &DatabaseConnection) -> Result<Vec<ReportedI
             id: row.try_get::<Uuid>("", "id")?,
             product_id: row.try_get::<Uuid>("", "product_id")?,
             product_name: row.try_get::<String>("", "product_name")?,
+            question_id: row.try_get::<Option<Uuid>>("", "question_id")?,
+            question: row.try_get::<Option<String>>("", "question")?,
             seller_id: row.try_get::<Uuid>("", "seller_id")?,
             seller_name: row.try_get::<String>("", "seller_name")?,
             reporter_id: row.try_get::<Uuid>("", "reporter_id")?,
@@ -193,7 +197,7 @@ pub async fn get_reported_items(db: &DatabaseConnection) -> Result<Vec<ReportedI
     Ok(items)
 }
 
-// Report a product
+// Report a product or one of its questions
 pub async fn report_item(db: &DatabaseConnection, user_id: Uuid, payload: ReportItemRequest) -> Result<()> {
     // Check if product exists
     let product = product::Entity::find_by_id(payload.product_id)
@@ -201,8 +205,20 @@ pub async fn report_item(db: &DatabaseConnection, user_id: Uuid, payload: Report
         .await?
         .ok_or_else(|| AppError::not_found("Product not found"))?;
     
-    // Prevent reporting own products
-    if product.seller_id == user_id {
+    if let Some(question_id) = payload.question_id {
+        // The question must belong to the reported product
+        let question = product_question::Entity::find_by_id(question_id)
+            .filter(product_question::Column::ProductId.eq(payload.product_id))
+            .one(db)
+            .await?
+            .ok_or_else(|| AppError::not_found("Question not found"))?;
+        
+        // Prevent reporting own questions
+        if question.user_id == user_id {
+            return Err(AppError::bad_request("Cannot report your own question"));
+        }
+    } else if product.seller_id == user_id {
+        // Prevent reporting own products
         return Err(AppError::bad_request("Cannot report your own product"));
     }
     
@@ -211,6 +227,7 @@ pub async fn report_item(db: &DatabaseConnection, user_id: Uuid, payload: Report
         id: Set(Uuid::new_v4()),
         user_id: Set(user_id),
         product_id: Set(payload.product_id),
+        question_id: Set(payload.question_id),
         reason: Set(payload.reason),
         status: Set("pending".to_string()),
         created_at: Set(Utc::now().into()),
@@ -222,12 +239,13 @@ pub async fn report_item(db: &DatabaseConnection, user_id: Uuid, payload: Report
     Ok(())
 }
 
-// Delete a reported item (and the product)
+// Delete a reported item (the question if one was reported, otherwise the product)
 pub async fn delete_reported_item(db: &DatabaseConnection, report_id: Uuid, payload: ReportActionRequest) -> Result<()> {
     let txn = db.begin().await?;
     
-    // Get the report
+    // Get the report, locked so its question cannot be deleted from under it
     let report = reported_item::Entity::find_by_id(report_id)
+        .lock_exclusive()
         .one(&txn)
         .await?
         .ok_or_else(|| AppError::not_found("Report not found"))?;
@@ -237,12 +255,19 @@ pub async fn delete_reported_item(db: &DatabaseConnection, report_id: Uuid, payl
     report_model.status = Set("deleted".to_string());
     report_model.update(&txn).await?;
     
-    // Delete the product
-    product::Entity::delete_by_id(report.product_id)
-        .exec(&txn)
-        .await?;
-    
-    // Here you would ideally notify the seller about their product being removed
+    if let Some(question_id) = report.question_id {
+        // Delete only the abusive question and its answers. Its reports go with it.
+        product_question::Entity::delete_by_id(question_id)
+            .exec(&txn)
+            .await?;
+    } else {
+        // Delete the product
+        product::Entity::delete_by_id(report.product_id)
+            .exec(&txn)
+            .await?;
+        
+        // Here you would ideally notify the seller about their product being removed
+    }
     
     txn.commit().await?;
     
@@ -9,7 +9,7 @@ use bigdecimal::BigDecimal;
 
 use crate::errors::{AppError, Result};
 use crate::models::analytics::{
-    AnalyticsTimeRange, SalesSummary, TopSellingProduct,
+    AnalyticsTimeRange, CancellationStats, SalesSummary, TopSellingProduct,
     MonthlySales, ProductPerformance, SellerAnalytics
 };
 use crate::entities::{order, order_item, product};
@@ -50,11 +50,17 @@ impl AnalyticsService {
                 Ok(order::Model {
                     id: row.try_get("", "id")?,
                     user_id: row.try_get("", "user_id")?,
+                    subtotal: row.try_get("", "subtotal")?,
+                    discount_amount: row.try_get("", "discount_amount")?,
+                    shipping_amount: row.try_get("", "shipping_amount")?,
+                    tax_amount: row.try_get("", "tax_amount")?,
                     total_amount: row.try_get("", "total_amount")?,
+                    discount_code_id: row.try_get("", "discount_code_id")?,
                     status: row.try_get("", "status")?,
                     payment_status: row.try_get("", "payment_status")?,
                     payment_method: row.try_get("", "payment_method")?,
                     shipping_address: row.try_get("", "shipping_address")?,
+                    idempotency_key: row.try_get("", "idempotency_key")?,
                     created_at: row.try_get("", "created_at")?,
                     updated_at: row.try_get("", "updated_at")?,
                 })
@@ -70,13 +76,61 @@ impl AnalyticsService {
         // Get top products
         let top_products = Self::get_top_products(db, seller_id, &time_range).await?;
 
+        let cancellations = Self::get_cancellation_stats(db, seller_id, &time_range).await?;
+
         Ok(SellerAnalytics {
             summary,
+            cancellations,
             monthly_sales,
             top_products,
         })
     }
 
+    pub async fn get_cancellation_stats(
+        db: &DatabaseConnection,
+        seller_id: Uuid,
+        time_range: &AnalyticsTimeRange,
+    ) -> Result<CancellationStats> {
+        let query = Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            r#"
+            SELECT
+                COUNT(*) AS total_orders,
+                COUNT(*) FILTER (WHERE status = 'canceled' AND canceled_by = 'seller') AS canceled_by_seller,
+                COUNT(*) FILTER (WHERE status = 'canceled' AND canceled_by = 'buyer') AS canceled_by_buyer
+            FROM seller_orders
+            WHERE seller_id = $1
+            AND created_at BETWEEN $2 AND $3
+            "#,
+            vec![
+                seller_id.into(),
+                time_range.start_date.into(),
+                time_range.end_date.into(),
+            ]
+        );
+
+        let row = db.query_one(query)
+            .await?
+            .ok_or_else(|| AppError::internal("Failed to compute cancellation stats"))?;
+
+        let total_orders: i64 = row.try_get("", "total_orders")?;
+        let canceled_by_seller: i64 = row.try_get("", "canceled_by_seller")?;
+        let canceled_by_buyer: i64 = row.try_get("", "canceled_by_buyer")?;
+
+        let seller_cancellation_rate = if total_orders > 0 {
+            canceled_by_seller as f64 / total_orders as f64 * 100.0
+        } else {
+            0.0
+        };
+
+        Ok(CancellationStats {
+            total_orders,
+            canceled_by_seller,
+            canceled_by_buyer,
+            seller_cancellation_rate,
+        })
+    }
+
     async fn calculate_summary(
         db: &DatabaseConnection,
         orders: &[order::Model],
@@ -184,13 +238,14 @@ impl AnalyticsService {
                 p.title,
                 COALESCE(SUM(oi.quantity), 0) as total_quantity,
                 COALESCE(SUM(oi.unit_price * oi.quantity), 0) as total_revenue,
-                CAST(0 AS FLOAT8) as average_rating
+                CAST(0 AS FLOAT8) as average_rating,
+                p.view_count
             FROM products p
             LEFT JOIN order_items oi ON p.id = oi.product_id
             LEFT JOIN orders o ON oi.order_id = o.id
             WHERE p.seller_id = $1
             AND (o.created_at IS NULL OR o.created_at BETWEEN $2 AND $3)
-            GROUP BY p.id, p.title
+            GROUP BY p.id, p.title, p.view_count
             ORDER BY total_revenue DESC NULLS LAST
             LIMIT 10
             "#,
@@ -211,6 +266,7 @@ impl AnalyticsService {
                     total_quantity: row.try_get("", "total_quantity")?,
                     total_revenue: row.try_get("", "total_revenue")?,
                     average_rating: Some(row.try_get("", "average_rating")?),
+                    view_count: row.try_get("", "view_count")?,
                 })
             })
             .collect::<Result<Vec<_>>>()?;
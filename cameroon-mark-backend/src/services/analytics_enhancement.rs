@@ -178,6 +178,50 @@ pub async fn get_product_performance(
         top_selling_products.push(product);
     }
     
+    // Query for the most viewed products in the period
+    let most_viewed_query = Statement::from_sql_and_values(
+        sea_orm::DatabaseBackend::Postgres,
+        r#"
+        SELECT
+            p.id,
+            p.title as name,
+            COUNT(pv.id) as view_count,
+            u.id as seller_id,
+            u.name as seller_name
+        FROM
+            product_views pv
+            JOIN products p ON pv.product_id = p.id
+            JOIN users u ON p.seller_id = u.id
+        WHERE
+            pv.viewed_at BETWEEN $1 AND $2
+        GROUP BY
+            p.id, p.title, u.id, u.name
+        ORDER BY
+            view_count DESC
+        LIMIT 10
+        "#,
+        vec![
+            start_date.into(),
+            end_date.into(),
+        ],
+    );
+
+    let rows = db.query_all(most_viewed_query)
+        .await
+        .map_err(|e| AppError::internal(format!("Database error: {}", e)))?;
+
+    let mut most_viewed_products = Vec::new();
+    for row in rows {
+        let product = MostViewedProduct {
+            id: row.try_get("", "id")?,
+            name: row.try_get("", "name")?,
+            view_count: row.try_get("", "view_count")?,
+            seller_id: row.try_get("", "seller_id")?,
+            seller_name: row.try_get("", "seller_name")?,
+        };
+        most_viewed_products.push(product);
+    }
+    
     // For this example, we'll return empty lists for other metrics
     // In a real implementation, you would query each metric separately
     
@@ -185,7 +229,7 @@ pub async fn get_product_performance(
         top_selling_products,
         highest_rated_products: Vec::new(),
         category_performance: Vec::new(),
-        most_viewed_products: Vec::new(),
+        most_viewed_products,
         most_wished_products: Vec::new(),
     };
     
@@ -1,51 +1,429 @@
-use sea_orm::{DatabaseConnection, EntityTrait, QueryFilter, ColumnTrait, Set, ActiveValue, QuerySelect};
-use sea_orm::sea_query::Expr;
+use bigdecimal::BigDecimal;
+use chrono::{Duration, Utc};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, Condition, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder,
+    Set, TransactionTrait,
+};
+use std::collections::HashMap;
 use uuid::Uuid;
 
+use crate::entities::{cart_item, guest_cart, product, user};
 use crate::errors::{AppError, Result};
-use crate::models::cart::{CartResponse, CartItemResponse, AddToCartRequest};
-
-// Get user's cart
-pub async fn get_cart(db: &DatabaseConnection, user_id: Uuid) -> Result<CartResponse> {
-    // This is a placeholder implementation using Sea-ORM
-    // In a real implementation, you would query the cart_items table and join with products
-    
-    // For now, return an empty cart
+use crate::models::cart::{
+    AddToCartRequest, CartItemResponse, CartOwner, CartResponse, CartSellerGroup, CartWarning, GuestCartResponse,
+};
+use crate::models::money::{Currency, Money};
+use crate::models::product::ProductStatus;
+use crate::models::shipping::{SellerShippingQuote, ShippingQuoteQuery};
+use crate::services::shipping::{self, Destination};
+use crate::utils::{jwt, validation};
+
+// Guest carts are removed after this many days without activity
+const GUEST_CART_IDLE_DAYS: i64 = 30;
+
+// Cart tokens outlive a single idle period so an active cart keeps working
+const GUEST_CART_TOKEN_DAYS: i64 = 90;
+
+fn to_money(value: &BigDecimal) -> Result<Money> {
+    Ok(Money::from_decimal(value, Currency::XAF)?)
+}
+
+fn owned_by(owner: CartOwner) -> Condition {
+    match owner {
+        CartOwner::User(user_id) => Condition::all().add(cart_item::Column::UserId.eq(user_id)),
+        CartOwner::Guest(guest_cart_id) => Condition::all().add(cart_item::Column::GuestCartId.eq(guest_cart_id)),
+    }
+}
+
+// Make sure a guest cart still exists and push back its expiry
+async fn touch_guest_cart(db: &DatabaseConnection, owner: CartOwner) -> Result<()> {
+    let CartOwner::Guest(guest_cart_id) = owner else {
+        return Ok(());
+    };
+
+    let now = Utc::now();
+    let cart = guest_cart::Entity::find_by_id(guest_cart_id)
+        .filter(guest_cart::Column::ExpiresAt.gt(now))
+        .one(db)
+        .await?
+        .ok_or_else(|| AppError::not_found("Cart not found or expired"))?;
+
+    let mut cart: guest_cart::ActiveModel = cart.into();
+    cart.expires_at = Set(now + Duration::days(GUEST_CART_IDLE_DAYS));
+    cart.update(db).await?;
+
+    Ok(())
+}
+
+// Start an anonymous cart and hand out its token
+pub async fn create_guest_cart(db: &DatabaseConnection) -> Result<GuestCartResponse> {
+    let now = Utc::now();
+    let cart = guest_cart::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        expires_at: Set(now + Duration::days(GUEST_CART_IDLE_DAYS)),
+        created_at: Set(now),
+        updated_at: Set(now),
+    };
+    let cart = cart.insert(db).await?;
+
+    Ok(GuestCartResponse {
+        cart_token: jwt::generate_cart_token(cart.id, now + Duration::days(GUEST_CART_TOKEN_DAYS))?,
+        expires_at: cart.expires_at,
+    })
+}
+
+// Load a product and make sure it can be bought in the requested quantity
+async fn check_purchasable(
+    db: &DatabaseConnection,
+    owner: CartOwner,
+    product_id: Uuid,
+    quantity: i32,
+) -> Result<product::Model> {
+    let product = product::Entity::find_by_id(product_id)
+        .one(db)
+        .await?
+        .ok_or_else(|| AppError::not_found("Product not found"))?;
+
+    if product.status != ProductStatus::Active.to_string() {
+        return Err(AppError::bad_request("This product is not available for sale"));
+    }
+
+    if owner == CartOwner::User(product.seller_id) {
+        return Err(AppError::bad_request("Cannot add your own product to the cart"));
+    }
+
+    if product.stock <= 0 {
+        return Err(AppError::bad_request("This product is out of stock"));
+    }
+
+    if quantity > product.stock {
+        return Err(AppError::bad_request(format!(
+            "Only {} left in stock",
+            product.stock
+        )));
+    }
+
+    Ok(product)
+}
+
+// Work out what changed on a product since it was put in the cart
+fn line_warnings(item: &cart_item::Model, product: &product::Model) -> Result<Vec<CartWarning>> {
+    let mut warnings = Vec::new();
+
+    if product.status != ProductStatus::Active.to_string() {
+        warnings.push(CartWarning::Unavailable);
+    } else if product.stock <= 0 {
+        warnings.push(CartWarning::OutOfStock);
+    } else if item.quantity > product.stock {
+        warnings.push(CartWarning::InsufficientStock { available: product.stock });
+    }
+
+    if product.price != item.price_at_add {
+        warnings.push(CartWarning::PriceChanged {
+            previous_price: to_money(&item.price_at_add)?,
+            current_price: to_money(&product.price)?,
+        });
+    }
+
+    Ok(warnings)
+}
+
+// Get a user's or guest's cart
+pub async fn get_cart(db: &DatabaseConnection, owner: CartOwner) -> Result<CartResponse> {
+    touch_guest_cart(db, owner).await?;
+
+    let items = cart_item::Entity::find()
+        .filter(owned_by(owner))
+        .order_by_asc(cart_item::Column::CreatedAt)
+        .find_also_related(product::Entity)
+        .all(db)
+        .await?;
+
+    let seller_ids: Vec<Uuid> = items
+        .iter()
+        .filter_map(|(_, product)| product.as_ref().map(|p| p.seller_id))
+        .collect();
+
+    let sellers: HashMap<Uuid, String> = if seller_ids.is_empty() {
+        HashMap::new()
+    } else {
+        user::Entity::find()
+            .filter(user::Column::Id.is_in(seller_ids))
+            .all(db)
+            .await?
+            .into_iter()
+            .map(|u| (u.id, u.name))
+            .collect()
+    };
+
+    // Group lines by seller, keeping sellers in the order their first item was added
+    let mut groups: Vec<CartSellerGroup> = Vec::new();
+    let mut total_items = 0;
+    let mut total_price = Money::zero(Currency::XAF);
+    let mut has_warnings = false;
+
+    for (item, product) in items {
+        // Deleted products cascade out of the cart, so this is only a safety net
+        let Some(product) = product else { continue };
+
+        let warnings = line_warnings(&item, &product)?;
+        let blocked = warnings.iter().any(CartWarning::is_blocking);
+        has_warnings |= !warnings.is_empty();
+
+        let unit_price = to_money(&product.price)?;
+        let line_total = if blocked {
+            Money::zero(Currency::XAF)
+        } else {
+            unit_price.checked_mul(item.quantity as i64)?
+        };
+        let seller_name = sellers.get(&product.seller_id).cloned().unwrap_or_default();
+
+        let line = CartItemResponse {
+            id: item.id,
+            product_id: product.id,
+            product_title: product.title,
+            product_image: product.images.0.first().cloned().unwrap_or_default(),
+            product_price: unit_price,
+            price_at_add: to_money(&item.price_at_add)?,
+            quantity: item.quantity,
+            available_stock: product.stock.max(0),
+            total_price: line_total,
+            seller_id: product.seller_id,
+            seller_name: seller_name.clone(),
+            warnings,
+        };
+
+        if !blocked {
+            total_items += item.quantity as usize;
+            total_price = total_price.checked_add(line_total)?;
+        }
+
+        match groups.iter_mut().find(|g| g.seller_id == product.seller_id) {
+            Some(group) => {
+                group.subtotal = group.subtotal.checked_add(line_total)?;
+                group.items.push(line);
+            }
+            None => groups.push(CartSellerGroup {
+                seller_id: product.seller_id,
+                seller_name,
+                subtotal: line_total,
+                items: vec![line],
+            }),
+        }
+    }
+
     Ok(CartResponse {
-        items: Vec::new(),
-        total_items: 0,
-        total_price: 0.0,
+        sellers: groups,
+        total_items,
+        total_price,
+        has_warnings,
     })
 }
 
-// Add item to cart
-pub async fn add_to_cart(db: &DatabaseConnection, user_id: Uuid, payload: AddToCartRequest) -> Result<()> {
-    // This is a placeholder implementation using Sea-ORM
-    // In a real implementation, you would insert a new cart item or update an existing one
-    
+// Quote delivery of each seller's items in the cart to the buyer's city. Lines that cannot be
+// checked out as they are, and so would not be shipped, are left out.
+pub async fn get_shipping_quotes(
+    db: &DatabaseConnection,
+    owner: CartOwner,
+    query: ShippingQuoteQuery,
+) -> Result<Vec<SellerShippingQuote>> {
+    validation::validate(&query)?;
+    touch_guest_cart(db, owner).await?;
+
+    let items = cart_item::Entity::find()
+        .filter(owned_by(owner))
+        .order_by_asc(cart_item::Column::CreatedAt)
+        .find_also_related(product::Entity)
+        .all(db)
+        .await?;
+
+    // (seller, weight in grams, goods), sellers in the order their first item was added
+    let mut sellers: Vec<(Uuid, i64, Money)> = Vec::new();
+    for (item, product) in items {
+        let Some(product) = product else { continue };
+        if line_warnings(&item, &product)?.iter().any(CartWarning::is_blocking) {
+            continue;
+        }
+
+        let weight = product.weight_grams as i64 * item.quantity as i64;
+        let goods = to_money(&product.price)?.checked_mul(item.quantity as i64)?;
+        match sellers.iter_mut().find(|(id, _, _)| *id == product.seller_id) {
+            Some((_, total_weight, total_goods)) => {
+                *total_weight += weight;
+                *total_goods = total_goods.checked_add(goods)?;
+            }
+            None => sellers.push((product.seller_id, weight, goods)),
+        }
+    }
+
+    let destination = Destination::new(&query.city, query.country.as_deref());
+    let mut quotes = Vec::with_capacity(sellers.len());
+    for (seller_id, weight_grams, goods) in sellers {
+        quotes.push(shipping::quote_seller(db, seller_id, weight_grams, goods, &destination).await?);
+    }
+
+    Ok(quotes)
+}
+
+// Add item to cart, or increase its quantity if it is already there
+pub async fn add_to_cart(db: &DatabaseConnection, owner: CartOwner, payload: AddToCartRequest) -> Result<()> {
+    touch_guest_cart(db, owner).await?;
+
+    let existing = cart_item::Entity::find()
+        .filter(owned_by(owner))
+        .filter(cart_item::Column::ProductId.eq(payload.product_id))
+        .one(db)
+        .await?;
+
+    let quantity = existing.as_ref().map(|item| item.quantity).unwrap_or(0) + payload.quantity;
+    let product = check_purchasable(db, owner, payload.product_id, quantity).await?;
+
+    match existing {
+        Some(item) => {
+            // The buyer has now seen the current price
+            let mut item: cart_item::ActiveModel = item.into();
+            item.quantity = Set(quantity);
+            item.price_at_add = Set(product.price);
+            item.update(db).await?;
+        }
+        None => {
+            let (user_id, guest_cart_id) = match owner {
+                CartOwner::User(user_id) => (Some(user_id), None),
+                CartOwner::Guest(guest_cart_id) => (None, Some(guest_cart_id)),
+            };
+
+            let now = Utc::now();
+            let item = cart_item::ActiveModel {
+                id: Set(Uuid::new_v4()),
+                user_id: Set(user_id),
+                guest_cart_id: Set(guest_cart_id),
+                product_id: Set(product.id),
+                quantity: Set(quantity),
+                price_at_add: Set(product.price),
+                created_at: Set(now),
+                updated_at: Set(now),
+            };
+            item.insert(db).await?;
+        }
+    }
+
     Ok(())
 }
 
 // Update cart item quantity
-pub async fn update_cart_item(db: &DatabaseConnection, user_id: Uuid, product_id: Uuid, quantity: i32) -> Result<()> {
-    // This is a placeholder implementation using Sea-ORM
-    // In a real implementation, you would update the quantity of a cart item
-    
+pub async fn update_cart_item(db: &DatabaseConnection, owner: CartOwner, product_id: Uuid, quantity: i32) -> Result<()> {
+    touch_guest_cart(db, owner).await?;
+
+    let item = cart_item::Entity::find()
+        .filter(owned_by(owner))
+        .filter(cart_item::Column::ProductId.eq(product_id))
+        .one(db)
+        .await?
+        .ok_or_else(|| AppError::not_found("Item not found in cart"))?;
+
+    let product = check_purchasable(db, owner, product_id, quantity).await?;
+
+    let mut item: cart_item::ActiveModel = item.into();
+    item.quantity = Set(quantity);
+    item.price_at_add = Set(product.price);
+    item.update(db).await?;
+
     Ok(())
 }
 
 // Remove item from cart
-pub async fn remove_from_cart(db: &DatabaseConnection, user_id: Uuid, product_id: Uuid) -> Result<()> {
-    // This is a placeholder implementation using Sea-ORM
-    // In a real implementation, you would delete a cart item
-    
+pub async fn remove_from_cart(db: &DatabaseConnection, owner: CartOwner, product_id: Uuid) -> Result<()> {
+    touch_guest_cart(db, owner).await?;
+
+    let result = cart_item::Entity::delete_many()
+        .filter(owned_by(owner))
+        .filter(cart_item::Column::ProductId.eq(product_id))
+        .exec(db)
+        .await?;
+
+    if result.rows_affected == 0 {
+        return Err(AppError::not_found("Item not found in cart"));
+    }
+
     Ok(())
 }
 
 // Clear cart
-pub async fn clear_cart(db: &DatabaseConnection, user_id: Uuid) -> Result<()> {
-    // This is a placeholder implementation using Sea-ORM
-    // In a real implementation, you would delete all cart items for a user
-    
+pub async fn clear_cart(db: &DatabaseConnection, owner: CartOwner) -> Result<()> {
+    touch_guest_cart(db, owner).await?;
+
+    cart_item::Entity::delete_many()
+        .filter(owned_by(owner))
+        .exec(db)
+        .await?;
+
     Ok(())
 }
+
+// Move a guest cart into a user's cart after login or registration.
+// When both carts hold the same product the larger quantity wins, capped at the stock left.
+pub async fn merge_guest_cart(db: &DatabaseConnection, guest_cart_id: Uuid, user_id: Uuid) -> Result<()> {
+    let guest_items = cart_item::Entity::find()
+        .filter(cart_item::Column::GuestCartId.eq(guest_cart_id))
+        .find_also_related(product::Entity)
+        .all(db)
+        .await?;
+
+    let user_items: HashMap<Uuid, cart_item::Model> = cart_item::Entity::find()
+        .filter(cart_item::Column::UserId.eq(user_id))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|item| (item.product_id, item))
+        .collect();
+
+    let txn = db.begin().await?;
+
+    for (guest_item, product) in guest_items {
+        let Some(product) = product else { continue };
+
+        // Sellers cannot buy their own listings
+        if product.seller_id == user_id {
+            continue;
+        }
+
+        match user_items.get(&guest_item.product_id) {
+            Some(user_item) => {
+                let mut quantity = user_item.quantity.max(guest_item.quantity);
+                if product.stock > 0 {
+                    quantity = quantity.min(product.stock);
+                }
+
+                let mut item: cart_item::ActiveModel = user_item.clone().into();
+                item.quantity = Set(quantity);
+                if guest_item.quantity > user_item.quantity {
+                    item.price_at_add = Set(guest_item.price_at_add.clone());
+                }
+                item.update(&txn).await?;
+            }
+            None => {
+                let mut item: cart_item::ActiveModel = guest_item.clone().into();
+                item.user_id = Set(Some(user_id));
+                item.guest_cart_id = Set(None);
+                item.update(&txn).await?;
+            }
+        }
+    }
+
+    // Lines not moved over go with the guest cart
+    guest_cart::Entity::delete_by_id(guest_cart_id).exec(&txn).await?;
+
+    txn.commit().await?;
+
+    Ok(())
+}
+
+// Remove guest carts nobody has touched for a while
+pub async fn expire_guest_carts(db: &DatabaseConnection) -> Result<u64> {
+    let result = guest_cart::Entity::delete_many()
+        .filter(guest_cart::Column::ExpiresAt.lte(Utc::now()))
+        .exec(db)
+        .await?;
+
+    Ok(result.rows_affected)
+}
@@ -3,13 +3,21 @@ use sea_orm::{
     Condition, Set, ActiveValue, IntoActiveValue,
     ActiveModelTrait, ColumnTrait, ModelTrait, PaginatorTrait,
 };
+use bigdecimal::BigDecimal;
 use chrono::Utc;
+use std::str::FromStr;
 use uuid::Uuid;
 
 use crate::errors::{AppError, Result};
 use crate::entities::category::{self, Entity as Category, Model as CategoryModel};
 use crate::models::category::{CreateCategoryRequest, UpdateCategoryRequest};
 
+pub(crate) fn to_rate(value: f64) -> Result<BigDecimal> {
+    BigDecimal::from_str(&value.to_string())
+        .map(|rate| rate.with_scale_round(4, bigdecimal::RoundingMode::HalfUp))
+        .map_err(|_| AppError::bad_request("Invalid commission rate"))
+}
+
 // Get all categories
 pub async fn get_categories(db: &DatabaseConnection) -> Result<Vec<CategoryModel>> {
     let categories = Category::find()
@@ -46,7 +54,7 @@ pub async fn create_category(
     }
     
     let now = Utc::now();
-    let category = category::ActiveModel {
+    let mut category = category::ActiveModel {
         id: Uuid::new_v4().into_active_value(),
         name: payload.name.into_active_value(),
         description: payload.description.into_active_value(),
@@ -54,7 +62,12 @@ pub async fn create_category(
         slug: payload.slug.into_active_value(),
         created_at: now.into_active_value(),
         updated_at: now.into_active_value(),
+        ..Default::default()
     };
+
+    if let Some(rate) = payload.commission_rate {
+        category.commission_rate = Set(to_rate(rate)?);
+    }
     
     let category = category
         .insert(db)
@@ -102,6 +115,10 @@ pub async fn update_category(
     if let Some(slug) = payload.slug {
         category.slug = Set(slug);
     }
+
+    if let Some(rate) = payload.commission_rate {
+        category.commission_rate = Set(to_rate(rate)?);
+    }
     
     category.updated_at = Set(Utc::now());
     
@@ -0,0 +1,286 @@
+use bigdecimal::BigDecimal;
+use chrono::Utc;
+use rand::{thread_rng, Rng};
+use sea_orm::sea_query::Expr;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, EntityTrait, PaginatorTrait, QueryFilter,
+    QuerySelect, Set, Statement, TransactionTrait,
+};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::entities::{order, seller_order, seller_payment_settings, user};
+use crate::errors::{AppError, Result};
+use crate::models::cod::{CodSettings, CodSummary, ConfirmDeliveryRequest, UpdateCodSettingsRequest};
+use crate::models::commission::SellerTier;
+use crate::models::money::{Currency, Money};
+use crate::models::order::{OrderActor, OrderStatus, PaymentMethod, PaymentStatus, PayoutStatus};
+use crate::models::user::UserRole;
+use crate::services::ledger;
+use crate::services::order_status::{self, StatusChange};
+
+// Wrong codes allowed on a sub-order before it has to be confirmed by support
+const MAX_DELIVERY_CODE_ATTEMPTS: i32 = 5;
+
+fn to_money(value: &BigDecimal) -> Result<Money> {
+    Ok(Money::from_decimal(value, Currency::XAF)?)
+}
+
+// Six digit code the buyer reads out to the courier
+pub fn generate_delivery_code() -> String {
+    format!("{:06}", thread_rng().gen_range(0..1_000_000))
+}
+
+// Get a seller's cash on delivery settings, which are off until the seller opts in
+pub async fn get_settings(db: &DatabaseConnection, seller_id: Uuid) -> Result<CodSettings> {
+    let settings = seller_payment_settings::Entity::find_by_id(seller_id).one(db).await?;
+
+    Ok(match settings {
+        Some(settings) => CodSettings {
+            cod_enabled: settings.cod_enabled,
+            cod_max_order_value: settings.cod_max_order_value.as_ref().map(to_money).transpose()?,
+            updated_at: Some(settings.updated_at),
+        },
+        None => CodSettings {
+            cod_enabled: false,
+            cod_max_order_value: None,
+            updated_at: None,
+        },
+    })
+}
+
+// Turn cash on delivery on or off for a seller and set the order value limit
+pub async fn update_settings(
+    db: &DatabaseConnection,
+    seller_id: Uuid,
+    payload: UpdateCodSettingsRequest,
+) -> Result<CodSettings> {
+    if let Some(value) = payload.cod_max_order_value {
+        if value.is_negative() {
+            return Err(AppError::validation("cod_max_order_value: Maximum order value cannot be negative"));
+        }
+        if value.currency() != Currency::XAF {
+            return Err(AppError::validation("cod_max_order_value: Orders are paid in XAF"));
+        }
+    }
+    let max_order_value = payload.cod_max_order_value.map(|value| value.to_decimal());
+    let now = Utc::now();
+
+    match seller_payment_settings::Entity::find_by_id(seller_id).one(db).await? {
+        Some(settings) => {
+            let mut settings: seller_payment_settings::ActiveModel = settings.into();
+            settings.cod_enabled = Set(payload.cod_enabled);
+            settings.cod_max_order_value = Set(max_order_value);
+            settings.updated_at = Set(now);
+            settings.update(db).await?;
+        }
+        None => {
+            let settings = seller_payment_settings::ActiveModel {
+                seller_id: Set(seller_id),
+                cod_enabled: Set(payload.cod_enabled),
+                cod_max_order_value: Set(max_order_value),
+                seller_tier: Set(SellerTier::default().to_string()),
+                created_at: Set(now),
+                updated_at: Set(now),
+            };
+            settings.insert(db).await?;
+        }
+    }
+
+    get_settings(db, seller_id).await
+}
+
+// Make sure every seller in the order accepts cash for their part of it
+pub async fn ensure_cod_allowed<C: ConnectionTrait>(db: &C, seller_totals: &[(Uuid, Money)]) -> Result<()> {
+    let seller_ids: Vec<Uuid> = seller_totals.iter().map(|(id, _)| *id).collect();
+
+    let settings: HashMap<Uuid, seller_payment_settings::Model> = seller_payment_settings::Entity::find()
+        .filter(seller_payment_settings::Column::SellerId.is_in(seller_ids.clone()))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|s| (s.seller_id, s))
+        .collect();
+
+    let names: HashMap<Uuid, String> = user::Entity::find()
+        .filter(user::Column::Id.is_in(seller_ids))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|u| (u.id, u.name))
+        .collect();
+
+    for (seller_id, total) in seller_totals {
+        let name = names.get(seller_id).cloned().unwrap_or_default();
+
+        let Some(settings) = settings.get(seller_id).filter(|s| s.cod_enabled) else {
+            return Err(AppError::bad_request(format!("{} does not accept cash on delivery", name)));
+        };
+
+        if let Some(max) = settings.cod_max_order_value.as_ref().map(to_money).transpose()? {
+            if *total > max {
+                return Err(AppError::bad_request(format!(
+                    "{} accepts cash on delivery for orders up to {}",
+                    name, max
+                )));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// Check the buyer's code and mark a cash on delivery sub-order delivered and paid.
+// The courier holds the cash for the seller, so the seller's share counts as paid out.
+pub async fn confirm_delivery(
+    db: &DatabaseConnection,
+    order_id: Uuid,
+    user_id: Uuid,
+    role: &UserRole,
+    payload: ConfirmDeliveryRequest,
+) -> Result<()> {
+    // Everything is checked on locked rows, so concurrent submissions are counted one at a time
+    // and only one of them can collect the cash
+    let txn = db.begin().await?;
+
+    let order = order::Entity::find_by_id(order_id)
+        .lock_exclusive()
+        .one(&txn)
+        .await?
+        .ok_or_else(|| AppError::not_found("Order not found"))?;
+
+    if order.payment_method != PaymentMethod::CashOnDelivery.to_string() {
+        return Err(AppError::bad_request("This order is not paid by cash on delivery"));
+    }
+
+    let code = payload.code.trim();
+    let seller_orders = seller_order::Entity::find()
+        .filter(seller_order::Column::OrderId.eq(order_id))
+        .lock_exclusive()
+        .all(&txn)
+        .await?;
+
+    // The buyer's code is their confirmation that the parcel arrived, so it counts as theirs
+    let (seller_order, actor, actor_id) = match role {
+        UserRole::Seller => (
+            seller_orders
+                .into_iter()
+                .find(|so| so.seller_id == user_id)
+                .ok_or_else(|| AppError::forbidden("You are not authorized to confirm this delivery"))?,
+            OrderActor::Buyer,
+            order.user_id,
+        ),
+        // Support confirms on behalf of a courier, so the code picks the sub-order
+        UserRole::Admin => (
+            seller_orders
+                .into_iter()
+                .find(|so| so.delivery_code.as_deref() == Some(code))
+                .ok_or_else(|| AppError::bad_request("Invalid confirmation code"))?,
+            OrderActor::Admin,
+            user_id,
+        ),
+        _ => return Err(AppError::forbidden("You are not authorized to confirm this delivery")),
+    };
+
+    if seller_order.cod_collected_at.is_some() {
+        return Err(AppError::bad_request("Cash for this order has already been collected"));
+    }
+
+    if seller_order.status != OrderStatus::Shipped.to_string() {
+        return Err(AppError::bad_request("The order must be shipped before delivery can be confirmed"));
+    }
+
+    if seller_order.delivery_code_attempts >= MAX_DELIVERY_CODE_ATTEMPTS {
+        return Err(AppError::forbidden(
+            "Too many wrong codes. Contact support to confirm this delivery",
+        ));
+    }
+
+    if seller_order.delivery_code.as_deref() != Some(code) {
+        seller_order::Entity::update_many()
+            .col_expr(
+                seller_order::Column::DeliveryCodeAttempts,
+                Expr::col(seller_order::Column::DeliveryCodeAttempts).add(1),
+            )
+            .filter(seller_order::Column::Id.eq(seller_order.id))
+            .exec(&txn)
+            .await?;
+        txn.commit().await?;
+
+        return Err(AppError::bad_request("Invalid confirmation code"));
+    }
+
+    let now = Utc::now();
+
+    let change = StatusChange {
+        to: OrderStatus::Delivered,
+        actor,
+        actor_id: Some(actor_id),
+        note: Some("Delivered and paid in cash".to_string()),
+    };
+    let delivered = order_status::transition_seller_order(&txn, seller_order, change).await?;
+
+    // The seller keeps the cash and owes the marketplace its commission
+    let net = ledger::record_cash_collected(&txn, &delivered).await?;
+
+    let collected = delivered.total_amount.clone();
+    let mut active: seller_order::ActiveModel = delivered.into();
+    // The code only works once
+    active.delivery_code = Set(None);
+    active.cod_collected_amount = Set(Some(collected));
+    active.cod_collected_at = Set(Some(now));
+    active.payout_status = Set(PayoutStatus::Paid.to_string());
+    active.payout_amount = Set(net.to_decimal());
+    active.paid_out_at = Set(Some(now));
+    active.update(&txn).await?;
+
+    // The order is paid once cash is in for every part that was not canceled
+    let outstanding = seller_order::Entity::find()
+        .filter(seller_order::Column::OrderId.eq(order_id))
+        .filter(seller_order::Column::Status.ne(OrderStatus::Canceled.to_string()))
+        .filter(seller_order::Column::CodCollectedAt.is_null())
+        .count(&txn)
+        .await?;
+
+    if outstanding == 0 {
+        order::Entity::update_many()
+            .col_expr(order::Column::PaymentStatus, Expr::value(PaymentStatus::Paid.to_string()))
+            .col_expr(order::Column::UpdatedAt, Expr::value(now))
+            .filter(order::Column::Id.eq(order_id))
+            .exec(&txn)
+            .await?;
+    }
+
+    txn.commit().await?;
+
+    Ok(())
+}
+
+// Reconcile a seller's cash on delivery orders: cash collected so far and cash still to come
+pub async fn get_summary(db: &DatabaseConnection, seller_id: Uuid) -> Result<CodSummary> {
+    let row = db
+        .query_one(Statement::from_sql_and_values(
+            sea_orm::DatabaseBackend::Postgres,
+            r#"
+            SELECT
+                COUNT(*) FILTER (WHERE so.status IN ('pending', 'processing', 'shipped')) AS orders_awaiting_collection,
+                COALESCE(SUM(so.total_amount) FILTER (WHERE so.status IN ('pending', 'processing', 'shipped')), 0) AS amount_awaiting_collection,
+                COUNT(*) FILTER (WHERE so.cod_collected_at IS NOT NULL) AS orders_collected,
+                COALESCE(SUM(so.cod_collected_amount), 0) AS amount_collected
+            FROM seller_orders so
+            JOIN orders o ON o.id = so.order_id
+            WHERE so.seller_id = $1
+            AND o.payment_method = $2
+            "#,
+            vec![seller_id.into(), PaymentMethod::CashOnDelivery.to_string().into()],
+        ))
+        .await?
+        .ok_or_else(|| AppError::internal("Failed to compute cash on delivery summary"))?;
+
+    Ok(CodSummary {
+        orders_awaiting_collection: row.try_get("", "orders_awaiting_collection")?,
+        amount_awaiting_collection: to_money(&row.try_get("", "amount_awaiting_collection")?)?,
+        orders_collected: row.try_get("", "orders_collected")?,
+        amount_collected: to_money(&row.try_get("", "amount_collected")?)?,
+    })
+}
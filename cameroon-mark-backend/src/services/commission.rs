@@ -0,0 +1,346 @@
+use bigdecimal::{BigDecimal, ToPrimitive};
+use chrono::{DateTime, Utc};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, Condition, ConnectionTrait, DatabaseConnection, EntityTrait, IntoActiveModel,
+    QueryFilter, QueryOrder, Set,
+};
+use std::collections::HashMap;
+use std::str::FromStr;
+use uuid::Uuid;
+
+use crate::entities::{category, commission_rule, order_item, seller_payment_settings, user};
+use crate::errors::{AppError, Result};
+use crate::models::commission::{
+    CommissionRule, CommissionRuleQuery, CreateCommissionRuleRequest, SellerTier, UpdateCommissionRuleRequest,
+};
+use crate::models::money::{Currency, Money};
+use crate::models::user::UserRole;
+use crate::services::category::to_rate;
+use crate::utils::validation;
+
+fn to_money(value: &BigDecimal) -> Result<Money> {
+    Ok(Money::from_decimal(value, Currency::XAF)?)
+}
+
+fn to_rule(model: commission_rule::Model) -> Result<CommissionRule> {
+    Ok(CommissionRule {
+        id: model.id,
+        name: model.name,
+        category_id: model.category_id,
+        seller_tier: model
+            .seller_tier
+            .as_deref()
+            .map(SellerTier::from_str)
+            .transpose()
+            .map_err(AppError::internal)?,
+        is_promotion: model.is_promotion,
+        percentage: model.percentage.to_f64().unwrap_or_default(),
+        fixed_fee: to_money(&model.fixed_fee)?,
+        starts_at: model.starts_at,
+        ends_at: model.ends_at,
+        created_at: model.created_at,
+    })
+}
+
+fn ensure_xaf(amount: Money) -> Result<Money> {
+    if amount.currency() != Currency::XAF {
+        return Err(AppError::validation("fixed_fee: Fees are charged in XAF"));
+    }
+    if amount.is_negative() {
+        return Err(AppError::validation("fixed_fee: The fixed fee cannot be negative"));
+    }
+    Ok(amount)
+}
+
+fn ensure_period(is_promotion: bool, starts_at: DateTime<Utc>, ends_at: Option<DateTime<Utc>>) -> Result<()> {
+    if ends_at.is_some_and(|ends_at| ends_at <= starts_at) {
+        return Err(AppError::validation("ends_at: A rule must end after it starts"));
+    }
+    if is_promotion && ends_at.is_none() {
+        return Err(AppError::validation("ends_at: Promotions need an end date"));
+    }
+    Ok(())
+}
+
+// A seller's tier, standard until an admin sets one
+pub async fn seller_tier<C: ConnectionTrait>(db: &C, seller_id: Uuid) -> Result<SellerTier> {
+    match seller_payment_settings::Entity::find_by_id(seller_id).one(db).await? {
+        Some(settings) => SellerTier::from_str(&settings.seller_tier).map_err(AppError::internal),
+        None => Ok(SellerTier::default()),
+    }
+}
+
+// Place a seller in a tier (admin only). Orders already placed keep their fees.
+pub async fn set_seller_tier(db: &DatabaseConnection, seller_id: Uuid, tier: SellerTier) -> Result<SellerTier> {
+    let seller = user::Entity::find_by_id(seller_id)
+        .one(db)
+        .await?
+        .ok_or_else(|| AppError::not_found("Seller not found"))?;
+    if seller.role != UserRole::Seller {
+        return Err(AppError::bad_request("Only sellers have a tier"));
+    }
+
+    let now = Utc::now();
+    match seller_payment_settings::Entity::find_by_id(seller_id).one(db).await? {
+        Some(settings) => {
+            let mut settings: seller_payment_settings::ActiveModel = settings.into();
+            settings.seller_tier = Set(tier.to_string());
+            settings.updated_at = Set(now);
+            settings.update(db).await?;
+        }
+        None => {
+            let settings = seller_payment_settings::ActiveModel {
+                seller_id: Set(seller_id),
+                cod_enabled: Set(false),
+                cod_max_order_value: Set(None),
+                seller_tier: Set(tier.to_string()),
+                created_at: Set(now),
+                updated_at: Set(now),
+            };
+            settings.insert(db).await?;
+        }
+    }
+
+    Ok(tier)
+}
+
+// Commission rules, most recent first
+pub async fn get_rules(db: &DatabaseConnection, query: CommissionRuleQuery) -> Result<Vec<CommissionRule>> {
+    let mut select = commission_rule::Entity::find();
+    if let Some(category_id) = query.category_id {
+        select = select.filter(commission_rule::Column::CategoryId.eq(category_id));
+    }
+    if query.active == Some(true) {
+        select = select.filter(in_force(Utc::now()));
+    }
+
+    select
+        .order_by_desc(commission_rule::Column::StartsAt)
+        .all(db)
+        .await?
+        .into_iter()
+        .map(to_rule)
+        .collect()
+}
+
+pub async fn create_rule(
+    db: &DatabaseConnection,
+    admin_id: Uuid,
+    payload: CreateCommissionRuleRequest,
+) -> Result<CommissionRule> {
+    validation::validate(&payload)?;
+
+    if payload.percentage.is_none() && payload.fixed_fee.is_none() {
+        return Err(AppError::validation("A rule needs a percentage, a fixed fee or both"));
+    }
+    if let Some(category_id) = payload.category_id {
+        category::Entity::find_by_id(category_id)
+            .one(db)
+            .await?
+            .ok_or_else(|| AppError::not_found("Category not found"))?;
+    }
+
+    let now = Utc::now();
+    let starts_at = payload.starts_at.unwrap_or(now);
+    ensure_period(payload.is_promotion, starts_at, payload.ends_at)?;
+
+    let rule = commission_rule::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        name: Set(payload.name),
+        category_id: Set(payload.category_id),
+        seller_tier: Set(payload.seller_tier.map(|tier| tier.to_string())),
+        is_promotion: Set(payload.is_promotion),
+        percentage: Set(to_rate(payload.percentage.unwrap_or_default())?),
+        fixed_fee: Set(ensure_xaf(payload.fixed_fee.unwrap_or(Money::zero(Currency::XAF)))?.to_decimal()),
+        starts_at: Set(starts_at),
+        ends_at: Set(payload.ends_at),
+        created_by: Set(Some(admin_id)),
+        created_at: Set(now),
+        updated_at: Set(now),
+    }
+    .insert(db)
+    .await?;
+
+    to_rule(rule)
+}
+
+pub async fn update_rule(
+    db: &DatabaseConnection,
+    rule_id: Uuid,
+    payload: UpdateCommissionRuleRequest,
+) -> Result<CommissionRule> {
+    validation::validate(&payload)?;
+
+    let rule = commission_rule::Entity::find_by_id(rule_id)
+        .one(db)
+        .await?
+        .ok_or_else(|| AppError::not_found("Commission rule not found"))?;
+
+    let starts_at = payload.starts_at.unwrap_or(rule.starts_at);
+    let ends_at = payload.ends_at.or(rule.ends_at);
+    ensure_period(rule.is_promotion, starts_at, ends_at)?;
+
+    let mut active = rule.into_active_model();
+    if let Some(name) = payload.name {
+        active.name = Set(name);
+    }
+    if let Some(percentage) = payload.percentage {
+        active.percentage = Set(to_rate(percentage)?);
+    }
+    if let Some(fixed_fee) = payload.fixed_fee {
+        active.fixed_fee = Set(ensure_xaf(fixed_fee)?.to_decimal());
+    }
+    active.starts_at = Set(starts_at);
+    active.ends_at = Set(ends_at);
+    active.updated_at = Set(Utc::now());
+
+    to_rule(active.update(db).await?)
+}
+
+// Rules that were never used are deleted; the others stop applying now so past orders keep them
+pub async fn end_rule(db: &DatabaseConnection, rule_id: Uuid) -> Result<()> {
+    let rule = commission_rule::Entity::find_by_id(rule_id)
+        .one(db)
+        .await?
+        .ok_or_else(|| AppError::not_found("Commission rule not found"))?;
+
+    let used = order_item::Entity::find()
+        .filter(order_item::Column::CommissionRuleId.eq(rule.id))
+        .one(db)
+        .await?
+        .is_some();
+
+    let now = Utc::now();
+    if !used || rule.starts_at > now {
+        commission_rule::Entity::delete_by_id(rule.id).exec(db).await?;
+        return Ok(());
+    }
+
+    if rule.ends_at.is_none_or(|ends_at| ends_at > now) {
+        let mut active = rule.into_active_model();
+        active.ends_at = Set(Some(now));
+        active.updated_at = Set(now);
+        active.update(db).await?;
+    }
+
+    Ok(())
+}
+
+fn in_force(at: DateTime<Utc>) -> Condition {
+    Condition::all()
+        .add(commission_rule::Column::StartsAt.lte(at))
+        .add(
+            Condition::any()
+                .add(commission_rule::Column::EndsAt.is_null())
+                .add(commission_rule::Column::EndsAt.gt(at)),
+        )
+}
+
+// Promotions beat everything, then rules naming a category, then rules naming a tier
+fn specificity(rule: &commission_rule::Model) -> u8 {
+    (rule.is_promotion as u8) * 4 + (rule.category_id.is_some() as u8) * 2 + rule.seller_tier.is_some() as u8
+}
+
+// One item of a seller's sub-order, as priced at checkout
+pub struct FeeLine {
+    pub category_id: Uuid,
+    pub quantity: i32,
+    pub line_total: Money,
+}
+
+// What the marketplace takes on one item
+pub struct ItemFees {
+    pub rule_id: Option<Uuid>,
+    pub rate: BigDecimal,
+    pub commission: Money,
+    pub processing_fee: Money,
+}
+
+// Work out the fees on a seller's items. The commission is charged on each item's price after its
+// share of the seller's discount, and never exceeds it. The provider's processing fee on the
+// sub-order is shared between the items by price.
+pub async fn compute_fees<C: ConnectionTrait>(
+    db: &C,
+    seller_id: Uuid,
+    lines: &[FeeLine],
+    discount: Money,
+    processing_fee: Money,
+    at: DateTime<Utc>,
+) -> Result<Vec<ItemFees>> {
+    if lines.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let tier = seller_tier(db, seller_id).await?;
+    let category_ids: Vec<Uuid> = lines.iter().map(|line| line.category_id).collect();
+
+    let rules = commission_rule::Entity::find()
+        .filter(in_force(at))
+        .filter(
+            Condition::any()
+                .add(commission_rule::Column::CategoryId.is_null())
+                .add(commission_rule::Column::CategoryId.is_in(category_ids.clone())),
+        )
+        .filter(
+            Condition::any()
+                .add(commission_rule::Column::SellerTier.is_null())
+                .add(commission_rule::Column::SellerTier.eq(tier.to_string())),
+        )
+        .all(db)
+        .await?;
+    let category_rates: HashMap<Uuid, BigDecimal> = category::Entity::find()
+        .filter(category::Column::Id.is_in(category_ids))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|c| (c.id, c.commission_rate))
+        .collect();
+
+    let zero = Money::zero(Currency::XAF);
+    let totals: Vec<Money> = lines.iter().map(|line| line.line_total).collect();
+    let share = |amount: Money| -> Result<Vec<Money>> {
+        if amount.is_positive() && totals.iter().any(|total| total.is_positive()) {
+            Ok(amount.allocate(&totals)?)
+        } else {
+            Ok(vec![zero; totals.len()])
+        }
+    };
+    let discounts = share(discount)?;
+    let processing_fees = share(processing_fee)?;
+
+    let mut fees = Vec::with_capacity(lines.len());
+    for ((line, discount), processing_fee) in lines.iter().zip(discounts).zip(processing_fees) {
+        let base = line.line_total.checked_sub(discount)?.max(zero)?;
+
+        let rule = rules
+            .iter()
+            .filter(|rule| rule.category_id.is_none_or(|id| id == line.category_id))
+            .max_by_key(|rule| (specificity(rule), rule.starts_at));
+
+        let (rule_id, rate, fixed_fee) = match rule {
+            Some(rule) => (Some(rule.id), rule.percentage.clone(), to_money(&rule.fixed_fee)?),
+            None => (
+                None,
+                category_rates
+                    .get(&line.category_id)
+                    .cloned()
+                    .unwrap_or_else(|| BigDecimal::new(10.into(), 2)),
+                zero,
+            ),
+        };
+
+        let commission = base
+            .mul_rate(&rate)?
+            .checked_add(fixed_fee.checked_mul(line.quantity as i64)?)?
+            .min(base)?;
+
+        fees.push(ItemFees {
+            rule_id,
+            rate,
+            commission,
+            processing_fee,
+        });
+    }
+
+    Ok(fees)
+}
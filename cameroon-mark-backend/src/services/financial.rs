@@ -1,372 +1,721 @@
+use bigdecimal::{BigDecimal, ToPrimitive};
 use sea_orm::{
-    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, Set,
-    TransactionTrait, QuerySelect, QueryOrder, Order, IntoActiveModel,
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseBackend, DatabaseConnection, EntityTrait, QueryFilter,
+    Set, Statement, TransactionTrait, QueryOrder, QuerySelect, Order, IntoActiveModel,
 };
 use uuid::Uuid;
 use chrono::{Utc, NaiveDate};
 use std::collections::HashMap;
+use std::str::FromStr;
 
 use crate::{
-    entities::{payment, expense, order, order_item, product},
+    entities::{payment, expense, order, product, tax_rule},
     errors::{AppError, Result},
     models::financial::{
-        Payment, PaymentProcessor, PaymentStatus, ProcessPaymentRequest, PaymentResponse,
-        RefundRequest, RevenueReport, CategoryRevenue, ProductRevenue, TaxCalculation,
-        TaxItem, CalculateTaxRequest, Expense, CreateExpenseRequest, FinancialStatement,
-        IncomeStatement, BalanceSheet, CashFlow, FinancialReportRequest
+        PaymentProcessor, PaymentStatus, ProcessPaymentRequest, PaymentResponse,
+        RevenueReport, CategoryRevenue, Earnings, ProductRevenue, TaxCalculation,
+        TaxItem, CalculateTaxRequest, Expense, CreateExpenseRequest,
+        ProfitMargins, ProductMargin, CategoryMargin, CostBreakdown,
     },
+    models::money::{Currency, Money},
+    models::order::{OrderStatus, PaymentMethod, PaymentStatus as OrderPaymentStatus},
+    services::payment_webhook,
+    services::shipping::{self, Destination},
+    services::tax::{self, ItemTax, TaxLine},
+    services::payment_gateway::{GatewayPaymentStatus, PaymentGateways, PaymentRequest},
     utils::validation,
 };
 
+fn to_money(value: &BigDecimal) -> Result<Money> {
+    Ok(Money::from_decimal(value, Currency::XAF)?)
+}
+
+fn to_expense(e: expense::Model) -> Result<Expense> {
+    let currency = Currency::from_str(&e.currency).map_err(AppError::internal)?;
+
+    Ok(Expense {
+        id: e.id,
+        user_id: e.user_id,
+        amount: Money::from_decimal(&e.amount, currency)?,
+        category: e.category,
+        description: e.description,
+        date: e.date,
+        receipt_url: e.receipt_url,
+        created_at: e.created_at,
+        updated_at: e.updated_at,
+    })
+}
+
 // Process payment (MTN Mobile Money, Orange Money, etc.)
 pub async fn process_payment(
     db: &DatabaseConnection,
+    gateways: &PaymentGateways,
     user_id: Uuid,
     payload: ProcessPaymentRequest,
 ) -> Result<PaymentResponse> {
     // Validate request
     validation::validate(&payload)?;
 
+    // The order stays locked until the attempt is recorded, so two taps on "pay" cannot both go through
+    let txn = db.begin().await?;
+
     // Check if order exists and belongs to user
     let order = order::Entity::find_by_id(payload.order_id)
         .filter(order::Column::UserId.eq(user_id))
-        .one(db)
+        .lock_exclusive()
+        .one(&txn)
         .await?
         .ok_or_else(|| AppError::not_found("Order not found"))?;
     
-    // Verify payment amount matches order total
-    if payload.amount != order.total_amount {
+    if order.payment_status != OrderPaymentStatus::Pending.to_string()
+        && order.payment_status != OrderPaymentStatus::Failed.to_string()
+    {
+        return Err(AppError::bad_request("This order has already been paid"));
+    }
+
+    if order.status == OrderStatus::Canceled.to_string() {
+        return Err(AppError::bad_request("Canceled orders cannot be paid"));
+    }
+
+    // Verify payment amount matches order total, to the franc
+    if payload.amount != to_money(&order.total_amount)? {
         return Err(AppError::bad_request("Payment amount does not match order total"));
     }
     
-    // Begin transaction
-    let txn = db.begin().await?;
-    
-    // Create payment record
+    // Cash on delivery is collected by the courier against the buyer's delivery code,
+    // so only the mobile money processors have a gateway
+    let method = match payload.processor {
+        PaymentProcessor::MtnMobileMoney => PaymentMethod::Mtn,
+        PaymentProcessor::OrangeMoney => PaymentMethod::Orange,
+        PaymentProcessor::CashOnDelivery => PaymentMethod::CashOnDelivery,
+        _ => PaymentMethod::Other,
+    };
+    let gateway = gateways.for_method(&method)?;
+
+    // One attempt at a time: the buyer approves or declines it, or it expires, before paying again
+    let in_progress = payment::Entity::find()
+        .filter(payment::Column::OrderId.eq(order.id))
+        .filter(payment::Column::Status.is_in([
+            PaymentStatus::Pending.to_string(),
+            PaymentStatus::Processing.to_string(),
+        ]))
+        .one(&txn)
+        .await?;
+    if in_progress.is_some() {
+        return Err(AppError::bad_request(
+            "A payment for this order is already in progress. Approve it on your phone or wait for it to expire",
+        ));
+    }
+
+    // Record the attempt, with the secret for its callback, before calling the provider so a callback
+    // that beats the provider's answer can always find it
     let payment_id = Uuid::new_v4();
     let now = Utc::now();
-    
+
+    let request = PaymentRequest {
+        payment_id,
+        order_id: order.id,
+        amount: payload.amount,
+        phone_number: payload.phone_number,
+        description: format!("Cameroon Mark order {}", order.id),
+        callback_token: Uuid::new_v4().simple().to_string(),
+    };
+    let known_reference = gateway.reference_for(&request);
+
     let payment_model = payment::ActiveModel {
         id: Set(payment_id),
         order_id: Set(payload.order_id),
         user_id: Set(user_id),
-        amount: Set(payload.amount),
-        currency: Set(payload.currency),
+        amount: Set(order.total_amount.clone()),
+        currency: Set(payload.amount.currency().to_string()),
         processor: Set(payload.processor.to_string()),
-        processor_payment_id: Set(None),
+        processor_payment_id: Set(known_reference.clone()),
         status: Set(PaymentStatus::Pending.to_string()),
         payment_method: Set(payload.payment_method),
         metadata: Set(serde_json::to_value(payload.metadata).ok()),
+        callback_token: Set(Some(request.callback_token.clone())),
         created_at: Set(now),
         updated_at: Set(now),
         completed_at: Set(None),
         ..Default::default()
     };
-    
+
     let payment_result = payment_model.insert(&txn).await?;
-    
-    // In a real implementation, this would integrate with the actual payment provider API
-    // For now, we'll simulate the payment process
-    
-    let (payment_status, redirect_url, processor_reference, message) = match payload.processor {
-        PaymentProcessor::MtnMobileMoney => {
-            if let Some(phone) = &payload.phone_number {
-                // Simulate successful payment for specific test phone numbers
-                if phone.ends_with("1234") {
-                    (
-                        PaymentStatus::Completed,
-                        None,
-                        Some(format!("MTN-{}", Uuid::new_v4())),
-                        "Payment completed successfully".to_string()
-                    )
-                } else {
-                    (
-                        PaymentStatus::Pending,
-                        Some(format!("https://mtn-money.example.com/pay/{}", payment_id)),
-                        Some(format!("MTN-PENDING-{}", payment_id)),
-                        "Payment initiated. Complete the payment on your mobile phone".to_string()
-                    )
-                }
-            } else {
-                (
-                    PaymentStatus::Failed,
-                    None,
-                    None,
-                    "Phone number required for Mobile Money payments".to_string()
-                )
-            }
-        },
-        PaymentProcessor::OrangeMoney => {
-            if let Some(phone) = &payload.phone_number {
-                // Simulate payment
-                (
-                    PaymentStatus::Pending,
-                    Some(format!("https://orange-money.example.com/pay/{}", payment_id)),
-                    Some(format!("OM-{}", Uuid::new_v4())),
-                    "Payment initiated. Complete the payment on your mobile phone".to_string()
-                )
-            } else {
-                (
-                    PaymentStatus::Failed,
-                    None,
-                    None,
-                    "Phone number required for Mobile Money payments".to_string()
-                )
-            }
-        },
-        _ => (
-            PaymentStatus::Processing,
-            None,
-            Some(format!("PROC-{}", Uuid::new_v4())),
-            "Payment is being processed".to_string()
-        )
+    txn.commit().await?;
+
+    let initiation = match gateway.request_to_pay(&request).await {
+        Ok(initiation) => initiation,
+        // The provider may have taken the request before the error reached us (a timeout, say), so a
+        // payment it can be looked up by is left pending for the reconciler to settle or expire
+        Err(e) if known_reference.is_some() => {
+            tracing::error!(
+                "{} payment request {} did not complete, leaving it to the reconciler: {:?}",
+                gateway.name(),
+                payment_id,
+                e
+            );
+            return Ok(PaymentResponse {
+                payment_id,
+                status: PaymentStatus::Pending,
+                redirect_url: None,
+                processor_reference: known_reference,
+                message: "Payment initiated. If no prompt reaches your phone, the payment will expire and you can try again"
+                    .to_string(),
+            });
+        }
+        // Without the provider's reference the payer has nothing to approve, so nothing can be collected
+        Err(e) => {
+            let mut payment_update = payment_result.into_active_model();
+            payment_update.status = Set(PaymentStatus::Failed.to_string());
+            payment_update.failure_reason = Set(Some(e.to_string()));
+            payment_update.updated_at = Set(Utc::now());
+            payment_update.update(db).await?;
+            return Err(e);
+        }
     };
-    
-    // Update payment status
-    let mut payment_update = payment_result.clone().into_active_model();
-    payment_update.status = Set(payment_status.to_string());
-    payment_update.processor_payment_id = Set(processor_reference.clone());
-    
-    if payment_status == PaymentStatus::Completed {
-        payment_update.completed_at = Set(Some(Utc::now()));
-        
-        // Update order status
-        let mut order_update = order.into_active_model();
-        order_update.status = Set("paid".to_string());
-        order_update.updated_at = Set(Utc::now());
-        
-        order_update.update(&txn).await?;
+
+    let txn = db.begin().await?;
+
+    // A callback may have settled the payment in the meantime
+    let payment = payment_webhook::lock_payment(&txn, &payment_result).await?;
+
+    let mut payment_update = payment.into_active_model();
+    payment_update.processor_payment_id = Set(Some(initiation.provider_reference.clone()));
+    if let Some(token) = initiation.callback_token {
+        payment_update.callback_token = Set(Some(token));
     }
-    
-    payment_update.update(&txn).await?;
-    
-    // Commit transaction
+    payment_update.updated_at = Set(Utc::now());
+    let mut payment = payment_update.update(&txn).await?;
+
+    let current = PaymentStatus::from_str(&payment.status).map_err(AppError::internal)?;
+    if !current.is_final() && initiation.status != GatewayPaymentStatus::Pending {
+        payment_webhook::settle(&txn, payment, &initiation.status, None).await?;
+        payment = payment::Entity::find_by_id(payment_id)
+            .one(&txn)
+            .await?
+            .ok_or_else(|| AppError::not_found("Payment not found"))?;
+    }
+
     txn.commit().await?;
-    
+
+    let payment_status = PaymentStatus::from_str(&payment.status).map_err(AppError::internal)?;
+    let message = match payment_status {
+        PaymentStatus::Completed => "Payment completed successfully".to_string(),
+        PaymentStatus::Failed => format!("Payment failed: {}", payment.failure_reason.unwrap_or_default()),
+        _ if initiation.redirect_url.is_some() => format!("Complete the payment on the {} page", gateway.name()),
+        _ => "Payment initiated. Complete the payment on your mobile phone".to_string(),
+    };
+
     // Return payment result
     let response = PaymentResponse {
         payment_id,
         status: payment_status,
-        redirect_url,
-        processor_reference,
+        redirect_url: initiation.redirect_url,
+        processor_reference: Some(initiation.provider_reference),
         message,
     };
     
     Ok(response)
 }
 
-// Process refund
-pub async fn process_refund(
+// Revenue from paid orders placed between two dates, both included.
+// A seller sees their own sub-orders, the marketplace as a whole is seen with no seller.
+pub async fn get_revenue_report(
     db: &DatabaseConnection,
-    user_id: Uuid,
-    payload: RefundRequest,
-) -> Result<PaymentResponse> {
-    // Validate request
-    validation::validate(&payload)?;
+    seller_id: Option<Uuid>,
+    start_date: NaiveDate,
+    end_date: NaiveDate,
+) -> Result<RevenueReport> {
+    if end_date < start_date {
+        return Err(AppError::bad_request("The end date must not be before the start date"));
+    }
 
-    // Get payment
-    let payment_record = payment::Entity::find_by_id(payload.payment_id)
-        .filter(payment::Column::UserId.eq(user_id))
-        .one(db)
-        .await?
-        .ok_or_else(|| AppError::not_found("Payment not found"))?;
-    
-    // Check if payment is eligible for refund
-    if payment_record.status != PaymentStatus::Completed.to_string() {
-        return Err(AppError::bad_request("Only completed payments can be refunded"));
+    let start = start_date.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc();
+    let end = (end_date + chrono::Duration::days(1)).and_hms_opt(0, 0, 0).unwrap_or_default().and_utc();
+    let canceled = OrderStatus::Canceled.to_string();
+    let values = || -> Vec<sea_orm::Value> {
+        vec![
+            seller_id.into(),
+            start.into(),
+            end.into(),
+            OrderPaymentStatus::Paid.to_string().into(),
+            OrderPaymentStatus::PartiallyRefunded.to_string().into(),
+            OrderPaymentStatus::Refunded.to_string().into(),
+            canceled.clone().into(),
+        ]
+    };
+    // Sub-orders of orders that were paid, whatever happened to them since
+    let scope = r#"
+        FROM seller_orders so
+        JOIN orders o ON o.id = so.order_id
+        WHERE ($1::uuid IS NULL OR so.seller_id = $1)
+          AND o.created_at >= $2 AND o.created_at < $3
+          AND o.payment_status IN ($4, $5, $6)
+    "#;
+
+    // Refunds include sub-orders canceled after payment and partial refunds on the rest
+    let totals = db
+        .query_one(Statement::from_sql_and_values(
+            DatabaseBackend::Postgres,
+            format!(
+                r#"
+                SELECT COALESCE(SUM(so.total_amount), 0)::FLOAT8 AS total_revenue,
+                       COALESCE(SUM(ri.refunded), 0)::FLOAT8 AS refunds,
+                       COALESCE(SUM(so.tax_amount) FILTER (WHERE so.status <> $7), 0)::FLOAT8 AS taxes_collected
+                {}
+                "#,
+                scope.replace(
+                    "JOIN orders o ON o.id = so.order_id",
+                    "JOIN orders o ON o.id = so.order_id
+        LEFT JOIN (
+            SELECT seller_order_id, SUM(amount) AS refunded FROM refund_items GROUP BY seller_order_id
+        ) ri ON ri.seller_order_id = so.id",
+                )
+            ),
+            values(),
+        ))
+        .await?;
+    let (total_revenue, refunds, taxes_collected) = match totals {
+        Some(row) => (
+            row.try_get::<f64>("", "total_revenue")?,
+            row.try_get::<f64>("", "refunds")?,
+            row.try_get::<f64>("", "taxes_collected")?,
+        ),
+        None => (0.0, 0.0, 0.0),
+    };
+
+    // The breakdowns only count what was kept
+    let mut revenue_by_day = HashMap::new();
+    let rows = db
+        .query_all(Statement::from_sql_and_values(
+            DatabaseBackend::Postgres,
+            format!(
+                r#"
+                SELECT TO_CHAR(o.created_at, 'YYYY-MM-DD') AS day, SUM(so.total_amount)::FLOAT8 AS revenue
+                {} AND so.status <> $7
+                GROUP BY day
+                "#,
+                scope
+            ),
+            values(),
+        ))
+        .await?;
+    for row in rows {
+        revenue_by_day.insert(row.try_get::<String>("", "day")?, row.try_get::<f64>("", "revenue")?);
     }
-    
-    // Determine refund amount
-    let refund_amount = payload.amount.unwrap_or(payment_record.amount);
-    
-    if refund_amount > payment_record.amount {
-        return Err(AppError::bad_request("Refund amount cannot exceed payment amount"));
+
+    let mut revenue_by_payment_method = HashMap::new();
+    let rows = db
+        .query_all(Statement::from_sql_and_values(
+            DatabaseBackend::Postgres,
+            format!(
+                r#"
+                SELECT o.payment_method, SUM(so.total_amount)::FLOAT8 AS revenue
+                {} AND so.status <> $7
+                GROUP BY o.payment_method
+                "#,
+                scope
+            ),
+            values(),
+        ))
+        .await?;
+    for row in rows {
+        let method: String = row.try_get("", "payment_method")?;
+        let label = match PaymentMethod::from_str(&method) {
+            Ok(PaymentMethod::Mtn) => "MTN Mobile Money".to_string(),
+            Ok(PaymentMethod::Orange) => "Orange Money".to_string(),
+            Ok(PaymentMethod::CashOnDelivery) => "Cash on Delivery".to_string(),
+            _ => method,
+        };
+        *revenue_by_payment_method.entry(label).or_insert(0.0) += row.try_get::<f64>("", "revenue")?;
     }
-    
-    // Begin transaction
-    let txn = db.begin().await?;
-    
-    // In a real implementation, this would call the payment provider's API
-    // For this example, we'll simulate the refund process
-    
-    // Update payment status
-    let mut payment_update = payment_record.clone().into_active_model();
-    
-    if refund_amount == payment_record.amount {
-        // Full refund
-        payment_update.status = Set(PaymentStatus::Refunded.to_string());
-    } else {
-        // Partial refund
-        payment_update.status = Set(PaymentStatus::PartiallyRefunded.to_string());
+
+    // Item sales, before discounts and shipping
+    let rows = db
+        .query_all(Statement::from_sql_and_values(
+            DatabaseBackend::Postgres,
+            format!(
+                r#"
+                SELECT c.id AS category_id, c.name AS category_name,
+                       SUM(oi.unit_price * oi.quantity)::FLOAT8 AS revenue
+                {} AND so.status <> $7
+                GROUP BY c.id, c.name
+                ORDER BY revenue DESC
+                "#,
+                scope.replace(
+                    "FROM seller_orders so",
+                    "FROM seller_orders so
+        JOIN order_items oi ON oi.seller_order_id = so.id
+        JOIN products p ON p.id = oi.product_id
+        JOIN categories c ON c.id = p.category_id",
+                )
+            ),
+            values(),
+        ))
+        .await?;
+    let mut revenue_by_category = Vec::new();
+    for row in rows {
+        revenue_by_category.push(CategoryRevenue {
+            category_id: row.try_get("", "category_id")?,
+            category_name: row.try_get("", "category_name")?,
+            revenue: row.try_get("", "revenue")?,
+            percentage: 0.0,
+        });
     }
-    
-    payment_update.updated_at = Set(Utc::now());
-    payment_update.update(&txn).await?;
-    
-    // Update order status
-    let order = order::Entity::find_by_id(payment_record.order_id)
-        .one(&txn)
-        .await?
-        .ok_or_else(|| AppError::internal("Order not found for payment"))?;
-    
-    let mut order_update = order.into_active_model();
-    
-    if refund_amount == payment_record.amount {
-        order_update.status = Set("refunded".to_string());
-    } else {
-        order_update.status = Set("partially_refunded".to_string());
+    let items_total: f64 = revenue_by_category.iter().map(|c| c.revenue).sum();
+    if items_total > 0.0 {
+        for category in &mut revenue_by_category {
+            category.percentage = (category.revenue / items_total * 100.0) as f32;
+        }
     }
-    
-    order_update.updated_at = Set(Utc::now());
-    order_update.update(&txn).await?;
-    
-    // Create refund record in a real implementation
-    
-    // Commit transaction
-    txn.commit().await?;
-    
-    // Return response
-    let response = PaymentResponse {
-        payment_id: payment_record.id,
-        status: if refund_amount == payment_record.amount {
-            PaymentStatus::Refunded
-        } else {
-            PaymentStatus::PartiallyRefunded
-        },
-        redirect_url: None,
-        processor_reference: Some(format!("REFUND-{}", Uuid::new_v4())),
-        message: format!("Refund of {} {} processed successfully", refund_amount, payment_record.currency),
+
+    let rows = db
+        .query_all(Statement::from_sql_and_values(
+            DatabaseBackend::Postgres,
+            format!(
+                r#"
+                SELECT oi.product_id, MAX(oi.product_title) AS product_name,
+                       SUM(oi.unit_price * oi.quantity)::FLOAT8 AS revenue,
+                       SUM(oi.quantity)::INT4 AS units_sold
+                {} AND so.status <> $7
+                GROUP BY oi.product_id
+                ORDER BY revenue DESC
+                LIMIT 10
+                "#,
+                scope.replace(
+                    "FROM seller_orders so",
+                    "FROM seller_orders so
+        JOIN order_items oi ON oi.seller_order_id = so.id",
+                )
+            ),
+            values(),
+        ))
+        .await?;
+    let mut top_products = Vec::new();
+    for row in rows {
+        top_products.push(ProductRevenue {
+            product_id: row.try_get("", "product_id")?,
+            product_name: row.try_get("", "product_name")?,
+            revenue: row.try_get("", "revenue")?,
+            units_sold: row.try_get("", "units_sold")?,
+        });
+    }
+
+    // Fees fixed on each item at checkout
+    let fees = db
+        .query_one(Statement::from_sql_and_values(
+            DatabaseBackend::Postgres,
+            format!(
+                r#"
+                SELECT COALESCE(SUM(oi.commission_amount), 0)::FLOAT8 AS commission,
+                       COALESCE(SUM(oi.processing_fee), 0)::FLOAT8 AS processing_fees
+                {} AND so.status <> $7
+                "#,
+                scope.replace(
+                    "FROM seller_orders so",
+                    "FROM seller_orders so
+        JOIN order_items oi ON oi.seller_order_id = so.id",
+                )
+            ),
+            values(),
+        ))
+        .await?;
+    let (commission, processing_fees) = match fees {
+        Some(row) => (row.try_get::<f64>("", "commission")?, row.try_get::<f64>("", "processing_fees")?),
+        None => (0.0, 0.0),
     };
-    
-    Ok(response)
+
+    // VAT is collected on behalf of the state, so it is not revenue
+    let seller_gross = total_revenue - refunds - taxes_collected;
+
+    Ok(RevenueReport {
+        total_revenue,
+        net_revenue: total_revenue - refunds - processing_fees - taxes_collected,
+        processing_fees,
+        commission,
+        refunds,
+        taxes_collected,
+        seller_earnings: Earnings {
+            gross: seller_gross,
+            net: seller_gross - commission - processing_fees,
+        },
+        platform_earnings: Earnings {
+            gross: commission + processing_fees,
+            net: commission,
+        },
+        revenue_by_day,
+        revenue_by_payment_method,
+        revenue_by_category,
+        top_products,
+    })
 }
 
-// Get revenue report
-pub async fn get_revenue_report(
+// Margins from the fees stored on each order item at checkout. Sellers keep what is left of their
+// item sales after fees; the marketplace keeps the commission and passes processing fees on.
+pub async fn get_profit_margins(
     db: &DatabaseConnection,
-    user_id: Uuid,
+    seller_id: Option<Uuid>,
     start_date: NaiveDate,
     end_date: NaiveDate,
-) -> Result<RevenueReport> {
-    // In a real implementation, this would query the database for orders, payments, etc.
-    // For this example, we'll return mock data
-    
-    let revenue_report = RevenueReport {
-        total_revenue: 50000.0,
-        net_revenue: 45000.0,
-        processing_fees: 1500.0,
-        refunds: 2000.0,
-        taxes_collected: 1500.0,
-        revenue_by_day: {
-            let mut map = HashMap::new();
-            map.insert("2023-05-01".to_string(), 2500.0);
-            map.insert("2023-05-02".to_string(), 3000.0);
-            map.insert("2023-05-03".to_string(), 3500.0);
-            map
-        },
-        revenue_by_payment_method: {
-            let mut map = HashMap::new();
-            map.insert("MTN Mobile Money".to_string(), 30000.0);
-            map.insert("Orange Money".to_string(), 15000.0);
-            map.insert("Cash on Delivery".to_string(), 5000.0);
-            map
-        },
-        revenue_by_category: vec![
-            CategoryRevenue {
-                category_id: Uuid::new_v4(),
-                category_name: "Electronics".to_string(),
-                revenue: 25000.0,
-                percentage: 50.0,
-            },
-            CategoryRevenue {
-                category_id: Uuid::new_v4(),
-                category_name: "Clothing".to_string(),
-                revenue: 15000.0,
-                percentage: 30.0,
-            },
-            CategoryRevenue {
-                category_id: Uuid::new_v4(),
-                category_name: "Home Goods".to_string(),
-                revenue: 10000.0,
-                percentage: 20.0,
-            },
-        ],
-        top_products: vec![
-            ProductRevenue {
-                product_id: Uuid::new_v4(),
-                product_name: "Smartphone".to_string(),
-                revenue: 15000.0,
-                units_sold: 30,
-            },
-            ProductRevenue {
-                product_id: Uuid::new_v4(),
-                product_name: "Laptop".to_string(),
-                revenue: 10000.0,
-                units_sold: 10,
-            },
-            ProductRevenue {
-                product_id: Uuid::new_v4(),
-                product_name: "T-shirt".to_string(),
-                revenue: 5000.0,
-                units_sold: 100,
-            },
-        ],
+) -> Result<ProfitMargins> {
+    if end_date < start_date {
+        return Err(AppError::bad_request("The end date must not be before the start date"));
+    }
+
+    let start = start_date.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc();
+    let end = (end_date + chrono::Duration::days(1)).and_hms_opt(0, 0, 0).unwrap_or_default().and_utc();
+    let values = || -> Vec<sea_orm::Value> {
+        vec![
+            seller_id.into(),
+            start.into(),
+            end.into(),
+            OrderPaymentStatus::Paid.to_string().into(),
+            OrderPaymentStatus::PartiallyRefunded.to_string().into(),
+            OrderPaymentStatus::Refunded.to_string().into(),
+            OrderStatus::Canceled.to_string().into(),
+        ]
     };
-    
-    Ok(revenue_report)
+    // Items of paid orders, whatever happened to them since
+    let scope = r#"
+        FROM order_items oi
+        JOIN seller_orders so ON so.id = oi.seller_order_id
+        JOIN orders o ON o.id = so.order_id
+        WHERE ($1::uuid IS NULL OR so.seller_id = $1)
+          AND o.created_at >= $2 AND o.created_at < $3
+          AND o.payment_status IN ($4, $5, $6)
+    "#;
+
+    let totals = db
+        .query_one(Statement::from_sql_and_values(
+            DatabaseBackend::Postgres,
+            format!(
+                r#"
+                SELECT COALESCE(SUM(oi.unit_price * oi.quantity) FILTER (WHERE so.status <> $7), 0)::FLOAT8 AS sales,
+                       COALESCE(SUM(oi.commission_amount) FILTER (WHERE so.status <> $7), 0)::FLOAT8 AS commission,
+                       COALESCE(SUM(oi.processing_fee) FILTER (WHERE so.status <> $7), 0)::FLOAT8 AS processing_fees,
+                       COALESCE(SUM(ri.refunded), 0)::FLOAT8 AS refunds
+                {}
+                "#,
+                scope.replace(
+                    "JOIN orders o ON o.id = so.order_id",
+                    "JOIN orders o ON o.id = so.order_id
+        LEFT JOIN (
+            SELECT order_item_id, SUM(amount) AS refunded FROM refund_items GROUP BY order_item_id
+        ) ri ON ri.order_item_id = oi.id",
+                )
+            ),
+            values(),
+        ))
+        .await?;
+    let (sales, commission, processing_fees, refunds) = match totals {
+        Some(row) => (
+            row.try_get::<f64>("", "sales")?,
+            row.try_get::<f64>("", "commission")?,
+            row.try_get::<f64>("", "processing_fees")?,
+            row.try_get::<f64>("", "refunds")?,
+        ),
+        None => (0.0, 0.0, 0.0, 0.0),
+    };
+
+    let kept = |sales: f64, commission: f64, processing_fees: f64| match seller_id {
+        Some(_) => sales - commission - processing_fees,
+        None => commission,
+    };
+    let percent = |part: f64, whole: f64| if whole > 0.0 { (part / whole * 100.0) as f32 } else { 0.0 };
+
+    let rows = db
+        .query_all(Statement::from_sql_and_values(
+            DatabaseBackend::Postgres,
+            format!(
+                r#"
+                SELECT oi.product_id, MAX(oi.product_title) AS product_name,
+                       SUM(oi.quantity)::INT4 AS units_sold,
+                       SUM(oi.unit_price * oi.quantity)::FLOAT8 AS sales,
+                       SUM(oi.commission_amount)::FLOAT8 AS commission,
+                       SUM(oi.processing_fee)::FLOAT8 AS processing_fees
+                {} AND so.status <> $7
+                GROUP BY oi.product_id
+                ORDER BY sales DESC
+                LIMIT 20
+                "#,
+                scope
+            ),
+            values(),
+        ))
+        .await?;
+    let mut product_margins = Vec::new();
+    for row in rows {
+        let revenue: f64 = row.try_get("", "sales")?;
+        let units_sold: i32 = row.try_get("", "units_sold")?;
+        let profit = kept(revenue, row.try_get("", "commission")?, row.try_get("", "processing_fees")?);
+        product_margins.push(ProductMargin {
+            id: row.try_get("", "product_id")?,
+            name: row.try_get("", "product_name")?,
+            revenue,
+            cost: revenue - profit,
+            profit,
+            margin: percent(profit, revenue),
+            units_sold,
+            profit_per_unit: if units_sold > 0 { profit / units_sold as f64 } else { 0.0 },
+        });
+    }
+
+    let rows = db
+        .query_all(Statement::from_sql_and_values(
+            DatabaseBackend::Postgres,
+            format!(
+                r#"
+                SELECT c.id, c.name,
+                       SUM(oi.unit_price * oi.quantity)::FLOAT8 AS sales,
+                       SUM(oi.commission_amount)::FLOAT8 AS commission,
+                       SUM(oi.processing_fee)::FLOAT8 AS processing_fees
+                {} AND so.status <> $7
+                GROUP BY c.id, c.name
+                ORDER BY sales DESC
+                "#,
+                scope.replace(
+                    "JOIN orders o ON o.id = so.order_id",
+                    "JOIN orders o ON o.id = so.order_id
+        JOIN products p ON p.id = oi.product_id
+        JOIN categories c ON c.id = p.category_id",
+                )
+            ),
+            values(),
+        ))
+        .await?;
+    let mut category_margins = Vec::new();
+    for row in rows {
+        let revenue: f64 = row.try_get("", "sales")?;
+        let profit = kept(revenue, row.try_get("", "commission")?, row.try_get("", "processing_fees")?);
+        category_margins.push(CategoryMargin {
+            id: row.try_get("", "id")?,
+            name: row.try_get("", "name")?,
+            revenue,
+            cost: revenue - profit,
+            profit,
+            margin: percent(profit, revenue),
+        });
+    }
+
+    let gross = match seller_id {
+        Some(_) => sales - commission,
+        None => commission + processing_fees,
+    };
+
+    Ok(ProfitMargins {
+        sales,
+        gross_margin: percent(gross, sales),
+        net_margin: percent(kept(sales, commission, processing_fees), sales),
+        product_margins,
+        category_margins,
+        cost_breakdown: CostBreakdown {
+            commission,
+            processing_fees,
+            refunds,
+        },
+    })
 }
 
-// Calculate taxes
+// VAT on products at their current prices, as checkout would charge it before any discount code.
+// Each seller ships their own items, so delivery is quoted per seller at their cheapest option.
 pub async fn calculate_taxes(
     db: &DatabaseConnection,
     payload: CalculateTaxRequest,
 ) -> Result<TaxCalculation> {
-    // Validate request
     validation::validate(&payload)?;
 
-    // In a real implementation, this would use tax rules based on jurisdiction
-    // For this example, we'll simulate a simple tax calculation
-    
-    // Apply a basic tax rate based on shipping address
-    let base_tax_rate = match &payload.shipping_address {
-        Some(address) => {
-            match address.country.as_str() {
-                "Cameroon" => 0.195, // 19.5% VAT
-                _ => 0.0,  // No tax for international
-            }
-        },
-        None => 0.195, // Default to Cameroon VAT
-    };
-    
-    let mut tax_items = Vec::new();
-    let mut total_tax = 0.0;
-    
-    // Calculate tax for each item
+    let products: HashMap<Uuid, product::Model> = product::Entity::find()
+        .filter(product::Column::Id.is_in(payload.items.iter().map(|item| item.product_id)))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|p| (p.id, p))
+        .collect();
+
+    let mut lines = Vec::with_capacity(payload.items.len());
     for item in &payload.items {
-        let item_total = item.unit_price * item.quantity as f64;
-        let item_tax = item_total * base_tax_rate;
-        total_tax += item_tax;
-        
-        tax_items.push(TaxItem {
-            name: "VAT".to_string(),
-            rate: base_tax_rate as f32,
-            amount: item_tax,
+        let product = products
+            .get(&item.product_id)
+            .ok_or_else(|| AppError::not_found("Product not found"))?;
+        lines.push((product, to_money(&product.price)?.checked_mul(item.quantity as i64)?));
+    }
+
+    let mut sellers: Vec<Uuid> = lines.iter().map(|(product, _)| product.seller_id).collect();
+    sellers.sort();
+    sellers.dedup();
+
+    let now = Utc::now();
+    let mut item_taxes: Vec<Option<ItemTax>> = lines.iter().map(|_| None).collect();
+    for seller_id in &sellers {
+        let seller_lines: Vec<usize> = (0..lines.len())
+            .filter(|&i| lines[i].0.seller_id == *seller_id)
+            .collect();
+        let tax_lines: Vec<TaxLine> = seller_lines
+            .iter()
+            .map(|&i| TaxLine {
+                category_id: lines[i].0.category_id,
+                line_total: lines[i].1,
+            })
+            .collect();
+        let taxes = tax::compute_taxes(db, *seller_id, &tax_lines, Money::zero(Currency::XAF), now).await?;
+        for (&i, item_tax) in seller_lines.iter().zip(taxes) {
+            item_taxes[i] = Some(item_tax);
+        }
+    }
+
+    let rule_names: HashMap<Uuid, String> = tax_rule::Entity::find()
+        .filter(tax_rule::Column::Id.is_in(item_taxes.iter().flatten().filter_map(|t| t.rule_id)))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|rule| (rule.id, rule.name))
+        .collect();
+
+    let mut taxes = Vec::with_capacity(lines.len());
+    let mut added_tax = Money::zero(Currency::XAF);
+    for ((product, _), item_tax) in lines.iter().zip(item_taxes) {
+        let item_tax = item_tax.ok_or_else(|| AppError::internal("VAT was not worked out for every item"))?;
+        added_tax = added_tax.checked_add(item_tax.added())?;
+
+        let name = match item_tax.rule_id {
+            Some(rule_id) => rule_names.get(&rule_id).cloned().unwrap_or_default(),
+            None => "Not subject to VAT".to_string(),
+        };
+        taxes.push(TaxItem {
+            product_id: product.id,
+            name,
+            rate: item_tax.rate.to_f64().unwrap_or_default(),
+            prices_include_tax: item_tax.prices_include_tax,
+            taxable_amount: item_tax.taxable_amount,
+            amount: item_tax.tax,
         });
     }
-    
-    // Calculate grand total
-    let grand_total = payload.subtotal + payload.shipping_cost + total_tax;
-    
-    let tax_calculation = TaxCalculation {
-        order_id: payload.order_id,
-        subtotal: payload.subtotal,
-        shipping_cost: payload.shipping_cost,
-        taxes: tax_items,
+
+    let subtotal = Money::sum(lines.iter().map(|(_, total)| *total), Currency::XAF)?;
+    let mut shipping_cost = Money::zero(Currency::XAF);
+    if let Some(city) = payload.shipping_city.as_deref() {
+        let destination = Destination::new(city, payload.shipping_country.as_deref());
+        for seller_id in &sellers {
+            let seller_lines = lines.iter().zip(&payload.items).filter(|((product, _), _)| product.seller_id == *seller_id);
+            let weight_grams = seller_lines
+                .clone()
+                .map(|((product, _), item)| product.weight_grams as i64 * item.quantity as i64)
+                .sum();
+            let goods = Money::sum(seller_lines.map(|((_, total), _)| *total), Currency::XAF)?;
+            let quote = shipping::quote_seller(db, *seller_id, weight_grams, goods, &destination).await?;
+            let option = shipping::choose_option(&quote, None, &destination)?;
+            shipping_cost = shipping_cost.checked_add(option.price)?;
+        }
+    }
+    let total_tax = Money::sum(taxes.iter().map(|t| t.amount), Currency::XAF)?;
+
+    Ok(TaxCalculation {
+        subtotal,
+        shipping_cost,
+        taxes,
         total_tax,
-        grand_total,
-    };
-    
-    Ok(tax_calculation)
+        grand_total: subtotal.checked_add(shipping_cost)?.checked_add(added_tax)?,
+    })
 }
 
 // Track expense
@@ -378,6 +727,10 @@ pub async fn track_expense(
     // Validate request
     validation::validate(&payload)?;
 
+    if !payload.amount.is_positive() {
+        return Err(AppError::bad_request("Amount must be positive"));
+    }
+
     // Create expense record
     let expense_id = Uuid::new_v4();
     let now = Utc::now();
@@ -385,33 +738,19 @@ pub async fn track_expense(
     let expense_model = expense::ActiveModel {
         id: Set(expense_id),
         user_id: Set(user_id),
-        amount: Set(payload.amount),
-        currency: Set(payload.currency),
-        category: Set(payload.category),
-        description: Set(payload.description),
+        amount: Set(payload.amount.to_decimal()),
+        currency: Set(payload.amount.currency().to_string()),
+        category: Set(payload.category.trim().to_string()),
+        description: Set(payload.description.trim().to_string()),
         date: Set(payload.date),
         receipt_url: Set(payload.receipt_url),
         created_at: Set(now),
         updated_at: Set(now),
-        ..Default::default()
     };
     
     let expense_result = expense_model.insert(db).await?;
-    
-    let expense = Expense {
-        id: expense_result.id,
-        user_id: expense_result.user_id,
-        amount: expense_result.amount,
-        currency: expense_result.currency,
-        category: expense_result.category,
-        description: expense_result.description,
-        date: expense_result.date,
-        receipt_url: expense_result.receipt_url,
-        created_at: expense_result.created_at,
-        updated_at: expense_result.updated_at,
-    };
-    
-    Ok(expense)
+
+    to_expense(expense_result)
 }
 
 // Get expenses
@@ -440,109 +779,6 @@ pub async fn get_expenses(
     
     let expenses = query.all(db).await?;
     
-    let result = expenses.into_iter()
-        .map(|e| Expense {
-            id: e.id,
-            user_id: e.user_id,
-            amount: e.amount,
-            currency: e.currency,
-            category: e.category,
-            description: e.description,
-            date: e.date,
-            receipt_url: e.receipt_url,
-            created_at: e.created_at,
-            updated_at: e.updated_at,
-        })
-        .collect();
-    
-    Ok(result)
+    expenses.into_iter().map(to_expense).collect()
 }
 
-// Generate financial statement
-pub async fn generate_financial_statement(
-    db: &DatabaseConnection,
-    user_id: Uuid,
-    start_date: NaiveDate,
-    end_date: NaiveDate,
-) -> Result<FinancialStatement> {
-    // In a real implementation, this would query various financial records
-    // For this example, we'll return mock data
-    
-    let statement = FinancialStatement {
-        period_start: start_date,
-        period_end: end_date,
-        income_statement: IncomeStatement {
-            revenue: 50000.0,
-            cost_of_goods_sold: 25000.0,
-            gross_profit: 25000.0,
-            gross_margin: 50.0,
-            operating_expenses: {
-                let mut map = HashMap::new();
-                map.insert("Rent".to_string(), 2000.0);
-                map.insert("Utilities".to_string(), 500.0);
-                map.insert("Salaries".to_string(), 10000.0);
-                map.insert("Marketing".to_string(), 3000.0);
-                map.insert("Other".to_string(), 1500.0);
-                map
-            },
-            total_operating_expenses: 17000.0,
-            operating_income: 8000.0,
-            other_income: 500.0,
-            other_expenses: 300.0,
-            net_income: 8200.0,
-            net_margin: 16.4,
-        },
-        balance_sheet: BalanceSheet {
-            assets: {
-                let mut map = HashMap::new();
-                map.insert("Cash".to_string(), 15000.0);
-                map.insert("Accounts Receivable".to_string(), 5000.0);
-                map.insert("Inventory".to_string(), 20000.0);
-                map.insert("Equipment".to_string(), 10000.0);
-                map
-            },
-            total_assets: 50000.0,
-            liabilities: {
-                let mut map = HashMap::new();
-                map.insert("Accounts Payable".to_string(), 8000.0);
-                map.insert("Loans".to_string(), 10000.0);
-                map
-            },
-            total_liabilities: 18000.0,
-            equity: {
-                let mut map = HashMap::new();
-                map.insert("Owner's Equity".to_string(), 24000.0);
-                map.insert("Retained Earnings".to_string(), 8000.0);
-                map
-            },
-            total_equity: 32000.0,
-        },
-        cash_flow: CashFlow {
-            operating_activities: {
-                let mut map = HashMap::new();
-                map.insert("Net Income".to_string(), 8200.0);
-                map.insert("Depreciation".to_string(), 1000.0);
-                map.insert("Changes in Working Capital".to_string(), -2000.0);
-                map
-            },
-            net_cash_from_operating: 7200.0,
-            investing_activities: {
-                let mut map = HashMap::new();
-                map.insert("Purchase of Equipment".to_string(), -3000.0);
-                map
-            },
-            net_cash_from_investing: -3000.0,
-            financing_activities: {
-                let mut map = HashMap::new();
-                map.insert("Loan Payments".to_string(), -1000.0);
-                map
-            },
-            net_cash_from_financing: -1000.0,
-            net_change_in_cash: 3200.0,
-            beginning_cash_balance: 11800.0,
-            ending_cash_balance: 15000.0,
-        },
-    };
-    
-    Ok(statement)
-}
@@ -0,0 +1,516 @@
+use aws_sdk_s3::Client as S3Client;
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, Utc};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder, Set,
+    SqlErr, Statement, TransactionTrait,
+};
+use std::str::FromStr;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::entities::{invoice, order, order_item, seller_order, seller_tax_profile, user};
+use crate::errors::{AppError, Result};
+use crate::models::invoice::{TaxProfile, UpdateTaxProfileRequest};
+use crate::models::money::{Currency, Money};
+use crate::models::order::{OrderStatus, PaymentMethod, PaymentStatus, ShippingAddress};
+use crate::models::user::UserRole;
+use crate::utils::pdf::{load_pdf, store_pdf, PdfWriter, MARGIN, PAGE_WIDTH};
+
+// Longest product title that fits the description column
+const MAX_TITLE_CHARS: usize = 48;
+
+fn to_money(value: &BigDecimal) -> Result<Money> {
+    Ok(Money::from_decimal(value, Currency::XAF)?)
+}
+
+// NIUs are a letter, twelve digits and a check letter, e.g. M012345678901A
+fn normalize_niu(niu: &str) -> Option<String> {
+    let niu = niu.trim().to_uppercase();
+    let chars: Vec<char> = niu.chars().collect();
+
+    let valid = chars.len() == 14
+        && chars[0].is_ascii_alphabetic()
+        && chars[13].is_ascii_alphabetic()
+        && chars[1..13].iter().all(|c| c.is_ascii_digit());
+
+    valid.then_some(niu)
+}
+
+fn invoice_number(seller_id: Uuid, sequence: i32) -> String {
+    let prefix: String = seller_id.simple().to_string().chars().take(8).collect();
+    format!("INV-{}-{:06}", prefix.to_uppercase(), sequence)
+}
+
+// Get the tax details a seller prints on invoices
+pub async fn get_tax_profile(db: &DatabaseConnection, seller_id: Uuid) -> Result<TaxProfile> {
+    let profile = seller_tax_profile::Entity::find_by_id(seller_id).one(db).await?;
+
+    Ok(match profile {
+        Some(profile) => TaxProfile {
+            legal_name: profile.legal_name,
+            niu: profile.niu,
+            vat_registered: profile.vat_registered,
+            invoices_issued: profile.next_invoice_number - 1,
+            updated_at: Some(profile.updated_at),
+        },
+        None => TaxProfile {
+            legal_name: None,
+            niu: None,
+            vat_registered: true,
+            invoices_issued: 0,
+            updated_at: None,
+        },
+    })
+}
+
+// Update the seller's legal name, NIU and VAT registration. Invoices already issued keep the details
+// they were issued with, and VAT registration only changes the tax on orders placed from now on.
+pub async fn update_tax_profile(
+    db: &DatabaseConnection,
+    seller_id: Uuid,
+    payload: UpdateTaxProfileRequest,
+) -> Result<TaxProfile> {
+    let niu = match payload.niu.as_deref() {
+        Some(niu) => Some(
+            normalize_niu(niu).ok_or_else(|| AppError::validation("niu: Enter a valid NIU, e.g. M012345678901A"))?,
+        ),
+        None => None,
+    };
+    if payload.vat_registered == Some(true) && niu.is_none() {
+        return Err(AppError::validation("niu: Sellers registered for VAT must give their NIU"));
+    }
+    let legal_name = payload.legal_name.map(|name| name.trim().to_string());
+    let now = Utc::now();
+
+    match seller_tax_profile::Entity::find_by_id(seller_id).one(db).await? {
+        Some(profile) => {
+            let mut profile: seller_tax_profile::ActiveModel = profile.into();
+            profile.legal_name = Set(legal_name);
+            profile.niu = Set(niu);
+            if let Some(vat_registered) = payload.vat_registered {
+                profile.vat_registered = Set(vat_registered);
+            }
+            profile.updated_at = Set(now);
+            profile.update(db).await?;
+        }
+        None => {
+            let profile = seller_tax_profile::ActiveModel {
+                seller_id: Set(seller_id),
+                legal_name: Set(legal_name),
+                niu: Set(niu),
+                next_invoice_number: Set(1),
+                vat_registered: Set(payload.vat_registered.unwrap_or(true)),
+                created_at: Set(now),
+                updated_at: Set(now),
+            };
+            profile.insert(db).await?;
+        }
+    }
+
+    get_tax_profile(db, seller_id).await
+}
+
+// Pick the sub-order whose invoice the viewer asked for
+fn select_seller_order(
+    order: &order::Model,
+    mut seller_orders: Vec<seller_order::Model>,
+    viewer_id: Uuid,
+    role: &UserRole,
+    seller_order_id: Option<Uuid>,
+) -> Result<seller_order::Model> {
+    match role {
+        UserRole::Customer => {
+            if order.user_id != viewer_id {
+                return Err(AppError::forbidden("You are not authorized to view this order"));
+            }
+        }
+        UserRole::Seller => {
+            seller_orders.retain(|so| so.seller_id == viewer_id);
+            if seller_orders.is_empty() {
+                return Err(AppError::forbidden("You are not authorized to view this order"));
+            }
+        }
+        UserRole::Admin => {}
+        UserRole::PendingSeller => {
+            return Err(AppError::forbidden("Pending sellers cannot view orders"));
+        }
+    }
+
+    let canceled = OrderStatus::Canceled.to_string();
+
+    let selected = match seller_order_id {
+        Some(id) => seller_orders
+            .into_iter()
+            .find(|so| so.id == id)
+            .ok_or_else(|| AppError::not_found("Sub-order not found on this order"))?,
+        None => {
+            let mut open: Vec<seller_order::Model> =
+                seller_orders.into_iter().filter(|so| so.status != canceled).collect();
+            match open.len() {
+                0 => return Err(AppError::bad_request("Canceled orders are not invoiced")),
+                1 => open.remove(0),
+                _ => {
+                    return Err(AppError::bad_request(
+                        "Each seller issues their own invoice for this order, choose one with seller_order_id",
+                    ))
+                }
+            }
+        }
+    };
+
+    if selected.status == canceled {
+        return Err(AppError::bad_request("Canceled orders are not invoiced"));
+    }
+
+    Ok(selected)
+}
+
+// A sub-order counts as paid once the buyer paid online or the courier collected the cash
+fn paid_at(order: &order::Model, seller_order: &seller_order::Model) -> Option<DateTime<Utc>> {
+    if seller_order.cod_collected_at.is_some() {
+        return seller_order.cod_collected_at;
+    }
+
+    let paid = [PaymentStatus::Paid, PaymentStatus::PartiallyRefunded]
+        .iter()
+        .any(|status| order.payment_status == status.to_string());
+    paid.then(Utc::now)
+}
+
+// Give the sub-order the seller's next invoice number. The counter and the invoice are written in
+// one transaction so numbers have no gaps, and a second request for the same sub-order gets the
+// invoice the first one issued.
+async fn issue_invoice(
+    db: &DatabaseConnection,
+    order: &order::Model,
+    seller_order: &seller_order::Model,
+    paid_at: Option<DateTime<Utc>>,
+) -> Result<invoice::Model> {
+    let txn = db.begin().await?;
+
+    let row = txn
+        .query_one(Statement::from_sql_and_values(
+            sea_orm::DatabaseBackend::Postgres,
+            r#"
+            INSERT INTO seller_tax_profiles (seller_id, next_invoice_number)
+            VALUES ($1, 2)
+            ON CONFLICT (seller_id) DO UPDATE
+            SET next_invoice_number = seller_tax_profiles.next_invoice_number + 1
+            RETURNING next_invoice_number - 1 AS sequence, niu, vat_registered
+            "#,
+            [seller_order.seller_id.into()],
+        ))
+        .await?
+        .ok_or_else(|| AppError::internal("Failed to allocate an invoice number"))?;
+    let sequence: i32 = row.try_get("", "sequence")?;
+    let niu: Option<String> = row.try_get("", "niu")?;
+    let vat_registered: bool = row.try_get("", "vat_registered")?;
+
+    // A single rate is printed on the invoice; lines taxed at several rates each show their own
+    let mut rates: Vec<BigDecimal> = order_item::Entity::find()
+        .filter(order_item::Column::SellerOrderId.eq(seller_order.id))
+        .all(&txn)
+        .await?
+        .into_iter()
+        .map(|item| item.tax_rate)
+        .collect();
+    rates.sort();
+    rates.dedup();
+    let tax_rate = if rates.len() == 1 { rates.pop() } else { None };
+
+    let number = invoice_number(seller_order.seller_id, sequence);
+    let now = Utc::now();
+
+    let invoice = invoice::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        invoice_number: Set(number.clone()),
+        seller_id: Set(seller_order.seller_id),
+        sequence: Set(sequence),
+        order_id: Set(order.id),
+        seller_order_id: Set(seller_order.id),
+        seller_niu: Set(niu),
+        subtotal: Set(seller_order.subtotal.clone()),
+        discount_amount: Set(seller_order.discount_amount.clone()),
+        shipping_amount: Set(seller_order.shipping_amount.clone()),
+        tax_rate: Set(tax_rate),
+        tax_amount: Set(seller_order.tax_amount.clone()),
+        total_amount: Set(seller_order.total_amount.clone()),
+        vat_registered: Set(vat_registered),
+        paid_at: Set(paid_at),
+        storage_key: Set(format!("invoices/{}/{}.pdf", seller_order.seller_id, number)),
+        issued_at: Set(now),
+        updated_at: Set(now),
+    };
+
+    match invoice.insert(&txn).await {
+        Ok(invoice) => {
+            txn.commit().await?;
+            Ok(invoice)
+        }
+        Err(e) if matches!(e.sql_err(), Some(SqlErr::UniqueConstraintViolation(_))) => {
+            txn.rollback().await?;
+            invoice::Entity::find()
+                .filter(invoice::Column::SellerOrderId.eq(seller_order.id))
+                .one(db)
+                .await?
+                .ok_or_else(|| AppError::internal("Invoice not found after numbering conflict"))
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+// Get the PDF invoice for one sub-order of an order, issuing it on first request.
+// The document is stored in MinIO and only rendered again when the sub-order has since been paid,
+// at which point it is reissued as a receipt under the same number.
+pub async fn get_invoice_pdf(
+    db: &DatabaseConnection,
+    s3_client: &Arc<S3Client>,
+    bucket: &str,
+    order_id: Uuid,
+    viewer_id: Uuid,
+    role: &UserRole,
+    seller_order_id: Option<Uuid>,
+) -> Result<(String, Vec<u8>)> {
+    let order = order::Entity::find_by_id(order_id)
+        .one(db)
+        .await?
+        .ok_or_else(|| AppError::not_found("Order not found"))?;
+
+    let seller_orders = seller_order::Entity::find()
+        .filter(seller_order::Column::OrderId.eq(order_id))
+        .all(db)
+        .await?;
+    let seller_order = select_seller_order(&order, seller_orders, viewer_id, role, seller_order_id)?;
+    let paid_at = paid_at(&order, &seller_order);
+
+    let existing = invoice::Entity::find()
+        .filter(invoice::Column::SellerOrderId.eq(seller_order.id))
+        .one(db)
+        .await?;
+
+    let invoice = match existing {
+        Some(invoice) if invoice.paid_at.is_some() || paid_at.is_none() => {
+            // Regenerate if the stored copy went missing
+            if let Ok(pdf) = load_pdf(s3_client, bucket, &invoice.storage_key).await {
+                return Ok((invoice.invoice_number, pdf));
+            }
+            invoice
+        }
+        Some(invoice) => {
+            let mut active: invoice::ActiveModel = invoice.into();
+            active.paid_at = Set(paid_at);
+            active.updated_at = Set(Utc::now());
+            active.update(db).await?
+        }
+        None => issue_invoice(db, &order, &seller_order, paid_at).await?,
+    };
+
+    let pdf = render(db, &order, &invoice).await?;
+    store_pdf(s3_client, bucket, &invoice.storage_key, pdf.clone()).await?;
+
+    Ok((invoice.invoice_number, pdf))
+}
+
+// Everything printed on an invoice, gathered before drawing
+struct InvoiceContent {
+    invoice: invoice::Model,
+    seller: user::Model,
+    legal_name: Option<String>,
+    buyer_email: String,
+    shipping_address: Option<ShippingAddress>,
+    payment_method: String,
+    items: Vec<order_item::Model>,
+}
+
+async fn render(db: &DatabaseConnection, order: &order::Model, invoice: &invoice::Model) -> Result<Vec<u8>> {
+    let seller = user::Entity::find_by_id(invoice.seller_id)
+        .one(db)
+        .await?
+        .ok_or_else(|| AppError::not_found("Seller not found"))?;
+    let buyer = user::Entity::find_by_id(order.user_id)
+        .one(db)
+        .await?
+        .ok_or_else(|| AppError::not_found("Buyer not found"))?;
+    let legal_name = seller_tax_profile::Entity::find_by_id(invoice.seller_id)
+        .one(db)
+        .await?
+        .and_then(|profile| profile.legal_name);
+
+    let items = order_item::Entity::find()
+        .filter(order_item::Column::SellerOrderId.eq(invoice.seller_order_id))
+        .order_by_asc(order_item::Column::CreatedAt)
+        .all(db)
+        .await?;
+
+    let payment_method = match PaymentMethod::from_str(&order.payment_method) {
+        Ok(PaymentMethod::Mtn) => "MTN Mobile Money".to_string(),
+        Ok(PaymentMethod::Orange) => "Orange Money".to_string(),
+        Ok(PaymentMethod::CashOnDelivery) => "Cash on delivery".to_string(),
+        Ok(PaymentMethod::Other) | Err(_) => "Other".to_string(),
+    };
+
+    let content = InvoiceContent {
+        invoice: invoice.clone(),
+        seller,
+        legal_name,
+        buyer_email: buyer.email,
+        shipping_address: serde_json::from_value(order.shipping_address.clone()).ok(),
+        payment_method,
+        items,
+    };
+
+    draw(&content)
+}
+
+// A VAT rate as printed, e.g. 19.25%
+fn format_rate(rate: &BigDecimal) -> String {
+    format!("{}%", (rate * BigDecimal::from(100)).normalized())
+}
+
+fn draw(content: &InvoiceContent) -> Result<Vec<u8>> {
+    let invoice = &content.invoice;
+    let right = PAGE_WIDTH - MARGIN;
+    let title = if invoice.paid_at.is_some() { "INVOICE / RECEIPT" } else { "INVOICE" };
+
+    let mut pdf = PdfWriter::new(&format!("Invoice {}", invoice.invoice_number))?;
+
+    // Header: seller on the left, invoice details on the right
+    pdf.text(title, MARGIN, 18.0, true);
+    pdf.text_right(&invoice.invoice_number, right, 11.0, true);
+    pdf.advance(8.0);
+    pdf.text_right(&format!("Issued {}", invoice.issued_at.format("%d/%m/%Y")), right, 9.0, false);
+    pdf.advance(5.0);
+    pdf.text_right(&format!("Order {}", invoice.order_id), right, 9.0, false);
+    pdf.advance(10.0);
+
+    let seller_name = content.legal_name.as_deref().unwrap_or(&content.seller.name);
+    let mut seller_lines = vec![seller_name.to_string()];
+    seller_lines.push(format!("NIU: {}", invoice.seller_niu.as_deref().unwrap_or("not provided")));
+    seller_lines.extend(content.seller.address_street.clone());
+    let city = [content.seller.address_city.clone(), content.seller.address_country.clone()]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>()
+        .join(", ");
+    if !city.is_empty() {
+        seller_lines.push(city);
+    }
+    seller_lines.push(content.seller.email.clone());
+    seller_lines.extend(content.seller.phone.clone());
+
+    let mut buyer_lines = Vec::new();
+    if let Some(address) = &content.shipping_address {
+        buyer_lines.push(address.name.clone());
+        buyer_lines.push(address.address_1.clone());
+        buyer_lines.extend(address.address_2.clone());
+        buyer_lines.push(format!("{}, {}", address.city, address.country));
+        buyer_lines.extend(address.phone.clone());
+    }
+    buyer_lines.push(content.buyer_email.clone());
+
+    pdf.text("Sold by", MARGIN, 9.0, true);
+    pdf.text("Billed to", 115.0, 9.0, true);
+    pdf.advance(5.0);
+    for i in 0..seller_lines.len().max(buyer_lines.len()) {
+        if let Some(line) = seller_lines.get(i) {
+            pdf.text(line, MARGIN, 9.0, false);
+        }
+        if let Some(line) = buyer_lines.get(i) {
+            pdf.text(line, 115.0, 9.0, false);
+        }
+        pdf.advance(4.5);
+    }
+    pdf.advance(8.0);
+
+    // Line items
+    pdf.text("Description", MARGIN, 9.0, true);
+    pdf.text_right("Qty", 112.0, 9.0, true);
+    pdf.text_right("Unit price", 140.0, 9.0, true);
+    pdf.text_right("VAT", 160.0, 9.0, true);
+    pdf.text_right("Amount", right, 9.0, true);
+    pdf.advance(2.0);
+    pdf.rule();
+    pdf.advance(4.0);
+
+    for item in &content.items {
+        let unit_price = to_money(&item.unit_price)?;
+        let mut title: String = item.product_title.chars().take(MAX_TITLE_CHARS).collect();
+        if item.product_title.chars().count() > MAX_TITLE_CHARS {
+            title.push_str("...");
+        }
+
+        let vat = if item.tax_rule_id.is_some() { format_rate(&item.tax_rate) } else { "-".to_string() };
+
+        pdf.text(&title, MARGIN, 9.0, false);
+        pdf.text_right(&item.quantity.to_string(), 112.0, 9.0, false);
+        pdf.text_right(&unit_price.to_string(), 140.0, 9.0, false);
+        pdf.text_right(&vat, 160.0, 9.0, false);
+        pdf.text_right(&unit_price.checked_mul(item.quantity as i64)?.to_string(), right, 9.0, false);
+        pdf.advance(5.5);
+    }
+    pdf.rule();
+    pdf.advance(4.0);
+
+    // Totals and VAT breakdown by rate. VAT is charged on each item after its share of the discount,
+    // and taken out of prices that already include it; delivery is billed without VAT.
+    let subtotal = to_money(&invoice.subtotal)?;
+    let discount = to_money(&invoice.discount_amount)?;
+    let mut taxable = Money::zero(Currency::XAF);
+    let mut breakdown: Vec<(BigDecimal, Money, Money)> = Vec::new();
+    for item in &content.items {
+        let (base, tax) = (to_money(&item.taxable_amount)?, to_money(&item.tax_amount)?);
+        taxable = taxable.checked_add(base)?;
+        if item.tax_rule_id.is_none() {
+            continue;
+        }
+        match breakdown.iter_mut().find(|(rate, _, _)| *rate == item.tax_rate) {
+            Some((_, rate_base, rate_tax)) => {
+                *rate_base = rate_base.checked_add(base)?;
+                *rate_tax = rate_tax.checked_add(tax)?;
+            }
+            None => breakdown.push((item.tax_rate.clone(), base, tax)),
+        }
+    }
+
+    let mut totals = vec![("Subtotal".to_string(), subtotal)];
+    if discount.is_positive() {
+        totals.push(("Discount".to_string(), Money::zero(Currency::XAF).checked_sub(discount)?));
+    }
+    totals.push(("Taxable amount (HT)".to_string(), taxable));
+    for (rate, base, tax) in breakdown {
+        let label = if rate == 0 {
+            format!("VAT exempt on {}", base)
+        } else {
+            format!("VAT {} on {}", format_rate(&rate), base)
+        };
+        totals.push((label, tax));
+    }
+    totals.push(("Delivery".to_string(), to_money(&invoice.shipping_amount)?));
+
+    for (label, amount) in totals {
+        pdf.text(&label, 115.0, 9.0, false);
+        pdf.text_right(&amount.to_string(), right, 9.0, false);
+        pdf.advance(5.0);
+    }
+    pdf.advance(1.0);
+    pdf.text("Total (TTC)", 115.0, 11.0, true);
+    pdf.text_right(&to_money(&invoice.total_amount)?.to_string(), right, 11.0, true);
+    pdf.advance(12.0);
+
+    // Payment
+    pdf.text(&format!("Payment method: {}", content.payment_method), MARGIN, 9.0, false);
+    pdf.advance(5.0);
+    match invoice.paid_at {
+        Some(paid_at) => pdf.text(&format!("Paid on {}", paid_at.format("%d/%m/%Y")), MARGIN, 9.0, true),
+        None => pdf.text("Awaiting payment", MARGIN, 9.0, true),
+    }
+    pdf.advance(5.0);
+    pdf.text("All amounts in CFA francs (XAF).", MARGIN, 8.0, false);
+    if !invoice.vat_registered {
+        pdf.advance(4.5);
+        pdf.text("VAT not applicable: the seller is not registered for VAT.", MARGIN, 8.0, false);
+    }
+
+    pdf.finish()
+}
@@ -0,0 +1,591 @@
+use bigdecimal::BigDecimal;
+use sea_orm::{
+    ColumnTrait, ConnectionTrait, DatabaseBackend, DatabaseConnection, EntityTrait, QueryFilter, QuerySelect,
+    Statement,
+};
+use std::str::FromStr;
+use uuid::Uuid;
+
+use crate::entities::{ledger_account, order_item, seller_order};
+use crate::errors::{AppError, Result};
+use crate::models::ledger::{LedgerAccountKind, LedgerTransactionKind, WalletBalance, WalletHistoryQuery, WalletTransaction};
+use crate::models::money::{Currency, Money};
+use crate::models::order::OrderStatus;
+
+fn to_money(value: &BigDecimal) -> Result<Money> {
+    Ok(Money::from_decimal(value, Currency::XAF)?)
+}
+
+// One side of a movement: money into (positive) or out of (negative) an account
+pub struct Posting {
+    pub owner_id: Option<Uuid>,
+    pub account: LedgerAccountKind,
+    pub amount: Money,
+}
+
+impl Posting {
+    pub fn seller(seller_id: Uuid, account: LedgerAccountKind, amount: Money) -> Self {
+        Self {
+            owner_id: Some(seller_id),
+            account,
+            amount,
+        }
+    }
+
+    pub fn platform(account: LedgerAccountKind, amount: Money) -> Self {
+        Self {
+            owner_id: None,
+            account,
+            amount,
+        }
+    }
+}
+
+// What a ledger transaction is about
+pub struct NewTransaction {
+    pub kind: LedgerTransactionKind,
+    pub order_id: Option<Uuid>,
+    pub seller_order_id: Option<Uuid>,
+    pub withdrawal_id: Option<Uuid>,
+    pub refund_id: Option<Uuid>,
+    pub description: String,
+}
+
+impl NewTransaction {
+    fn for_seller_order(kind: LedgerTransactionKind, seller_order: &seller_order::Model, description: String) -> Self {
+        Self {
+            kind,
+            order_id: Some(seller_order.order_id),
+            seller_order_id: Some(seller_order.id),
+            withdrawal_id: None,
+            refund_id: None,
+            description,
+        }
+    }
+}
+
+// The account's id, opening it on first use
+async fn account_id<C: ConnectionTrait>(db: &C, owner_id: Option<Uuid>, kind: LedgerAccountKind) -> Result<Uuid> {
+    let find = || async {
+        let query = ledger_account::Entity::find().filter(ledger_account::Column::Kind.eq(kind.to_string()));
+        let query = match owner_id {
+            Some(owner_id) => query.filter(ledger_account::Column::OwnerId.eq(owner_id)),
+            None => query.filter(ledger_account::Column::OwnerId.is_null()),
+        };
+        query.one(db).await
+    };
+
+    if let Some(account) = find().await? {
+        return Ok(account.id);
+    }
+
+    db.execute(Statement::from_sql_and_values(
+        DatabaseBackend::Postgres,
+        "INSERT INTO ledger_accounts (owner_id, kind) VALUES ($1, $2) ON CONFLICT DO NOTHING",
+        [owner_id.into(), kind.to_string().into()],
+    ))
+    .await?;
+
+    find()
+        .await?
+        .map(|account| account.id)
+        .ok_or_else(|| AppError::internal(format!("Could not open the {} ledger account", kind)))
+}
+
+// Record a movement of money. The postings must add up to zero. Returns false, changing nothing,
+// when a sub-order's capture or release was already recorded. Run this inside a transaction.
+pub async fn post<C: ConnectionTrait>(db: &C, transaction: NewTransaction, postings: Vec<Posting>) -> Result<bool> {
+    let postings: Vec<Posting> = postings.into_iter().filter(|p| !p.amount.is_zero()).collect();
+    if postings.is_empty() {
+        return Ok(false);
+    }
+    if !Money::sum(postings.iter().map(|p| p.amount), Currency::XAF)?.is_zero() {
+        return Err(AppError::internal(format!(
+            "Unbalanced {} ledger transaction: {}",
+            transaction.kind, transaction.description
+        )));
+    }
+
+    let row = db
+        .query_one(Statement::from_sql_and_values(
+            DatabaseBackend::Postgres,
+            r#"
+            INSERT INTO ledger_transactions (kind, order_id, seller_order_id, withdrawal_id, refund_id, description)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            ON CONFLICT DO NOTHING
+            RETURNING id
+            "#,
+            [
+                transaction.kind.to_string().into(),
+                transaction.order_id.into(),
+                transaction.seller_order_id.into(),
+                transaction.withdrawal_id.into(),
+                transaction.refund_id.into(),
+                transaction.description.into(),
+            ],
+        ))
+        .await?;
+    let Some(row) = row else {
+        return Ok(false);
+    };
+    let transaction_id: Uuid = row.try_get("", "id")?;
+
+    let mut entries = Vec::with_capacity(postings.len());
+    for posting in postings {
+        entries.push((account_id(db, posting.owner_id, posting.account).await?, posting.amount));
+    }
+    // Accounts are always updated in the same order so concurrent postings cannot deadlock
+    entries.sort_by_key(|(account_id, _)| *account_id);
+
+    for (account_id, amount) in entries {
+        db.execute(Statement::from_sql_and_values(
+            DatabaseBackend::Postgres,
+            r#"
+            WITH updated AS (
+                UPDATE ledger_accounts SET balance = balance + $2 WHERE id = $1 RETURNING balance
+            )
+            INSERT INTO ledger_entries (transaction_id, account_id, amount, balance_after)
+            SELECT $3, $1, $2, balance FROM updated
+            "#,
+            [account_id.into(), amount.to_decimal().into(), transaction_id.into()],
+        ))
+        .await?;
+    }
+
+    Ok(true)
+}
+
+async fn has_transaction<C: ConnectionTrait>(db: &C, seller_order_id: Uuid, kind: LedgerTransactionKind) -> Result<bool> {
+    let row = db
+        .query_one(Statement::from_sql_and_values(
+            DatabaseBackend::Postgres,
+            "SELECT EXISTS (SELECT 1 FROM ledger_transactions WHERE seller_order_id = $1 AND kind = $2) AS found",
+            [seller_order_id.into(), kind.to_string().into()],
+        ))
+        .await?
+        .ok_or_else(|| AppError::internal("Ledger lookup returned no row"))?;
+
+    Ok(row.try_get("", "found")?)
+}
+
+// What a sub-order has moved into one kind of account so far
+async fn seller_order_balance<C: ConnectionTrait>(
+    db: &C,
+    seller_order_id: Uuid,
+    account: LedgerAccountKind,
+) -> Result<Money> {
+    let row = db
+        .query_one(Statement::from_sql_and_values(
+            DatabaseBackend::Postgres,
+            r#"
+            SELECT COALESCE(SUM(e.amount), 0) AS balance
+            FROM ledger_entries e
+            JOIN ledger_transactions t ON t.id = e.transaction_id
+            JOIN ledger_accounts a ON a.id = e.account_id
+            WHERE t.seller_order_id = $1 AND a.kind = $2
+            "#,
+            [seller_order_id.into(), account.to_string().into()],
+        ))
+        .await?
+        .ok_or_else(|| AppError::internal("Ledger lookup returned no row"))?;
+
+    to_money(&row.try_get("", "balance")?)
+}
+
+// The marketplace's share of a sub-order: commission and processing fees fixed on each item at checkout
+pub async fn fees_for<C: ConnectionTrait>(db: &C, seller_order: &seller_order::Model) -> Result<Money> {
+    let items = order_item::Entity::find()
+        .filter(order_item::Column::SellerOrderId.eq(seller_order.id))
+        .all(db)
+        .await?;
+
+    let mut fees = Money::zero(Currency::XAF);
+    for item in items {
+        fees = fees
+            .checked_add(to_money(&item.commission_amount)?)?
+            .checked_add(to_money(&item.processing_fee)?)?;
+    }
+
+    Ok(fees.min(to_money(&seller_order.total_amount)?)?)
+}
+
+// An order was paid online: hold each seller's part in escrow until it is delivered.
+// Parts delivered before the payment came in are released straight away.
+pub async fn record_payment_captured<C: ConnectionTrait>(db: &C, order_id: Uuid) -> Result<()> {
+    let seller_orders = seller_order::Entity::find()
+        .filter(seller_order::Column::OrderId.eq(order_id))
+        .filter(seller_order::Column::Status.ne(OrderStatus::Canceled.to_string()))
+        .all(db)
+        .await?;
+
+    for seller_order in seller_orders {
+        let total = to_money(&seller_order.total_amount)?;
+        let order_number = order_id.to_string()[..8].to_uppercase();
+
+        post(
+            db,
+            NewTransaction::for_seller_order(
+                LedgerTransactionKind::PaymentCaptured,
+                &seller_order,
+                format!("Payment for order #{}", order_number),
+            ),
+            vec![
+                Posting::platform(LedgerAccountKind::PlatformCash, Money::zero(Currency::XAF).checked_sub(total)?),
+                Posting::seller(seller_order.seller_id, LedgerAccountKind::SellerEscrow, total),
+            ],
+        )
+        .await?;
+
+        if seller_order.status == OrderStatus::Delivered.to_string() {
+            release_seller_order(db, &seller_order).await?;
+        }
+    }
+
+    Ok(())
+}
+
+// A sub-order was delivered: move what escrow holds for it to the seller's wallet, less fees.
+// Nothing happens for sub-orders not paid online yet.
+pub async fn release_seller_order<C: ConnectionTrait>(db: &C, seller_order: &seller_order::Model) -> Result<Option<Money>> {
+    if !has_transaction(db, seller_order.id, LedgerTransactionKind::PaymentCaptured).await? {
+        return Ok(None);
+    }
+
+    let held = seller_order_balance(db, seller_order.id, LedgerAccountKind::SellerEscrow).await?;
+    if !held.is_positive() {
+        return Ok(None);
+    }
+
+    // Refunds before delivery shrink the fees in proportion
+    let total = to_money(&seller_order.total_amount)?;
+    let commission = fees_for(db, seller_order).await?;
+    let parts = held.allocate(&[total.checked_sub(commission)?, commission]).unwrap_or_else(|_| vec![held, Money::zero(Currency::XAF)]);
+    let (net, commission) = (parts[0], parts[1]);
+
+    let order_number = seller_order.order_id.to_string()[..8].to_uppercase();
+    let posted = post(
+        db,
+        NewTransaction::for_seller_order(
+            LedgerTransactionKind::DeliveryReleased,
+            seller_order,
+            format!("Order #{} delivered, {} in fees", order_number, commission),
+        ),
+        vec![
+            Posting::seller(seller_order.seller_id, LedgerAccountKind::SellerEscrow, Money::zero(Currency::XAF).checked_sub(held)?),
+            Posting::seller(seller_order.seller_id, LedgerAccountKind::SellerWallet, net),
+            Posting::platform(LedgerAccountKind::PlatformCommission, commission),
+        ],
+    )
+    .await?;
+
+    Ok(posted.then_some(net))
+}
+
+// A cash on delivery sub-order was paid to the seller's courier. The seller keeps the cash
+// and owes the marketplace its fees, which are taken from their wallet.
+pub async fn record_cash_collected<C: ConnectionTrait>(db: &C, seller_order: &seller_order::Model) -> Result<Money> {
+    let commission = fees_for(db, seller_order).await?;
+    let order_number = seller_order.order_id.to_string()[..8].to_uppercase();
+
+    post(
+        db,
+        NewTransaction::for_seller_order(
+            LedgerTransactionKind::CashCollected,
+            seller_order,
+            format!("Cash collected for order #{}, {} in fees", order_number, commission),
+        ),
+        vec![
+            Posting::seller(seller_order.seller_id, LedgerAccountKind::SellerWallet, Money::zero(Currency::XAF).checked_sub(commission)?),
+            Posting::platform(LedgerAccountKind::PlatformCommission, commission),
+        ],
+    )
+    .await?;
+
+    to_money(&seller_order.total_amount)?.checked_sub(commission).map_err(Into::into)
+}
+
+// Money refunded to the buyer for part of a sub-order is taken back from the seller: first from
+// what escrow still holds for it, the rest from the wallet and the commission already taken, in
+// the proportion they were paid. Sub-orders the marketplace never received money for are skipped.
+pub async fn record_refund<C: ConnectionTrait>(
+    db: &C,
+    refund_id: Uuid,
+    seller_order: &seller_order::Model,
+    amount: Money,
+) -> Result<()> {
+    let captured = has_transaction(db, seller_order.id, LedgerTransactionKind::PaymentCaptured).await?;
+    let cash_collected = has_transaction(db, seller_order.id, LedgerTransactionKind::CashCollected).await?;
+    if !amount.is_positive() || !(captured || cash_collected) {
+        return Ok(());
+    }
+
+    let zero = Money::zero(Currency::XAF);
+    let held = seller_order_balance(db, seller_order.id, LedgerAccountKind::SellerEscrow).await?;
+    let from_escrow = amount.min(held.max(zero)?)?;
+    let rest = amount.checked_sub(from_escrow)?;
+
+    let (from_wallet, from_commission) = if rest.is_positive() {
+        let commission = seller_order_balance(db, seller_order.id, LedgerAccountKind::PlatformCommission).await?.max(zero)?;
+        let total = to_money(&seller_order.total_amount)?;
+        let parts = rest
+            .allocate(&[total.checked_sub(commission)?.max(zero)?, commission])
+            .unwrap_or_else(|_| vec![rest, zero]);
+        (parts[0], parts[1])
+    } else {
+        (zero, zero)
+    };
+
+    let seller_id = seller_order.seller_id;
+    let order_number = seller_order.order_id.to_string()[..8].to_uppercase();
+
+    post(
+        db,
+        NewTransaction {
+            refund_id: Some(refund_id),
+            ..NewTransaction::for_seller_order(
+                LedgerTransactionKind::Refund,
+                seller_order,
+                format!("Refund of {} on order #{}", amount, order_number),
+            )
+        },
+        vec![
+            Posting::seller(seller_id, LedgerAccountKind::SellerEscrow, zero.checked_sub(from_escrow)?),
+            Posting::seller(seller_id, LedgerAccountKind::SellerWallet, zero.checked_sub(from_wallet)?),
+            Posting::platform(LedgerAccountKind::PlatformCommission, zero.checked_sub(from_commission)?),
+            Posting::platform(LedgerAccountKind::PlatformCash, amount),
+        ],
+    )
+    .await?;
+
+    Ok(())
+}
+
+// Balance of one of a seller's accounts, zero when it was never opened
+pub async fn seller_balance<C: ConnectionTrait>(db: &C, seller_id: Uuid, account: LedgerAccountKind) -> Result<Money> {
+    let balance = ledger_account::Entity::find()
+        .filter(ledger_account::Column::OwnerId.eq(seller_id))
+        .filter(ledger_account::Column::Kind.eq(account.to_string()))
+        .one(db)
+        .await?
+        .map(|account| to_money(&account.balance))
+        .transpose()?;
+
+    Ok(balance.unwrap_or(Money::zero(Currency::XAF)))
+}
+
+// Like seller_balance, but holds the account until the transaction ends so the balance
+// cannot change between checking it and spending it
+pub async fn lock_seller_balance<C: ConnectionTrait>(db: &C, seller_id: Uuid, account: LedgerAccountKind) -> Result<Money> {
+    let id = account_id(db, Some(seller_id), account).await?;
+    let account = ledger_account::Entity::find_by_id(id)
+        .lock_exclusive()
+        .one(db)
+        .await?
+        .ok_or_else(|| AppError::internal("Ledger account disappeared"))?;
+
+    to_money(&account.balance)
+}
+
+pub async fn get_wallet_balance(db: &DatabaseConnection, seller_id: Uuid) -> Result<WalletBalance> {
+    Ok(WalletBalance {
+        available: seller_balance(db, seller_id, LedgerAccountKind::SellerWallet).await?,
+        in_escrow: seller_balance(db, seller_id, LedgerAccountKind::SellerEscrow).await?,
+        pending_withdrawal: seller_balance(db, seller_id, LedgerAccountKind::SellerWithdrawals).await?,
+    })
+}
+
+// Movements on a seller's accounts, newest first
+pub async fn get_wallet_history(
+    db: &DatabaseConnection,
+    seller_id: Uuid,
+    query: WalletHistoryQuery,
+) -> Result<(Vec<WalletTransaction>, u64)> {
+    let page = query.page.unwrap_or(1).max(1);
+    let per_page = query.per_page.unwrap_or(20).clamp(1, 50);
+    let account = query.account;
+
+    if account.is_some_and(|account| !account.is_seller_account()) {
+        return Err(AppError::bad_request("Only seller accounts can be listed"));
+    }
+    let account = account.map(|account| account.to_string());
+
+    let total_row = db
+        .query_one(Statement::from_sql_and_values(
+            DatabaseBackend::Postgres,
+            r#"
+            SELECT COUNT(*) AS total
+            FROM ledger_entries e
+            JOIN ledger_accounts a ON a.id = e.account_id
+            WHERE a.owner_id = $1 AND ($2::TEXT IS NULL OR a.kind = $2)
+            "#,
+            [seller_id.into(), account.clone().into()],
+        ))
+        .await?
+        .ok_or_else(|| AppError::internal("Count query returned no row"))?;
+    let total: i64 = total_row.try_get("", "total")?;
+
+    let rows = db
+        .query_all(Statement::from_sql_and_values(
+            DatabaseBackend::Postgres,
+            r#"
+            SELECT e.id, t.kind, a.kind AS account, e.amount, e.balance_after, t.order_id, t.seller_order_id,
+                   t.withdrawal_id, t.description, e.created_at
+            FROM ledger_entries e
+            JOIN ledger_accounts a ON a.id = e.account_id
+            JOIN ledger_transactions t ON t.id = e.transaction_id
+            WHERE a.owner_id = $1 AND ($2::TEXT IS NULL OR a.kind = $2)
+            ORDER BY e.created_at DESC, e.id
+            LIMIT $3 OFFSET $4
+            "#,
+            [
+                seller_id.into(),
+                account.into(),
+                (per_page as i64).into(),
+                (((page - 1) * per_page) as i64).into(),
+            ],
+        ))
+        .await?;
+
+    let entries = rows
+        .into_iter()
+        .map(|row| {
+            Ok(WalletTransaction {
+                id: row.try_get("", "id")?,
+                kind: LedgerTransactionKind::from_str(&row.try_get::<String>("", "kind")?).map_err(AppError::internal)?,
+                account: LedgerAccountKind::from_str(&row.try_get::<String>("", "account")?).map_err(AppError::internal)?,
+                amount: to_money(&row.try_get("", "amount")?)?,
+                balance_after: to_money(&row.try_get("", "balance_after")?)?,
+                order_id: row.try_get("", "order_id")?,
+                seller_order_id: row.try_get("", "seller_order_id")?,
+                withdrawal_id: row.try_get("", "withdrawal_id")?,
+                description: row.try_get("", "description")?,
+                created_at: row.try_get("", "created_at")?,
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok((entries, total as u64))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use sea_orm::{DatabaseBackend, MockDatabase, MockExecResult, Transaction, Value};
+    use std::collections::BTreeMap;
+
+    fn seller_order(order_id: Uuid, total: i64) -> seller_order::Model {
+        let now = Utc::now();
+        seller_order::Model {
+            id: Uuid::new_v4(),
+            order_id,
+            seller_id: Uuid::new_v4(),
+            status: OrderStatus::Processing.to_string(),
+            subtotal: BigDecimal::from(total),
+            discount_amount: BigDecimal::from(0),
+            shipping_amount: BigDecimal::from(0),
+            tax_amount: BigDecimal::from(0),
+            total_amount: BigDecimal::from(total),
+            fulfillment_status: "unfulfilled".to_string(),
+            shipping_method: "delivery".to_string(),
+            shipping_zone: None,
+            shipping_rate_id: None,
+            tracking_number: None,
+            shipping_provider: None,
+            shipped_at: None,
+            delivered_at: None,
+            payout_status: "pending".to_string(),
+            payout_amount: BigDecimal::from(0),
+            paid_out_at: None,
+            cancellation_reason: None,
+            cancellation_note: None,
+            canceled_by: None,
+            canceled_at: None,
+            delivery_code: None,
+            delivery_code_attempts: 0,
+            cod_collected_amount: None,
+            cod_collected_at: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    fn account(owner_id: Option<Uuid>, kind: LedgerAccountKind) -> ledger_account::Model {
+        let now = Utc::now();
+        ledger_account::Model {
+            id: Uuid::new_v4(),
+            owner_id,
+            kind: kind.to_string(),
+            currency: "XAF".to_string(),
+            balance: BigDecimal::from(0),
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    // The amount of every entry written, in the order they were posted
+    fn entry_amounts(log: Vec<Transaction>) -> Vec<Money> {
+        log.iter()
+            .flat_map(|txn| txn.statements().to_vec())
+            .filter(|stmt| stmt.sql.contains("INSERT INTO ledger_entries"))
+            .map(|stmt| match &stmt.values.as_ref().unwrap().0[1] {
+                Value::BigDecimal(Some(amount)) => to_money(amount).unwrap(),
+                other => panic!("unexpected entry amount {:?}", other),
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn captured_payments_balance() {
+        let order_id = Uuid::new_v4();
+        let parts = [seller_order(order_id, 12_500), seller_order(order_id, 2_599)];
+
+        let mut mock = MockDatabase::new(DatabaseBackend::Postgres).append_query_results([parts.to_vec()]);
+        for part in &parts {
+            mock = mock
+                .append_query_results([[BTreeMap::from([("id", Value::from(Uuid::new_v4()))])]])
+                .append_query_results([[account(None, LedgerAccountKind::PlatformCash)]])
+                .append_query_results([[account(Some(part.seller_id), LedgerAccountKind::SellerEscrow)]])
+                .append_exec_results([
+                    MockExecResult { last_insert_id: 0, rows_affected: 1 },
+                    MockExecResult { last_insert_id: 0, rows_affected: 1 },
+                ]);
+        }
+        let db = mock.into_connection();
+
+        record_payment_captured(&db, order_id).await.unwrap();
+
+        // Each part moves from the marketplace's cash into the seller's escrow
+        let amounts = entry_amounts(db.into_transaction_log());
+        assert_eq!(amounts.len(), 4);
+        assert!(Money::sum(amounts.iter().copied(), Currency::XAF).unwrap().is_zero());
+        assert!(amounts.contains(&Money::xaf(12_500)) && amounts.contains(&Money::xaf(-12_500)));
+        assert!(amounts.contains(&Money::xaf(2_599)) && amounts.contains(&Money::xaf(-2_599)));
+    }
+
+    #[tokio::test]
+    async fn unbalanced_postings_are_refused() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres).into_connection();
+        let transaction = NewTransaction {
+            kind: LedgerTransactionKind::PaymentCaptured,
+            order_id: None,
+            seller_order_id: None,
+            withdrawal_id: None,
+            refund_id: None,
+            description: "test".to_string(),
+        };
+
+        let result = post(
+            &db,
+            transaction,
+            vec![
+                Posting::platform(LedgerAccountKind::PlatformCash, Money::xaf(-1000)),
+                Posting::seller(Uuid::new_v4(), LedgerAccountKind::SellerEscrow, Money::xaf(999)),
+            ],
+        )
+        .await;
+
+        assert!(result.is_err());
+        assert!(db.into_transaction_log().is_empty());
+    }
+}
@@ -1,5 +1,5 @@
 use sea_orm::{
-    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, Set,
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, EntityTrait, QueryFilter, Set,
     TransactionTrait, QuerySelect, QueryOrder, Order, Condition, IntoActiveModel, Statement, Value,
 };
 use uuid::Uuid;
@@ -8,10 +8,13 @@ use std::collections::HashMap;
 use rand::{thread_rng, Rng};
 use rand::distributions::Alphanumeric;
 use sea_orm::prelude::Decimal;
+use bigdecimal::{BigDecimal, ToPrimitive};
+use std::str::FromStr;
 
 use crate::{
     entities::{product, user, order, saved_item, campaign, discount_code, email_campaign},
     errors::{AppError, Result},
+    models::money::{Currency, Money},
     models::marketing::{
         Campaign, CampaignType, CreateCampaignRequest, TargetAudience,
         DiscountCode, DiscountType, CreateDiscountCodeRequest,
@@ -19,9 +22,51 @@ use crate::{
         ProductRecommendation, GetRecommendationsRequest,
         SocialMediaPost, SocialEngagement, CreateSocialPostRequest
     },
+    services::product_view,
     utils::validation,
 };
 
+// Number of days of views that count towards trending products
+const TRENDING_WINDOW_DAYS: i64 = 7;
+
+fn to_money(value: &BigDecimal) -> Result<Money> {
+    Ok(Money::from_decimal(value, Currency::XAF)?)
+}
+
+// Discount codes store amounts as rust_decimal values
+fn decimal_to_big(value: &Decimal) -> BigDecimal {
+    BigDecimal::new(value.mantissa().into(), value.scale() as i64)
+}
+
+fn big_to_decimal(value: &BigDecimal) -> Result<Decimal> {
+    let (_, scale) = value.as_bigint_and_exponent();
+    let (mantissa, scale) = value.with_scale(scale.clamp(0, 28)).into_bigint_and_exponent();
+    let mantissa = mantissa.to_i64().ok_or_else(|| AppError::bad_request(format!("Value out of range: {}", value)))?;
+
+    Ok(Decimal::new(mantissa, scale as u32))
+}
+
+fn decimal_to_money(value: &Decimal) -> Result<Money> {
+    to_money(&decimal_to_big(value))
+}
+
+fn money_to_decimal(value: Money) -> Decimal {
+    Decimal::new(value.amount(), value.currency().minor_digits())
+}
+
+// A percentage sent as a JSON number, e.g. 12.5
+fn percent_from(value: f64) -> Result<BigDecimal> {
+    BigDecimal::from_str(&value.to_string()).map_err(|_| AppError::bad_request(format!("Invalid percentage: {}", value)))
+}
+
+// What a discount code stores as its value: the percentage as sent, or a fixed amount in whole francs
+fn discount_value_from(discount_type: &DiscountType, value: f64) -> Result<BigDecimal> {
+    match discount_type {
+        DiscountType::FixedAmount => Ok(Money::from_major(value, Currency::XAF)?.to_decimal()),
+        _ => percent_from(value),
+    }
+}
+
 // Create promotional campaign
 pub async fn create_campaign(
     db: &DatabaseConnection,
@@ -203,8 +248,8 @@ async fn create_discount_code_internal(
         campaign_id: Set(campaign_id),
         code: Set(payload.code.clone()),
         discount_type: Set(format!("{:?}", payload.discount_type)),
-        value: Set(Decimal::new((payload.discount_value * 100.0) as i64, 2)), // Convert from f64 to Decimal
-        min_purchase_amount: Set(payload.min_purchase_amount.map(|val| Decimal::new((val * 100.0) as i64, 2))),
+        value: Set(big_to_decimal(&discount_value_from(&payload.discount_type, payload.discount_value)?)?),
+        min_purchase_amount: Set(payload.min_purchase_amount.map(money_to_decimal)),
         max_uses: Set(payload.usage_limit),
         times_used: Set(0),
         start_date: Set(payload.start_date.into()),
@@ -229,8 +274,8 @@ async fn create_discount_code_internal(
             "FreeShipping" => DiscountType::FreeShipping,
             _ => DiscountType::Bundled,
         },
-        discount_value: code_result.value.to_string().parse::<f64>().unwrap_or(0.0),
-        min_purchase_amount: code_result.min_purchase_amount.map(|d| d.to_string().parse::<f64>().unwrap_or(0.0)),
+        discount_value: decimal_to_big(&code_result.value),
+        min_purchase_amount: code_result.min_purchase_amount.as_ref().map(decimal_to_money).transpose()?,
         max_discount_amount: None, // Entity doesn't have this field
         usage_limit: code_result.max_uses,
         usage_count: code_result.times_used,
@@ -287,11 +332,11 @@ pub async fn generate_discount_code(
 }
 
 // Validate a discount code
-pub async fn validate_discount_code(
-    db: &DatabaseConnection,
+pub async fn validate_discount_code<C: ConnectionTrait>(
+    db: &C,
     user_id: Uuid,
     code: &str,
-    subtotal: f64,
+    subtotal: Money,
     products: Vec<Uuid>,
 ) -> Result<DiscountCode> {
     // Find code
@@ -316,11 +361,10 @@ pub async fn validate_discount_code(
     }
     
     // Check minimum purchase amount
-    if let Some(min_amount) = discount.min_purchase_amount {
-        // Convert Decimal to f64 for comparison
-        let min_amount_f64 = min_amount.to_string().parse::<f64>().unwrap_or(0.0);
-        if subtotal < min_amount_f64 {
-            return Err(AppError::bad_request(format!("Order subtotal must be at least {}", min_amount_f64)));
+    let min_purchase_amount = discount.min_purchase_amount.as_ref().map(decimal_to_money).transpose()?;
+    if let Some(min_amount) = min_purchase_amount {
+        if subtotal.checked_sub(min_amount)?.is_negative() {
+            return Err(AppError::bad_request(format!("Order subtotal must be at least {}", min_amount)));
         }
     }
     
@@ -341,8 +385,8 @@ pub async fn validate_discount_code(
             "FreeShipping" => DiscountType::FreeShipping,
             _ => DiscountType::Bundled,
         },
-        discount_value: discount.value.to_string().parse::<f64>().unwrap_or(0.0),
-        min_purchase_amount: discount.min_purchase_amount.map(|d| d.to_string().parse::<f64>().unwrap_or(0.0)),
+        discount_value: decimal_to_big(&discount.value),
+        min_purchase_amount,
         max_discount_amount: None, // Entity doesn't have this field
         usage_limit: discount.max_uses,
         usage_count: discount.times_used,
@@ -358,45 +402,47 @@ pub async fn validate_discount_code(
     Ok(discount_code)
 }
 
+// What a discount code takes off a seller's items and off their shipping
+pub struct DiscountAmounts {
+    pub goods: Money,
+    pub shipping: Money,
+}
+
 // Apply discount to cart
 pub async fn apply_discount(
     discount: &DiscountCode,
-    subtotal: f64,
-    products: Vec<(Uuid, f64, i32)>, // (product_id, price, quantity)
-) -> Result<f64> {
-    let mut discount_amount = 0.0;
-    
-    match discount.discount_type {
-        DiscountType::Percentage => {
-            // Use the discount_value from the DiscountCode model
-            discount_amount = subtotal * (discount.discount_value / 100.0);
-        },
+    subtotal: Money,
+    shipping: Money,
+) -> Result<DiscountAmounts> {
+    let zero = Money::zero(subtotal.currency());
+    let (goods, shipping) = match discount.discount_type {
+        DiscountType::Percentage => (subtotal.percent(&discount.discount_value)?, zero),
         DiscountType::FixedAmount => {
-            // Use the discount_value from the DiscountCode model
-            discount_amount = discount.discount_value;
-            if discount_amount > subtotal {
-                discount_amount = subtotal;
-            }
+            // A fixed discount never takes the subtotal below zero
+            (Money::from_decimal(&discount.discount_value, subtotal.currency())?.min(subtotal)?, zero)
         },
         DiscountType::FreeShipping => {
-            // Free shipping is handled separately at checkout
-            discount_amount = 0.0;
+            // Waives the seller's delivery charge, leaving the items at full price
+            (zero, shipping.max(zero)?)
         },
         DiscountType::BuyXGetY => {
             // Complex logic for BOGO discounts would go here
             // For simplicity, treating as a percentage discount
-            discount_amount = subtotal * 0.1; // 10% off as placeholder
+            (subtotal.percent(&BigDecimal::from(10))?, zero) // 10% off as placeholder
         },
         DiscountType::Bundled => {
             // Complex logic for bundle discounts would go here
-            discount_amount = subtotal * 0.15; // 15% off as placeholder
+            (subtotal.percent(&BigDecimal::from(15))?, zero) // 15% off as placeholder
         },
-    }
-    
-    // Our entity doesn't have a max_discount_amount field, so we'll skip this check
-    // In a production system, we might want to add this field to the entity
-    
-    Ok(discount_amount)
+    };
+
+    Ok(match discount.max_discount_amount {
+        Some(max) => DiscountAmounts {
+            goods: goods.min(max)?,
+            shipping: shipping.min(max)?,
+        },
+        None => DiscountAmounts { goods, shipping },
+    })
 }
 
 // Increment discount code usage count
@@ -444,15 +490,15 @@ pub async fn get_product_recommendations(
                     .await?;
                 
                 recommendations = similar_products.into_iter()
-                    .map(|p| ProductRecommendation {
+                    .map(|p| Ok(ProductRecommendation {
                         product_id: p.id,
                         name: p.title,
                         image: p.images.0.first().cloned().unwrap_or_default(),
-                        price: p.price.to_string().parse::<f64>().unwrap_or(0.0),
+                        price: to_money(&p.price)?,
                         relevance_score: 0.95, // Placeholder
                         recommendation_type: "similar".to_string(),
-                    })
-                    .collect();
+                    }))
+                    .collect::<Result<_>>()?;
             }
         },
         Some("frequently_bought_together") => {
@@ -467,36 +513,48 @@ pub async fn get_product_recommendations(
                 
                 recommendations = related_products.into_iter()
                     .filter(|p| p.id != product_id)
-                    .map(|p| ProductRecommendation {
+                    .map(|p| Ok(ProductRecommendation {
                         product_id: p.id,
                         name: p.title,
                         image: p.images.0.first().cloned().unwrap_or_default(),
-                        price: p.price.to_string().parse::<f64>().unwrap_or(0.0),
+                        price: to_money(&p.price)?,
                         relevance_score: 0.85, // Placeholder
                         recommendation_type: "frequently_bought_together".to_string(),
-                    })
-                    .collect();
+                    }))
+                    .collect::<Result<_>>()?;
             }
         },
         Some("trending") => {
-            // Get trending products based on views and purchases
-            // This would query product_view and order_item tables
-            // Simplified for prototype
-            let trending_products = product::Entity::find()
-                .limit(limit)
-                .all(db)
-                .await?;
-            
+            // Trending products are the most viewed over the last week
+            let mut trending_products = product_view::get_trending_products(db, TRENDING_WINDOW_DAYS, limit).await?;
+
+            // Fall back to all-time view counts while there is little recent traffic
+            if trending_products.is_empty() {
+                trending_products = product::Entity::find()
+                    .order_by_desc(product::Column::ViewCount)
+                    .limit(limit)
+                    .all(db)
+                    .await?
+                    .into_iter()
+                    .map(|p| {
+                        let views = p.view_count;
+                        (p, views)
+                    })
+                    .collect();
+            }
+
+            let max_views = trending_products.iter().map(|(_, views)| *views).max().unwrap_or(0).max(1);
+
             recommendations = trending_products.into_iter()
-                .map(|p| ProductRecommendation {
+                .map(|(p, views)| Ok(ProductRecommendation {
                     product_id: p.id,
                     name: p.title,
                     image: p.images.0.first().cloned().unwrap_or_default(),
-                    price: p.price.to_string().parse::<f64>().unwrap_or(0.0),
-                    relevance_score: 0.9, // Placeholder
+                    price: to_money(&p.price)?,
+                    relevance_score: views as f32 / max_views as f32,
                     recommendation_type: "trending".to_string(),
-                })
-                .collect();
+                }))
+                .collect::<Result<_>>()?;
         },
         Some("for_you") | None => {
             // Personalized recommendations based on user's history
@@ -531,15 +589,15 @@ pub async fn get_product_recommendations(
                 
                 recommendations = product_map.values()
                     .take(limit as usize)
-                    .map(|p| ProductRecommendation {
+                    .map(|p| Ok(ProductRecommendation {
                         product_id: p.id,
                         name: p.title.clone(),
                         image: p.images.0.first().cloned().unwrap_or_default().clone(),
-                        price: p.price.to_string().parse::<f64>().unwrap_or(0.0),
+                        price: to_money(&p.price)?,
                         relevance_score: 0.9, // Placeholder
                         recommendation_type: "for_you".to_string(),
-                    })
-                    .collect();
+                    }))
+                    .collect::<Result<_>>()?;
             }
         },
         _ => {
@@ -550,15 +608,15 @@ pub async fn get_product_recommendations(
                 .await?;
             
             recommendations = default_products.into_iter()
-                .map(|p| ProductRecommendation {
+                .map(|p| Ok(ProductRecommendation {
                     product_id: p.id,
                     name: p.title,
                     image: p.images.0.first().cloned().unwrap_or_default(),
-                    price: p.price.to_string().parse::<f64>().unwrap_or(0.0),
+                    price: to_money(&p.price)?,
                     relevance_score: 0.8, // Placeholder
                     recommendation_type: "recommended".to_string(),
-                })
-                .collect();
+                }))
+                .collect::<Result<_>>()?;
         }
     }
     
@@ -5,8 +5,31 @@ pub mod category;
 pub mod upload;
 pub mod cart;
 pub mod order;
+pub mod order_status;
 pub mod message;
 pub mod saved_item;
 pub mod analytics;
 pub mod admin;
 pub mod marketing;
+pub mod notification;
+pub mod product_question;
+pub mod product_view;
+pub mod wishlist;
+pub mod cod;
+pub mod invoice;
+pub mod reorder;
+pub mod payment_gateway;
+pub mod mtn_momo;
+pub mod orange_money;
+pub mod payment_webhook;
+pub mod payment_reconciliation;
+pub mod financial;
+pub mod ledger;
+pub mod withdrawal;
+pub mod commission;
+pub mod tax;
+pub mod statement;
+pub mod refund;
+pub mod shipping;
+pub mod shipping_carrier;
+pub mod shipment;
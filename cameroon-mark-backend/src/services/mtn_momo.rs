@@ -0,0 +1,423 @@
+use async_trait::async_trait;
+use bigdecimal::BigDecimal;
+use reqwest::{Client, Response, StatusCode};
+use serde::Deserialize;
+use serde_json::json;
+use std::time::Duration;
+
+use crate::config::PaymentConfig;
+use crate::errors::{AppError, Result};
+use crate::models::money::{Currency, Money};
+use crate::services::payment_gateway::{
+    fee_rate, format_amount, msisdn, CallbackEvent, GatewayPaymentStatus, PaymentGateway, PaymentInitiation, PaymentLookup, PaymentRequest,
+    RefundInstruction, TokenCache, TransferInitiation, TransferRequest, PROVIDER_TIMEOUT,
+};
+
+const SANDBOX_URL: &str = "https://sandbox.momodeveloper.mtn.com";
+const PRODUCTION_URL: &str = "https://proxy.momoapi.mtn.com";
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    expires_in: u64,
+}
+
+// Final state of a request to pay or a transfer
+#[derive(Deserialize)]
+struct TransactionStatus {
+    status: String,
+    reason: Option<serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+struct ErrorBody {
+    code: Option<String>,
+    message: Option<String>,
+}
+
+// Collections and Disbursements are separate MoMo products, each with its own credentials
+struct MomoProduct {
+    path: &'static str,
+    api_user: String,
+    api_key: String,
+    subscription_key: String,
+    token: TokenCache,
+}
+
+impl MomoProduct {
+    fn new(path: &'static str, api_user: &str, api_key: &str, subscription_key: &str) -> Self {
+        Self {
+            path,
+            api_user: api_user.to_string(),
+            api_key: api_key.to_string(),
+            subscription_key: subscription_key.to_string(),
+            token: TokenCache::new(),
+        }
+    }
+}
+
+// MTN Mobile Money Collections: the payer approves a prompt on their phone.
+// Disbursements pay sellers straight to their MoMo account.
+pub struct MtnMomoGateway {
+    client: Client,
+    base_url: &'static str,
+    target_environment: &'static str,
+    currency: &'static str,
+    callback_url: String,
+    collection: MomoProduct,
+    disbursement: MomoProduct,
+    fee_rate: BigDecimal,
+}
+
+impl MtnMomoGateway {
+    pub fn new(config: &PaymentConfig) -> Self {
+        let client = Client::builder().timeout(PROVIDER_TIMEOUT).build().unwrap_or_default();
+
+        // The sandbox only takes EUR
+        let (base_url, target_environment, currency) = if config.sandbox {
+            (SANDBOX_URL, "sandbox", "EUR")
+        } else {
+            (PRODUCTION_URL, "mtncameroon", "XAF")
+        };
+
+        Self {
+            client,
+            base_url,
+            target_environment,
+            currency,
+            callback_url: format!("{}/api/payments/webhooks/mtn", config.callback_base_url.trim_end_matches('/')),
+            collection: MomoProduct::new(
+                "collection",
+                &config.mtn_api_key,
+                &config.mtn_api_secret,
+                &config.mtn_subscription_key,
+            ),
+            disbursement: MomoProduct::new(
+                "disbursement",
+                &config.mtn_disbursement_api_key,
+                &config.mtn_disbursement_api_secret,
+                &config.mtn_disbursement_subscription_key,
+            ),
+            fee_rate: fee_rate(config.mtn_processing_fee_rate),
+        }
+    }
+
+    async fn access_token(&self, product: &MomoProduct) -> Result<String> {
+        product
+            .token
+            .get_or_fetch(|| async {
+                let response = self
+                    .client
+                    .post(format!("{}/{}/token/", self.base_url, product.path))
+                    .basic_auth(&product.api_user, Some(&product.api_key))
+                    .header("Ocp-Apim-Subscription-Key", &product.subscription_key)
+                    .send()
+                    .await
+                    .map_err(unreachable)?;
+                let token: TokenResponse = check(response).await?.json().await.map_err(unreadable)?;
+
+                Ok((token.access_token, Duration::from_secs(token.expires_in)))
+            })
+            .await
+    }
+}
+
+fn unreachable(e: reqwest::Error) -> AppError {
+    AppError::external_service(format!("MTN MoMo is unreachable: {}", e))
+}
+
+fn unreadable(e: reqwest::Error) -> AppError {
+    AppError::external_service(format!("MTN MoMo sent an unexpected response: {}", e))
+}
+
+// Turn MoMo error codes into messages support can act on
+fn describe(code: &str) -> &'static str {
+    match code {
+        "PAYER_NOT_FOUND" => "the phone number has no MTN MoMo account",
+        "NOT_ENOUGH_FUNDS" => "the payer does not have enough funds",
+        "PAYER_LIMIT_REACHED" | "PAYEE_LIMIT_REACHED" => "a transaction limit was reached",
+        "APPROVAL_REJECTED" | "TRANSACTION_CANCELED" => "the payer declined the payment",
+        "EXPIRED" => "the payer did not approve the payment in time",
+        "RESOURCE_ALREADY_EXIST" => "the payment was already requested",
+        "INVALID_CURRENCY" => "the currency is not supported",
+        "NOT_ALLOWED" | "NOT_ALLOWED_TARGET_ENVIRONMENT" | "PAYEE_NOT_ALLOWED_TO_RECEIVE" => {
+            "the merchant account is not allowed to collect this payment"
+        }
+        "SERVICE_UNAVAILABLE" | "INTERNAL_PROCESSING_ERROR" | "COULD_NOT_PERFORM_TRANSACTION" => {
+            "the service is temporarily unavailable"
+        }
+        _ => "the request was rejected",
+    }
+}
+
+async fn check(response: Response) -> Result<Response> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+
+    let body: Option<ErrorBody> = response.json().await.ok();
+    let code = body.as_ref().and_then(|b| b.code.clone()).unwrap_or_default();
+    let message = body.and_then(|b| b.message).unwrap_or_default();
+
+    let detail = match status {
+        StatusCode::UNAUTHORIZED => "the API credentials were refused",
+        StatusCode::NOT_FOUND if code.is_empty() => "the payment was not found",
+        _ => describe(&code),
+    };
+
+    Err(AppError::external_service(format!(
+        "MTN MoMo: {} ({} {} {})",
+        detail, status, code, message
+    )))
+}
+
+fn parse_status(status: &str, reason: Option<&serde_json::Value>) -> GatewayPaymentStatus {
+    match status {
+        "SUCCESSFUL" => GatewayPaymentStatus::Successful,
+        "FAILED" | "REJECTED" | "TIMEOUT" => {
+            let code = match reason {
+                Some(serde_json::Value::String(code)) => code.as_str(),
+                Some(reason) => reason["code"].as_str().unwrap_or_default(),
+                None => "",
+            };
+            GatewayPaymentStatus::Failed {
+                reason: describe(code).to_string(),
+            }
+        }
+        _ => GatewayPaymentStatus::Pending,
+    }
+}
+
+#[async_trait]
+impl PaymentGateway for MtnMomoGateway {
+    fn name(&self) -> &'static str {
+        "MTN MoMo"
+    }
+
+    async fn request_to_pay(&self, request: &PaymentRequest) -> Result<PaymentInitiation> {
+        let phone = request
+            .phone_number
+            .as_deref()
+            .ok_or_else(|| AppError::bad_request("A phone number is required for MTN Mobile Money payments"))?;
+        let token = self.access_token(&self.collection).await?;
+
+        // Our payment id doubles as the MoMo reference, so retrying the same payment cannot charge twice
+        let reference = request.payment_id.to_string();
+
+        // MoMo does not sign callbacks, so each payment gets a secret in its callback URL instead
+        let callback_token = &request.callback_token;
+
+        let response = self
+            .client
+            .post(format!("{}/collection/v1_0/requesttopay", self.base_url))
+            .bearer_auth(token)
+            .header("X-Reference-Id", &reference)
+            .header("X-Target-Environment", self.target_environment)
+            .header("X-Callback-Url", format!("{}?token={}", self.callback_url, callback_token))
+            .header("Ocp-Apim-Subscription-Key", &self.collection.subscription_key)
+            .json(&json!({
+                "amount": format_amount(request.amount),
+                "currency": self.currency,
+                "externalId": request.order_id.to_string(),
+                "payer": {
+                    "partyIdType": "MSISDN",
+                    "partyId": msisdn(phone),
+                },
+                "payerMessage": request.description,
+                "payeeNote": request.description,
+            }))
+            .send()
+            .await
+            .map_err(unreachable)?;
+
+        // A 4xx other than a reused reference means MoMo turned the request down and nothing can be
+        // collected under it. Anything else leaves the outcome to the status check.
+        let status = response.status();
+        if let Err(e) = check(response).await {
+            if !status.is_client_error() || status == StatusCode::CONFLICT {
+                return Err(e);
+            }
+            tracing::warn!("MTN MoMo turned down payment {}: {:?}", reference, e);
+            return Ok(PaymentInitiation {
+                provider_reference: reference,
+                status: GatewayPaymentStatus::Failed {
+                    reason: "MTN MoMo turned the payment request down".to_string(),
+                },
+                redirect_url: None,
+                callback_token: None,
+            });
+        }
+
+        Ok(PaymentInitiation {
+            provider_reference: reference,
+            status: GatewayPaymentStatus::Pending,
+            redirect_url: None,
+            callback_token: None,
+        })
+    }
+
+    fn reference_for(&self, request: &PaymentRequest) -> Option<String> {
+        Some(request.payment_id.to_string())
+    }
+
+    async fn check_status(&self, lookup: &PaymentLookup) -> Result<GatewayPaymentStatus> {
+        let token = self.access_token(&self.collection).await?;
+
+        let response = self
+            .client
+            .get(format!(
+                "{}/collection/v1_0/requesttopay/{}",
+                self.base_url, lookup.provider_reference
+            ))
+            .bearer_auth(token)
+            .header("X-Target-Environment", self.target_environment)
+            .header("Ocp-Apim-Subscription-Key", &self.collection.subscription_key)
+            .send()
+            .await
+            .map_err(unreachable)?;
+
+        // A request to pay MoMo has no record of never reached it, so nothing was collected
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(GatewayPaymentStatus::Failed {
+                reason: "MTN MoMo never received the payment request".to_string(),
+            });
+        }
+        let status: TransactionStatus = check(response).await?.json().await.map_err(unreadable)?;
+
+        Ok(parse_status(&status.status, status.reason.as_ref()))
+    }
+
+    fn processing_fee_rate(&self) -> BigDecimal {
+        self.fee_rate.clone()
+    }
+
+    fn supports_transfers(&self) -> bool {
+        !self.disbursement.subscription_key.is_empty()
+    }
+
+    async fn transfer(&self, request: &TransferRequest) -> Result<TransferInitiation> {
+        let token = self.access_token(&self.disbursement).await?;
+
+        // Our withdrawal id doubles as the MoMo reference, so a retried transfer cannot pay twice
+        let reference = request.withdrawal_id.to_string();
+
+        let response = self
+            .client
+            .post(format!("{}/disbursement/v1_0/transfer", self.base_url))
+            .bearer_auth(token)
+            .header("X-Reference-Id", &reference)
+            .header("X-Target-Environment", self.target_environment)
+            .header("Ocp-Apim-Subscription-Key", &self.disbursement.subscription_key)
+            .json(&json!({
+                "amount": format_amount(request.amount),
+                "currency": self.currency,
+                "externalId": reference,
+                "payee": {
+                    "partyIdType": "MSISDN",
+                    "partyId": msisdn(&request.phone_number),
+                },
+                "payerMessage": request.description,
+                "payeeNote": request.description,
+            }))
+            .send()
+            .await
+            .map_err(unreachable)?;
+        check(response).await?;
+
+        Ok(TransferInitiation {
+            provider_reference: reference,
+            status: GatewayPaymentStatus::Pending,
+        })
+    }
+
+    async fn check_transfer(&self, lookup: &PaymentLookup) -> Result<GatewayPaymentStatus> {
+        let token = self.access_token(&self.disbursement).await?;
+
+        let response = self
+            .client
+            .get(format!(
+                "{}/disbursement/v1_0/transfer/{}",
+                self.base_url, lookup.provider_reference
+            ))
+            .bearer_auth(token)
+            .header("X-Target-Environment", self.target_environment)
+            .header("Ocp-Apim-Subscription-Key", &self.disbursement.subscription_key)
+            .send()
+            .await
+            .map_err(unreachable)?;
+        let status: TransactionStatus = check(response).await?.json().await.map_err(unreadable)?;
+
+        Ok(parse_status(&status.status, status.reason.as_ref()))
+    }
+
+    // Refunds are paid from the disbursement account, like transfers
+    fn supports_refunds(&self) -> bool {
+        self.supports_transfers()
+    }
+
+    async fn refund(&self, instruction: &RefundInstruction) -> Result<TransferInitiation> {
+        let token = self.access_token(&self.disbursement).await?;
+        let reference = instruction.reference.to_string();
+
+        let response = self
+            .client
+            .post(format!("{}/disbursement/v1_0/refund", self.base_url))
+            .bearer_auth(token)
+            .header("X-Reference-Id", &reference)
+            .header("X-Target-Environment", self.target_environment)
+            .header("Ocp-Apim-Subscription-Key", &self.disbursement.subscription_key)
+            .json(&json!({
+                "amount": format_amount(instruction.amount),
+                "currency": self.currency,
+                "externalId": reference,
+                "payerMessage": instruction.description,
+                "payeeNote": instruction.description,
+                "referenceIdToRefund": instruction.payment_reference,
+            }))
+            .send()
+            .await
+            .map_err(unreachable)?;
+        check(response).await?;
+
+        Ok(TransferInitiation {
+            provider_reference: reference,
+            status: GatewayPaymentStatus::Pending,
+        })
+    }
+
+    async fn check_refund(&self, lookup: &PaymentLookup) -> Result<GatewayPaymentStatus> {
+        let token = self.access_token(&self.disbursement).await?;
+
+        let response = self
+            .client
+            .get(format!(
+                "{}/disbursement/v1_0/refund/{}",
+                self.base_url, lookup.provider_reference
+            ))
+            .bearer_auth(token)
+            .header("X-Target-Environment", self.target_environment)
+            .header("Ocp-Apim-Subscription-Key", &self.disbursement.subscription_key)
+            .send()
+            .await
+            .map_err(unreachable)?;
+        let status: TransactionStatus = check(response).await?.json().await.map_err(unreadable)?;
+
+        Ok(parse_status(&status.status, status.reason.as_ref()))
+    }
+
+    // MoMo posts the final request-to-pay object; the payment is identified by the token in the callback URL
+    fn parse_callback(&self, payload: &serde_json::Value) -> Option<CallbackEvent> {
+        let status = payload["status"].as_str()?;
+
+        Some(CallbackEvent {
+            token: None,
+            status: parse_status(status, payload.get("reason")),
+            transaction_id: payload["financialTransactionId"].as_str().map(str::to_string),
+            amount: payload["amount"]
+                .as_str()
+                .and_then(|amount| amount.parse().ok())
+                .and_then(|amount| Money::from_major(amount, Currency::XAF).ok()),
+        })
+    }
+}
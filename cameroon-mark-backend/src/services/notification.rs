@@ -0,0 +1,203 @@
+use chrono::{FixedOffset, Timelike, Utc};
+use std::str::FromStr;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, EntityTrait, IntoActiveModel,
+    QueryFilter, QueryOrder, Set,
+};
+use uuid::Uuid;
+
+use crate::{
+    entities::{notification, notification_preferences},
+    errors::{AppError, Result},
+    models::message_enhancement::{MessageNotificationPreferences, UpdateNotificationPreferencesRequest},
+    models::notification::{Notification, NotificationFrequency, NotificationKind},
+};
+
+// Quiet hours are expressed in Cameroon local time (WAT, UTC+1)
+const LOCAL_UTC_OFFSET_SECONDS: i32 = 3600;
+
+impl From<notification_preferences::Model> for MessageNotificationPreferences {
+    fn from(model: notification_preferences::Model) -> Self {
+        Self {
+            user_id: model.user_id,
+            email_notifications: model.email_notifications,
+            push_notifications: model.push_notifications,
+            notification_frequency: model.notification_frequency,
+            quiet_hours_start: model.quiet_hours_start,
+            quiet_hours_end: model.quiet_hours_end,
+            updated_at: model.updated_at,
+        }
+    }
+}
+
+// Get a user's notification preferences, falling back to the defaults
+pub async fn get_preferences<C: ConnectionTrait>(db: &C, user_id: Uuid) -> Result<MessageNotificationPreferences> {
+    let preferences = notification_preferences::Entity::find_by_id(user_id)
+        .one(db)
+        .await?;
+
+    Ok(match preferences {
+        Some(prefs) => MessageNotificationPreferences::from(prefs),
+        None => MessageNotificationPreferences {
+            user_id,
+            email_notifications: true,
+            push_notifications: true,
+            notification_frequency: NotificationFrequency::Immediate.to_string(),
+            quiet_hours_start: None,
+            quiet_hours_end: None,
+            updated_at: Utc::now(),
+        },
+    })
+}
+
+// Create or update a user's notification preferences
+pub async fn update_preferences(
+    db: &DatabaseConnection,
+    user_id: Uuid,
+    payload: UpdateNotificationPreferencesRequest,
+) -> Result<MessageNotificationPreferences> {
+    let frequency = NotificationFrequency::from_str(&payload.notification_frequency)
+        .map_err(|_| AppError::validation("notification_frequency: must be immediate, daily or off"))?;
+
+    for hour in [payload.quiet_hours_start, payload.quiet_hours_end].into_iter().flatten() {
+        if !(0..=23).contains(&hour) {
+            return Err(AppError::validation("Quiet hours must be between 0 and 23"));
+        }
+    }
+
+    let existing = notification_preferences::Entity::find_by_id(user_id)
+        .one(db)
+        .await?;
+
+    let now = Utc::now();
+    let preferences = match existing {
+        Some(prefs) => {
+            let mut prefs = prefs.into_active_model();
+            prefs.email_notifications = Set(payload.email_notifications);
+            prefs.push_notifications = Set(payload.push_notifications);
+            prefs.notification_frequency = Set(frequency.to_string());
+            prefs.quiet_hours_start = Set(payload.quiet_hours_start);
+            prefs.quiet_hours_end = Set(payload.quiet_hours_end);
+            prefs.updated_at = Set(now);
+            prefs.update(db).await?
+        }
+        None => {
+            let prefs = notification_preferences::ActiveModel {
+                user_id: Set(user_id),
+                email_notifications: Set(payload.email_notifications),
+                push_notifications: Set(payload.push_notifications),
+                notification_frequency: Set(frequency.to_string()),
+                quiet_hours_start: Set(payload.quiet_hours_start),
+                quiet_hours_end: Set(payload.quiet_hours_end),
+                created_at: Set(now),
+                updated_at: Set(now),
+            };
+            prefs.insert(db).await?
+        }
+    };
+
+    Ok(MessageNotificationPreferences::from(preferences))
+}
+
+// Work out which channels a notification goes out on right now
+fn delivery_channels(prefs: &MessageNotificationPreferences) -> Vec<String> {
+    let mut channels = vec!["in_app".to_string()];
+
+    // Anything stored before frequencies were checked is treated as immediate
+    let frequency =
+        NotificationFrequency::from_str(&prefs.notification_frequency).unwrap_or(NotificationFrequency::Immediate);
+    match frequency {
+        NotificationFrequency::Off => {}
+        NotificationFrequency::Daily => {
+            if prefs.email_notifications {
+                channels.push("email_digest".to_string());
+            }
+        }
+        NotificationFrequency::Immediate => {
+            if prefs.email_notifications {
+                channels.push("email".to_string());
+            }
+            if prefs.push_notifications && !in_quiet_hours(prefs) {
+                channels.push("push".to_string());
+            }
+        }
+    }
+
+    channels
+}
+
+fn in_quiet_hours(prefs: &MessageNotificationPreferences) -> bool {
+    let (Some(start), Some(end)) = (prefs.quiet_hours_start, prefs.quiet_hours_end) else {
+        return false;
+    };
+
+    let offset = FixedOffset::east_opt(LOCAL_UTC_OFFSET_SECONDS).expect("valid offset");
+    let hour = Utc::now().with_timezone(&offset).hour() as i32;
+
+    if start <= end {
+        hour >= start && hour < end
+    } else {
+        // Quiet hours wrap around midnight, e.g. 22 -> 7
+        hour >= start || hour < end
+    }
+}
+
+// Record a notification for a user on the channels they opted in to
+pub async fn notify<C: ConnectionTrait>(
+    db: &C,
+    user_id: Uuid,
+    kind: NotificationKind,
+    title: impl Into<String>,
+    body: impl Into<String>,
+    link: Option<String>,
+) -> Result<()> {
+    let prefs = get_preferences(db, user_id).await?;
+
+    let notification = notification::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        user_id: Set(user_id),
+        kind: Set(kind.to_string()),
+        title: Set(title.into()),
+        body: Set(body.into()),
+        link: Set(link),
+        read: Set(false),
+        channels: Set(delivery_channels(&prefs)),
+        created_at: Set(Utc::now()),
+    };
+
+    notification.insert(db).await?;
+
+    Ok(())
+}
+
+// Get notifications for a user, newest first
+pub async fn get_notifications(db: &DatabaseConnection, user_id: Uuid, unread_only: bool) -> Result<Vec<Notification>> {
+    let mut query = notification::Entity::find()
+        .filter(notification::Column::UserId.eq(user_id));
+
+    if unread_only {
+        query = query.filter(notification::Column::Read.eq(false));
+    }
+
+    let notifications = query
+        .order_by_desc(notification::Column::CreatedAt)
+        .all(db)
+        .await?;
+
+    Ok(notifications.into_iter().map(Notification::from).collect())
+}
+
+// Mark a notification as read
+pub async fn mark_as_read(db: &DatabaseConnection, user_id: Uuid, notification_id: Uuid) -> Result<()> {
+    let notification = notification::Entity::find_by_id(notification_id)
+        .filter(notification::Column::UserId.eq(user_id))
+        .one(db)
+        .await?
+        .ok_or_else(|| AppError::not_found("Notification not found"))?;
+
+    let mut notification: notification::ActiveModel = notification.into();
+    notification.read = Set(true);
+    notification.update(db).await?;
+
+    Ok(())
+}
@@ -0,0 +1,221 @@
+use async_trait::async_trait;
+use bigdecimal::BigDecimal;
+use reqwest::{Client, Response, StatusCode};
+use serde::Deserialize;
+use serde_json::json;
+use std::time::Duration;
+
+use crate::config::PaymentConfig;
+use crate::errors::{AppError, Result};
+use crate::services::payment_gateway::{
+    fee_rate, CallbackEvent, GatewayPaymentStatus, PaymentGateway, PaymentInitiation, PaymentLookup, PaymentRequest, TokenCache, PROVIDER_TIMEOUT,
+};
+
+const API_URL: &str = "https://api.orange.com";
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    expires_in: u64,
+}
+
+#[derive(Deserialize)]
+struct WebPaymentResponse {
+    pay_token: String,
+    payment_url: String,
+    notif_token: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct TransactionStatus {
+    status: String,
+}
+
+#[derive(Deserialize)]
+struct ErrorBody {
+    code: Option<serde_json::Value>,
+    message: Option<String>,
+    description: Option<String>,
+}
+
+// Orange Money Web Payment: the buyer is sent to Orange's page to pay
+pub struct OrangeMoneyGateway {
+    client: Client,
+    // Sandbox and production differ only in the country segment of the path
+    payment_url: String,
+    currency: &'static str,
+    client_id: String,
+    client_secret: String,
+    merchant_key: String,
+    return_url: String,
+    notif_url: String,
+    token: TokenCache,
+    fee_rate: BigDecimal,
+}
+
+impl OrangeMoneyGateway {
+    pub fn new(config: &PaymentConfig) -> Self {
+        let client = Client::builder().timeout(PROVIDER_TIMEOUT).build().unwrap_or_default();
+
+        // The sandbox uses its own test currency
+        let (segment, currency) = if config.sandbox { ("dev", "OUV") } else { ("cm", "XAF") };
+
+        Self {
+            client,
+            payment_url: format!("{}/orange-money-webpay/{}/v1", API_URL, segment),
+            currency,
+            client_id: config.orange_api_key.clone(),
+            client_secret: config.orange_api_secret.clone(),
+            merchant_key: config.orange_merchant_key.clone(),
+            return_url: config.return_url.clone(),
+            notif_url: format!("{}/api/payments/webhooks/orange", config.callback_base_url.trim_end_matches('/')),
+            token: TokenCache::new(),
+            fee_rate: fee_rate(config.orange_processing_fee_rate),
+        }
+    }
+
+    async fn access_token(&self) -> Result<String> {
+        self.token
+            .get_or_fetch(|| async {
+                let response = self
+                    .client
+                    .post(format!("{}/oauth/v3/token", API_URL))
+                    .basic_auth(&self.client_id, Some(&self.client_secret))
+                    .form(&[("grant_type", "client_credentials")])
+                    .send()
+                    .await
+                    .map_err(unreachable)?;
+                let token: TokenResponse = check(response).await?.json().await.map_err(unreadable)?;
+
+                Ok((token.access_token, Duration::from_secs(token.expires_in)))
+            })
+            .await
+    }
+}
+
+fn unreachable(e: reqwest::Error) -> AppError {
+    AppError::external_service(format!("Orange Money is unreachable: {}", e))
+}
+
+fn unreadable(e: reqwest::Error) -> AppError {
+    AppError::external_service(format!("Orange Money sent an unexpected response: {}", e))
+}
+
+async fn check(response: Response) -> Result<Response> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+
+    let body: Option<ErrorBody> = response.json().await.ok();
+    let code = body
+        .as_ref()
+        .and_then(|b| b.code.as_ref())
+        .map(|code| code.to_string())
+        .unwrap_or_default();
+    let message = body
+        .and_then(|b| b.description.or(b.message))
+        .unwrap_or_default();
+
+    let detail = match status {
+        StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => "the API credentials were refused",
+        StatusCode::BAD_REQUEST => "the payment request was rejected",
+        StatusCode::NOT_FOUND => "the payment was not found",
+        StatusCode::TOO_MANY_REQUESTS => "too many requests, try again shortly",
+        _ if status.is_server_error() => "the service is temporarily unavailable",
+        _ => "the request was rejected",
+    };
+
+    Err(AppError::external_service(format!(
+        "Orange Money: {} ({} {} {})",
+        detail, status, code, message
+    )))
+}
+
+fn parse_status(status: &str) -> GatewayPaymentStatus {
+    match status {
+        "SUCCESS" => GatewayPaymentStatus::Successful,
+        "FAILED" => GatewayPaymentStatus::Failed {
+            reason: "the payer declined the payment".to_string(),
+        },
+        "EXPIRED" => GatewayPaymentStatus::Failed {
+            reason: "the payer did not pay in time".to_string(),
+        },
+        _ => GatewayPaymentStatus::Pending,
+    }
+}
+
+#[async_trait]
+impl PaymentGateway for OrangeMoneyGateway {
+    fn name(&self) -> &'static str {
+        "Orange Money"
+    }
+
+    async fn request_to_pay(&self, request: &PaymentRequest) -> Result<PaymentInitiation> {
+        let token = self.access_token().await?;
+
+        // Orange wants the order id unique per attempt, so it gets our payment id
+        let response = self
+            .client
+            .post(format!("{}/webpayment", self.payment_url))
+            .bearer_auth(token)
+            .json(&json!({
+                "merchant_key": self.merchant_key,
+                "currency": self.currency,
+                "order_id": request.payment_id.to_string(),
+                "amount": request.amount.whole_units(),
+                "return_url": self.return_url,
+                "cancel_url": self.return_url,
+                "notif_url": self.notif_url,
+                "lang": "fr",
+                "reference": request.description,
+            }))
+            .send()
+            .await
+            .map_err(unreachable)?;
+        let payment: WebPaymentResponse = check(response).await?.json().await.map_err(unreadable)?;
+
+        Ok(PaymentInitiation {
+            provider_reference: payment.pay_token,
+            status: GatewayPaymentStatus::Pending,
+            redirect_url: Some(payment.payment_url),
+            callback_token: payment.notif_token,
+        })
+    }
+
+    async fn check_status(&self, lookup: &PaymentLookup) -> Result<GatewayPaymentStatus> {
+        let token = self.access_token().await?;
+
+        let response = self
+            .client
+            .post(format!("{}/transactionstatus", self.payment_url))
+            .bearer_auth(token)
+            .json(&json!({
+                "order_id": lookup.payment_id.to_string(),
+                "amount": lookup.amount.whole_units(),
+                "pay_token": lookup.provider_reference,
+            }))
+            .send()
+            .await
+            .map_err(unreachable)?;
+        let status: TransactionStatus = check(response).await?.json().await.map_err(unreadable)?;
+
+        Ok(parse_status(&status.status))
+    }
+
+    // Orange posts the status with the notif_token it handed out when the payment was created
+    fn parse_callback(&self, payload: &serde_json::Value) -> Option<CallbackEvent> {
+        let status = payload["status"].as_str()?;
+
+        Some(CallbackEvent {
+            token: payload["notif_token"].as_str().map(str::to_string),
+            status: parse_status(status),
+            transaction_id: payload["txnid"].as_str().map(str::to_string),
+            amount: None,
+        })
+    }
+
+    fn processing_fee_rate(&self) -> BigDecimal {
+        self.fee_rate.clone()
+    }
+}
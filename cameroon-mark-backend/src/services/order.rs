@@ -1,73 +1,1188 @@
-use sea_orm::{DatabaseConnection, EntityTrait, QueryFilter, ColumnTrait, Set, ActiveValue, QuerySelect, QueryOrder};
-use sea_orm::sea_query::Expr;
+use bigdecimal::BigDecimal;
+use chrono::Utc;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait, PaginatorTrait, QueryFilter, QueryOrder,
+    QuerySelect, Select, Set, SqlErr, TransactionTrait,
+};
+use sea_orm::sea_query::{Condition, Expr, Query};
+use std::collections::HashMap;
+use std::str::FromStr;
 use uuid::Uuid;
 
+use crate::entities::{cart_item, discount_code, order, order_item, payment, product, seller_order, user};
 use crate::errors::{AppError, Result};
-use crate::models::order::{OrderResponse, CreateOrderRequest, OrderStatus, OrderItemResponse};
-
-// Create order from cart items
-pub async fn create_order(db: &DatabaseConnection, user_id: Uuid, payload: CreateOrderRequest) -> Result<OrderResponse> {
-    // This is a placeholder implementation using Sea-ORM
-    // In a real implementation, you would:
-    // 1. Create a new order record
-    // 2. Get items from the user's cart
-    // 3. Create order items for each cart item
-    // 4. Calculate the total amount
-    // 5. Clear the user's cart
-    // 6. Return the order with its items
-    
-    Err(AppError::internal("Not implemented"))
+use crate::models::financial::PaymentStatus as FinancialPaymentStatus;
+use crate::models::marketing::DiscountCode;
+use crate::models::money::{Currency, Money};
+use crate::models::order::{
+    CancelOrderRequest, CancellationReason, ConfirmReceiptQuery, CreateOrderRequest, FulfillmentStatus, OrderActor, OrderItemResponse, OrderListOptions, OrderResponse, OrderStatus,
+    PaymentMethod,
+    PaymentStatus, PayoutStatus, SellerOrderResponse, ShippingAddress, UpdateOrderStatusRequest,
+};
+use crate::models::notification::NotificationKind;
+use crate::models::product::ProductStatus;
+use crate::models::shipping::{ShippingMethod, ShippingZone};
+use crate::models::user::UserRole;
+use crate::services::commission::{self, FeeLine, ItemFees};
+use crate::services::tax::{self, ItemTax, TaxLine};
+use crate::services::payment_gateway::PaymentGateways;
+use crate::services::shipping::{self, Destination};
+use crate::services::{cod, marketing, notification, refund};
+use crate::services::order_status::{self, StatusChange};
+
+fn to_money(value: &BigDecimal) -> Result<Money> {
+    Ok(Money::from_decimal(value, Currency::XAF)?)
+}
+
+// Accept local or international Cameroon mobile and landline numbers, returned as +237XXXXXXXXX
+pub(crate) fn normalize_phone(phone: &str) -> Option<String> {
+    let digits: String = phone
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '-' && *c != '.')
+        .collect();
+    let local = digits
+        .strip_prefix("+237")
+        .or_else(|| digits.strip_prefix("00237"))
+        .or_else(|| if digits.len() == 12 { digits.strip_prefix("237") } else { None })
+        .unwrap_or(&digits);
+
+    let valid = local.len() == 9
+        && local.chars().all(|c| c.is_ascii_digit())
+        && (local.starts_with('6') || local.starts_with('2'));
+
+    valid.then(|| format!("+237{}", local))
+}
+
+// Check the delivery details and clean them up for storage
+fn validate_shipping(payload: &CreateOrderRequest) -> Result<ShippingAddress> {
+    let mut address = payload.shipping_address();
+
+    let phone = address
+        .phone
+        .as_deref()
+        .filter(|p| !p.is_empty())
+        .ok_or_else(|| AppError::validation("shipping_phone: A phone number is required for delivery"))?;
+    address.phone = Some(
+        normalize_phone(phone)
+            .ok_or_else(|| AppError::validation("shipping_phone: Enter a valid Cameroon phone number"))?,
+    );
+
+    if address.name.is_empty() || address.address_1.is_empty() || address.city.is_empty() {
+        return Err(AppError::validation("Shipping name, address and city are required"));
+    }
+
+    Ok(address)
+}
+
+async fn find_by_idempotency_key(db: &DatabaseConnection, user_id: Uuid, key: &str) -> Result<Option<order::Model>> {
+    Ok(order::Entity::find()
+        .filter(order::Column::UserId.eq(user_id))
+        .filter(order::Column::IdempotencyKey.eq(key))
+        .one(db)
+        .await?)
+}
+
+// A cart line locked in for checkout
+struct CheckoutLine {
+    product: product::Model,
+    quantity: i32,
+}
+
+impl CheckoutLine {
+    fn total(&self) -> Result<Money> {
+        Ok(to_money(&self.product.price)?.checked_mul(self.quantity as i64)?)
+    }
+
+    fn weight_grams(&self) -> i64 {
+        self.product.weight_grams as i64 * self.quantity as i64
+    }
+}
+
+// Totals for the items of a single seller
+struct SellerTotals {
+    subtotal: Money,
+    discount: Money,
+    shipping: Money,
+    // All VAT on the items, and the part of it added to prices that did not include it
+    tax: Money,
+    added_tax: Money,
+}
+
+impl SellerTotals {
+    fn total(&self) -> Result<Money> {
+        Ok(self
+            .subtotal
+            .checked_sub(self.discount)?
+            .checked_add(self.shipping)?
+            .checked_add(self.added_tax)?)
+    }
+}
+
+// Turn the user's cart into an order in a single transaction.
+// The order is split into one sub-order per seller. Stock is reserved, prices are frozen on the order lines, the discount code is used up
+// and the cart is emptied, or nothing happens at all. Replaying the same idempotency key
+// returns the order created the first time.
+pub async fn create_order(
+    db: &DatabaseConnection,
+    gateways: &PaymentGateways,
+    user_id: Uuid,
+    payload: CreateOrderRequest,
+    idempotency_key: Option<String>,
+) -> Result<OrderResponse> {
+    let shipping_address = validate_shipping(&payload)?;
+
+    if let Some(key) = &idempotency_key {
+        if let Some(existing) = find_by_idempotency_key(db, user_id, key).await? {
+            return get_order_by_id(db, existing.id).await;
+        }
+    }
+
+    let txn = db.begin().await?;
+
+    let cart_items = cart_item::Entity::find()
+        .filter(cart_item::Column::UserId.eq(user_id))
+        .order_by_asc(cart_item::Column::CreatedAt)
+        .all(&txn)
+        .await?;
+
+    if cart_items.is_empty() {
+        return Err(AppError::bad_request("Your cart is empty"));
+    }
+
+    // Lock the products so stock and prices cannot move under us until commit
+    let mut products: HashMap<Uuid, product::Model> = product::Entity::find()
+        .filter(product::Column::Id.is_in(cart_items.iter().map(|item| item.product_id)))
+        .lock_exclusive()
+        .all(&txn)
+        .await?
+        .into_iter()
+        .map(|p| (p.id, p))
+        .collect();
+
+    let mut lines = Vec::with_capacity(cart_items.len());
+    for item in &cart_items {
+        let product = products
+            .remove(&item.product_id)
+            .ok_or_else(|| AppError::bad_request("An item in your cart no longer exists"))?;
+
+        if product.status != ProductStatus::Active.to_string() {
+            return Err(AppError::bad_request(format!("{} is no longer available", product.title)));
+        }
+        if product.seller_id == user_id {
+            return Err(AppError::bad_request("Cannot order your own product"));
+        }
+        if item.quantity > product.stock {
+            return Err(AppError::bad_request(if product.stock <= 0 {
+                format!("{} is out of stock", product.title)
+            } else {
+                format!("Only {} of {} left in stock", product.stock, product.title)
+            }));
+        }
+
+        lines.push(CheckoutLine {
+            product,
+            quantity: item.quantity,
+        });
+    }
+
+    let mut sellers: Vec<(Uuid, SellerTotals)> = Vec::new();
+    for line in &lines {
+        let line_total = line.total()?;
+        match sellers.iter_mut().find(|(id, _)| *id == line.product.seller_id) {
+            Some((_, totals)) => totals.subtotal = totals.subtotal.checked_add(line_total)?,
+            None => sellers.push((
+                line.product.seller_id,
+                SellerTotals {
+                    subtotal: line_total,
+                    discount: Money::zero(Currency::XAF),
+                    shipping: Money::zero(Currency::XAF),
+                    tax: Money::zero(Currency::XAF),
+                    added_tax: Money::zero(Currency::XAF),
+                },
+            )),
+        }
+    }
+
+    // Every seller ships their own items, so shipping is quoted per seller by zone and weight.
+    // Buyers pick an option per seller, or get the cheapest delivery.
+    let destination = Destination::new(&shipping_address.city, Some(&shipping_address.country));
+    let mut shipping_options = HashMap::with_capacity(sellers.len());
+    for (seller_id, totals) in sellers.iter_mut() {
+        let weight_grams = lines
+            .iter()
+            .filter(|line| line.product.seller_id == *seller_id)
+            .map(CheckoutLine::weight_grams)
+            .sum();
+        let quote = shipping::quote_seller(&txn, *seller_id, weight_grams, totals.subtotal, &destination).await?;
+        let choice = payload.shipping.iter().find(|choice| choice.seller_id == *seller_id);
+        let option = shipping::choose_option(&quote, choice, &destination)?;
+        totals.shipping = option.price;
+        shipping_options.insert(*seller_id, (quote.zone, option));
+    }
+
+    // Discount codes belong to a seller and only apply to that seller's items
+    let discount = match payload.discount_code.as_deref().map(str::trim) {
+        Some(code) if !code.is_empty() => Some(apply_discount_code(&txn, user_id, code, &lines, &mut sellers).await?),
+        _ => None,
+    };
+
+    // VAT is worked out per item once the discount is known, and kept on the item
+    let now = Utc::now();
+    let mut item_taxes: Vec<Option<ItemTax>> = lines.iter().map(|_| None).collect();
+    for (seller_id, totals) in sellers.iter_mut() {
+        let seller_lines: Vec<usize> = (0..lines.len())
+            .filter(|&i| lines[i].product.seller_id == *seller_id)
+            .collect();
+        let tax_lines = seller_lines
+            .iter()
+            .map(|&i| {
+                Ok(TaxLine {
+                    category_id: lines[i].product.category_id,
+                    line_total: lines[i].total()?,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let taxes = tax::compute_taxes(&txn, *seller_id, &tax_lines, totals.discount, now).await?;
+        for (&i, item_tax) in seller_lines.iter().zip(taxes) {
+            totals.tax = totals.tax.checked_add(item_tax.tax)?;
+            totals.added_tax = totals.added_tax.checked_add(item_tax.added())?;
+            item_taxes[i] = Some(item_tax);
+        }
+    }
+
+    let subtotal = Money::sum(sellers.iter().map(|(_, t)| t.subtotal), Currency::XAF)?;
+    let discount_amount = Money::sum(sellers.iter().map(|(_, t)| t.discount), Currency::XAF)?;
+    let shipping_amount = Money::sum(sellers.iter().map(|(_, t)| t.shipping), Currency::XAF)?;
+    let tax_amount = Money::sum(sellers.iter().map(|(_, t)| t.tax), Currency::XAF)?;
+    let mut total_amount = Money::zero(Currency::XAF);
+    for (_, totals) in &sellers {
+        total_amount = total_amount.checked_add(totals.total()?)?;
+    }
+
+    // Sellers opt in to cash on delivery and may cap how much cash a courier carries
+    let cash_on_delivery = payload.payment_method == PaymentMethod::CashOnDelivery;
+    if cash_on_delivery {
+        let seller_totals = sellers
+            .iter()
+            .map(|(id, t)| Ok((*id, t.total()?)))
+            .collect::<Result<Vec<(Uuid, Money)>>>()?;
+        cod::ensure_cod_allowed(&txn, &seller_totals).await?;
+    }
+
+    let order_id = Uuid::new_v4();
+    let order = order::ActiveModel {
+        id: Set(order_id),
+        user_id: Set(user_id),
+        subtotal: Set(subtotal.to_decimal()),
+        discount_amount: Set(discount_amount.to_decimal()),
+        shipping_amount: Set(shipping_amount.to_decimal()),
+        tax_amount: Set(tax_amount.to_decimal()),
+        total_amount: Set(total_amount.to_decimal()),
+        discount_code_id: Set(discount.as_ref().map(|d| d.id)),
+        status: Set(OrderStatus::Pending.to_string()),
+        payment_status: Set(PaymentStatus::Pending.to_string()),
+        payment_method: Set(payload.payment_method.to_string()),
+        shipping_address: Set(serde_json::to_value(&shipping_address)
+            .map_err(|e| AppError::internal(format!("Failed to store shipping address: {}", e)))?),
+        idempotency_key: Set(idempotency_key.clone()),
+        created_at: Set(now),
+        updated_at: Set(now),
+    };
+
+    if let Err(err) = order.insert(&txn).await {
+        return match (is_unique_violation(&err), &idempotency_key) {
+            // Another request with the same key won the race
+            (true, Some(key)) => {
+                txn.rollback().await?;
+                let existing = find_by_idempotency_key(db, user_id, key)
+                    .await?
+                    .ok_or_else(|| AppError::internal("Failed to create order"))?;
+                get_order_by_id(db, existing.id).await
+            }
+            _ => Err(err.into()),
+        };
+    }
+
+    // Providers' fees on online payments are passed on to the sellers
+    let processing_fee_rate = match payload.payment_method {
+        PaymentMethod::Mtn | PaymentMethod::Orange => gateways.for_method(&payload.payment_method)?.processing_fee_rate(),
+        _ => BigDecimal::from(0),
+    };
+
+    // The buyer pays once for the whole order, but each seller ships and is paid out separately
+    let mut seller_order_ids = HashMap::with_capacity(sellers.len());
+    let mut item_fees: Vec<Option<ItemFees>> = lines.iter().map(|_| None).collect();
+    for (seller_id, totals) in &sellers {
+        let seller_lines: Vec<usize> = (0..lines.len())
+            .filter(|&i| lines[i].product.seller_id == *seller_id)
+            .collect();
+        let fee_lines = seller_lines
+            .iter()
+            .map(|&i| {
+                Ok(FeeLine {
+                    category_id: lines[i].product.category_id,
+                    quantity: lines[i].quantity,
+                    line_total: lines[i].total()?,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let processing_fee = totals.total()?.mul_rate(&processing_fee_rate)?;
+        let fees = commission::compute_fees(&txn, *seller_id, &fee_lines, totals.discount, processing_fee, now).await?;
+
+        // What the seller should receive once delivered, settled again when it is released
+        let mut payout = totals.total()?;
+        for (&i, fees) in seller_lines.iter().zip(fees) {
+            payout = payout.checked_sub(fees.commission)?.checked_sub(fees.processing_fee)?;
+            item_fees[i] = Some(fees);
+        }
+
+        let (zone, option) = shipping_options
+            .remove(seller_id)
+            .ok_or_else(|| AppError::internal("Shipping was not worked out for every seller"))?;
+
+        let seller_order = seller_order::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            order_id: Set(order_id),
+            seller_id: Set(*seller_id),
+            status: Set(OrderStatus::Pending.to_string()),
+            subtotal: Set(totals.subtotal.to_decimal()),
+            discount_amount: Set(totals.discount.to_decimal()),
+            shipping_amount: Set(totals.shipping.to_decimal()),
+            tax_amount: Set(totals.tax.to_decimal()),
+            total_amount: Set(totals.total()?.to_decimal()),
+            fulfillment_status: Set(FulfillmentStatus::Unfulfilled.to_string()),
+            shipping_method: Set(option.method.to_string()),
+            shipping_zone: Set((option.method == ShippingMethod::Delivery).then(|| zone.to_string())),
+            shipping_rate_id: Set(option.rate_id),
+            tracking_number: Set(None),
+            shipping_provider: Set(None),
+            shipped_at: Set(None),
+            delivered_at: Set(None),
+            payout_status: Set(PayoutStatus::Pending.to_string()),
+            payout_amount: Set(payout.to_decimal()),
+            paid_out_at: Set(None),
+            cancellation_reason: Set(None),
+            cancellation_note: Set(None),
+            canceled_by: Set(None),
+            canceled_at: Set(None),
+            delivery_code: Set(cash_on_delivery.then(cod::generate_delivery_code)),
+            delivery_code_attempts: Set(0),
+            cod_collected_amount: Set(None),
+            cod_collected_at: Set(None),
+            created_at: Set(now),
+            updated_at: Set(now),
+        };
+        let seller_order = seller_order.insert(&txn).await?;
+        seller_order_ids.insert(*seller_id, seller_order.id);
+    }
+
+    for ((line, fees), item_tax) in lines.iter().zip(item_fees).zip(item_taxes) {
+        let fees = fees.ok_or_else(|| AppError::internal("Fees were not worked out for every item"))?;
+        let item_tax = item_tax.ok_or_else(|| AppError::internal("VAT was not worked out for every item"))?;
+        let reserved = product::Entity::update_many()
+            .col_expr(product::Column::Stock, Expr::col(product::Column::Stock).sub(line.quantity))
+            .filter(product::Column::Id.eq(line.product.id))
+            .filter(product::Column::Stock.gte(line.quantity))
+            .exec(&txn)
+            .await?;
+
+        if reserved.rows_affected == 0 {
+            return Err(AppError::bad_request(format!("{} is out of stock", line.product.title)));
+        }
+
+        let item = order_item::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            order_id: Set(order_id),
+            seller_order_id: Set(seller_order_ids[&line.product.seller_id]),
+            product_id: Set(line.product.id),
+            seller_id: Set(line.product.seller_id),
+            product_title: Set(line.product.title.clone()),
+            quantity: Set(line.quantity),
+            unit_price: Set(line.product.price.clone()),
+            commission_rule_id: Set(fees.rule_id),
+            commission_rate: Set(fees.rate),
+            commission_amount: Set(fees.commission.to_decimal()),
+            processing_fee: Set(fees.processing_fee.to_decimal()),
+            tax_rule_id: Set(item_tax.rule_id),
+            tax_rate: Set(item_tax.rate),
+            taxable_amount: Set(item_tax.taxable_amount.to_decimal()),
+            tax_amount: Set(item_tax.tax.to_decimal()),
+            created_at: Set(now),
+            updated_at: Set(now),
+        };
+        item.insert(&txn).await?;
+    }
+
+    if let Some(discount) = &discount {
+        // Guard against the last use being taken by a concurrent checkout
+        let used = discount_code::Entity::update_many()
+            .col_expr(discount_code::Column::TimesUsed, Expr::col(discount_code::Column::TimesUsed).add(1))
+            .filter(discount_code::Column::Id.eq(discount.id))
+            .filter(
+                Condition::any()
+                    .add(discount_code::Column::MaxUses.is_null())
+                    .add(Expr::col(discount_code::Column::TimesUsed).lt(Expr::col(discount_code::Column::MaxUses))),
+            )
+            .exec(&txn)
+            .await?;
+
+        if used.rows_affected == 0 {
+            return Err(AppError::bad_request("Discount code has reached its usage limit"));
+        }
+    }
+
+    order_status::record(
+        &txn,
+        order_id,
+        None,
+        None,
+        &StatusChange {
+            to: OrderStatus::Pending,
+            actor: OrderActor::Buyer,
+            actor_id: Some(user_id),
+            note: Some("Order placed".to_string()),
+        },
+    )
+    .await?;
+
+    cart_item::Entity::delete_many()
+        .filter(cart_item::Column::UserId.eq(user_id))
+        .exec(&txn)
+        .await?;
+
+    txn.commit().await?;
+
+    get_order_by_id(db, order_id).await
+}
+
+fn is_unique_violation(err: &DbErr) -> bool {
+    matches!(err.sql_err(), Some(SqlErr::UniqueConstraintViolation(_)))
+}
+
+// Validate a discount code against the items of the seller who issued it
+// and record the discount (or waived shipping) on that seller's totals
+async fn apply_discount_code<C: ConnectionTrait>(
+    db: &C,
+    user_id: Uuid,
+    code: &str,
+    lines: &[CheckoutLine],
+    sellers: &mut [(Uuid, SellerTotals)],
+) -> Result<DiscountCode> {
+    let seller_id = discount_code::Entity::find()
+        .filter(discount_code::Column::Code.eq(code))
+        .one(db)
+        .await?
+        .map(|d| d.seller_id)
+        .ok_or_else(|| AppError::not_found("Discount code not found or inactive"))?;
+
+    let totals = sellers
+        .iter_mut()
+        .find(|(id, _)| *id == seller_id)
+        .map(|(_, totals)| totals)
+        .ok_or_else(|| AppError::bad_request("This discount code does not apply to any item in your cart"))?;
+
+    let product_ids = lines
+        .iter()
+        .filter(|line| line.product.seller_id == seller_id)
+        .map(|line| line.product.id)
+        .collect();
+
+    let discount = marketing::validate_discount_code(db, user_id, code, totals.subtotal, product_ids).await?;
+
+    let amounts = marketing::apply_discount(&discount, totals.subtotal, totals.shipping).await?;
+    let zero = Money::zero(Currency::XAF);
+    totals.discount = amounts.goods.max(zero)?.min(totals.subtotal)?;
+    totals.shipping = totals.shipping.checked_sub(amounts.shipping.max(zero)?.min(totals.shipping)?)?;
+
+    Ok(discount)
+}
+
+// Build the full response for an order with its lines, buyer and seller names
+async fn build_order_response(db: &DatabaseConnection, order: order::Model) -> Result<OrderResponse> {
+    let items = order_item::Entity::find()
+        .filter(order_item::Column::OrderId.eq(order.id))
+        .order_by_asc(order_item::Column::CreatedAt)
+        .find_also_related(product::Entity)
+        .all(db)
+        .await?;
+
+    let seller_orders = seller_order::Entity::find()
+        .filter(seller_order::Column::OrderId.eq(order.id))
+        .order_by_asc(seller_order::Column::CreatedAt)
+        .all(db)
+        .await?;
+
+    let mut user_ids: Vec<Uuid> = seller_orders.iter().map(|so| so.seller_id).collect();
+    user_ids.push(order.user_id);
+
+    let names: HashMap<Uuid, String> = user::Entity::find()
+        .filter(user::Column::Id.is_in(user_ids))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|u| (u.id, u.name))
+        .collect();
+
+    let items = items
+        .into_iter()
+        .map(|(item, product)| {
+            Ok(OrderItemResponse {
+                id: item.id,
+                seller_order_id: item.seller_order_id,
+                product_id: item.product_id,
+                product_title: item.product_title,
+                product_image: product
+                    .and_then(|p| p.images.0.first().cloned())
+                    .unwrap_or_default(),
+                quantity: item.quantity,
+                unit_price: to_money(&item.unit_price)?,
+                seller_id: item.seller_id,
+                seller_name: names.get(&item.seller_id).cloned().unwrap_or_default(),
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let seller_orders = seller_orders
+        .into_iter()
+        .map(|so| {
+            Ok(SellerOrderResponse {
+                id: so.id,
+                seller_id: so.seller_id,
+                seller_name: names.get(&so.seller_id).cloned().unwrap_or_default(),
+                status: OrderStatus::from_str(&so.status).map_err(AppError::internal)?,
+                subtotal: to_money(&so.subtotal)?,
+                discount_amount: to_money(&so.discount_amount)?,
+                shipping_amount: to_money(&so.shipping_amount)?,
+                tax_amount: to_money(&so.tax_amount)?,
+                total_amount: to_money(&so.total_amount)?,
+                fulfillment_status: FulfillmentStatus::from_str(&so.fulfillment_status).map_err(AppError::internal)?,
+                shipping_method: ShippingMethod::from_str(&so.shipping_method).map_err(AppError::internal)?,
+                shipping_zone: so
+                    .shipping_zone
+                    .as_deref()
+                    .map(ShippingZone::from_str)
+                    .transpose()
+                    .map_err(AppError::internal)?,
+                tracking_number: so.tracking_number,
+                shipping_provider: so.shipping_provider,
+                shipped_at: so.shipped_at,
+                delivered_at: so.delivered_at,
+                payout_status: PayoutStatus::from_str(&so.payout_status).map_err(AppError::internal)?,
+                payout_amount: to_money(&so.payout_amount)?,
+                paid_out_at: so.paid_out_at,
+                cancellation_reason: so
+                    .cancellation_reason
+                    .as_deref()
+                    .map(CancellationReason::from_str)
+                    .transpose()
+                    .map_err(AppError::internal)?,
+                cancellation_note: so.cancellation_note,
+                canceled_by: so
+                    .canceled_by
+                    .as_deref()
+                    .map(OrderActor::from_str)
+                    .transpose()
+                    .map_err(AppError::internal)?,
+                canceled_at: so.canceled_at,
+                delivery_code: so.delivery_code,
+                cod_collected_amount: so.cod_collected_amount.as_ref().map(to_money).transpose()?,
+                cod_collected_at: so.cod_collected_at,
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(OrderResponse {
+        id: order.id,
+        buyer_id: order.user_id,
+        buyer_name: names.get(&order.user_id).cloned().unwrap_or_default(),
+        subtotal: to_money(&order.subtotal)?,
+        discount_amount: to_money(&order.discount_amount)?,
+        shipping_amount: to_money(&order.shipping_amount)?,
+        tax_amount: to_money(&order.tax_amount)?,
+        total_amount: to_money(&order.total_amount)?,
+        discount_code_id: order.discount_code_id,
+        status: OrderStatus::from_str(&order.status).map_err(AppError::internal)?,
+        payment_status: PaymentStatus::from_str(&order.payment_status).map_err(AppError::internal)?,
+        shipping_address: serde_json::from_value(order.shipping_address)
+            .map_err(|e| AppError::internal(format!("Invalid shipping address on order: {}", e)))?,
+        payment_method: PaymentMethod::from_str(&order.payment_method).map_err(AppError::internal)?,
+        created_at: order.created_at,
+        updated_at: order.updated_at,
+        seller_orders,
+        items,
+    })
+}
+
+// Whose orders a list is limited to
+enum OrderScope {
+    Buyer(Uuid),
+    Seller(Uuid),
+    All,
+}
+
+// Match an order id, the start of one, or a buyer phone number
+fn search_condition(search: &str) -> Condition {
+    if let Ok(id) = Uuid::parse_str(search) {
+        return Condition::all().add(order::Column::Id.eq(id));
+    }
+
+    let mut condition = Condition::any();
+
+    // Buyers usually quote the first characters of the order number
+    let prefix = search.to_lowercase();
+    if prefix.len() >= 6 && prefix.chars().all(|c| c.is_ascii_hexdigit() || c == '-') {
+        condition = condition.add(Expr::cust_with_values("orders.id::text LIKE $1", [format!("{}%", prefix)]));
+    }
+
+    if let Some(phone) = normalize_phone(search) {
+        let local = phone.trim_start_matches("+237").to_string();
+        condition = condition
+            .add(
+                order::Column::UserId.in_subquery(
+                    Query::select()
+                        .column(user::Column::Id)
+                        .from(user::Entity)
+                        .and_where(user::Column::Phone.is_in([phone.clone(), local.clone()]))
+                        .to_owned(),
+                ),
+            )
+            .add(Expr::cust_with_values("orders.shipping_address->>'phone' = $1", [phone]));
+    }
+
+    // Nothing recognisable, so nothing matches
+    if condition.is_empty() {
+        condition = condition.add(Expr::cust("FALSE"));
+    }
+
+    condition
+}
+
+fn filtered_orders(scope: &OrderScope, options: &OrderListOptions) -> Result<Select<order::Entity>> {
+    let status = options
+        .status
+        .as_deref()
+        .map(|s| OrderStatus::from_str(&s.to_lowercase()).map_err(AppError::bad_request))
+        .transpose()?;
+
+    let mut query = order::Entity::find();
+
+    match scope {
+        OrderScope::Buyer(buyer_id) => {
+            query = query.filter(order::Column::UserId.eq(*buyer_id));
+        }
+        OrderScope::Seller(seller_id) => {
+            // Sellers filter on the status of their own sub-order
+            let mut sub_orders = Query::select()
+                .column(seller_order::Column::OrderId)
+                .from(seller_order::Entity)
+                .and_where(seller_order::Column::SellerId.eq(*seller_id))
+                .to_owned();
+            if let Some(status) = &status {
+                sub_orders.and_where(seller_order::Column::Status.eq(status.to_string()));
+            }
+            query = query.filter(order::Column::Id.in_subquery(sub_orders));
+        }
+        OrderScope::All => {}
+    }
+
+    if let Some(status) = &status {
+        if !matches!(scope, OrderScope::Seller(_)) {
+            query = query.filter(order::Column::Status.eq(status.to_string()));
+        }
+    }
+    if let Some(from) = options.from {
+        query = query.filter(order::Column::CreatedAt.gte(from));
+    }
+    if let Some(to) = options.to {
+        query = query.filter(order::Column::CreatedAt.lte(to));
+    }
+    if let Some(search) = options.search.as_deref().map(str::trim).filter(|s| !s.is_empty()) {
+        query = query.filter(search_condition(search));
+    }
+
+    Ok(query.order_by_desc(order::Column::CreatedAt))
+}
+
+async fn list_orders(
+    db: &DatabaseConnection,
+    scope: OrderScope,
+    options: OrderListOptions,
+) -> Result<(Vec<OrderResponse>, u64)> {
+    let page = options.page.unwrap_or(1).max(1);
+    let per_page = options.per_page.unwrap_or(10).clamp(1, 50);
+
+    let paginator = filtered_orders(&scope, &options)?.paginate(db, per_page);
+    let total = paginator.num_items().await?;
+    let orders = paginator.fetch_page(page - 1).await?;
+
+    let mut responses = Vec::with_capacity(orders.len());
+    for order in orders {
+        let mut response = build_order_response(db, order).await?;
+        match scope {
+            OrderScope::Buyer(_) => {}
+            OrderScope::Seller(seller_id) => {
+                scope_to_seller(&mut response, seller_id);
+                hide_delivery_codes(&mut response);
+            }
+            OrderScope::All => hide_delivery_codes(&mut response),
+        }
+        responses.push(response);
+    }
+
+    Ok((responses, total))
+}
+
+// Keep only the lines and sub-order that belong to one seller
+pub fn scope_to_seller(order: &mut OrderResponse, seller_id: Uuid) {
+    order.items.retain(|item| item.seller_id == seller_id);
+    order.seller_orders.retain(|so| so.seller_id == seller_id);
+}
+
+// Delivery codes are for the buyer's eyes only
+pub fn hide_delivery_codes(order: &mut OrderResponse) {
+    for seller_order in &mut order.seller_orders {
+        seller_order.delivery_code = None;
+    }
+}
+
+// Trim an order down to what the viewer is allowed to see
+pub fn present_for(order: &mut OrderResponse, viewer_id: Uuid, role: &UserRole) {
+    if *role == UserRole::Seller {
+        scope_to_seller(order, viewer_id);
+    }
+    if order.buyer_id != viewer_id {
+        hide_delivery_codes(order);
+    }
 }
 
 // Get orders for a buyer
-pub async fn get_buyer_orders(db: &DatabaseConnection, buyer_id: Uuid) -> Result<Vec<OrderResponse>> {
-    // This is a placeholder implementation using Sea-ORM
-    // In a real implementation, you would query orders where buyer_id matches
-    // and include all related order items
-    
-    Ok(Vec::new())
-}
-
-// Get orders for a seller
-pub async fn get_seller_orders(db: &DatabaseConnection, seller_id: Uuid) -> Result<Vec<OrderResponse>> {
-    // This is a placeholder implementation using Sea-ORM
-    // In a real implementation, you would:
-    // 1. Query order items where seller_id matches
-    // 2. Get the unique order IDs from those items
-    // 3. Query orders with those IDs
-    // 4. Include all related order items for each order
-    
-    Ok(Vec::new())
+pub async fn get_buyer_orders(
+    db: &DatabaseConnection,
+    buyer_id: Uuid,
+    options: OrderListOptions,
+) -> Result<(Vec<OrderResponse>, u64)> {
+    list_orders(db, OrderScope::Buyer(buyer_id), options).await
+}
+
+// Get orders containing a seller's products, showing only that seller's part
+pub async fn get_seller_orders(
+    db: &DatabaseConnection,
+    seller_id: Uuid,
+    options: OrderListOptions,
+) -> Result<(Vec<OrderResponse>, u64)> {
+    list_orders(db, OrderScope::Seller(seller_id), options).await
 }
 
 // Get all orders (admin only)
-pub async fn get_all_orders(db: &DatabaseConnection) -> Result<Vec<OrderResponse>> {
-    // This is a placeholder implementation using Sea-ORM
-    // In a real implementation, you would query all orders
-    // and include all related order items
-    
-    Ok(Vec::new())
+pub async fn get_all_orders(db: &DatabaseConnection, options: OrderListOptions) -> Result<(Vec<OrderResponse>, u64)> {
+    list_orders(db, OrderScope::All, options).await
 }
 
 // Get order by ID
 pub async fn get_order_by_id(db: &DatabaseConnection, order_id: Uuid) -> Result<OrderResponse> {
-    // This is a placeholder implementation using Sea-ORM
-    // In a real implementation, you would:
-    // 1. Query the order with the given ID
-    // 2. Query all order items for that order
-    // 3. Get buyer information
-    // 4. Combine everything into an OrderResponse
-    
-    Err(AppError::not_found("Order not found"))
-}
-
-// Update order status
-pub async fn update_order_status(db: &DatabaseConnection, order_id: Uuid, status: OrderStatus) -> Result<OrderResponse> {
-    // This is a placeholder implementation using Sea-ORM
-    // In a real implementation, you would:
-    // 1. Find the order with the given ID
-    // 2. Update its status
-    // 3. Return the updated order with its items
-    
-    Err(AppError::internal("Not implemented"))
+    let order = order::Entity::find_by_id(order_id)
+        .one(db)
+        .await?
+        .ok_or_else(|| AppError::not_found("Order not found"))?;
+
+    build_order_response(db, order).await
+}
+
+// Update the status of a sub-order and roll it up to the parent order.
+// Sellers update their own part; admins choose the part with seller_order_id on orders with several sellers.
+pub async fn update_order_status(
+    db: &DatabaseConnection,
+    order_id: Uuid,
+    user_id: Uuid,
+    role: &UserRole,
+    payload: UpdateOrderStatusRequest,
+) -> Result<OrderResponse> {
+    let txn = db.begin().await?;
+
+    // Lock the order and its parts so the transition is checked against what is stored now
+    order::Entity::find_by_id(order_id)
+        .lock_exclusive()
+        .one(&txn)
+        .await?
+        .ok_or_else(|| AppError::not_found("Order not found"))?;
+
+    let mut seller_orders = seller_order::Entity::find()
+        .filter(seller_order::Column::OrderId.eq(order_id))
+        .lock_exclusive()
+        .all(&txn)
+        .await?;
+
+    let actor = match role {
+        UserRole::Seller => {
+            seller_orders.retain(|so| so.seller_id == user_id);
+            OrderActor::Seller
+        }
+        UserRole::Admin => OrderActor::Admin,
+        _ => return Err(AppError::forbidden("You are not authorized to update this order")),
+    };
+
+    let seller_order = match payload.seller_order_id {
+        Some(id) => seller_orders.into_iter().find(|so| so.id == id),
+        None if seller_orders.len() > 1 => {
+            return Err(AppError::bad_request(
+                "This order has several sellers, choose one with seller_order_id",
+            ))
+        }
+        None => seller_orders.pop(),
+    }
+    .ok_or_else(|| AppError::forbidden("You are not authorized to update this order"))?;
+
+    // Cash on delivery is only delivered once the buyer's code is checked and the cash is booked
+    if payload.status == OrderStatus::Delivered && seller_order.delivery_code.is_some() {
+        return Err(AppError::bad_request(
+            "Cash on delivery orders are confirmed with the buyer's delivery code",
+        ));
+    }
+
+    let change = StatusChange {
+        to: payload.status,
+        actor,
+        actor_id: Some(user_id),
+        note: payload.note.map(|n| n.trim().to_string()).filter(|n| !n.is_empty()),
+    };
+    let seller_order = order_status::transition_seller_order(&txn, seller_order, change).await?;
+
+    if payload.tracking_number.is_some() || payload.shipping_provider.is_some() {
+        let mut active: seller_order::ActiveModel = seller_order.into();
+        if let Some(tracking_number) = payload.tracking_number {
+            active.tracking_number = Set(Some(tracking_number.trim().to_string()));
+        }
+        if let Some(shipping_provider) = payload.shipping_provider {
+            active.shipping_provider = Set(Some(shipping_provider.trim().to_string()));
+        }
+        active.update(&txn).await?;
+    }
+
+    txn.commit().await?;
+
+    get_order_by_id(db, order_id).await
+}
+
+// The buyer confirms their parcels arrived, which releases the sellers' money.
+// Every shipped part is confirmed unless seller_order_id picks one. Cash on delivery
+// parts are confirmed by handing over the delivery code instead.
+pub async fn confirm_receipt(
+    db: &DatabaseConnection,
+    order_id: Uuid,
+    buyer_id: Uuid,
+    query: ConfirmReceiptQuery,
+) -> Result<OrderResponse> {
+    let order = order::Entity::find_by_id(order_id)
+        .one(db)
+        .await?
+        .ok_or_else(|| AppError::not_found("Order not found"))?;
+
+    if order.user_id != buyer_id {
+        return Err(AppError::forbidden("You are not authorized to confirm this order"));
+    }
+
+    let txn = db.begin().await?;
+
+    let mut select = seller_order::Entity::find()
+        .filter(seller_order::Column::OrderId.eq(order_id))
+        .filter(seller_order::Column::Status.eq(OrderStatus::Shipped.to_string()))
+        .filter(seller_order::Column::DeliveryCode.is_null());
+    if let Some(seller_order_id) = query.seller_order_id {
+        select = select.filter(seller_order::Column::Id.eq(seller_order_id));
+    }
+    let shipped = select.lock_exclusive().all(&txn).await?;
+
+    if shipped.is_empty() {
+        return Err(AppError::bad_request("Nothing on this order is waiting to be received"));
+    }
+
+    for seller_order in shipped {
+        let change = StatusChange {
+            to: OrderStatus::Delivered,
+            actor: OrderActor::Buyer,
+            actor_id: Some(buyer_id),
+            note: Some("Received by the buyer".to_string()),
+        };
+        order_status::transition_seller_order(&txn, seller_order, change).await?;
+    }
+
+    txn.commit().await?;
+
+    get_order_by_id(db, order_id).await
+}
+
+// Cancel an order, or part of it, before it ships.
+// Buyers cancel every part that has not shipped yet, sellers cancel their own part
+// and admins can also write off parts already in transit. Reserved stock goes back
+// on sale and the other side is notified.
+pub async fn cancel_order(
+    db: &DatabaseConnection,
+    order_id: Uuid,
+    user_id: Uuid,
+    role: &UserRole,
+    payload: CancelOrderRequest,
+) -> Result<OrderResponse> {
+    let txn = db.begin().await?;
+
+    // Lock the order and its parts so what can be canceled is decided on what is stored now
+    let order = order::Entity::find_by_id(order_id)
+        .lock_exclusive()
+        .one(&txn)
+        .await?
+        .ok_or_else(|| AppError::not_found("Order not found"))?;
+
+    let seller_orders = seller_order::Entity::find()
+        .filter(seller_order::Column::OrderId.eq(order_id))
+        .lock_exclusive()
+        .all(&txn)
+        .await?;
+
+    let actor = match role {
+        UserRole::Customer if order.user_id == user_id => OrderActor::Buyer,
+        UserRole::Seller if seller_orders.iter().any(|so| so.seller_id == user_id) => OrderActor::Seller,
+        UserRole::Admin => OrderActor::Admin,
+        _ => return Err(AppError::forbidden("You are not authorized to cancel this order")),
+    };
+
+    let cancelable = |status: &str| match actor {
+        OrderActor::Admin => {
+            status != OrderStatus::Delivered.to_string() && status != OrderStatus::Canceled.to_string()
+        }
+        _ => status == OrderStatus::Pending.to_string() || status == OrderStatus::Processing.to_string(),
+    };
+
+    let targets: Vec<seller_order::Model> = seller_orders
+        .into_iter()
+        .filter(|so| actor != OrderActor::Seller || so.seller_id == user_id)
+        .filter(|so| cancelable(&so.status))
+        .collect();
+
+    if targets.is_empty() {
+        return Err(AppError::bad_request(
+            "This order has already shipped or been canceled and can no longer be canceled",
+        ));
+    }
+
+    let note = payload.note.map(|n| n.trim().to_string()).filter(|n| !n.is_empty());
+    cancel_seller_orders(&txn, &order, &targets, actor, Some(user_id), payload.reason, note).await?;
+    txn.commit().await?;
+
+    get_order_by_id(db, order_id).await
+}
+
+// Cancel unpaid sub-orders once nobody is going to pay for them, putting the stock back on sale.
+// Returns false when the order was paid, is cash on delivery or has nothing left to cancel.
+pub async fn cancel_unpaid_order(db: &DatabaseConnection, order_id: Uuid) -> Result<bool> {
+    let txn = db.begin().await?;
+
+    // Lock the order so a payment landing now is either seen here or waits for the cancellation
+    let order = order::Entity::find_by_id(order_id)
+        .lock_exclusive()
+        .one(&txn)
+        .await?
+        .ok_or_else(|| AppError::not_found("Order not found"))?;
+
+    let unpaid = [PaymentStatus::Pending, PaymentStatus::Failed]
+        .iter()
+        .any(|status| order.payment_status == status.to_string());
+    if !unpaid || order.payment_method == PaymentMethod::CashOnDelivery.to_string() {
+        return Ok(false);
+    }
+
+    let targets: Vec<seller_order::Model> = seller_order::Entity::find()
+        .filter(seller_order::Column::OrderId.eq(order_id))
+        .filter(seller_order::Column::Status.is_in([
+            OrderStatus::Pending.to_string(),
+            OrderStatus::Processing.to_string(),
+        ]))
+        .lock_exclusive()
+        .all(&txn)
+        .await?;
+    if targets.is_empty() {
+        return Ok(false);
+    }
+
+    cancel_seller_orders(
+        &txn,
+        &order,
+        &targets,
+        OrderActor::System,
+        None,
+        CancellationReason::PaymentNotReceived,
+        None,
+    )
+    .await?;
+    txn.commit().await?;
+
+    Ok(true)
+}
+
+// Cancel sub-orders inside the caller's transaction: record who canceled and why, release their
+// stock, flag money owed back to the buyer and tell the other side
+async fn cancel_seller_orders<C: ConnectionTrait>(
+    db: &C,
+    order: &order::Model,
+    targets: &[seller_order::Model],
+    actor: OrderActor,
+    actor_id: Option<Uuid>,
+    reason: CancellationReason,
+    note: Option<String>,
+) -> Result<()> {
+    let order_id = order.id;
+    let now = Utc::now();
+
+    let mut canceled_total = Money::zero(Currency::XAF);
+    let mut canceled: [BigDecimal; 4] = Default::default();
+    for target in targets {
+        // Lock the row so its stock is only released once, whoever else is canceling it
+        let seller_order = seller_order::Entity::find_by_id(target.id)
+            .lock_exclusive()
+            .one(db)
+            .await?
+            .ok_or_else(|| AppError::not_found("Seller order not found"))?;
+
+        let change = StatusChange {
+            to: OrderStatus::Canceled,
+            actor,
+            actor_id,
+            note: Some(match &note {
+                Some(note) => format!("{}: {}", reason.label(), note),
+                None => reason.label().to_string(),
+            }),
+        };
+        let updated = order_status::transition_seller_order(db, seller_order.clone(), change).await?;
+
+        let mut active: seller_order::ActiveModel = updated.into();
+        active.cancellation_reason = Set(Some(reason.to_string()));
+        active.cancellation_note = Set(note.clone());
+        active.canceled_by = Set(Some(actor.to_string()));
+        active.canceled_at = Set(Some(now));
+        active.update(db).await?;
+
+        // Put the reserved units back on sale
+        let items = order_item::Entity::find()
+            .filter(order_item::Column::SellerOrderId.eq(seller_order.id))
+            .all(db)
+            .await?;
+        for item in items {
+            product::Entity::update_many()
+                .col_expr(product::Column::Stock, Expr::col(product::Column::Stock).add(item.quantity))
+                .filter(product::Column::Id.eq(item.product_id))
+                .exec(db)
+                .await?;
+        }
+
+        canceled_total = canceled_total.checked_add(to_money(&seller_order.total_amount)?)?;
+        canceled[0] += &seller_order.subtotal;
+        canceled[1] += &seller_order.discount_amount;
+        canceled[2] += &seller_order.shipping_amount;
+        canceled[3] += &seller_order.tax_amount;
+    }
+
+    // An unpaid order now only asks for what is still coming. A payment already on its way for the
+    // old total has the difference refunded when it settles.
+    let unpaid = [PaymentStatus::Pending.to_string(), PaymentStatus::Failed.to_string()];
+    if unpaid.contains(&order.payment_status) && canceled_total.is_positive() {
+        let remaining = seller_order::Entity::find()
+            .filter(seller_order::Column::OrderId.eq(order_id))
+            .filter(seller_order::Column::Status.ne(OrderStatus::Canceled.to_string()))
+            .count(db)
+            .await?;
+
+        if remaining > 0 {
+            let [subtotal, discount, shipping, tax] = canceled;
+            order::Entity::update_many()
+                .col_expr(order::Column::Subtotal, Expr::col(order::Column::Subtotal).sub(subtotal))
+                .col_expr(order::Column::DiscountAmount, Expr::col(order::Column::DiscountAmount).sub(discount))
+                .col_expr(order::Column::ShippingAmount, Expr::col(order::Column::ShippingAmount).sub(shipping))
+                .col_expr(order::Column::TaxAmount, Expr::col(order::Column::TaxAmount).sub(tax))
+                .col_expr(
+                    order::Column::TotalAmount,
+                    Expr::col(order::Column::TotalAmount).sub(canceled_total.to_decimal()),
+                )
+                .col_expr(order::Column::UpdatedAt, Expr::value(now))
+                .filter(order::Column::Id.eq(order_id))
+                .exec(db)
+                .await?;
+        }
+    }
+
+    // Money already captured for the canceled parts is owed back to the buyer.
+    // Orders paid through a provider get a refund on the payment, sent by the refunds job;
+    // the rest are flagged so finance can settle them.
+    let paid = [PaymentStatus::Paid.to_string(), PaymentStatus::PartiallyRefunded.to_string()];
+    if paid.contains(&order.payment_status) && canceled_total.is_positive() {
+        let fully_canceled = seller_order::Entity::find()
+            .filter(seller_order::Column::OrderId.eq(order_id))
+            .filter(seller_order::Column::Status.ne(OrderStatus::Canceled.to_string()))
+            .count(db)
+            .await?
+            == 0;
+
+        let captured = payment::Entity::find()
+            .filter(payment::Column::OrderId.eq(order_id))
+            .filter(payment::Column::Status.is_in([
+                FinancialPaymentStatus::Completed.to_string(),
+                FinancialPaymentStatus::PartiallyRefunded.to_string(),
+            ]))
+            .lock_exclusive()
+            .one(db)
+            .await?;
+
+        match captured {
+            Some(payment) => {
+                refund::refund_seller_orders(db, payment, targets, reason.label().to_string()).await?;
+            }
+            None => {
+                let payment_status = if fully_canceled {
+                    PaymentStatus::Refunded
+                } else {
+                    PaymentStatus::PartiallyRefunded
+                };
+
+                order::Entity::update_many()
+                    .col_expr(order::Column::PaymentStatus, Expr::value(payment_status.to_string()))
+                    .col_expr(order::Column::UpdatedAt, Expr::value(now))
+                    .filter(order::Column::Id.eq(order_id))
+                    .exec(db)
+                    .await?;
+            }
+        }
+    }
+
+    let order_number = order_id.to_string()[..8].to_uppercase();
+    let link = Some(format!("/orders/{}", order_id));
+
+    // Let every affected seller know they should not ship
+    if matches!(actor, OrderActor::Buyer | OrderActor::System) {
+        let title = match actor {
+            OrderActor::Buyer => format!("Order #{} was canceled by the buyer", order_number),
+            _ => format!("Order #{} was canceled", order_number),
+        };
+        for seller_order in targets {
+            notification::notify(
+                db,
+                seller_order.seller_id,
+                NotificationKind::OrderCanceled,
+                title.clone(),
+                reason.label(),
+                link.clone(),
+            )
+            .await?;
+        }
+    }
+
+    if actor != OrderActor::Buyer {
+        notification::notify(
+            db,
+            order.user_id,
+            NotificationKind::OrderCanceled,
+            format!("Your order #{} was canceled", order_number),
+            reason.label(),
+            link,
+        )
+        .await?;
+    }
+
+    Ok(())
 }
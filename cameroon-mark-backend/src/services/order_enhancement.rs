@@ -9,9 +9,10 @@ use std::collections::HashMap;
 use crate::{
     entities::{order, order_item, order_fulfillment, order_return},
     errors::{AppError, Result},
+    models::order::OrderStatus,
     models::order_enhancement::{
         OrderBatch, BatchOrderProcessingRequest, OrderFulfillment, OrderFulfillmentRequest,
-        OrderFulfillmentStatus, ShippingLabelRequest, OrderReturnRequest, OrderReturn,
+        OrderFulfillmentStatus, OrderReturnRequest, OrderReturn,
         OrderReturnItem
     },
     utils::validation,
@@ -61,23 +62,24 @@ pub async fn process_order_batch(
         
         match payload.action.as_str() {
             "confirm" => {
-                order_model.status = Set("confirmed".to_string());
+                order_model.status = Set(OrderStatus::Processing.to_string());
             },
             "process" => {
-                order_model.status = Set("processing".to_string());
+                order_model.status = Set(OrderStatus::Processing.to_string());
             },
             "ready" => {
-                order_model.status = Set("ready_for_shipment".to_string());
+                // Packing progress lives on the fulfillment record, the order stays processing
+                order_model.status = Set(OrderStatus::Processing.to_string());
             },
             "ship" => {
-                order_model.status = Set("shipped".to_string());
+                order_model.status = Set(OrderStatus::Shipped.to_string());
                 // Additional shipping details would be handled separately
             },
             "deliver" => {
-                order_model.status = Set("delivered".to_string());
+                order_model.status = Set(OrderStatus::Delivered.to_string());
             },
             "cancel" => {
-                order_model.status = Set("cancelled".to_string());
+                order_model.status = Set(OrderStatus::Canceled.to_string());
             },
             _ => return Err(AppError::bad_request("Invalid batch action")),
         }
@@ -292,67 +294,6 @@ pub async fn update_order_fulfillment(
     })
 }
 
-// Generate shipping label (mock implementation, would integrate with shipping APIs)
-pub async fn generate_shipping_label(
-    db: &DatabaseConnection,
-    user_id: Uuid,
-    payload: ShippingLabelRequest,
-) -> Result<String> {
-    // Validate request
-    validation::validate(&payload)?;
-
-    // Check if order exists
-    let order = order::Entity::find_by_id(payload.order_id)
-        .one(db)
-        .await?
-        .ok_or_else(|| AppError::not_found("Order not found"))?;
-    
-    // In a real implementation, this would call a shipping API
-    // For mock purposes, we'll just return a fake URL and update the fulfillment record
-    
-    // Mock shipping label URL
-    let label_url = format!("https://shipping-api.example.com/labels/{}", Uuid::new_v4());
-    
-    // Update fulfillment record
-    let fulfillment = order_fulfillment::Entity::find()
-        .filter(order_fulfillment::Column::OrderId.eq(payload.order_id))
-        .one(db)
-        .await?;
-    
-    if let Some(existing_fulfillment) = fulfillment {
-        let mut fulfillment_model = existing_fulfillment.into_active_model();
-        fulfillment_model.shipping_label_url = Set(Some(label_url.clone()));
-        fulfillment_model.shipping_provider = Set(Some(payload.shipping_provider));
-        fulfillment_model.package_weight = Set(Some(payload.package_weight));
-        fulfillment_model.package_dimensions = Set(payload.package_dimensions.clone());
-        fulfillment_model.handler_id = Set(Some(user_id));
-        fulfillment_model.updated_at = Set(Utc::now());
-        
-        fulfillment_model.update(db).await?;
-    } else {
-        let now = Utc::now();
-        let fulfillment_model = order_fulfillment::ActiveModel {
-            id: Set(Uuid::new_v4()),
-            order_id: Set(payload.order_id),
-            status: Set(OrderFulfillmentStatus::ReadyForShipment.to_string()),
-            tracking_number: Set(None),
-            shipping_provider: Set(Some(payload.shipping_provider)),
-            shipping_label_url: Set(Some(label_url.clone())),
-            package_weight: Set(Some(payload.package_weight)),
-            package_dimensions: Set(payload.package_dimensions.clone()),
-            notes: Set(Some(format!("Shipping label generated for {}", payload.shipping_provider))),
-            handler_id: Set(Some(user_id)),
-            created_at: Set(now),
-            updated_at: Set(now),
-            ..Default::default()
-        };
-        
-        fulfillment_model.insert(db).await?;
-    }
-    
-    Ok(label_url)
-}
-
 // Process order return/refund
 pub async fn process_order_return(
     db: &DatabaseConnection,
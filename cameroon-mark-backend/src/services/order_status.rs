@@ -0,0 +1,202 @@
+use chrono::Utc;
+use sea_orm::{ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder, Set};
+use std::str::FromStr;
+use uuid::Uuid;
+
+use crate::entities::{order, order_status_history, seller_order};
+use crate::errors::{AppError, Result};
+use crate::models::order::{
+    FulfillmentStatus, OrderActor, OrderStatus, OrderStatusHistoryEntry, PayoutStatus,
+};
+use crate::services::ledger;
+
+// A requested status change and who asked for it
+pub struct StatusChange {
+    pub to: OrderStatus,
+    pub actor: OrderActor,
+    pub actor_id: Option<Uuid>,
+    pub note: Option<String>,
+}
+
+fn parse_status(status: &str) -> Result<OrderStatus> {
+    OrderStatus::from_str(status).map_err(AppError::internal)
+}
+
+// Reject moves the state machine does not allow, with a message saying why
+pub fn ensure_transition(from: &OrderStatus, to: &OrderStatus, actor: OrderActor) -> Result<()> {
+    if from == to {
+        return Err(AppError::bad_request(format!("Order is already {}", to)));
+    }
+
+    if from.is_terminal() {
+        return Err(AppError::bad_request(format!("Order is {} and can no longer change", from)));
+    }
+
+    if from.allowed_actors(to).is_empty() {
+        return Err(AppError::bad_request(format!(
+            "Cannot change order status from {} to {}",
+            from, to
+        )));
+    }
+
+    if !from.can_transition_to(to, actor) {
+        return Err(AppError::forbidden(format!(
+            "A {} cannot change order status from {} to {}",
+            actor, from, to
+        )));
+    }
+
+    Ok(())
+}
+
+// Write one entry to the status history
+pub async fn record<C: ConnectionTrait>(
+    db: &C,
+    order_id: Uuid,
+    seller_order_id: Option<Uuid>,
+    from: Option<&OrderStatus>,
+    change: &StatusChange,
+) -> Result<()> {
+    let entry = order_status_history::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        order_id: Set(order_id),
+        seller_order_id: Set(seller_order_id),
+        from_status: Set(from.map(|s| s.to_string())),
+        to_status: Set(change.to.to_string()),
+        actor_id: Set(change.actor_id),
+        actor_role: Set(change.actor.to_string()),
+        note: Set(change.note.clone()),
+        created_at: Set(Utc::now()),
+    };
+    entry.insert(db).await?;
+
+    Ok(())
+}
+
+// The buyer-facing status of an order follows its slowest active sub-order.
+// It is only canceled once every seller has canceled.
+fn derive_order_status(statuses: &[OrderStatus]) -> OrderStatus {
+    let rank = |status: &OrderStatus| match status {
+        OrderStatus::Pending => 0,
+        OrderStatus::Processing => 1,
+        OrderStatus::Shipped => 2,
+        OrderStatus::Delivered => 3,
+        OrderStatus::Canceled => 4,
+    };
+
+    statuses
+        .iter()
+        .filter(|s| **s != OrderStatus::Canceled)
+        .min_by_key(|s| rank(s))
+        .cloned()
+        .unwrap_or(OrderStatus::Canceled)
+}
+
+// Recompute the parent order's status from its sub-orders, recording the change if there is one
+async fn sync_order_status<C: ConnectionTrait>(db: &C, order_id: Uuid, change: &StatusChange) -> Result<()> {
+    let order = order::Entity::find_by_id(order_id)
+        .one(db)
+        .await?
+        .ok_or_else(|| AppError::not_found("Order not found"))?;
+
+    let statuses = seller_order::Entity::find()
+        .filter(seller_order::Column::OrderId.eq(order_id))
+        .all(db)
+        .await?
+        .iter()
+        .map(|so| parse_status(&so.status))
+        .collect::<Result<Vec<_>>>()?;
+
+    let current = parse_status(&order.status)?;
+    let derived = derive_order_status(&statuses);
+    if derived == current {
+        return Ok(());
+    }
+
+    let mut active: order::ActiveModel = order.into();
+    active.status = Set(derived.to_string());
+    active.updated_at = Set(Utc::now());
+    active.update(db).await?;
+
+    let parent_change = StatusChange {
+        to: derived,
+        actor: change.actor,
+        actor_id: change.actor_id,
+        note: change.note.clone(),
+    };
+    record(db, order_id, None, Some(&current), &parent_change).await
+}
+
+// Move a seller's sub-order to a new status, applying fulfillment and payout side effects,
+// and roll the result up to the parent order. Run this inside a transaction.
+pub async fn transition_seller_order<C: ConnectionTrait>(
+    db: &C,
+    seller_order: seller_order::Model,
+    change: StatusChange,
+) -> Result<seller_order::Model> {
+    let from = parse_status(&seller_order.status)?;
+    ensure_transition(&from, &change.to, change.actor)?;
+
+    let now = Utc::now();
+    let order_id = seller_order.order_id;
+    let seller_order_id = seller_order.id;
+
+    let mut active: seller_order::ActiveModel = seller_order.into();
+    active.status = Set(change.to.to_string());
+    match change.to {
+        OrderStatus::Shipped => {
+            active.fulfillment_status = Set(FulfillmentStatus::Shipped.to_string());
+            active.shipped_at = Set(Some(now));
+        }
+        OrderStatus::Delivered => {
+            active.fulfillment_status = Set(FulfillmentStatus::Delivered.to_string());
+            active.delivered_at = Set(Some(now));
+            // Delivery releases the seller's money for payout
+            active.payout_status = Set(PayoutStatus::Released.to_string());
+        }
+        OrderStatus::Canceled => {
+            active.fulfillment_status = Set(FulfillmentStatus::Canceled.to_string());
+            active.payout_status = Set(PayoutStatus::Canceled.to_string());
+        }
+        OrderStatus::Pending | OrderStatus::Processing => {}
+    }
+    active.updated_at = Set(now);
+    let mut updated = active.update(db).await?;
+
+    // Online payments held in escrow reach the seller's wallet, less commission
+    if change.to == OrderStatus::Delivered {
+        if let Some(net) = ledger::release_seller_order(db, &updated).await? {
+            let mut active: seller_order::ActiveModel = updated.into();
+            active.payout_amount = Set(net.to_decimal());
+            updated = active.update(db).await?;
+        }
+    }
+
+    record(db, order_id, Some(seller_order_id), Some(&from), &change).await?;
+    sync_order_status(db, order_id, &change).await?;
+
+    Ok(updated)
+}
+
+// Get the status history of an order, oldest first
+pub async fn get_history(db: &DatabaseConnection, order_id: Uuid) -> Result<Vec<OrderStatusHistoryEntry>> {
+    order_status_history::Entity::find()
+        .filter(order_status_history::Column::OrderId.eq(order_id))
+        .order_by_asc(order_status_history::Column::CreatedAt)
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|entry| {
+            Ok(OrderStatusHistoryEntry {
+                id: entry.id,
+                seller_order_id: entry.seller_order_id,
+                from_status: entry.from_status.as_deref().map(parse_status).transpose()?,
+                to_status: parse_status(&entry.to_status)?,
+                actor_id: entry.actor_id,
+                actor_role: OrderActor::from_str(&entry.actor_role).map_err(AppError::internal)?,
+                note: entry.note,
+                created_at: entry.created_at,
+            })
+        })
+        .collect()
+}
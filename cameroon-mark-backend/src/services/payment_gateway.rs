@@ -0,0 +1,458 @@
+use async_trait::async_trait;
+use bigdecimal::{BigDecimal, FromPrimitive};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+use crate::config::PaymentConfig;
+use crate::errors::{AppError, Result};
+use crate::models::money::{Currency, Money};
+use crate::models::order::PaymentMethod;
+use crate::services::{mtn_momo::MtnMomoGateway, orange_money::OrangeMoneyGateway};
+
+// Requests to a provider are abandoned after this long
+pub const PROVIDER_TIMEOUT: Duration = Duration::from_secs(30);
+
+// Where a payment stands on the provider's side
+#[derive(Debug, Clone, PartialEq)]
+pub enum GatewayPaymentStatus {
+    Pending,
+    Successful,
+    Failed { reason: String },
+}
+
+// What we ask the provider to collect
+#[derive(Debug, Clone)]
+pub struct PaymentRequest {
+    // Our payment id, sent to the provider as the external reference
+    pub payment_id: Uuid,
+    pub order_id: Uuid,
+    pub amount: Money,
+    // +237XXXXXXXXX, required by providers that push the prompt to the payer's phone
+    pub phone_number: Option<String>,
+    pub description: String,
+    // Secret for the callback URL, saved on the payment before the provider is called
+    pub callback_token: String,
+}
+
+// The provider's answer to a payment request
+#[derive(Debug, Clone)]
+pub struct PaymentInitiation {
+    // Id the provider knows the payment by, used for status checks and callbacks
+    pub provider_reference: String,
+    pub status: GatewayPaymentStatus,
+    // Page the buyer is sent to when the provider collects payment on its own site
+    pub redirect_url: Option<String>,
+    // Secret the provider chose for its callback, for providers that do not use ours
+    pub callback_token: Option<String>,
+}
+
+// Everything a provider needs to look a payment up again
+#[derive(Debug, Clone)]
+pub struct PaymentLookup {
+    pub payment_id: Uuid,
+    pub provider_reference: String,
+    pub amount: Money,
+}
+
+// What a provider's callback says about a payment
+#[derive(Debug, Clone)]
+pub struct CallbackEvent {
+    // Secret identifying the payment, for providers that send it in the body
+    pub token: Option<String>,
+    pub status: GatewayPaymentStatus,
+    pub transaction_id: Option<String>,
+    pub amount: Option<Money>,
+}
+
+// What we ask the provider to send to a seller
+#[derive(Debug, Clone)]
+pub struct TransferRequest {
+    // Our withdrawal id, sent to the provider as the external reference
+    pub withdrawal_id: Uuid,
+    pub amount: Money,
+    // +237XXXXXXXXX
+    pub phone_number: String,
+    pub description: String,
+}
+
+// What we ask the provider to give back to a payer
+#[derive(Debug, Clone)]
+pub struct RefundInstruction {
+    // Sent to the provider as the refund's reference. The same for every send of a refund,
+    // unless the provider reported the last one failed
+    pub reference: Uuid,
+    // Id the provider knows the refunded payment by
+    pub payment_reference: String,
+    pub amount: Money,
+    pub description: String,
+}
+
+// The provider's answer to a transfer or refund request
+#[derive(Debug, Clone)]
+pub struct TransferInitiation {
+    pub provider_reference: String,
+    pub status: GatewayPaymentStatus,
+}
+
+// A mobile money provider we can collect payments through, and for some, pay sellers through
+#[async_trait]
+pub trait PaymentGateway: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    // Start collecting a payment. Mobile money payments usually stay pending until the payer approves them.
+    // A request the provider turned down comes back as Failed; an error means the outcome is unknown.
+    async fn request_to_pay(&self, request: &PaymentRequest) -> Result<PaymentInitiation>;
+
+    // The reference the provider will know a payment by, for providers where we choose it.
+    // Lets a payment whose request errored be looked up later.
+    fn reference_for(&self, _request: &PaymentRequest) -> Option<String> {
+        None
+    }
+
+    // Ask the provider where a payment stands
+    async fn check_status(&self, lookup: &PaymentLookup) -> Result<GatewayPaymentStatus>;
+
+    // Read a provider callback. None when the body is not a payment notification from this provider.
+    fn parse_callback(&self, payload: &serde_json::Value) -> Option<CallbackEvent>;
+
+    // Share of each payment the provider keeps, passed on to sellers as a processing fee
+    fn processing_fee_rate(&self) -> BigDecimal {
+        BigDecimal::from(0)
+    }
+
+    // Whether payouts can be sent through the provider's API. Otherwise they are paid by hand.
+    fn supports_transfers(&self) -> bool {
+        false
+    }
+
+    // Send money to a mobile money account
+    async fn transfer(&self, _request: &TransferRequest) -> Result<TransferInitiation> {
+        Err(AppError::bad_request(format!("{} payouts are made by hand", self.name())))
+    }
+
+    // Ask the provider where a transfer stands
+    async fn check_transfer(&self, _lookup: &PaymentLookup) -> Result<GatewayPaymentStatus> {
+        Err(AppError::bad_request(format!("{} payouts are made by hand", self.name())))
+    }
+
+    // Whether refunds can be sent through the provider's API. Otherwise they are paid by hand.
+    fn supports_refunds(&self) -> bool {
+        false
+    }
+
+    // Give part or all of a completed payment back to the payer
+    async fn refund(&self, _instruction: &RefundInstruction) -> Result<TransferInitiation> {
+        Err(AppError::bad_request(format!("{} refunds are made by hand", self.name())))
+    }
+
+    // Ask the provider where a refund stands
+    async fn check_refund(&self, _lookup: &PaymentLookup) -> Result<GatewayPaymentStatus> {
+        Err(AppError::bad_request(format!("{} refunds are made by hand", self.name())))
+    }
+}
+
+// An OAuth access token reused until shortly before it expires
+pub struct TokenCache {
+    token: tokio::sync::Mutex<Option<(String, Instant)>>,
+}
+
+impl TokenCache {
+    pub fn new() -> Self {
+        Self {
+            token: tokio::sync::Mutex::new(None),
+        }
+    }
+
+    pub async fn get_or_fetch<F, Fut>(&self, fetch: F) -> Result<String>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<(String, Duration)>>,
+    {
+        let mut token = self.token.lock().await;
+
+        if let Some((value, expires_at)) = token.as_ref() {
+            if Instant::now() < *expires_at {
+                return Ok(value.clone());
+            }
+        }
+
+        let (value, lifetime) = fetch().await?;
+        // Renew a minute early so a token never expires mid-request
+        let expires_at = Instant::now() + lifetime.saturating_sub(Duration::from_secs(60));
+        *token = Some((value.clone(), expires_at));
+
+        Ok(value)
+    }
+}
+
+impl Default for TokenCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// A configured fee rate, e.g. 0.01 for 1%
+pub fn fee_rate(rate: f64) -> BigDecimal {
+    BigDecimal::from_f64(rate)
+        .unwrap_or_default()
+        .with_scale_round(4, bigdecimal::RoundingMode::HalfUp)
+}
+
+// Amounts go to providers in whole francs
+pub fn format_amount(amount: Money) -> String {
+    amount.whole_units().to_string()
+}
+
+// International number without the plus sign, e.g. 237677123456
+pub fn msisdn(phone: &str) -> String {
+    phone.chars().filter(|c| c.is_ascii_digit()).collect()
+}
+
+// How the fake gateway answers payment requests
+#[derive(Debug, Clone, PartialEq)]
+pub enum FakeOutcome {
+    Succeed,
+    Pending,
+    Decline(String),
+    Unavailable,
+}
+
+// In-memory gateway for tests and local development. Payments can be settled by hand to stand in for the payer.
+pub struct FakeGateway {
+    outcome: FakeOutcome,
+    payments: Mutex<HashMap<String, GatewayPaymentStatus>>,
+}
+
+impl FakeGateway {
+    pub fn new(outcome: FakeOutcome) -> Self {
+        Self {
+            outcome,
+            payments: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn answer(&self) -> Result<GatewayPaymentStatus> {
+        match &self.outcome {
+            FakeOutcome::Succeed => Ok(GatewayPaymentStatus::Successful),
+            FakeOutcome::Pending => Ok(GatewayPaymentStatus::Pending),
+            FakeOutcome::Decline(reason) => Ok(GatewayPaymentStatus::Failed { reason: reason.clone() }),
+            FakeOutcome::Unavailable => Err(AppError::external_service("Fake: provider unavailable")),
+        }
+    }
+
+    fn lookup(&self, provider_reference: &str) -> Result<GatewayPaymentStatus> {
+        self.payments
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(provider_reference)
+            .cloned()
+            .ok_or_else(|| AppError::external_service(format!("Fake: unknown payment {}", provider_reference)))
+    }
+
+    // Complete or fail a pending payment as if the payer had answered the prompt
+    pub fn settle(&self, provider_reference: &str, status: GatewayPaymentStatus) {
+        self.payments
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(provider_reference.to_string(), status);
+    }
+}
+
+#[async_trait]
+impl PaymentGateway for FakeGateway {
+    fn name(&self) -> &'static str {
+        "Fake"
+    }
+
+    async fn request_to_pay(&self, request: &PaymentRequest) -> Result<PaymentInitiation> {
+        let status = self.answer()?;
+        let provider_reference = format!("FAKE-{}", request.payment_id);
+        self.settle(&provider_reference, status.clone());
+
+        Ok(PaymentInitiation {
+            provider_reference,
+            status,
+            redirect_url: None,
+            callback_token: None,
+        })
+    }
+
+    fn reference_for(&self, request: &PaymentRequest) -> Option<String> {
+        Some(format!("FAKE-{}", request.payment_id))
+    }
+
+    async fn check_status(&self, lookup: &PaymentLookup) -> Result<GatewayPaymentStatus> {
+        self.lookup(&lookup.provider_reference)
+    }
+
+    fn parse_callback(&self, payload: &serde_json::Value) -> Option<CallbackEvent> {
+        let status = match payload["status"].as_str()? {
+            "successful" => GatewayPaymentStatus::Successful,
+            "failed" => GatewayPaymentStatus::Failed {
+                reason: payload["reason"].as_str().unwrap_or("declined").to_string(),
+            },
+            _ => GatewayPaymentStatus::Pending,
+        };
+
+        Some(CallbackEvent {
+            token: payload["token"].as_str().map(str::to_string),
+            status,
+            transaction_id: payload["transaction_id"].as_str().map(str::to_string),
+            amount: payload["amount"]
+                .as_f64()
+                .and_then(|amount| Money::from_major(amount, Currency::XAF).ok()),
+        })
+    }
+
+    fn supports_transfers(&self) -> bool {
+        true
+    }
+
+    async fn transfer(&self, request: &TransferRequest) -> Result<TransferInitiation> {
+        let status = self.answer()?;
+        let provider_reference = format!("FAKE-TRANSFER-{}", request.withdrawal_id);
+        self.settle(&provider_reference, status.clone());
+
+        Ok(TransferInitiation {
+            provider_reference,
+            status,
+        })
+    }
+
+    async fn check_transfer(&self, lookup: &PaymentLookup) -> Result<GatewayPaymentStatus> {
+        self.lookup(&lookup.provider_reference)
+    }
+
+    fn supports_refunds(&self) -> bool {
+        true
+    }
+
+    async fn refund(&self, instruction: &RefundInstruction) -> Result<TransferInitiation> {
+        let status = self.answer()?;
+        let provider_reference = format!("FAKE-REFUND-{}", instruction.reference);
+        self.settle(&provider_reference, status.clone());
+
+        Ok(TransferInitiation {
+            provider_reference,
+            status,
+        })
+    }
+
+    async fn check_refund(&self, lookup: &PaymentLookup) -> Result<GatewayPaymentStatus> {
+        self.lookup(&lookup.provider_reference)
+    }
+}
+
+// The gateway behind each payment method
+pub struct PaymentGateways {
+    mtn: Arc<dyn PaymentGateway>,
+    orange: Arc<dyn PaymentGateway>,
+}
+
+impl PaymentGateways {
+    pub fn new(mtn: Arc<dyn PaymentGateway>, orange: Arc<dyn PaymentGateway>) -> Self {
+        Self { mtn, orange }
+    }
+
+    // Real providers, sandbox or production as configured
+    pub fn from_config(config: &PaymentConfig) -> Self {
+        Self::new(
+            Arc::new(MtnMomoGateway::new(config)),
+            Arc::new(OrangeMoneyGateway::new(config)),
+        )
+    }
+
+    // Every method served by a fake gateway that leaves payments pending
+    pub fn fake() -> Self {
+        Self::new(
+            Arc::new(FakeGateway::new(FakeOutcome::Pending)),
+            Arc::new(FakeGateway::new(FakeOutcome::Pending)),
+        )
+    }
+
+    pub fn for_method(&self, method: &PaymentMethod) -> Result<Arc<dyn PaymentGateway>> {
+        match method {
+            PaymentMethod::Mtn => Ok(self.mtn.clone()),
+            PaymentMethod::Orange => Ok(self.orange.clone()),
+            PaymentMethod::CashOnDelivery => Err(AppError::bad_request(
+                "Cash on delivery orders are paid to the courier on delivery",
+            )),
+            PaymentMethod::Other => Err(AppError::bad_request("This payment method cannot be paid online")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request() -> PaymentRequest {
+        PaymentRequest {
+            payment_id: Uuid::new_v4(),
+            order_id: Uuid::new_v4(),
+            amount: Money::xaf(5000),
+            phone_number: Some("+237677123456".to_string()),
+            description: "Order".to_string(),
+            callback_token: "secret".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn fake_payments_wait_for_the_payer() {
+        let gateways = PaymentGateways::fake();
+        let gateway = gateways.for_method(&PaymentMethod::Mtn).unwrap();
+        let request = request();
+
+        let initiation = gateway.request_to_pay(&request).await.unwrap();
+        assert_eq!(initiation.status, GatewayPaymentStatus::Pending);
+        assert_eq!(gateway.reference_for(&request), Some(initiation.provider_reference.clone()));
+
+        let lookup = PaymentLookup {
+            payment_id: request.payment_id,
+            provider_reference: initiation.provider_reference,
+            amount: request.amount,
+        };
+        assert_eq!(gateway.check_status(&lookup).await.unwrap(), GatewayPaymentStatus::Pending);
+        assert!(gateways.for_method(&PaymentMethod::CashOnDelivery).is_err());
+    }
+
+    #[tokio::test]
+    async fn fake_payments_settle_by_hand() {
+        let gateway = FakeGateway::new(FakeOutcome::Pending);
+        let request = request();
+        let initiation = gateway.request_to_pay(&request).await.unwrap();
+
+        gateway.settle(&initiation.provider_reference, GatewayPaymentStatus::Successful);
+
+        let lookup = PaymentLookup {
+            payment_id: request.payment_id,
+            provider_reference: initiation.provider_reference,
+            amount: request.amount,
+        };
+        assert_eq!(gateway.check_status(&lookup).await.unwrap(), GatewayPaymentStatus::Successful);
+    }
+
+    #[tokio::test]
+    async fn unavailable_fake_gateway_errors() {
+        let gateway = FakeGateway::new(FakeOutcome::Unavailable);
+
+        assert!(gateway.request_to_pay(&request()).await.is_err());
+    }
+
+    #[test]
+    fn fake_callbacks_are_parsed() {
+        let gateway = FakeGateway::new(FakeOutcome::Pending);
+
+        let event = gateway
+            .parse_callback(&serde_json::json!({"status": "failed", "reason": "NOT_ENOUGH_FUNDS", "token": "t", "amount": 5000}))
+            .unwrap();
+
+        assert_eq!(event.status, GatewayPaymentStatus::Failed { reason: "NOT_ENOUGH_FUNDS".to_string() });
+        assert_eq!(event.token.as_deref(), Some("t"));
+        assert_eq!(event.amount, Some(Money::xaf(5000)));
+        assert!(gateway.parse_callback(&serde_json::json!({})).is_none());
+    }
+}
@@ -0,0 +1,484 @@
+use bigdecimal::BigDecimal;
+use chrono::{Duration, NaiveDate, Utc};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, Condition, ConnectionTrait, DatabaseBackend, DatabaseConnection, EntityTrait, IntoActiveModel,
+    PaginatorTrait, QueryFilter, QueryOrder, QuerySelect, Set, Statement, TransactionTrait,
+};
+use sea_orm::sea_query::Query;
+use std::collections::HashMap;
+use std::str::FromStr;
+use uuid::Uuid;
+
+use crate::entities::{order, payment, payment_reconciliation_report, provider_settlement};
+use crate::errors::{AppError, Result};
+use crate::models::financial::{
+    ImportSettlementsRequest, ImportSettlementsResponse, PaymentProcessor, PaymentStatus, ReconciliationEntry,
+    ReconciliationReport, ReconciliationStatus,
+};
+use crate::models::money::{Currency, Money};
+use crate::models::order::{OrderStatus, PaymentMethod, PaymentStatus as OrderPaymentStatus};
+use crate::services::order as order_service;
+use crate::services::payment_gateway::{GatewayPaymentStatus, PaymentGateways, PaymentLookup};
+use crate::services::payment_webhook;
+
+// Payments still unsettled after this long are looked up with the provider
+const RECHECK_AFTER_MINUTES: i64 = 15;
+// Payments the payer has not completed after this long are given up on
+const EXPIRE_AFTER_MINUTES: i64 = 120;
+// Payments looked up per run, oldest first, to stay well inside provider rate limits
+const BATCH_SIZE: u64 = 200;
+
+// Providers we collect through and reconcile against
+const RECONCILED_PROCESSORS: [PaymentProcessor; 2] = [PaymentProcessor::MtnMobileMoney, PaymentProcessor::OrangeMoney];
+
+fn to_money(value: &BigDecimal) -> Result<Money> {
+    Ok(Money::from_decimal(value, Currency::XAF)?)
+}
+
+fn gateway_method(processor: &PaymentProcessor) -> Option<PaymentMethod> {
+    match processor {
+        PaymentProcessor::MtnMobileMoney => Some(PaymentMethod::Mtn),
+        PaymentProcessor::OrangeMoney => Some(PaymentMethod::Orange),
+        _ => None,
+    }
+}
+
+// What a reconciliation run did, for the job's log
+#[derive(Debug, Default)]
+pub struct ReconciliationSummary {
+    pub checked: u64,
+    pub settled: u64,
+    pub expired: u64,
+    pub orders_canceled: u64,
+    pub failed_lookups: u64,
+}
+
+// Find payments stuck in pending or processing, ask their provider where they stand and settle them.
+// Attempts the payer never completed are expired, and orders left with no payment in progress are
+// canceled so their stock goes back on sale.
+pub async fn reconcile_pending_payments(
+    db: &DatabaseConnection,
+    gateways: &PaymentGateways,
+) -> Result<ReconciliationSummary> {
+    let stuck = payment::Entity::find()
+        .filter(payment::Column::Status.is_in([
+            PaymentStatus::Pending.to_string(),
+            PaymentStatus::Processing.to_string(),
+        ]))
+        .filter(payment::Column::CreatedAt.lte(Utc::now() - Duration::minutes(RECHECK_AFTER_MINUTES)))
+        .order_by_asc(payment::Column::CreatedAt)
+        .limit(BATCH_SIZE)
+        .all(db)
+        .await?;
+
+    let mut summary = ReconciliationSummary::default();
+
+    for payment in stuck {
+        summary.checked += 1;
+        let payment_id = payment.id;
+        let order_id = payment.order_id;
+
+        let status = match provider_status(gateways, &payment).await {
+            Ok(status) => status,
+            Err(e) => {
+                tracing::warn!("Could not look up payment {} with its provider: {:?}", payment_id, e);
+                summary.failed_lookups += 1;
+                continue;
+            }
+        };
+
+        match apply_status(db, payment_id, status).await? {
+            Some(PaymentStatus::Expired) => summary.expired += 1,
+            Some(_) => summary.settled += 1,
+            None => continue,
+        }
+
+        if release_abandoned_order(db, order_id).await? {
+            summary.orders_canceled += 1;
+        }
+    }
+
+    Ok(summary)
+}
+
+// Where the provider says a payment stands. A payment the provider never acknowledged stays pending
+// on our side until it expires.
+async fn provider_status(gateways: &PaymentGateways, payment: &payment::Model) -> Result<GatewayPaymentStatus> {
+    let processor = PaymentProcessor::from_str(&payment.processor).map_err(AppError::internal)?;
+    let (Some(method), Some(reference)) = (gateway_method(&processor), payment.processor_payment_id.clone()) else {
+        return Ok(GatewayPaymentStatus::Pending);
+    };
+
+    gateways
+        .for_method(&method)?
+        .check_status(&PaymentLookup {
+            payment_id: payment.id,
+            provider_reference: reference,
+            amount: to_money(&payment.amount)?,
+        })
+        .await
+}
+
+// Settle or expire a payment with the provider's answer. Returns the payment's new status, or None
+// when it was left alone because it is still in progress or a callback settled it first.
+async fn apply_status(
+    db: &DatabaseConnection,
+    payment_id: Uuid,
+    status: GatewayPaymentStatus,
+) -> Result<Option<PaymentStatus>> {
+    let payment = payment::Entity::find_by_id(payment_id)
+        .one(db)
+        .await?
+        .ok_or_else(|| AppError::not_found("Payment not found"))?;
+
+    let txn = db.begin().await?;
+    let payment = payment_webhook::lock_payment(&txn, &payment).await?;
+    if PaymentStatus::from_str(&payment.status).map_err(AppError::internal)?.is_final() {
+        return Ok(None);
+    }
+
+    let next = match status {
+        GatewayPaymentStatus::Successful => {
+            payment_webhook::settle(&txn, payment, &status, None).await?;
+            PaymentStatus::Completed
+        }
+        GatewayPaymentStatus::Failed { .. } => {
+            payment_webhook::settle(&txn, payment, &status, None).await?;
+            PaymentStatus::Failed
+        }
+        GatewayPaymentStatus::Pending => {
+            if payment.created_at > Utc::now() - Duration::minutes(EXPIRE_AFTER_MINUTES) {
+                return Ok(None);
+            }
+            expire(&txn, payment).await?;
+            PaymentStatus::Expired
+        }
+    };
+
+    txn.commit().await?;
+
+    Ok(Some(next))
+}
+
+async fn expire<C: ConnectionTrait>(db: &C, payment: payment::Model) -> Result<()> {
+    let order = order::Entity::find_by_id(payment.order_id)
+        .lock_exclusive()
+        .one(db)
+        .await?
+        .ok_or_else(|| AppError::not_found("Order not found"))?;
+    let now = Utc::now();
+
+    let mut payment = payment.into_active_model();
+    payment.status = Set(PaymentStatus::Expired.to_string());
+    payment.failure_reason = Set(Some("the payer did not complete the payment in time".to_string()));
+    payment.updated_at = Set(now);
+    payment.update(db).await?;
+
+    if order.payment_status == OrderPaymentStatus::Pending.to_string() {
+        let mut order = order.into_active_model();
+        order.payment_status = Set(OrderPaymentStatus::Failed.to_string());
+        order.updated_at = Set(now);
+        order.update(db).await?;
+    }
+
+    Ok(())
+}
+
+// Cancel online orders nobody started paying for, or whose every attempt ended, once they are as old
+// as an expired payment. Their stock is held from checkout, so it goes back on sale. Returns how
+// many orders were canceled.
+pub async fn release_unpaid_orders(db: &DatabaseConnection) -> Result<u64> {
+    let cutoff = Utc::now() - Duration::minutes(EXPIRE_AFTER_MINUTES);
+
+    let live_payments = Query::select()
+        .column(payment::Column::OrderId)
+        .from(payment::Entity)
+        .cond_where(
+            Condition::any()
+                .add(payment::Column::Status.is_in([
+                    PaymentStatus::Pending.to_string(),
+                    PaymentStatus::Processing.to_string(),
+                    PaymentStatus::Completed.to_string(),
+                ]))
+                .add(payment::Column::CreatedAt.gt(cutoff)),
+        )
+        .to_owned();
+
+    let abandoned = order::Entity::find()
+        .filter(order::Column::PaymentStatus.is_in([
+            OrderPaymentStatus::Pending.to_string(),
+            OrderPaymentStatus::Failed.to_string(),
+        ]))
+        .filter(order::Column::PaymentMethod.ne(PaymentMethod::CashOnDelivery.to_string()))
+        .filter(order::Column::Status.ne(OrderStatus::Canceled.to_string()))
+        .filter(order::Column::CreatedAt.lte(cutoff))
+        .filter(order::Column::Id.not_in_subquery(live_payments))
+        .order_by_asc(order::Column::CreatedAt)
+        .limit(BATCH_SIZE)
+        .all(db)
+        .await?;
+
+    let mut canceled = 0;
+    for order in abandoned {
+        // Checked again in case the buyer started paying since
+        if release_abandoned_order(db, order.id).await? {
+            canceled += 1;
+        }
+    }
+
+    Ok(canceled)
+}
+
+// Cancel an unpaid order once its last payment attempt is old enough to count as abandoned.
+// A buyer who retries after a declined attempt keeps their order while the new attempt runs.
+async fn release_abandoned_order(db: &DatabaseConnection, order_id: Uuid) -> Result<bool> {
+    let recent = payment::Entity::find()
+        .filter(payment::Column::OrderId.eq(order_id))
+        .filter(
+            Condition::any()
+                .add(payment::Column::Status.is_in([
+                    PaymentStatus::Pending.to_string(),
+                    PaymentStatus::Processing.to_string(),
+                    PaymentStatus::Completed.to_string(),
+                ]))
+                .add(payment::Column::CreatedAt.gt(Utc::now() - Duration::minutes(EXPIRE_AFTER_MINUTES))),
+        )
+        .count(db)
+        .await?;
+    if recent > 0 {
+        return Ok(false);
+    }
+
+    order_service::cancel_unpaid_order(db, order_id).await
+}
+
+// Store the lines of a provider settlement report. Lines already imported are skipped, so the
+// same report can be uploaded again safely.
+pub async fn import_settlements(
+    db: &DatabaseConnection,
+    payload: ImportSettlementsRequest,
+) -> Result<ImportSettlementsResponse> {
+    if gateway_method(&payload.processor).is_none() {
+        return Err(AppError::bad_request("Settlement reports are only reconciled for mobile money providers"));
+    }
+
+    let processor = payload.processor.to_string();
+    let total = payload.settlements.len() as u64;
+    let mut imported = 0;
+
+    let txn = db.begin().await?;
+    for line in payload.settlements {
+        if line.amount.currency() != Currency::XAF || line.amount.is_negative() {
+            return Err(AppError::bad_request("Settlement amounts must be positive XAF amounts"));
+        }
+        let amount = line.amount.to_decimal().with_scale(2);
+
+        let result = txn
+            .execute(Statement::from_sql_and_values(
+                DatabaseBackend::Postgres,
+                r#"
+                INSERT INTO provider_settlements (processor, provider_reference, transaction_id, amount, settled_at)
+                VALUES ($1, $2, $3, $4, $5)
+                ON CONFLICT (processor, provider_reference) DO NOTHING
+                "#,
+                [
+                    processor.clone().into(),
+                    line.provider_reference.trim().to_string().into(),
+                    line.transaction_id.into(),
+                    amount.into(),
+                    line.settled_at.into(),
+                ],
+            ))
+            .await?;
+        imported += result.rows_affected();
+    }
+    txn.commit().await?;
+
+    Ok(ImportSettlementsResponse {
+        imported,
+        skipped: total - imported,
+    })
+}
+
+// Compare the payments we completed on a day with what each provider reports having settled.
+// Each side is matched against the whole of the other, so a payment completed just before midnight
+// and settled just after is not reported twice. Regenerating a day replaces its reports.
+pub async fn build_daily_report(db: &DatabaseConnection, date: NaiveDate) -> Result<Vec<ReconciliationReport>> {
+    let start = date.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc();
+    let end = start + Duration::days(1);
+    let mut reports = Vec::new();
+
+    for processor in RECONCILED_PROCESSORS {
+        let processor_name = processor.to_string();
+
+        // Refunded payments were still collected that day
+        let payments = payment::Entity::find()
+            .filter(payment::Column::Processor.eq(processor_name.clone()))
+            .filter(payment::Column::Status.is_in([
+                PaymentStatus::Completed.to_string(),
+                PaymentStatus::Refunded.to_string(),
+                PaymentStatus::PartiallyRefunded.to_string(),
+            ]))
+            .filter(payment::Column::CompletedAt.gte(start))
+            .filter(payment::Column::CompletedAt.lt(end))
+            .all(db)
+            .await?;
+        let settlements = provider_settlement::Entity::find()
+            .filter(provider_settlement::Column::Processor.eq(processor_name.clone()))
+            .filter(provider_settlement::Column::SettledAt.gte(start))
+            .filter(provider_settlement::Column::SettledAt.lt(end))
+            .all(db)
+            .await?;
+
+        let references: Vec<String> = payments.iter().filter_map(|p| p.processor_payment_id.clone()).collect();
+        let settled: HashMap<String, provider_settlement::Model> = provider_settlement::Entity::find()
+            .filter(provider_settlement::Column::Processor.eq(processor_name.clone()))
+            .filter(provider_settlement::Column::ProviderReference.is_in(references))
+            .all(db)
+            .await?
+            .into_iter()
+            .map(|s| (s.provider_reference.clone(), s))
+            .collect();
+
+        let references: Vec<String> = settlements.iter().map(|s| s.provider_reference.clone()).collect();
+        let recorded: HashMap<String, payment::Model> = payment::Entity::find()
+            .filter(payment::Column::Processor.eq(processor_name.clone()))
+            .filter(payment::Column::ProcessorPaymentId.is_in(references))
+            .all(db)
+            .await?
+            .into_iter()
+            .filter_map(|p| p.processor_payment_id.clone().map(|reference| (reference, p)))
+            .collect();
+
+        let mut missing_at_provider = Vec::new();
+        let mut missing_locally = Vec::new();
+        let mut amount_mismatches = Vec::new();
+
+        for payment in &payments {
+            let reference = payment.processor_payment_id.clone().unwrap_or_default();
+            let settlement = settled.get(&reference);
+            let entry = ReconciliationEntry {
+                provider_reference: reference,
+                payment_id: Some(payment.id),
+                order_id: Some(payment.order_id),
+                recorded_amount: Some(to_money(&payment.amount)?),
+                settled_amount: settlement.map(|s| to_money(&s.amount)).transpose()?,
+            };
+
+            match settlement {
+                None => missing_at_provider.push(entry),
+                Some(settlement) if settlement.amount != payment.amount => amount_mismatches.push(entry),
+                Some(_) => {}
+            }
+        }
+
+        let completed = [
+            PaymentStatus::Completed.to_string(),
+            PaymentStatus::Refunded.to_string(),
+            PaymentStatus::PartiallyRefunded.to_string(),
+        ];
+        for settlement in &settlements {
+            let payment = recorded.get(&settlement.provider_reference);
+            let entry = ReconciliationEntry {
+                provider_reference: settlement.provider_reference.clone(),
+                payment_id: payment.map(|p| p.id),
+                order_id: payment.map(|p| p.order_id),
+                recorded_amount: payment.map(|p| to_money(&p.amount)).transpose()?,
+                settled_amount: Some(to_money(&settlement.amount)?),
+            };
+
+            match payment {
+                Some(payment) if completed.contains(&payment.status) => {
+                    // Mismatches on payments completed that day were already found above
+                    let counted = payment.completed_at.is_some_and(|at| at >= start && at < end);
+                    if !counted && settlement.amount != payment.amount {
+                        amount_mismatches.push(entry);
+                    }
+                }
+                _ => missing_locally.push(entry),
+            }
+        }
+
+        let status = if missing_at_provider.is_empty() && missing_locally.is_empty() && amount_mismatches.is_empty() {
+            ReconciliationStatus::Matched
+        } else {
+            ReconciliationStatus::Discrepancies
+        };
+
+        let to_json = |entries: &Vec<ReconciliationEntry>| serde_json::to_value(entries).map_err(|e| AppError::internal(e.to_string()));
+        let payments_total: BigDecimal = payments.iter().map(|p| p.amount.clone()).sum();
+        let settlements_total: BigDecimal = settlements.iter().map(|s| s.amount.clone()).sum();
+
+        let txn = db.begin().await?;
+        payment_reconciliation_report::Entity::delete_many()
+            .filter(payment_reconciliation_report::Column::ReportDate.eq(date))
+            .filter(payment_reconciliation_report::Column::Processor.eq(processor_name.clone()))
+            .exec(&txn)
+            .await?;
+        let report = payment_reconciliation_report::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            report_date: Set(date),
+            processor: Set(processor_name),
+            payments_count: Set(payments.len() as i32),
+            payments_total: Set(payments_total),
+            settlements_count: Set(settlements.len() as i32),
+            settlements_total: Set(settlements_total),
+            missing_at_provider: Set(to_json(&missing_at_provider)?),
+            missing_locally: Set(to_json(&missing_locally)?),
+            amount_mismatches: Set(to_json(&amount_mismatches)?),
+            status: Set(status.to_string()),
+            generated_at: Set(Utc::now()),
+        }
+        .insert(&txn)
+        .await?;
+        txn.commit().await?;
+
+        if status == ReconciliationStatus::Discrepancies {
+            tracing::warn!(
+                "{} reconciliation for {} found {} missing at the provider, {} missing locally, {} amount mismatches",
+                processor,
+                date,
+                missing_at_provider.len(),
+                missing_locally.len(),
+                amount_mismatches.len()
+            );
+        }
+
+        reports.push(to_report(report)?);
+    }
+
+    Ok(reports)
+}
+
+// Reports already generated for a day
+pub async fn get_reports(db: &DatabaseConnection, date: NaiveDate) -> Result<Vec<ReconciliationReport>> {
+    payment_reconciliation_report::Entity::find()
+        .filter(payment_reconciliation_report::Column::ReportDate.eq(date))
+        .order_by_asc(payment_reconciliation_report::Column::Processor)
+        .all(db)
+        .await?
+        .into_iter()
+        .map(to_report)
+        .collect()
+}
+
+fn to_report(model: payment_reconciliation_report::Model) -> Result<ReconciliationReport> {
+    let entries = |value: serde_json::Value| -> Result<Vec<ReconciliationEntry>> {
+        serde_json::from_value(value).map_err(|e| AppError::internal(e.to_string()))
+    };
+
+    Ok(ReconciliationReport {
+        id: model.id,
+        report_date: model.report_date,
+        processor: PaymentProcessor::from_str(&model.processor).map_err(AppError::internal)?,
+        payments_count: model.payments_count,
+        payments_total: to_money(&model.payments_total)?,
+        settlements_count: model.settlements_count,
+        settlements_total: to_money(&model.settlements_total)?,
+        missing_at_provider: entries(model.missing_at_provider)?,
+        missing_locally: entries(model.missing_locally)?,
+        amount_mismatches: entries(model.amount_mismatches)?,
+        status: ReconciliationStatus::from_str(&model.status).map_err(AppError::internal)?,
+        generated_at: model.generated_at,
+    })
+}
@@ -0,0 +1,478 @@
+use bigdecimal::BigDecimal;
+use chrono::Utc;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, EntityTrait, IntoActiveModel, QueryFilter,
+    QuerySelect, Set, TransactionTrait,
+};
+use std::str::FromStr;
+use uuid::Uuid;
+
+use crate::entities::{order, payment, payment_callback};
+use crate::errors::{AppError, Result};
+use crate::models::financial::{CallbackOutcome, PaymentProcessor, PaymentStatus};
+use crate::models::money::{Currency, Money};
+use crate::models::order::{OrderStatus, PaymentStatus as OrderPaymentStatus};
+use crate::services::{ledger, refund};
+use crate::services::payment_gateway::{GatewayPaymentStatus, PaymentGateway};
+
+fn to_money(value: &BigDecimal) -> Result<Money> {
+    Ok(Money::from_decimal(value, Currency::XAF)?)
+}
+
+// A callback exactly as it reached us
+pub struct RawCallback {
+    pub processor: PaymentProcessor,
+    // Token from the callback URL, for providers that do not send one in the body
+    pub url_token: Option<String>,
+    pub headers: serde_json::Value,
+    pub body: String,
+}
+
+async fn store<C: ConnectionTrait>(
+    db: &C,
+    callback: &RawCallback,
+    payment_id: Option<Uuid>,
+    outcome: CallbackOutcome,
+    error: Option<String>,
+) -> Result<()> {
+    let record = payment_callback::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        processor: Set(callback.processor.to_string()),
+        payment_id: Set(payment_id),
+        headers: Set(callback.headers.clone()),
+        raw_body: Set(callback.body.clone()),
+        verified: Set(payment_id.is_some()),
+        outcome: Set(outcome.to_string()),
+        error: Set(error),
+        received_at: Set(Utc::now()),
+    };
+    record.insert(db).await?;
+
+    Ok(())
+}
+
+// Keep a record of a callback we refused, then refuse it
+async fn reject(
+    db: &DatabaseConnection,
+    callback: &RawCallback,
+    payment_id: Option<Uuid>,
+    error: AppError,
+) -> Result<CallbackOutcome> {
+    store(db, callback, payment_id, CallbackOutcome::Rejected, Some(error.to_string())).await?;
+    Err(error)
+}
+
+// Apply a provider's payment callback. The callback has to carry the secret we gave the provider
+// for that payment. Callbacks for a payment that already settled change nothing, and every
+// callback is stored as received whatever happens to it.
+pub async fn handle_callback(
+    db: &DatabaseConnection,
+    gateway: &dyn PaymentGateway,
+    callback: RawCallback,
+) -> Result<CallbackOutcome> {
+    let Ok(payload) = serde_json::from_str::<serde_json::Value>(&callback.body) else {
+        return reject(db, &callback, None, AppError::bad_request("Callback body is not valid JSON")).await;
+    };
+
+    let Some(event) = gateway.parse_callback(&payload) else {
+        return reject(db, &callback, None, AppError::bad_request("Unrecognized callback")).await;
+    };
+
+    let Some(token) = callback.url_token.clone().or(event.token.clone()) else {
+        return reject(db, &callback, None, AppError::auth("Missing callback token")).await;
+    };
+
+    let payment = payment::Entity::find()
+        .filter(payment::Column::Processor.eq(callback.processor.to_string()))
+        .filter(payment::Column::CallbackToken.eq(token))
+        .one(db)
+        .await?;
+    let Some(payment) = payment else {
+        return reject(db, &callback, None, AppError::auth("Invalid callback token")).await;
+    };
+
+    if let Some(amount) = event.amount {
+        if amount != to_money(&payment.amount)? {
+            return reject(
+                db,
+                &callback,
+                Some(payment.id),
+                AppError::bad_request("Callback amount does not match the payment"),
+            )
+            .await;
+        }
+    }
+
+    let txn = db.begin().await?;
+
+    // Lock the payment so a callback and a status check cannot both settle it
+    let payment = lock_payment(&txn, &payment).await?;
+    let current = PaymentStatus::from_str(&payment.status).map_err(AppError::internal)?;
+
+    let outcome = if current.is_final() {
+        CallbackOutcome::Duplicate
+    } else {
+        match &event.status {
+            GatewayPaymentStatus::Pending => CallbackOutcome::Ignored,
+            GatewayPaymentStatus::Successful | GatewayPaymentStatus::Failed { .. } => {
+                settle(&txn, payment.clone(), &event.status, event.transaction_id.clone()).await?;
+                CallbackOutcome::Applied
+            }
+        }
+    };
+
+    store(&txn, &callback, Some(payment.id), outcome.clone(), None).await?;
+    txn.commit().await?;
+
+    Ok(outcome)
+}
+
+// Lock a payment for an update that may touch its order. The order is locked first, as on every
+// path that holds both, so a callback and a cancellation never wait on each other.
+pub(crate) async fn lock_payment<C: ConnectionTrait>(db: &C, payment: &payment::Model) -> Result<payment::Model> {
+    order::Entity::find_by_id(payment.order_id)
+        .lock_exclusive()
+        .one(db)
+        .await?
+        .ok_or_else(|| AppError::not_found("Order not found"))?;
+
+    payment::Entity::find_by_id(payment.id)
+        .lock_exclusive()
+        .one(db)
+        .await?
+        .ok_or_else(|| AppError::not_found("Payment not found"))
+}
+
+// Move a pending payment to its final status and bring the order's payment status along
+pub async fn settle<C: ConnectionTrait>(
+    db: &C,
+    payment: payment::Model,
+    status: &GatewayPaymentStatus,
+    transaction_id: Option<String>,
+) -> Result<()> {
+    let order = order::Entity::find_by_id(payment.order_id)
+        .lock_exclusive()
+        .one(db)
+        .await?
+        .ok_or_else(|| AppError::not_found("Order not found"))?;
+    let order_payment_status = OrderPaymentStatus::from_str(&order.payment_status).map_err(AppError::internal)?;
+    let now = Utc::now();
+    let paid = to_money(&payment.amount)?;
+
+    let mut payment = payment.into_active_model();
+    payment.processor_transaction_id = Set(transaction_id);
+    payment.updated_at = Set(now);
+
+    let mut unused = None;
+    let mut overpaid = None;
+    let next_order_status = match status {
+        GatewayPaymentStatus::Successful => {
+            payment.status = Set(PaymentStatus::Completed.to_string());
+            payment.completed_at = Set(Some(now));
+
+            // Money the order cannot use goes back to the buyer instead of being captured
+            if order.status == OrderStatus::Canceled.to_string() {
+                unused = Some("Payment received after the order was canceled");
+                None
+            } else if !matches!(order_payment_status, OrderPaymentStatus::Pending | OrderPaymentStatus::Failed) {
+                unused = Some("Duplicate payment for an order that was already paid");
+                None
+            } else {
+                // Sub-orders canceled while the payment was on its way lowered the order's total
+                let over = paid.checked_sub(to_money(&order.total_amount)?)?;
+                if over.is_positive() {
+                    overpaid = Some(over);
+                }
+                Some(OrderPaymentStatus::Paid)
+            }
+        }
+        GatewayPaymentStatus::Failed { reason } => {
+            payment.status = Set(PaymentStatus::Failed.to_string());
+            payment.failure_reason = Set(Some(reason.clone()));
+
+            // A failed attempt does not undo an earlier successful one
+            (order_payment_status == OrderPaymentStatus::Pending).then_some(OrderPaymentStatus::Failed)
+        }
+        GatewayPaymentStatus::Pending => None,
+    };
+    let payment = payment.update(db).await?;
+
+    if let Some(reason) = unused {
+        refund::refund_unused_payment(db, payment, reason.to_string()).await?;
+    } else if let Some(amount) = overpaid {
+        refund::refund_uncaptured(db, payment, amount, "Sub-orders canceled before the payment arrived".to_string()).await?;
+    }
+
+    if let Some(next) = next_order_status {
+        let order_id = order.id;
+        let mut order = order.into_active_model();
+        order.payment_status = Set(next.to_string());
+        order.updated_at = Set(now);
+        order.update(db).await?;
+
+        if next == OrderPaymentStatus::Paid {
+            ledger::record_payment_captured(db, order_id).await?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entities::refund;
+    use crate::models::financial::RefundStatus;
+    use crate::services::payment_gateway::{FakeGateway, FakeOutcome};
+    use sea_orm::{DatabaseBackend, MockDatabase, Transaction};
+
+    fn payment(order_id: Uuid, status: PaymentStatus) -> payment::Model {
+        let now = Utc::now();
+        payment::Model {
+            id: Uuid::new_v4(),
+            order_id,
+            user_id: Uuid::new_v4(),
+            amount: BigDecimal::from(5000),
+            refunded_amount: BigDecimal::from(0),
+            currency: "XAF".to_string(),
+            processor: PaymentProcessor::MtnMobileMoney.to_string(),
+            processor_payment_id: Some("FAKE-1".to_string()),
+            processor_transaction_id: None,
+            status: status.to_string(),
+            payment_method: "mtn_mobile_money".to_string(),
+            metadata: None,
+            callback_token: Some("secret".to_string()),
+            failure_reason: None,
+            created_at: now,
+            updated_at: now,
+            completed_at: None,
+        }
+    }
+
+    fn order(id: Uuid, status: OrderStatus, payment_status: OrderPaymentStatus) -> order::Model {
+        let now = Utc::now();
+        order::Model {
+            id,
+            user_id: Uuid::new_v4(),
+            subtotal: BigDecimal::from(5000),
+            discount_amount: BigDecimal::from(0),
+            shipping_amount: BigDecimal::from(0),
+            tax_amount: BigDecimal::from(0),
+            total_amount: BigDecimal::from(5000),
+            discount_code_id: None,
+            status: status.to_string(),
+            payment_status: payment_status.to_string(),
+            payment_method: "mtn_mobile_money".to_string(),
+            shipping_address: serde_json::json!({}),
+            idempotency_key: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    fn stored(payment_id: Uuid, outcome: CallbackOutcome) -> payment_callback::Model {
+        payment_callback::Model {
+            id: Uuid::new_v4(),
+            processor: PaymentProcessor::MtnMobileMoney.to_string(),
+            payment_id: Some(payment_id),
+            headers: serde_json::json!({}),
+            raw_body: String::new(),
+            verified: true,
+            outcome: outcome.to_string(),
+            error: None,
+            received_at: Utc::now(),
+        }
+    }
+
+    fn unused_refund(payment: &payment::Model) -> refund::Model {
+        let now = Utc::now();
+        refund::Model {
+            id: Uuid::new_v4(),
+            payment_id: payment.id,
+            order_id: payment.order_id,
+            amount: payment.amount.clone(),
+            currency: "XAF".to_string(),
+            reason: String::new(),
+            status: RefundStatus::Pending.to_string(),
+            provider_reference: None,
+            failure_reason: None,
+            requested_by: None,
+            completed_at: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    fn callback(body: serde_json::Value) -> RawCallback {
+        RawCallback {
+            processor: PaymentProcessor::MtnMobileMoney,
+            url_token: None,
+            headers: serde_json::json!({}),
+            body: body.to_string(),
+        }
+    }
+
+    fn statements(log: Vec<Transaction>) -> Vec<String> {
+        log.iter()
+            .flat_map(|txn| txn.statements().iter().map(|stmt| stmt.sql.clone()).collect::<Vec<_>>())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn repeated_callbacks_change_nothing() {
+        let settled = payment(Uuid::new_v4(), PaymentStatus::Completed);
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([[settled.clone()]])
+            .append_query_results([[order(settled.order_id, OrderStatus::Processing, OrderPaymentStatus::Paid)]])
+            .append_query_results([[settled.clone()]])
+            .append_query_results([[stored(settled.id, CallbackOutcome::Duplicate)]])
+            .into_connection();
+        let gateway = FakeGateway::new(FakeOutcome::Pending);
+
+        let outcome = handle_callback(
+            &db,
+            &gateway,
+            callback(serde_json::json!({"status": "successful", "token": "secret", "amount": 5000})),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(outcome, CallbackOutcome::Duplicate);
+        let sql = statements(db.into_transaction_log());
+        // The order is locked before its payment, as when an order is canceled
+        let locks: Vec<_> = sql.iter().filter(|s| s.ends_with("FOR UPDATE")).collect();
+        assert!(locks[0].contains("FROM \"orders\""));
+        assert!(locks[1].contains("FROM \"payments\""));
+        assert!(sql.iter().any(|s| s.contains("INSERT INTO \"payment_callbacks\"")));
+        assert!(!sql.iter().any(|s| s.starts_with("UPDATE")));
+    }
+
+    #[tokio::test]
+    async fn callbacks_for_another_amount_are_rejected() {
+        let pending = payment(Uuid::new_v4(), PaymentStatus::Pending);
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([[pending.clone()]])
+            .append_query_results([[stored(pending.id, CallbackOutcome::Rejected)]])
+            .into_connection();
+        let gateway = FakeGateway::new(FakeOutcome::Pending);
+
+        let result = handle_callback(
+            &db,
+            &gateway,
+            callback(serde_json::json!({"status": "successful", "token": "secret", "amount": 50})),
+        )
+        .await;
+
+        assert!(result.is_err());
+        let sql = statements(db.into_transaction_log());
+        assert!(!sql.iter().any(|s| s.starts_with("UPDATE")));
+    }
+
+    // A payment the order cannot use is refunded in full and never reaches the sellers' ledger
+    async fn assert_refunded_unused(order_status: OrderStatus, order_payment_status: OrderPaymentStatus) {
+        let pending = payment(Uuid::new_v4(), PaymentStatus::Pending);
+        let completed = payment::Model {
+            status: PaymentStatus::Completed.to_string(),
+            ..pending.clone()
+        };
+        let refunded = payment::Model {
+            status: PaymentStatus::Refunded.to_string(),
+            refunded_amount: pending.amount.clone(),
+            ..pending.clone()
+        };
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([[pending.clone()]])
+            .append_query_results([[order(pending.order_id, order_status.clone(), order_payment_status.clone())]])
+            .append_query_results([[pending.clone()]])
+            .append_query_results([[order(pending.order_id, order_status, order_payment_status)]])
+            .append_query_results([[completed]])
+            .append_query_results([[unused_refund(&pending)]])
+            .append_query_results([[refunded]])
+            .append_query_results([[stored(pending.id, CallbackOutcome::Applied)]])
+            .into_connection();
+        let gateway = FakeGateway::new(FakeOutcome::Pending);
+
+        let outcome = handle_callback(
+            &db,
+            &gateway,
+            callback(serde_json::json!({"status": "successful", "token": "secret", "transaction_id": "TX-2"})),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(outcome, CallbackOutcome::Applied);
+        let sql = statements(db.into_transaction_log());
+        assert!(sql.iter().any(|s| s.contains("INSERT INTO \"refunds\"")));
+        assert!(!sql.iter().any(|s| s.contains("UPDATE \"orders\"")));
+        assert!(!sql.iter().any(|s| s.contains("ledger")));
+    }
+
+    #[tokio::test]
+    async fn second_payment_on_a_paid_order_is_refunded() {
+        assert_refunded_unused(OrderStatus::Processing, OrderPaymentStatus::Paid).await;
+    }
+
+    #[tokio::test]
+    async fn payment_on_a_canceled_order_is_refunded() {
+        assert_refunded_unused(OrderStatus::Canceled, OrderPaymentStatus::Pending).await;
+    }
+
+    #[tokio::test]
+    async fn share_of_sub_orders_canceled_before_payment_is_refunded() {
+        let pending = payment(Uuid::new_v4(), PaymentStatus::Pending);
+        let lowered = order::Model {
+            total_amount: BigDecimal::from(3000),
+            ..order(pending.order_id, OrderStatus::Processing, OrderPaymentStatus::Pending)
+        };
+        let completed = payment::Model {
+            status: PaymentStatus::Completed.to_string(),
+            ..pending.clone()
+        };
+        let partially_refunded = payment::Model {
+            status: PaymentStatus::PartiallyRefunded.to_string(),
+            refunded_amount: BigDecimal::from(2000),
+            ..pending.clone()
+        };
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([[pending.clone()]])
+            .append_query_results([[lowered.clone()]])
+            .append_query_results([[pending.clone()]])
+            .append_query_results([[lowered.clone()]])
+            .append_query_results([[completed]])
+            .append_query_results([[unused_refund(&pending)]])
+            .append_query_results([[partially_refunded]])
+            .append_query_results([[lowered]])
+            .append_query_results([Vec::<crate::entities::seller_order::Model>::new()])
+            .append_query_results([[stored(pending.id, CallbackOutcome::Applied)]])
+            .into_connection();
+        let gateway = FakeGateway::new(FakeOutcome::Pending);
+
+        let outcome = handle_callback(
+            &db,
+            &gateway,
+            callback(serde_json::json!({"status": "successful", "token": "secret", "transaction_id": "TX-3"})),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(outcome, CallbackOutcome::Applied);
+        let log = db.into_transaction_log();
+        let refund = log
+            .iter()
+            .flat_map(|txn| txn.statements().to_vec())
+            .find(|stmt| stmt.sql.starts_with("INSERT INTO \"refunds\""))
+            .unwrap();
+        let amounts: Vec<_> = refund
+            .values
+            .unwrap()
+            .0
+            .into_iter()
+            .filter_map(|value| match value {
+                sea_orm::Value::BigDecimal(Some(amount)) => Some(*amount),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(amounts, vec![BigDecimal::from(2000)]);
+        assert!(statements(log).iter().any(|s| s.contains("UPDATE \"orders\"")));
+    }
+}
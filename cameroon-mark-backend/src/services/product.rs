@@ -9,6 +9,7 @@ use crate::{
     errors::{AppError, Result},
     models::product::{Product, ProductResponse, CreateProductRequest, UpdateProductRequest, ProductFilterOptions, ImageArray},
     entities::product,
+    services::saved_item,
 };
 
 // Get products with filtering, sorting, and pagination
@@ -66,6 +67,7 @@ pub async fn create_product(db: &DatabaseConnection, seller_id: Uuid, payload: C
         stock: Set(payload.stock),
         images: Set(payload.images),
         location: Set(payload.location.unwrap_or_else(|| "Unknown".to_string())),
+        weight_grams: Set(payload.weight_grams.unwrap_or(1000)),
         featured: Set(payload.featured.unwrap_or(false)),
         ..Default::default()
     };
@@ -76,12 +78,17 @@ pub async fn create_product(db: &DatabaseConnection, seller_id: Uuid, payload: C
 
 // Update a product
 pub async fn update_product(db: &DatabaseConnection, product_id: Uuid, seller_id: Uuid, payload: UpdateProductRequest) -> Result<Product> {
+    let txn = db.begin().await?;
+
+    // Lock the product so the price and stock the watchers are alerted on are the ones replaced
     let product = product::Entity::find_by_id(product_id)
         .filter(product::Column::SellerId.eq(seller_id))
-        .one(db)
+        .lock_exclusive()
+        .one(&txn)
         .await?
         .ok_or_else(|| AppError::not_found("Product not found"))?;
 
+    let before = product.clone();
     let mut product: product::ActiveModel = product.into();
 
     if let Some(title) = payload.title {
@@ -105,11 +112,23 @@ pub async fn update_product(db: &DatabaseConnection, product_id: Uuid, seller_id
     if let Some(location) = payload.location {
         product.location = Set(location);
     }
+    if let Some(weight_grams) = payload.weight_grams {
+        product.weight_grams = Set(weight_grams);
+    }
     if let Some(featured) = payload.featured {
         product.featured = Set(featured);
     }
+    if let Some(status) = payload.status {
+        product.status = Set(status.to_string());
+    }
+
+    let product = product.update(&txn).await?;
+
+    // Price drops and restocks alert buyers who saved the product
+    saved_item::notify_product_watchers(&txn, &before, &product).await?;
+
+    txn.commit().await?;
 
-    let product = product.update(db).await?;
     Ok(Product::from(product))
 }
 
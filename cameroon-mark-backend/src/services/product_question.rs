@@ -0,0 +1,285 @@
+use chrono::Utc;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, PaginatorTrait, QueryFilter,
+    QueryOrder, Set, TransactionTrait,
+};
+use sea_orm::sea_query::{Expr, OnConflict};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::{
+    entities::{product, product_answer, product_answer_vote, product_question, user},
+    errors::{AppError, Result},
+    models::notification::NotificationKind,
+    models::product_question::{
+        AnswerQuestionRequest, AskQuestionRequest, ProductAnswer, ProductQuestion, QuestionListOptions,
+    },
+    services::notification,
+};
+
+// Look up display names for a set of users
+async fn get_user_names(db: &DatabaseConnection, user_ids: Vec<Uuid>) -> Result<HashMap<Uuid, String>> {
+    if user_ids.is_empty() {
+        return Ok(HashMap::new());
+    }
+
+    let users = user::Entity::find()
+        .filter(user::Column::Id.is_in(user_ids))
+        .all(db)
+        .await?;
+
+    Ok(users.into_iter().map(|u| (u.id, u.name)).collect())
+}
+
+fn to_answer(model: product_answer::Model, names: &HashMap<Uuid, String>) -> ProductAnswer {
+    ProductAnswer {
+        id: model.id,
+        question_id: model.question_id,
+        user_id: model.user_id,
+        user_name: names.get(&model.user_id).cloned().unwrap_or_default(),
+        answer: model.answer,
+        is_seller: model.is_seller,
+        upvotes: model.upvotes,
+        created_at: model.created_at,
+    }
+}
+
+// Get the questions asked on a product with their answers
+pub async fn get_product_questions(
+    db: &DatabaseConnection,
+    product_id: Uuid,
+    options: QuestionListOptions,
+) -> Result<(Vec<ProductQuestion>, u64)> {
+    let page = options.page.unwrap_or(1).max(1);
+    let per_page = options.per_page.unwrap_or(10).clamp(1, 50);
+
+    let paginator = product_question::Entity::find()
+        .filter(product_question::Column::ProductId.eq(product_id))
+        .order_by_desc(product_question::Column::CreatedAt)
+        .paginate(db, per_page);
+
+    let total = paginator.num_items().await?;
+    let questions = paginator.fetch_page(page - 1).await?;
+
+    let question_ids: Vec<Uuid> = questions.iter().map(|q| q.id).collect();
+    let answers = if question_ids.is_empty() {
+        Vec::new()
+    } else {
+        // Seller answers first, then the most helpful ones
+        product_answer::Entity::find()
+            .filter(product_answer::Column::QuestionId.is_in(question_ids))
+            .order_by_desc(product_answer::Column::IsSeller)
+            .order_by_desc(product_answer::Column::Upvotes)
+            .order_by_asc(product_answer::Column::CreatedAt)
+            .all(db)
+            .await?
+    };
+
+    let mut user_ids: Vec<Uuid> = questions.iter().map(|q| q.user_id).collect();
+    user_ids.extend(answers.iter().map(|a| a.user_id));
+    user_ids.sort();
+    user_ids.dedup();
+    let names = get_user_names(db, user_ids).await?;
+
+    let mut answers_by_question: HashMap<Uuid, Vec<ProductAnswer>> = HashMap::new();
+    for answer in answers {
+        answers_by_question
+            .entry(answer.question_id)
+            .or_default()
+            .push(to_answer(answer, &names));
+    }
+
+    let result = questions
+        .into_iter()
+        .map(|q| ProductQuestion {
+            id: q.id,
+            product_id: q.product_id,
+            user_id: q.user_id,
+            user_name: names.get(&q.user_id).cloned().unwrap_or_default(),
+            question: q.question,
+            answers: answers_by_question.remove(&q.id).unwrap_or_default(),
+            created_at: q.created_at,
+        })
+        .collect();
+
+    Ok((result, total))
+}
+
+// Ask a public question about a product
+pub async fn ask_question(
+    db: &DatabaseConnection,
+    user_id: Uuid,
+    product_id: Uuid,
+    payload: AskQuestionRequest,
+) -> Result<ProductQuestion> {
+    let product = product::Entity::find_by_id(product_id)
+        .one(db)
+        .await?
+        .ok_or_else(|| AppError::not_found("Product not found"))?;
+
+    if product.seller_id == user_id {
+        return Err(AppError::bad_request("Cannot ask a question on your own product"));
+    }
+
+    let now = Utc::now();
+    let question = product_question::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        product_id: Set(product_id),
+        user_id: Set(user_id),
+        question: Set(payload.question.trim().to_string()),
+        created_at: Set(now),
+        updated_at: Set(now),
+    };
+
+    let question = question.insert(db).await?;
+
+    // Let the seller know a buyer is waiting for an answer
+    notification::notify(
+        db,
+        product.seller_id,
+        NotificationKind::ProductQuestion,
+        format!("New question on {}", product.title),
+        question.question.clone(),
+        Some(format!("/products/{}#question-{}", product.id, question.id)),
+    )
+    .await?;
+
+    let names = get_user_names(db, vec![user_id]).await?;
+
+    Ok(ProductQuestion {
+        id: question.id,
+        product_id: question.product_id,
+        user_id: question.user_id,
+        user_name: names.get(&user_id).cloned().unwrap_or_default(),
+        question: question.question,
+        answers: Vec::new(),
+        created_at: question.created_at,
+    })
+}
+
+// Answer a question (seller or any other user)
+pub async fn answer_question(
+    db: &DatabaseConnection,
+    user_id: Uuid,
+    question_id: Uuid,
+    payload: AnswerQuestionRequest,
+) -> Result<ProductAnswer> {
+    let (question, product) = product_question::Entity::find_by_id(question_id)
+        .find_also_related(product::Entity)
+        .one(db)
+        .await?
+        .ok_or_else(|| AppError::not_found("Question not found"))?;
+
+    let product = product.ok_or_else(|| AppError::not_found("Product not found"))?;
+
+    let now = Utc::now();
+    let answer = product_answer::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        question_id: Set(question.id),
+        user_id: Set(user_id),
+        answer: Set(payload.answer.trim().to_string()),
+        is_seller: Set(product.seller_id == user_id),
+        upvotes: Set(0),
+        created_at: Set(now),
+        updated_at: Set(now),
+    };
+
+    let answer = answer.insert(db).await?;
+
+    if question.user_id != user_id {
+        notification::notify(
+            db,
+            question.user_id,
+            NotificationKind::QuestionAnswered,
+            format!("Your question on {} was answered", product.title),
+            answer.answer.clone(),
+            Some(format!("/products/{}#question-{}", product.id, question.id)),
+        )
+        .await?;
+    }
+
+    let names = get_user_names(db, vec![user_id]).await?;
+
+    Ok(to_answer(answer, &names))
+}
+
+// Upvote a helpful answer
+pub async fn upvote_answer(db: &DatabaseConnection, user_id: Uuid, answer_id: Uuid) -> Result<()> {
+    let answer = product_answer::Entity::find_by_id(answer_id)
+        .one(db)
+        .await?
+        .ok_or_else(|| AppError::not_found("Answer not found"))?;
+
+    if answer.user_id == user_id {
+        return Err(AppError::bad_request("Cannot upvote your own answer"));
+    }
+
+    let txn = db.begin().await?;
+
+    // The primary key keeps one vote per user, even when two upvotes race
+    let vote = product_answer_vote::ActiveModel {
+        answer_id: Set(answer_id),
+        user_id: Set(user_id),
+        created_at: Set(Utc::now()),
+    };
+    let inserted = product_answer_vote::Entity::insert(vote)
+        .on_conflict(
+            OnConflict::columns([product_answer_vote::Column::AnswerId, product_answer_vote::Column::UserId])
+                .do_nothing()
+                .to_owned(),
+        )
+        .exec_without_returning(&txn)
+        .await?;
+
+    if inserted == 0 {
+        return Err(AppError::bad_request("Answer already upvoted"));
+    }
+
+    product_answer::Entity::update_many()
+        .col_expr(product_answer::Column::Upvotes, Expr::col(product_answer::Column::Upvotes).add(1))
+        .filter(product_answer::Column::Id.eq(answer_id))
+        .exec(&txn)
+        .await?;
+
+    txn.commit().await?;
+
+    Ok(())
+}
+
+// Remove an upvote
+pub async fn remove_upvote(db: &DatabaseConnection, user_id: Uuid, answer_id: Uuid) -> Result<()> {
+    let txn = db.begin().await?;
+
+    let result = product_answer_vote::Entity::delete_by_id((answer_id, user_id))
+        .exec(&txn)
+        .await?;
+
+    if result.rows_affected == 0 {
+        return Err(AppError::not_found("Upvote not found"));
+    }
+
+    product_answer::Entity::update_many()
+        .col_expr(product_answer::Column::Upvotes, Expr::col(product_answer::Column::Upvotes).sub(1))
+        .filter(product_answer::Column::Id.eq(answer_id))
+        .exec(&txn)
+        .await?;
+
+    txn.commit().await?;
+
+    Ok(())
+}
+
+// Delete a question (only its author can)
+pub async fn delete_question(db: &DatabaseConnection, user_id: Uuid, question_id: Uuid) -> Result<()> {
+    let result = product_question::Entity::delete_many()
+        .filter(product_question::Column::Id.eq(question_id))
+        .filter(product_question::Column::UserId.eq(user_id))
+        .exec(db)
+        .await?;
+
+    if result.rows_affected == 0 {
+        return Err(AppError::not_found("Question not found"));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,143 @@
+use chrono::{Duration, Utc};
+use sea_orm::{
+    ColumnTrait, ConnectionTrait, DatabaseConnection, EntityTrait, QueryFilter,
+    Set, Statement, TransactionTrait,
+};
+use sea_orm::sea_query::{Expr, OnConflict};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::{
+    entities::{product, product_view},
+    errors::Result,
+    models::product::Product,
+};
+
+// Repeat views by the same viewer inside one of these windows are not counted again
+const VIEW_DEDUPE_WINDOW_MINUTES: i64 = 30;
+
+// Record a product view for a signed-in user or an anonymous session. The view is counted
+// once per viewer and window: the unique dedupe key lets concurrent requests race safely.
+pub async fn record_view(
+    db: &DatabaseConnection,
+    product_id: Uuid,
+    user_id: Option<Uuid>,
+    session_id: Option<String>,
+) -> Result<()> {
+    let viewer = match (user_id, &session_id) {
+        (Some(user_id), _) => format!("u:{}", user_id),
+        (None, Some(session_id)) => format!("s:{}", session_id),
+        // Nothing to deduplicate on, so the view is not counted
+        (None, None) => return Ok(()),
+    };
+
+    let now = Utc::now();
+    let window = now.timestamp().div_euclid(VIEW_DEDUPE_WINDOW_MINUTES * 60);
+
+    let txn = db.begin().await?;
+
+    let view = product_view::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        product_id: Set(product_id),
+        user_id: Set(user_id),
+        session_id: Set(session_id),
+        dedupe_key: Set(format!("{}:{}:{}", product_id, viewer, window)),
+        viewed_at: Set(now),
+    };
+    let inserted = product_view::Entity::insert(view)
+        .on_conflict(OnConflict::column(product_view::Column::DedupeKey).do_nothing().to_owned())
+        .exec_without_returning(&txn)
+        .await?;
+
+    if inserted > 0 {
+        product::Entity::update_many()
+            .col_expr(product::Column::ViewCount, Expr::col(product::Column::ViewCount).add(1))
+            .filter(product::Column::Id.eq(product_id))
+            .exec(&txn)
+            .await?;
+    }
+
+    txn.commit().await?;
+
+    Ok(())
+}
+
+// Get the products a user looked at most recently
+pub async fn get_recently_viewed(db: &DatabaseConnection, user_id: Uuid, limit: u64) -> Result<Vec<Product>> {
+    let rows = db
+        .query_all(Statement::from_sql_and_values(
+            sea_orm::DatabaseBackend::Postgres,
+            r#"
+            SELECT product_id, MAX(viewed_at) AS last_viewed_at
+            FROM product_views
+            WHERE user_id = $1
+            GROUP BY product_id
+            ORDER BY last_viewed_at DESC
+            LIMIT $2
+            "#,
+            vec![user_id.into(), (limit as i64).into()],
+        ))
+        .await?;
+
+    let product_ids = rows
+        .iter()
+        .map(|row| row.try_get::<Uuid>("", "product_id"))
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+
+    let mut products: HashMap<Uuid, product::Model> = product::Entity::find()
+        .filter(product::Column::Id.is_in(product_ids.clone()))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|p| (p.id, p))
+        .collect();
+
+    // Keep the most recent first
+    Ok(product_ids
+        .into_iter()
+        .filter_map(|id| products.remove(&id))
+        .map(Product::from)
+        .collect())
+}
+
+// Get the products with the most views over the last few days
+pub async fn get_trending_products<C: ConnectionTrait>(
+    db: &C,
+    days: i64,
+    limit: u64,
+) -> Result<Vec<(product::Model, i64)>> {
+    let rows = db
+        .query_all(Statement::from_sql_and_values(
+            sea_orm::DatabaseBackend::Postgres,
+            r#"
+            SELECT product_id, COUNT(*) AS recent_views
+            FROM product_views
+            WHERE viewed_at > $1
+            GROUP BY product_id
+            ORDER BY recent_views DESC
+            LIMIT $2
+            "#,
+            vec![(Utc::now() - Duration::days(days)).into(), (limit as i64).into()],
+        ))
+        .await?;
+
+    let mut counts = Vec::with_capacity(rows.len());
+    for row in rows {
+        let product_id: Uuid = row.try_get("", "product_id")?;
+        let recent_views: i64 = row.try_get("", "recent_views")?;
+        counts.push((product_id, recent_views));
+    }
+
+    let mut products: HashMap<Uuid, product::Model> = product::Entity::find()
+        .filter(product::Column::Id.is_in(counts.iter().map(|(id, _)| *id)))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|p| (p.id, p))
+        .collect();
+
+    Ok(counts
+        .into_iter()
+        .filter_map(|(id, views)| products.remove(&id).map(|p| (p, views)))
+        .collect())
+}
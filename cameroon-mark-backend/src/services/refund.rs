@@ -0,0 +1,821 @@
+use bigdecimal::BigDecimal;
+use chrono::Utc;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, EntityTrait, IntoActiveModel, PaginatorTrait,
+    QueryFilter, QueryOrder, QuerySelect, RelationTrait, Set, TransactionTrait,
+};
+use sea_orm::sea_query::{Expr, JoinType};
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+use uuid::Uuid;
+
+use crate::entities::{order, order_item, payment, refund, refund_item, seller_order};
+use crate::errors::{AppError, Result};
+use crate::models::financial::{
+    MarkRefundPaidRequest, PaymentProcessor, PaymentStatus, Refund, RefundItem, RefundQuery, RefundRequest,
+    RefundStatus,
+};
+use crate::models::money::{Currency, Money};
+use crate::models::notification::NotificationKind;
+use crate::models::order::{PaymentMethod, PaymentStatus as OrderPaymentStatus};
+use crate::services::ledger;
+use crate::services::notification;
+use crate::services::payment_webhook;
+use crate::services::payment_gateway::{GatewayPaymentStatus, PaymentGateway, PaymentGateways, PaymentLookup, RefundInstruction};
+use crate::utils::validation;
+
+fn to_money(value: &BigDecimal) -> Result<Money> {
+    Ok(Money::from_decimal(value, Currency::XAF)?)
+}
+
+fn to_refund(model: refund::Model, items: Vec<refund_item::Model>) -> Result<Refund> {
+    Ok(Refund {
+        id: model.id,
+        payment_id: model.payment_id,
+        order_id: model.order_id,
+        amount: to_money(&model.amount)?,
+        reason: model.reason,
+        status: RefundStatus::from_str(&model.status).map_err(AppError::internal)?,
+        provider_reference: model.provider_reference,
+        failure_reason: model.failure_reason,
+        items: items
+            .into_iter()
+            .map(|item| {
+                Ok(RefundItem {
+                    order_item_id: item.order_item_id,
+                    seller_order_id: item.seller_order_id,
+                    amount: to_money(&item.amount)?,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?,
+        completed_at: model.completed_at,
+        created_at: model.created_at,
+    })
+}
+
+async fn load_refund<C: ConnectionTrait>(db: &C, model: refund::Model) -> Result<Refund> {
+    let items = refund_item::Entity::find()
+        .filter(refund_item::Column::RefundId.eq(model.id))
+        .all(db)
+        .await?;
+    to_refund(model, items)
+}
+
+async fn find_locked<C: ConnectionTrait>(db: &C, refund_id: Uuid) -> Result<refund::Model> {
+    refund::Entity::find_by_id(refund_id)
+        .lock_exclusive()
+        .one(db)
+        .await?
+        .ok_or_else(|| AppError::not_found("Refund not found"))
+}
+
+// The gateway a payment was collected through, if it was collected online
+fn gateway_for(gateways: &PaymentGateways, payment: &payment::Model) -> Result<Option<std::sync::Arc<dyn PaymentGateway>>> {
+    let method = match PaymentProcessor::from_str(&payment.processor).map_err(AppError::internal)? {
+        PaymentProcessor::MtnMobileMoney => PaymentMethod::Mtn,
+        PaymentProcessor::OrangeMoney => PaymentMethod::Orange,
+        _ => return Ok(None),
+    };
+    Ok(Some(gateways.for_method(&method)?))
+}
+
+// An order item and how much of it can still be refunded. Each sub-order's total, shipping and
+// discounts included, is shared between its items by price, so the items of an order add up to
+// what was paid for it.
+struct Refundable {
+    item: order_item::Model,
+    remaining: Money,
+}
+
+async fn refundable_items<C: ConnectionTrait>(db: &C, order_id: Uuid) -> Result<Vec<Refundable>> {
+    let zero = Money::zero(Currency::XAF);
+    let seller_orders = seller_order::Entity::find()
+        .filter(seller_order::Column::OrderId.eq(order_id))
+        .all(db)
+        .await?;
+    let items = order_item::Entity::find()
+        .filter(order_item::Column::OrderId.eq(order_id))
+        .order_by_asc(order_item::Column::CreatedAt)
+        .all(db)
+        .await?;
+
+    let mut refunded: HashMap<Uuid, Money> = HashMap::new();
+    let item_ids: Vec<Uuid> = items.iter().map(|item| item.id).collect();
+    for line in refund_item::Entity::find()
+        .filter(refund_item::Column::OrderItemId.is_in(item_ids))
+        .all(db)
+        .await?
+    {
+        let total = refunded.entry(line.order_item_id).or_insert(zero);
+        *total = total.checked_add(to_money(&line.amount)?)?;
+    }
+
+    let mut refundable = Vec::with_capacity(items.len());
+    for seller_order in &seller_orders {
+        let items: Vec<&order_item::Model> = items.iter().filter(|item| item.seller_order_id == seller_order.id).collect();
+        let prices = items
+            .iter()
+            .map(|item| to_money(&item.unit_price)?.checked_mul(item.quantity as i64).map_err(Into::into))
+            .collect::<Result<Vec<_>>>()?;
+        let caps = to_money(&seller_order.total_amount)?
+            .allocate(&prices)
+            .unwrap_or_else(|_| vec![zero; prices.len()]);
+
+        for (item, cap) in items.into_iter().zip(caps) {
+            let already = refunded.get(&item.id).copied().unwrap_or(zero);
+            refundable.push(Refundable {
+                item: item.clone(),
+                remaining: cap.checked_sub(already)?.max(zero)?,
+            });
+        }
+    }
+
+    Ok(refundable)
+}
+
+// Record a refund against a locked payment: what is taken from each item, the sellers' share in
+// the ledger and the payment's refunded total, which can never pass what was paid.
+async fn record_refund<C: ConnectionTrait>(
+    db: &C,
+    payment: payment::Model,
+    lines: Vec<(order_item::Model, Money)>,
+    reason: String,
+    requested_by: Option<Uuid>,
+) -> Result<refund::Model> {
+    let amount = Money::sum(lines.iter().map(|(_, amount)| *amount), Currency::XAF)?;
+    let paid = to_money(&payment.amount)?;
+    let refunded = to_money(&payment.refunded_amount)?.checked_add(amount)?;
+    if !amount.is_positive() {
+        return Err(AppError::bad_request("Refund amount must be positive"));
+    }
+    if refunded.checked_sub(paid)?.is_positive() {
+        return Err(AppError::bad_request(format!(
+            "Refunds cannot exceed the {} left on the payment",
+            paid.checked_sub(to_money(&payment.refunded_amount)?)?
+        )));
+    }
+
+    let now = Utc::now();
+    let created = refund::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        payment_id: Set(payment.id),
+        order_id: Set(payment.order_id),
+        amount: Set(amount.to_decimal()),
+        currency: Set(amount.currency().to_string()),
+        reason: Set(reason),
+        status: Set(RefundStatus::Pending.to_string()),
+        provider_reference: Set(None),
+        failure_reason: Set(None),
+        requested_by: Set(requested_by),
+        completed_at: Set(None),
+        created_at: Set(now),
+        updated_at: Set(now),
+    }
+    .insert(db)
+    .await?;
+
+    let mut by_seller_order: HashMap<Uuid, Money> = HashMap::new();
+    for (item, amount) in &lines {
+        refund_item::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            refund_id: Set(created.id),
+            order_item_id: Set(item.id),
+            seller_order_id: Set(item.seller_order_id),
+            amount: Set(amount.to_decimal()),
+        }
+        .insert(db)
+        .await?;
+
+        let total = by_seller_order.entry(item.seller_order_id).or_insert(Money::zero(Currency::XAF));
+        *total = total.checked_add(*amount)?;
+    }
+
+    // Take each seller's part back
+    let seller_orders = seller_order::Entity::find()
+        .filter(seller_order::Column::Id.is_in(by_seller_order.keys().copied().collect::<Vec<_>>()))
+        .order_by_asc(seller_order::Column::Id)
+        .all(db)
+        .await?;
+    for seller_order in &seller_orders {
+        ledger::record_refund(db, created.id, seller_order, by_seller_order[&seller_order.id]).await?;
+    }
+
+    let full = refunded == paid;
+    let (status, order_payment_status) = if full {
+        (PaymentStatus::Refunded, OrderPaymentStatus::Refunded)
+    } else {
+        (PaymentStatus::PartiallyRefunded, OrderPaymentStatus::PartiallyRefunded)
+    };
+
+    let order_id = payment.order_id;
+    let mut payment = payment.into_active_model();
+    payment.refunded_amount = Set(refunded.to_decimal());
+    payment.status = Set(status.to_string());
+    payment.updated_at = Set(now);
+    payment.update(db).await?;
+
+    // The order's lifecycle status is left alone: a refund says nothing about delivery
+    order::Entity::update_many()
+        .col_expr(order::Column::PaymentStatus, Expr::value(order_payment_status.to_string()))
+        .col_expr(order::Column::UpdatedAt, Expr::value(now))
+        .filter(order::Column::Id.eq(order_id))
+        .exec(db)
+        .await?;
+
+    Ok(created)
+}
+
+// Refund a completed payment (admin only), in full or in part, optionally naming the items it is
+// for. The refund is sent through the provider when it supports refunds, otherwise it waits to be
+// paid by hand and marked paid.
+pub async fn process_refund(
+    db: &DatabaseConnection,
+    gateways: &PaymentGateways,
+    admin_id: Uuid,
+    payload: RefundRequest,
+) -> Result<Refund> {
+    validation::validate(&payload)?;
+
+    let payment = payment::Entity::find_by_id(payload.payment_id)
+        .one(db)
+        .await?
+        .ok_or_else(|| AppError::not_found("Payment not found"))?;
+
+    let txn = db.begin().await?;
+
+    // Lock the payment so two refunds cannot both pass the checks
+    let payment = payment_webhook::lock_payment(&txn, &payment).await?;
+
+    let status = PaymentStatus::from_str(&payment.status).map_err(AppError::internal)?;
+    if !matches!(status, PaymentStatus::Completed | PaymentStatus::PartiallyRefunded) {
+        return Err(AppError::bad_request("Only completed payments can be refunded"));
+    }
+
+    let refundable = refundable_items(&txn, payment.order_id).await?;
+    let lines = match payload.items {
+        Some(items) => {
+            if items.is_empty() {
+                return Err(AppError::validation("items: Name at least one item, or leave items out"));
+            }
+
+            let mut seen = HashSet::new();
+            let mut lines = Vec::with_capacity(items.len());
+            for requested in items {
+                let line = refundable
+                    .iter()
+                    .find(|line| line.item.id == requested.order_item_id)
+                    .ok_or_else(|| AppError::not_found(format!("Item {} is not part of this order", requested.order_item_id)))?;
+                if !seen.insert(line.item.id) {
+                    return Err(AppError::validation("items: Each item can only be named once"));
+                }
+
+                let amount = requested.amount.unwrap_or(line.remaining);
+                if !amount.is_positive() {
+                    return Err(AppError::bad_request(format!("Nothing is left to refund on {}", line.item.product_title)));
+                }
+                if amount.checked_sub(line.remaining)?.is_positive() {
+                    return Err(AppError::bad_request(format!(
+                        "Only {} is left to refund on {}",
+                        line.remaining, line.item.product_title
+                    )));
+                }
+                lines.push((line.item.clone(), amount));
+            }
+
+            if let Some(amount) = payload.amount {
+                if amount != Money::sum(lines.iter().map(|(_, amount)| *amount), Currency::XAF)? {
+                    return Err(AppError::validation("amount: Must match the sum of the items' refunds"));
+                }
+            }
+            lines
+        }
+        None => {
+            let left = to_money(&payment.amount)?.checked_sub(to_money(&payment.refunded_amount)?)?;
+            let amount = payload.amount.unwrap_or(left);
+            if !amount.is_positive() {
+                return Err(AppError::bad_request("Refund amount must be positive"));
+            }
+
+            let open: Vec<&Refundable> = refundable.iter().filter(|line| line.remaining.is_positive()).collect();
+            let remaining = Money::sum(open.iter().map(|line| line.remaining), Currency::XAF)?;
+            if open.is_empty() || amount.checked_sub(remaining.min(left)?)?.is_positive() {
+                return Err(AppError::bad_request(format!(
+                    "Refund amount cannot exceed the {} left to refund",
+                    remaining.min(left)?
+                )));
+            }
+
+            let weights: Vec<Money> = open.iter().map(|line| line.remaining).collect();
+            open.into_iter()
+                .zip(amount.allocate(&weights)?)
+                .filter(|(_, share)| share.is_positive())
+                .map(|(line, share)| (line.item.clone(), share))
+                .collect()
+        }
+    };
+
+    let created = record_refund(&txn, payment, lines, payload.reason, Some(admin_id)).await?;
+    txn.commit().await?;
+
+    tracing::info!("Refund {} of {} recorded on payment {}", created.id, created.amount, created.payment_id);
+
+    let sent = send(db, gateways, created.id).await?;
+    load_refund(db, sent).await
+}
+
+// Refund what is left on the items of canceled sub-orders, from a payment locked by the caller.
+// The refunds job sends it to the provider.
+pub(crate) async fn refund_seller_orders<C: ConnectionTrait>(
+    db: &C,
+    payment: payment::Model,
+    seller_orders: &[seller_order::Model],
+    reason: String,
+) -> Result<Option<refund::Model>> {
+    let ids: HashSet<Uuid> = seller_orders.iter().map(|so| so.id).collect();
+    let open: Vec<Refundable> = refundable_items(db, payment.order_id)
+        .await?
+        .into_iter()
+        .filter(|line| ids.contains(&line.item.seller_order_id) && line.remaining.is_positive())
+        .collect();
+    if open.is_empty() {
+        return Ok(None);
+    }
+
+    // Never more than is left on the payment, in case of refunds recorded before items were tracked
+    let left = to_money(&payment.amount)?.checked_sub(to_money(&payment.refunded_amount)?)?;
+    let weights: Vec<Money> = open.iter().map(|line| line.remaining).collect();
+    let amount = Money::sum(weights.iter().copied(), Currency::XAF)?.min(left)?;
+    if !amount.is_positive() {
+        return Ok(None);
+    }
+
+    let lines = open
+        .into_iter()
+        .zip(amount.allocate(&weights)?)
+        .filter(|(_, share)| share.is_positive())
+        .map(|(line, share)| (line.item, share))
+        .collect();
+
+    Ok(Some(record_refund(db, payment, lines, reason, None).await?))
+}
+
+// Refund the whole of a payment the order had no use for (the order was already paid or was
+// canceled), from a payment locked by the caller. The money was never captured for the sellers,
+// so nothing is taken back in the ledger and the order's payment status is left alone. The
+// refunds job sends it to the provider.
+pub(crate) async fn refund_unused_payment<C: ConnectionTrait>(
+    db: &C,
+    payment: payment::Model,
+    reason: String,
+) -> Result<refund::Model> {
+    let left = to_money(&payment.amount)?.checked_sub(to_money(&payment.refunded_amount)?)?;
+    refund_uncaptured(db, payment, left, reason).await
+}
+
+// Refund part of a payment that was never captured for the sellers, such as the share of
+// sub-orders canceled while the payment was on its way. Same rules as refund_unused_payment.
+pub(crate) async fn refund_uncaptured<C: ConnectionTrait>(
+    db: &C,
+    payment: payment::Model,
+    amount: Money,
+    reason: String,
+) -> Result<refund::Model> {
+    let paid = to_money(&payment.amount)?;
+    let refunded = to_money(&payment.refunded_amount)?;
+    if !amount.is_positive() || amount > paid.checked_sub(refunded)? {
+        return Err(AppError::bad_request("Nothing is left to refund on this payment"));
+    }
+    let refunded = refunded.checked_add(amount)?;
+
+    let now = Utc::now();
+    let created = refund::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        payment_id: Set(payment.id),
+        order_id: Set(payment.order_id),
+        amount: Set(amount.to_decimal()),
+        currency: Set(amount.currency().to_string()),
+        reason: Set(reason),
+        status: Set(RefundStatus::Pending.to_string()),
+        provider_reference: Set(None),
+        failure_reason: Set(None),
+        requested_by: Set(None),
+        completed_at: Set(None),
+        created_at: Set(now),
+        updated_at: Set(now),
+    }
+    .insert(db)
+    .await?;
+
+    let status = if refunded == paid {
+        PaymentStatus::Refunded
+    } else {
+        PaymentStatus::PartiallyRefunded
+    };
+    let mut payment = payment.into_active_model();
+    payment.refunded_amount = Set(refunded.to_decimal());
+    payment.status = Set(status.to_string());
+    payment.updated_at = Set(now);
+    payment.update(db).await?;
+
+    tracing::warn!("Refund {} of {} recorded for uncaptured payment {}", created.id, created.amount, created.payment_id);
+
+    Ok(created)
+}
+
+// The money reached the buyer
+async fn complete<C: ConnectionTrait>(db: &C, refund: refund::Model, provider_reference: String) -> Result<refund::Model> {
+    let now = Utc::now();
+    let mut active = refund.into_active_model();
+    active.status = Set(RefundStatus::Completed.to_string());
+    active.provider_reference = Set(Some(provider_reference));
+    active.failure_reason = Set(None);
+    active.completed_at = Set(Some(now));
+    active.updated_at = Set(now);
+    let completed = active.update(db).await?;
+
+    if let Some(order) = order::Entity::find_by_id(completed.order_id).one(db).await? {
+        notification::notify(
+            db,
+            order.user_id,
+            NotificationKind::RefundUpdate,
+            "Refund sent",
+            format!(
+                "{} was refunded for order #{}.",
+                to_money(&completed.amount)?,
+                order.id.to_string()[..8].to_uppercase()
+            ),
+            Some(format!("/orders/{}", order.id)),
+        )
+        .await?;
+    }
+
+    Ok(completed)
+}
+
+// Record where a refund sent through the provider stands. Only refunds still processing change.
+async fn apply_refund_status(
+    db: &DatabaseConnection,
+    refund_id: Uuid,
+    provider_reference: String,
+    status: GatewayPaymentStatus,
+) -> Result<refund::Model> {
+    let txn = db.begin().await?;
+    let refund = find_locked(&txn, refund_id).await?;
+
+    if refund.status != RefundStatus::Processing.to_string() {
+        return Ok(refund);
+    }
+
+    let updated = match status {
+        GatewayPaymentStatus::Successful => complete(&txn, refund, provider_reference).await?,
+        GatewayPaymentStatus::Failed { reason } => {
+            tracing::warn!("Refund {} failed at the provider: {}", refund.id, reason);
+            let mut active = refund.into_active_model();
+            active.status = Set(RefundStatus::Failed.to_string());
+            active.provider_reference = Set(Some(provider_reference));
+            active.failure_reason = Set(Some(reason));
+            active.updated_at = Set(Utc::now());
+            active.update(&txn).await?
+        }
+        GatewayPaymentStatus::Pending => {
+            let mut active = refund.into_active_model();
+            active.provider_reference = Set(Some(provider_reference));
+            active.updated_at = Set(Utc::now());
+            active.update(&txn).await?
+        }
+    };
+
+    txn.commit().await?;
+
+    Ok(updated)
+}
+
+// Send a pending or failed refund to the provider. Refunds the provider cannot take are left
+// pending to be paid by hand.
+async fn send(db: &DatabaseConnection, gateways: &PaymentGateways, refund_id: Uuid) -> Result<refund::Model> {
+    let txn = db.begin().await?;
+    let refund = find_locked(&txn, refund_id).await?;
+
+    let status = RefundStatus::from_str(&refund.status).map_err(AppError::internal)?;
+    if !matches!(status, RefundStatus::Pending | RefundStatus::Failed) {
+        return Ok(refund);
+    }
+
+    let payment = payment::Entity::find_by_id(refund.payment_id)
+        .one(&txn)
+        .await?
+        .ok_or_else(|| AppError::internal("Payment not found for refund"))?;
+    let gateway = match gateway_for(gateways, &payment)? {
+        Some(gateway) if gateway.supports_refunds() => gateway,
+        _ => return Ok(refund),
+    };
+    let Some(payment_reference) = payment.processor_payment_id.clone() else {
+        return Ok(refund);
+    };
+
+    // Marked processing before calling the provider so it cannot be sent twice. The refund's id is
+    // its reference, so a send that errored on our side is looked up rather than sent again. Only a
+    // refund the provider reported failed is sent under a new reference, as providers refuse one
+    // they have seen before and the failed attempt can no longer pay out.
+    let reference = match (&status, refund.provider_reference.as_deref()) {
+        (RefundStatus::Failed, Some(_)) => Uuid::new_v4(),
+        _ => refund.id,
+    };
+    let mut active = refund.into_active_model();
+    active.status = Set(RefundStatus::Processing.to_string());
+    active.provider_reference = Set(Some(reference.to_string()));
+    active.failure_reason = Set(None);
+    active.updated_at = Set(Utc::now());
+    let sending = active.update(&txn).await?;
+
+    txn.commit().await?;
+
+    let instruction = RefundInstruction {
+        reference,
+        payment_reference,
+        amount: to_money(&sending.amount)?,
+        description: format!("Refund for order #{}", sending.order_id.to_string()[..8].to_uppercase()),
+    };
+
+    match gateway.refund(&instruction).await {
+        Ok(initiation) => apply_refund_status(db, sending.id, initiation.provider_reference, initiation.status).await,
+        // The provider may have paid before the error reached us (a timeout, say), so the refund stays
+        // processing under its reference until a lookup settles it
+        Err(e) => {
+            tracing::error!(
+                "{} refund {} did not complete, leaving it to the refund check: {:?}",
+                gateway.name(),
+                sending.id,
+                e
+            );
+            apply_refund_status(db, sending.id, reference.to_string(), GatewayPaymentStatus::Pending).await
+        }
+    }
+}
+
+// Refunds on orders, newest first. Buyers only see refunds on their own payments.
+pub async fn get_refunds(
+    db: &DatabaseConnection,
+    buyer_id: Option<Uuid>,
+    query: RefundQuery,
+) -> Result<(Vec<Refund>, u64)> {
+    let page = query.page.unwrap_or(1).max(1);
+    let per_page = query.per_page.unwrap_or(20).clamp(1, 50);
+
+    let mut select = refund::Entity::find();
+    if let Some(buyer_id) = buyer_id {
+        select = select
+            .join(JoinType::InnerJoin, refund::Relation::Payment.def())
+            .filter(payment::Column::UserId.eq(buyer_id));
+    }
+    if let Some(order_id) = query.order_id {
+        select = select.filter(refund::Column::OrderId.eq(order_id));
+    }
+    if let Some(payment_id) = query.payment_id {
+        select = select.filter(refund::Column::PaymentId.eq(payment_id));
+    }
+    if let Some(status) = query.status {
+        select = select.filter(refund::Column::Status.eq(status.to_string()));
+    }
+
+    let paginator = select.order_by_desc(refund::Column::CreatedAt).paginate(db, per_page);
+    let total = paginator.num_items().await?;
+    let refunds = paginator.fetch_page(page - 1).await?;
+
+    let ids: Vec<Uuid> = refunds.iter().map(|refund| refund.id).collect();
+    let mut items: HashMap<Uuid, Vec<refund_item::Model>> = HashMap::new();
+    for item in refund_item::Entity::find()
+        .filter(refund_item::Column::RefundId.is_in(ids))
+        .all(db)
+        .await?
+    {
+        items.entry(item.refund_id).or_default().push(item);
+    }
+
+    let refunds = refunds
+        .into_iter()
+        .map(|refund| {
+            let lines = items.remove(&refund.id).unwrap_or_default();
+            to_refund(refund, lines)
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok((refunds, total))
+}
+
+// Send a failed or unsent refund to the provider again (admin only)
+pub async fn retry_refund(db: &DatabaseConnection, gateways: &PaymentGateways, refund_id: Uuid) -> Result<Refund> {
+    let refund = refund::Entity::find_by_id(refund_id)
+        .one(db)
+        .await?
+        .ok_or_else(|| AppError::not_found("Refund not found"))?;
+
+    let status = RefundStatus::from_str(&refund.status).map_err(AppError::internal)?;
+    if !matches!(status, RefundStatus::Pending | RefundStatus::Failed) {
+        return Err(AppError::bad_request(format!("Refund is already {}", status)));
+    }
+
+    let payment = payment::Entity::find_by_id(refund.payment_id)
+        .one(db)
+        .await?
+        .ok_or_else(|| AppError::internal("Payment not found for refund"))?;
+    match gateway_for(gateways, &payment)? {
+        Some(gateway) if gateway.supports_refunds() => {}
+        Some(gateway) => {
+            return Err(AppError::bad_request(format!(
+                "{} refunds are made by hand; mark the refund paid instead",
+                gateway.name()
+            )))
+        }
+        None => return Err(AppError::bad_request("This payment can only be refunded by hand")),
+    }
+
+    let sent = send(db, gateways, refund_id).await?;
+    load_refund(db, sent).await
+}
+
+// Record a refund paid by hand from the provider's merchant portal (admin only)
+pub async fn mark_refund_paid(db: &DatabaseConnection, refund_id: Uuid, payload: MarkRefundPaidRequest) -> Result<Refund> {
+    validation::validate(&payload)?;
+
+    let txn = db.begin().await?;
+    let refund = find_locked(&txn, refund_id).await?;
+
+    let status = RefundStatus::from_str(&refund.status).map_err(AppError::internal)?;
+    if !matches!(status, RefundStatus::Pending | RefundStatus::Failed) {
+        return Err(AppError::bad_request("Only pending or failed refunds can be marked paid"));
+    }
+
+    let paid = complete(&txn, refund, payload.provider_reference).await?;
+    let refund = load_refund(&txn, paid).await?;
+    txn.commit().await?;
+
+    Ok(refund)
+}
+
+// Send refunds recorded on cancellation and look up those waiting on the provider. Returns how
+// many reached a final status.
+pub async fn check_refunds(db: &DatabaseConnection, gateways: &PaymentGateways) -> Result<u64> {
+    let open = refund::Entity::find()
+        .filter(refund::Column::Status.is_in([RefundStatus::Pending.to_string(), RefundStatus::Processing.to_string()]))
+        .order_by_asc(refund::Column::CreatedAt)
+        .all(db)
+        .await?;
+
+    let mut settled = 0;
+    for refund in open {
+        let updated = if refund.status == RefundStatus::Pending.to_string() {
+            match send(db, gateways, refund.id).await {
+                Ok(updated) => updated,
+                Err(e) => {
+                    tracing::warn!("Could not send refund {}: {:?}", refund.id, e);
+                    continue;
+                }
+            }
+        } else {
+            let payment = payment::Entity::find_by_id(refund.payment_id)
+                .one(db)
+                .await?
+                .ok_or_else(|| AppError::internal("Payment not found for refund"))?;
+            let Some(gateway) = gateway_for(gateways, &payment)? else {
+                continue;
+            };
+            let provider_reference = refund.provider_reference.clone().unwrap_or_else(|| refund.id.to_string());
+
+            let lookup = PaymentLookup {
+                payment_id: refund.id,
+                provider_reference: provider_reference.clone(),
+                amount: to_money(&refund.amount)?,
+            };
+
+            match gateway.check_refund(&lookup).await {
+                Ok(GatewayPaymentStatus::Pending) => continue,
+                Ok(status) => apply_refund_status(db, refund.id, provider_reference, status).await?,
+                Err(e) => {
+                    tracing::warn!("Could not check refund {}: {:?}", refund.id, e);
+                    continue;
+                }
+            }
+        };
+
+        if matches!(
+            RefundStatus::from_str(&updated.status).map_err(AppError::internal)?,
+            RefundStatus::Completed | RefundStatus::Failed
+        ) {
+            settled += 1;
+        }
+    }
+
+    Ok(settled)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sea_orm::{DatabaseBackend, MockDatabase, Value};
+
+    fn payment(amount: i64, refunded: i64) -> payment::Model {
+        let now = Utc::now();
+        payment::Model {
+            id: Uuid::new_v4(),
+            order_id: Uuid::new_v4(),
+            user_id: Uuid::new_v4(),
+            amount: BigDecimal::from(amount),
+            refunded_amount: BigDecimal::from(refunded),
+            currency: "XAF".to_string(),
+            processor: PaymentProcessor::MtnMobileMoney.to_string(),
+            processor_payment_id: None,
+            processor_transaction_id: None,
+            status: PaymentStatus::Completed.to_string(),
+            payment_method: "mtn_mobile_money".to_string(),
+            metadata: None,
+            callback_token: None,
+            failure_reason: None,
+            created_at: now,
+            updated_at: now,
+            completed_at: Some(now),
+        }
+    }
+
+    fn item(order_id: Uuid, price: i64) -> order_item::Model {
+        let now = Utc::now();
+        order_item::Model {
+            id: Uuid::new_v4(),
+            order_id,
+            seller_order_id: Uuid::new_v4(),
+            product_id: Uuid::new_v4(),
+            seller_id: Uuid::new_v4(),
+            product_title: "Ndole spice mix".to_string(),
+            quantity: 1,
+            unit_price: BigDecimal::from(price),
+            commission_rule_id: None,
+            commission_rate: BigDecimal::from(0),
+            commission_amount: BigDecimal::from(0),
+            processing_fee: BigDecimal::from(0),
+            tax_rule_id: None,
+            tax_rate: BigDecimal::from(0),
+            taxable_amount: BigDecimal::from(price),
+            tax_amount: BigDecimal::from(0),
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    #[tokio::test]
+    async fn refunds_cannot_pass_what_was_paid() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres).into_connection();
+        let paid = payment(5000, 4000);
+        let lines = vec![(item(paid.order_id, 5000), Money::xaf(1500))];
+
+        let result = record_refund(&db, paid, lines, "Damaged".to_string(), None).await;
+
+        assert!(result.is_err());
+        assert!(db.into_transaction_log().is_empty());
+    }
+
+    #[tokio::test]
+    async fn unused_payments_are_refunded_for_what_is_left() {
+        let paid = payment(5000, 1000);
+        let now = Utc::now();
+        let created = refund::Model {
+            id: Uuid::new_v4(),
+            payment_id: paid.id,
+            order_id: paid.order_id,
+            amount: BigDecimal::from(4000),
+            currency: "XAF".to_string(),
+            reason: "Duplicate payment".to_string(),
+            status: RefundStatus::Pending.to_string(),
+            provider_reference: None,
+            failure_reason: None,
+            requested_by: None,
+            completed_at: None,
+            created_at: now,
+            updated_at: now,
+        };
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([[created]])
+            .append_query_results([[payment(5000, 5000)]])
+            .into_connection();
+
+        refund_unused_payment(&db, paid, "Duplicate payment".to_string()).await.unwrap();
+
+        let log = db.into_transaction_log();
+        let insert = log[0].statements()[0].clone();
+        assert!(insert.sql.starts_with("INSERT INTO \"refunds\""));
+        let amount = insert.values.unwrap().0.into_iter().find_map(|value| match value {
+            Value::BigDecimal(Some(amount)) => Some(*amount),
+            _ => None,
+        });
+        assert_eq!(amount, Some(BigDecimal::from(4000)));
+    }
+
+    #[tokio::test]
+    async fn fully_refunded_payments_have_nothing_left() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres).into_connection();
+
+        let result = refund_unused_payment(&db, payment(5000, 5000), "Duplicate payment".to_string()).await;
+
+        assert!(result.is_err());
+        assert!(db.into_transaction_log().is_empty());
+    }
+}
@@ -0,0 +1,230 @@
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, Utc};
+use sea_orm::{
+    ColumnTrait, ConnectionTrait, DatabaseBackend, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder, Statement,
+};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::entities::{cart_item, order, order_item, product};
+use crate::errors::{AppError, Result};
+use crate::models::cart::{AddToCartRequest, CartOwner};
+use crate::models::money::{Currency, Money};
+use crate::models::order::{BuyAgainItem, OrderStatus, ReorderIssue, ReorderItemResult, ReorderResponse};
+use crate::models::product::ProductStatus;
+use crate::services::cart;
+
+fn to_money(value: &BigDecimal) -> Result<Money> {
+    Ok(Money::from_decimal(value, Currency::XAF)?)
+}
+
+// A product from the past order, with its lines merged
+struct PastLine {
+    product_id: Uuid,
+    product_title: String,
+    quantity: i32,
+    unit_price: BigDecimal,
+}
+
+// Put the items of a past order back in the buyer's cart at today's prices.
+// Whatever can no longer be bought, or only in a smaller quantity, is left out and reported.
+pub async fn reorder(db: &DatabaseConnection, order_id: Uuid, user_id: Uuid) -> Result<ReorderResponse> {
+    let order = order::Entity::find_by_id(order_id)
+        .one(db)
+        .await?
+        .ok_or_else(|| AppError::not_found("Order not found"))?;
+
+    if order.user_id != user_id {
+        return Err(AppError::forbidden("You can only reorder your own orders"));
+    }
+
+    let items = order_item::Entity::find()
+        .filter(order_item::Column::OrderId.eq(order_id))
+        .order_by_asc(order_item::Column::CreatedAt)
+        .all(db)
+        .await?;
+
+    let mut lines: Vec<PastLine> = Vec::new();
+    for item in items {
+        match lines.iter_mut().find(|line| line.product_id == item.product_id) {
+            Some(line) => line.quantity += item.quantity,
+            None => lines.push(PastLine {
+                product_id: item.product_id,
+                product_title: item.product_title,
+                quantity: item.quantity,
+                unit_price: item.unit_price,
+            }),
+        }
+    }
+
+    let product_ids: Vec<Uuid> = lines.iter().map(|line| line.product_id).collect();
+    let products: HashMap<Uuid, product::Model> = product::Entity::find()
+        .filter(product::Column::Id.is_in(product_ids))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|p| (p.id, p))
+        .collect();
+
+    // Stock already claimed by the same products in the cart
+    let in_cart: HashMap<Uuid, i32> = cart_item::Entity::find()
+        .filter(cart_item::Column::UserId.eq(user_id))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|item| (item.product_id, item.quantity))
+        .collect();
+
+    let active = ProductStatus::Active.to_string();
+    let mut results = Vec::new();
+    let mut added_items = 0;
+
+    for line in lines {
+        let mut issues = Vec::new();
+
+        let added_quantity = match products.get(&line.product_id) {
+            Some(product) if product.status == active && product.seller_id != user_id => {
+                let room = (product.stock - in_cart.get(&product.id).copied().unwrap_or(0)).max(0);
+                let quantity = line.quantity.min(room);
+
+                if product.stock <= 0 {
+                    issues.push(ReorderIssue::OutOfStock);
+                } else if quantity < line.quantity {
+                    issues.push(ReorderIssue::QuantityReduced { available: room });
+                }
+
+                if product.price != line.unit_price {
+                    issues.push(ReorderIssue::PriceChanged {
+                        previous_price: to_money(&line.unit_price)?,
+                        current_price: to_money(&product.price)?,
+                    });
+                }
+
+                if quantity > 0 {
+                    cart::add_to_cart(
+                        db,
+                        CartOwner::User(user_id),
+                        AddToCartRequest {
+                            product_id: product.id,
+                            quantity,
+                        },
+                    )
+                    .await?;
+                    added_items += 1;
+                }
+
+                quantity
+            }
+            _ => {
+                issues.push(ReorderIssue::Unavailable);
+                0
+            }
+        };
+
+        results.push(ReorderItemResult {
+            product_id: line.product_id,
+            product_title: line.product_title,
+            ordered_quantity: line.quantity,
+            added_quantity,
+            issues,
+        });
+    }
+
+    Ok(ReorderResponse {
+        items: results,
+        added_items,
+        cart: cart::get_cart(db, CartOwner::User(user_id)).await?,
+    })
+}
+
+// Products the buyer has ordered before, most recently ordered first.
+// Lines from canceled sub-orders are left out.
+pub async fn get_buy_again(
+    db: &DatabaseConnection,
+    user_id: Uuid,
+    page: u64,
+    per_page: u64,
+) -> Result<(Vec<BuyAgainItem>, u64)> {
+    let canceled = OrderStatus::Canceled.to_string();
+
+    let count = db
+        .query_one(Statement::from_sql_and_values(
+            DatabaseBackend::Postgres,
+            r#"
+            SELECT COUNT(DISTINCT oi.product_id) AS total
+            FROM order_items oi
+            JOIN orders o ON o.id = oi.order_id
+            JOIN seller_orders so ON so.id = oi.seller_order_id
+            WHERE o.user_id = $1 AND so.status <> $2
+            "#,
+            [user_id.into(), canceled.clone().into()],
+        ))
+        .await?;
+    let total = match count {
+        Some(row) => row.try_get::<i64>("", "total")? as u64,
+        None => 0,
+    };
+
+    let rows = db
+        .query_all(Statement::from_sql_and_values(
+            DatabaseBackend::Postgres,
+            r#"
+            SELECT oi.product_id,
+                   COUNT(DISTINCT oi.order_id) AS times_ordered,
+                   MAX(o.created_at) AS last_ordered_at,
+                   (ARRAY_AGG(oi.unit_price ORDER BY o.created_at DESC))[1] AS last_price,
+                   (ARRAY_AGG(oi.quantity ORDER BY o.created_at DESC))[1] AS last_quantity
+            FROM order_items oi
+            JOIN orders o ON o.id = oi.order_id
+            JOIN seller_orders so ON so.id = oi.seller_order_id
+            WHERE o.user_id = $1 AND so.status <> $2
+            GROUP BY oi.product_id
+            ORDER BY last_ordered_at DESC
+            LIMIT $3 OFFSET $4
+            "#,
+            [
+                user_id.into(),
+                canceled.into(),
+                (per_page as i64).into(),
+                (((page - 1) * per_page) as i64).into(),
+            ],
+        ))
+        .await?;
+
+    let product_ids = rows
+        .iter()
+        .map(|row| row.try_get::<Uuid>("", "product_id"))
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    let products: HashMap<Uuid, product::Model> = product::Entity::find()
+        .filter(product::Column::Id.is_in(product_ids))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|p| (p.id, p))
+        .collect();
+
+    let active = ProductStatus::Active.to_string();
+    let mut items = Vec::new();
+
+    for row in rows {
+        let product_id: Uuid = row.try_get("", "product_id")?;
+        let Some(product) = products.get(&product_id) else { continue };
+        let last_ordered_at: DateTime<Utc> = row.try_get("", "last_ordered_at")?;
+
+        items.push(BuyAgainItem {
+            product_id,
+            product_title: product.title.clone(),
+            product_image: product.images.0.first().cloned().unwrap_or_default(),
+            seller_id: product.seller_id,
+            last_price: to_money(&row.try_get::<BigDecimal>("", "last_price")?)?,
+            current_price: to_money(&product.price)?,
+            times_ordered: row.try_get("", "times_ordered")?,
+            last_quantity: row.try_get("", "last_quantity")?,
+            last_ordered_at,
+            available: product.status == active && product.stock > 0,
+            available_stock: product.stock.max(0),
+        });
+    }
+
+    Ok((items, total))
+}
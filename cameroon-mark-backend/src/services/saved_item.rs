@@ -1,20 +1,30 @@
+use chrono::{Duration, Utc};
 use sea_orm::{
-    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, Set,
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, EntityTrait, QueryFilter, Select, Set,
     RelationTrait, QuerySelect, JoinType,
 };
+use sea_orm::sea_query::Expr;
+use std::collections::BTreeMap;
 use uuid::Uuid;
 
 use crate::{
     entities::{saved_item, product},
     errors::{AppError, Result},
-    models::saved_item::{SavedItem, SavedItemWithProduct, AddSavedItemRequest},
+    models::notification::NotificationKind,
+    models::saved_item::{SavedItem, SavedItemWithProduct, AddSavedItemRequest, UpdateSavedItemAlertsRequest},
     models::product::{ProductSummary, Product},
+    services::{notification, wishlist},
 };
 
-// Get all saved items for a user
-pub async fn get_saved_items(db: &DatabaseConnection, user_id: Uuid) -> Result<Vec<SavedItemWithProduct>> {
-    let saved_items = saved_item::Entity::find()
-        .filter(saved_item::Column::UserId.eq(user_id))
+// Minimum time between two alerts for the same saved item
+const ALERT_COOLDOWN_HOURS: i64 = 24;
+
+// Get the saved items matching a query, with their products
+pub async fn get_items_with_products(
+    db: &DatabaseConnection,
+    query: Select<saved_item::Entity>,
+) -> Result<Vec<SavedItemWithProduct>> {
+    let saved_items = query
         .find_with_related(product::Entity)
         .all(db)
         .await?;
@@ -25,7 +35,10 @@ pub async fn get_saved_items(db: &DatabaseConnection, user_id: Uuid) -> Result<V
             let product = products.first()?;
             Some(SavedItemWithProduct {
                 id: saved_item.id,
+                wishlist_id: saved_item.wishlist_id,
                 product: ProductSummary::from(Product::from(product.clone())),
+                price_at_save: saved_item.price_at_save,
+                alerts_enabled: saved_item.alerts_enabled,
                 created_at: saved_item.created_at,
             })
         })
@@ -34,18 +47,35 @@ pub async fn get_saved_items(db: &DatabaseConnection, user_id: Uuid) -> Result<V
     Ok(result)
 }
 
-// Add a product to saved items
-pub async fn add_saved_item(db: &DatabaseConnection, user_id: Uuid, payload: AddSavedItemRequest) -> Result<SavedItem> {
+// Get all saved items in the user's default list
+pub async fn get_saved_items(db: &DatabaseConnection, user_id: Uuid) -> Result<Vec<SavedItemWithProduct>> {
+    let default_list = wishlist::get_or_create_default(db, user_id).await?;
+
+    get_items_with_products(
+        db,
+        saved_item::Entity::find().filter(saved_item::Column::WishlistId.eq(default_list.id)),
+    )
+    .await
+}
+
+// Save a product into one of the user's lists
+pub async fn save_to_list(
+    db: &DatabaseConnection,
+    user_id: Uuid,
+    wishlist_id: Uuid,
+    product_id: Uuid,
+    alerts_enabled: bool,
+) -> Result<SavedItem> {
     // Check if product exists
-    let product = product::Entity::find_by_id(payload.product_id)
+    let product = product::Entity::find_by_id(product_id)
         .one(db)
         .await?
         .ok_or_else(|| AppError::not_found("Product not found"))?;
 
-    // Check if already saved
+    // Check if already in this list
     let existing = saved_item::Entity::find()
-        .filter(saved_item::Column::UserId.eq(user_id))
-        .filter(saved_item::Column::ProductId.eq(payload.product_id))
+        .filter(saved_item::Column::WishlistId.eq(wishlist_id))
+        .filter(saved_item::Column::ProductId.eq(product_id))
         .one(db)
         .await?;
 
@@ -53,10 +83,13 @@ pub async fn add_saved_item(db: &DatabaseConnection, user_id: Uuid, payload: Add
         return Err(AppError::bad_request("Product already saved"));
     }
 
-    // Create new saved item
+    // Create new saved item, remembering the price it was saved at
     let saved_item = saved_item::ActiveModel {
         user_id: Set(user_id),
-        product_id: Set(payload.product_id),
+        product_id: Set(product_id),
+        wishlist_id: Set(wishlist_id),
+        price_at_save: Set(Some(product.price)),
+        alerts_enabled: Set(alerts_enabled),
         ..Default::default()
     };
 
@@ -64,9 +97,46 @@ pub async fn add_saved_item(db: &DatabaseConnection, user_id: Uuid, payload: Add
     Ok(SavedItem::from(saved_item))
 }
 
+// Add a product to saved items
+pub async fn add_saved_item(db: &DatabaseConnection, user_id: Uuid, payload: AddSavedItemRequest) -> Result<SavedItem> {
+    let default_list = wishlist::get_or_create_default(db, user_id).await?;
+
+    save_to_list(
+        db,
+        user_id,
+        default_list.id,
+        payload.product_id,
+        payload.alerts_enabled.unwrap_or(false),
+    )
+    .await
+}
+
 // Remove a product from saved items
 pub async fn remove_saved_item(db: &DatabaseConnection, user_id: Uuid, product_id: Uuid) -> Result<()> {
+    let default_list = wishlist::get_or_create_default(db, user_id).await?;
+
     let result = saved_item::Entity::delete_many()
+        .filter(saved_item::Column::WishlistId.eq(default_list.id))
+        .filter(saved_item::Column::ProductId.eq(product_id))
+        .exec(db)
+        .await?;
+
+    if result.rows_affected == 0 {
+        return Err(AppError::not_found("Saved item not found"));
+    }
+
+    Ok(())
+}
+
+// Turn price-drop and back-in-stock alerts on or off for a saved product, in every list it is in
+pub async fn update_alerts(
+    db: &DatabaseConnection,
+    user_id: Uuid,
+    product_id: Uuid,
+    payload: UpdateSavedItemAlertsRequest,
+) -> Result<()> {
+    let result = saved_item::Entity::update_many()
+        .col_expr(saved_item::Column::AlertsEnabled, Expr::value(payload.alerts_enabled))
         .filter(saved_item::Column::UserId.eq(user_id))
         .filter(saved_item::Column::ProductId.eq(product_id))
         .exec(db)
@@ -77,4 +147,88 @@ pub async fn remove_saved_item(db: &DatabaseConnection, user_id: Uuid, product_i
     }
 
     Ok(())
-}
\ No newline at end of file
+}
+
+// Alert buyers watching a product after its price or stock changed. Run this in the
+// transaction that saved the change, so alerts and their cooldowns go in with it.
+pub async fn notify_product_watchers<C: ConnectionTrait>(
+    db: &C,
+    before: &product::Model,
+    after: &product::Model,
+) -> Result<()> {
+    let back_in_stock = before.stock <= 0 && after.stock > 0;
+    let price_lowered = after.price < before.price;
+
+    if !back_in_stock && !price_lowered {
+        return Ok(());
+    }
+
+    let watchers = saved_item::Entity::find()
+        .filter(saved_item::Column::ProductId.eq(after.id))
+        .filter(saved_item::Column::AlertsEnabled.eq(true))
+        .all(db)
+        .await?;
+
+    // The same product can sit in several of a buyer's lists, but the buyer hears about it once
+    let mut by_user: BTreeMap<Uuid, Vec<saved_item::Model>> = BTreeMap::new();
+    for watcher in watchers {
+        if watcher.user_id != after.seller_id {
+            by_user.entry(watcher.user_id).or_default().push(watcher);
+        }
+    }
+
+    let now = Utc::now();
+    let link = Some(format!("/products/{}", after.id));
+
+    for (user_id, rows) in by_user {
+        // One alert per buyer and product per cooldown window, whatever changed
+        let last_alerted_at = rows.iter().filter_map(|row| row.last_alerted_at).max();
+        if last_alerted_at.is_some_and(|at| now - at < Duration::hours(ALERT_COOLDOWN_HOURS)) {
+            continue;
+        }
+
+        // Only prices under what the buyer saw, and under the last price we alerted on, count as drops
+        let reference_price = rows
+            .iter()
+            .flat_map(|row| [row.price_at_save.clone(), row.last_alerted_price.clone()])
+            .flatten()
+            .min();
+        let price_dropped = price_lowered
+            && reference_price.as_ref().is_some_and(|reference| after.price < *reference);
+
+        let (kind, title, body) = if back_in_stock {
+            (
+                NotificationKind::BackInStock,
+                format!("{} is back in stock", after.title),
+                format!("{} is available again at {} XAF.", after.title, after.price),
+            )
+        } else if price_dropped {
+            (
+                NotificationKind::PriceDrop,
+                format!("Price drop on {}", after.title),
+                format!(
+                    "{} is now {} XAF, down from {} XAF.",
+                    after.title,
+                    after.price,
+                    reference_price.unwrap_or_else(|| before.price.clone()),
+                ),
+            )
+        } else {
+            continue;
+        };
+
+        notification::notify(db, user_id, kind, title, body, link.clone()).await?;
+
+        // Every row for the product starts its cooldown, so another list cannot alert again early
+        let mut update = saved_item::Entity::update_many()
+            .col_expr(saved_item::Column::LastAlertedAt, Expr::value(now))
+            .filter(saved_item::Column::UserId.eq(user_id))
+            .filter(saved_item::Column::ProductId.eq(after.id));
+        if price_dropped {
+            update = update.col_expr(saved_item::Column::LastAlertedPrice, Expr::value(after.price.clone()));
+        }
+        update.exec(db).await?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,950 @@
+use aws_sdk_s3::Client as S3Client;
+use chrono::{DateTime, Utc};
+use sea_orm::sea_query::{Expr, NullOrdering};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, EntityTrait, IntoActiveModel, Order,
+    QueryFilter, QueryOrder, QuerySelect, Set, TransactionTrait,
+};
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::entities::{order, order_item, product, seller_order, shipment, shipment_event, shipping_rate, user};
+use crate::errors::{AppError, Result};
+use crate::models::money::{Currency, Money};
+use crate::models::order::{OrderActor, OrderStatus, PaymentMethod, PaymentStatus, ShippingAddress};
+use crate::models::shipment::{
+    CreateShipmentRequest, Shipment, ShipmentEvent, ShipmentStatus, TrackingSource, TrackingWebhookAck,
+};
+use crate::models::shipping::ShippingMethod;
+use crate::models::user::UserRole;
+use crate::services::order_status::{self, StatusChange};
+use crate::services::shipping_carrier::{
+    CarrierShipment, ShipmentLookup, ShipmentRequest, ShippingCarrier, ShippingCarriers, TrackingEvent, MANUAL_CARRIER,
+};
+use crate::utils::pdf::{load_pdf, store_pdf};
+use crate::utils::validation;
+
+// Parcels with no weight on record are quoted and shipped at this weight
+const DEFAULT_WEIGHT_GRAMS: i32 = 1000;
+// Shipments looked up per carrier on each run of the tracking job
+const TRACKING_BATCH_SIZE: u64 = 100;
+
+fn parse_status(status: &str) -> Result<ShipmentStatus> {
+    ShipmentStatus::from_str(status).map_err(AppError::internal)
+}
+
+fn lookup(shipment: &shipment::Model) -> ShipmentLookup {
+    ShipmentLookup {
+        tracking_number: shipment.tracking_number.clone(),
+        carrier_reference: shipment.carrier_reference.clone(),
+    }
+}
+
+fn label_key(seller_order_id: Uuid, shipment_id: Uuid) -> String {
+    format!("labels/{}/{}.pdf", seller_order_id, shipment_id)
+}
+
+async fn to_shipment<C: ConnectionTrait>(
+    db: &C,
+    carriers: &ShippingCarriers,
+    order_id: Uuid,
+    model: shipment::Model,
+) -> Result<Shipment> {
+    let events = shipment_event::Entity::find()
+        .filter(shipment_event::Column::ShipmentId.eq(model.id))
+        .order_by_asc(shipment_event::Column::OccurredAt)
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|event| {
+            Ok(ShipmentEvent {
+                status: parse_status(&event.status)?,
+                description: event.description,
+                location: event.location,
+                occurred_at: event.occurred_at,
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    // Carriers that were since switched off keep their code as a name
+    let carrier_name = carriers
+        .get(&model.carrier)
+        .map(|carrier| carrier.name().to_string())
+        .unwrap_or_else(|_| model.carrier.clone());
+
+    Ok(Shipment {
+        id: model.id,
+        order_id,
+        seller_order_id: model.seller_order_id,
+        carrier: model.carrier,
+        carrier_name,
+        tracking_number: model.tracking_number,
+        status: parse_status(&model.status)?,
+        weight_grams: model.weight_grams,
+        created_at: model.created_at,
+        canceled_at: model.canceled_at,
+        events,
+    })
+}
+
+// The order and the sub-order a user is asking about. Buyers see every part of their order,
+// sellers only their own, and admins any. Sellers with several parts, and admins on orders
+// with several sellers, choose one with seller_order_id.
+async fn find_seller_order(
+    db: &DatabaseConnection,
+    order_id: Uuid,
+    user_id: Uuid,
+    role: &UserRole,
+    seller_order_id: Option<Uuid>,
+) -> Result<(order::Model, seller_order::Model)> {
+    let order = order::Entity::find_by_id(order_id)
+        .one(db)
+        .await?
+        .ok_or_else(|| AppError::not_found("Order not found"))?;
+
+    let mut seller_orders = seller_order::Entity::find()
+        .filter(seller_order::Column::OrderId.eq(order_id))
+        .all(db)
+        .await?;
+
+    match role {
+        UserRole::Customer => {
+            if order.user_id != user_id {
+                return Err(AppError::forbidden("You are not authorized to view this order"));
+            }
+        }
+        UserRole::Seller => {
+            seller_orders.retain(|so| so.seller_id == user_id);
+            if seller_orders.is_empty() {
+                return Err(AppError::forbidden("You are not authorized to view this order"));
+            }
+        }
+        UserRole::Admin => {}
+        UserRole::PendingSeller => {
+            return Err(AppError::forbidden("Pending sellers cannot view orders"));
+        }
+    }
+
+    let seller_order = match seller_order_id {
+        Some(id) => seller_orders
+            .into_iter()
+            .find(|so| so.id == id)
+            .ok_or_else(|| AppError::not_found("Sub-order not found on this order"))?,
+        None => {
+            let canceled = OrderStatus::Canceled.to_string();
+            let mut open: Vec<seller_order::Model> =
+                seller_orders.into_iter().filter(|so| so.status != canceled).collect();
+            match open.len() {
+                0 => return Err(AppError::bad_request("This order was canceled")),
+                1 => open.remove(0),
+                _ => {
+                    return Err(AppError::bad_request(
+                        "Each seller ships their part of this order, choose one with seller_order_id",
+                    ))
+                }
+            }
+        }
+    };
+
+    Ok((order, seller_order))
+}
+
+// Only the seller and admins book, print and cancel parcels
+fn ensure_shipper(role: &UserRole) -> Result<OrderActor> {
+    match role {
+        UserRole::Seller => Ok(OrderActor::Seller),
+        UserRole::Admin => Ok(OrderActor::Admin),
+        _ => Err(AppError::forbidden("Only the seller can ship this order")),
+    }
+}
+
+// The sub-order's parcel still in play, if any
+async fn open_shipment<C: ConnectionTrait>(db: &C, seller_order_id: Uuid) -> Result<Option<shipment::Model>> {
+    Ok(shipment::Entity::find()
+        .filter(shipment::Column::SellerOrderId.eq(seller_order_id))
+        .filter(shipment::Column::Status.ne(ShipmentStatus::Canceled.to_string()))
+        .one(db)
+        .await?)
+}
+
+// Weight of a seller's items, from the products' weights
+async fn parcel_weight(db: &DatabaseConnection, seller_order_id: Uuid) -> Result<i32> {
+    let items = order_item::Entity::find()
+        .filter(order_item::Column::SellerOrderId.eq(seller_order_id))
+        .all(db)
+        .await?;
+
+    let weights: HashMap<Uuid, i32> = product::Entity::find()
+        .filter(product::Column::Id.is_in(items.iter().map(|item| item.product_id)))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|product| (product.id, product.weight_grams))
+        .collect();
+
+    let grams: i64 = items
+        .iter()
+        .map(|item| {
+            let weight = weights.get(&item.product_id).copied().unwrap_or(DEFAULT_WEIGHT_GRAMS);
+            i64::from(weight) * i64::from(item.quantity)
+        })
+        .sum();
+
+    Ok(grams.clamp(1, i64::from(i32::MAX)) as i32)
+}
+
+// Everything the carrier needs to know about a parcel
+async fn shipment_request(
+    db: &DatabaseConnection,
+    order: &order::Model,
+    seller_order: &seller_order::Model,
+    shipment_id: Uuid,
+    weight_grams: i32,
+    tracking_number: Option<String>,
+    callback_url: String,
+) -> Result<ShipmentRequest> {
+    let seller = user::Entity::find_by_id(seller_order.seller_id)
+        .one(db)
+        .await?
+        .ok_or_else(|| AppError::not_found("Seller not found"))?;
+
+    let recipient: ShippingAddress = serde_json::from_value(order.shipping_address.clone())
+        .map_err(|_| AppError::bad_request("The order has no shipping address to deliver to"))?;
+
+    let cash_on_delivery = if order.payment_method == PaymentMethod::CashOnDelivery.to_string() {
+        Some(Money::from_decimal(&seller_order.total_amount, Currency::XAF)?)
+    } else {
+        None
+    };
+
+    Ok(ShipmentRequest {
+        shipment_id,
+        order_id: order.id,
+        seller_order_id: seller_order.id,
+        sender_name: seller.name,
+        sender_city: seller.address_city,
+        sender_phone: seller.phone,
+        recipient,
+        weight_grams,
+        cash_on_delivery,
+        tracking_number,
+        callback_url,
+    })
+}
+
+// Book a seller's part of an order with a carrier and store its label.
+// The carrier is the one asked for, else the one of the rate the buyer chose, else the seller's own courier.
+// Pending sub-orders move to processing.
+#[allow(clippy::too_many_arguments)]
+pub async fn create_shipment(
+    db: &DatabaseConnection,
+    s3_client: &Arc<S3Client>,
+    bucket: &str,
+    carriers: &ShippingCarriers,
+    order_id: Uuid,
+    user_id: Uuid,
+    role: &UserRole,
+    payload: CreateShipmentRequest,
+) -> Result<Shipment> {
+    validation::validate(&payload)?;
+    let actor = ensure_shipper(role)?;
+
+    let (order, seller_order) = find_seller_order(db, order_id, user_id, role, payload.seller_order_id).await?;
+
+    let status = OrderStatus::from_str(&seller_order.status).map_err(AppError::internal)?;
+    if !matches!(status, OrderStatus::Pending | OrderStatus::Processing) {
+        return Err(AppError::bad_request(format!("Cannot ship an order that is {}", status)));
+    }
+    if seller_order.shipping_method != ShippingMethod::Delivery.to_string() {
+        return Err(AppError::bad_request("This order is picked up in store"));
+    }
+    if order.payment_status != PaymentStatus::Paid.to_string()
+        && order.payment_method != PaymentMethod::CashOnDelivery.to_string()
+    {
+        return Err(AppError::bad_request("The order must be paid before it is shipped"));
+    }
+    if open_shipment(db, seller_order.id).await?.is_some() {
+        return Err(AppError::bad_request("This order already has a shipment, cancel it first"));
+    }
+
+    let code = match payload.carrier {
+        Some(code) => code,
+        None => {
+            let rate_carrier = match seller_order.shipping_rate_id {
+                Some(rate_id) => shipping_rate::Entity::find_by_id(rate_id)
+                    .one(db)
+                    .await?
+                    .map(|rate| rate.carrier),
+                None => None,
+            };
+            rate_carrier
+                .filter(|code| carriers.get(code).is_ok())
+                .unwrap_or_else(|| MANUAL_CARRIER.to_string())
+        }
+    };
+    let carrier = carriers.get(&code)?;
+
+    let tracking_number = payload
+        .tracking_number
+        .map(|number| number.trim().to_string())
+        .filter(|number| !number.is_empty());
+    if tracking_number.is_some() && carrier.code() != MANUAL_CARRIER {
+        return Err(AppError::validation(format!(
+            "tracking_number: {} issues its own tracking numbers",
+            carrier.name()
+        )));
+    }
+
+    let weight_grams = match payload.weight_grams {
+        Some(weight) => weight,
+        None => parcel_weight(db, seller_order.id).await?,
+    };
+
+    let shipment_id = Uuid::new_v4();
+    let callback_token = Uuid::new_v4().simple().to_string();
+    let request = shipment_request(
+        db,
+        &order,
+        &seller_order,
+        shipment_id,
+        weight_grams,
+        tracking_number,
+        carriers.callback_url(carrier.code(), &callback_token),
+    )
+    .await?;
+
+    let booked = carrier.create_shipment(&request).await?;
+    let label = carrier.label(&request, &booked).await?;
+    let storage_key = label_key(seller_order.id, shipment_id);
+    store_pdf(s3_client, bucket, &storage_key, label).await?;
+
+    let now = Utc::now();
+    let txn = db.begin().await?;
+
+    let created = shipment::ActiveModel {
+        id: Set(shipment_id),
+        seller_order_id: Set(seller_order.id),
+        carrier: Set(carrier.code().to_string()),
+        tracking_number: Set(booked.tracking_number.clone()),
+        carrier_reference: Set(booked.carrier_reference.clone()),
+        status: Set(ShipmentStatus::Created.to_string()),
+        weight_grams: Set(weight_grams),
+        label_storage_key: Set(storage_key),
+        callback_token: Set(callback_token),
+        created_by: Set(Some(user_id)),
+        last_event_at: Set(Some(now)),
+        last_checked_at: Set(None),
+        canceled_at: Set(None),
+        created_at: Set(now),
+        updated_at: Set(now),
+    }
+    .insert(&txn)
+    .await?;
+
+    shipment_event::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        shipment_id: Set(shipment_id),
+        status: Set(ShipmentStatus::Created.to_string()),
+        description: Set(Some(format!("Shipment booked with {}", carrier.name()))),
+        location: Set(None),
+        occurred_at: Set(now),
+        source: Set(TrackingSource::Carrier.to_string()),
+        raw_payload: Set(None),
+        created_at: Set(now),
+    }
+    .insert(&txn)
+    .await?;
+
+    let mut active = seller_order.into_active_model();
+    active.tracking_number = Set(Some(booked.tracking_number));
+    active.shipping_provider = Set(Some(carrier.name().to_string()));
+    active.updated_at = Set(now);
+    let seller_order = active.update(&txn).await?;
+
+    if status == OrderStatus::Pending {
+        let change = StatusChange {
+            to: OrderStatus::Processing,
+            actor,
+            actor_id: Some(user_id),
+            note: Some(format!("Shipment booked with {}", carrier.name())),
+        };
+        order_status::transition_seller_order(&txn, seller_order, change).await?;
+    }
+
+    let shipment = to_shipment(&txn, carriers, order_id, created).await?;
+    txn.commit().await?;
+
+    Ok(shipment)
+}
+
+// The latest parcel sent for a sub-order, with its tracking history
+pub async fn get_shipment(
+    db: &DatabaseConnection,
+    carriers: &ShippingCarriers,
+    order_id: Uuid,
+    user_id: Uuid,
+    role: &UserRole,
+    seller_order_id: Option<Uuid>,
+) -> Result<Shipment> {
+    let (_, seller_order) = find_seller_order(db, order_id, user_id, role, seller_order_id).await?;
+
+    let shipment = shipment::Entity::find()
+        .filter(shipment::Column::SellerOrderId.eq(seller_order.id))
+        .order_by_desc(shipment::Column::CreatedAt)
+        .one(db)
+        .await?
+        .ok_or_else(|| AppError::not_found("This order has not been shipped yet"))?;
+
+    to_shipment(db, carriers, order_id, shipment).await
+}
+
+// The label of a sub-order's parcel, rendered again if the stored copy went missing
+#[allow(clippy::too_many_arguments)]
+pub async fn get_label_pdf(
+    db: &DatabaseConnection,
+    s3_client: &Arc<S3Client>,
+    bucket: &str,
+    carriers: &ShippingCarriers,
+    order_id: Uuid,
+    user_id: Uuid,
+    role: &UserRole,
+    seller_order_id: Option<Uuid>,
+) -> Result<(String, Vec<u8>)> {
+    ensure_shipper(role)?;
+    let (order, seller_order) = find_seller_order(db, order_id, user_id, role, seller_order_id).await?;
+
+    let shipment = open_shipment(db, seller_order.id)
+        .await?
+        .ok_or_else(|| AppError::not_found("This order has not been shipped yet"))?;
+
+    if let Ok(pdf) = load_pdf(s3_client, bucket, &shipment.label_storage_key).await {
+        return Ok((shipment.tracking_number, pdf));
+    }
+
+    let carrier = carriers.get(&shipment.carrier)?;
+    let request = shipment_request(
+        db,
+        &order,
+        &seller_order,
+        shipment.id,
+        shipment.weight_grams,
+        Some(shipment.tracking_number.clone()),
+        carriers.callback_url(&shipment.carrier, &shipment.callback_token),
+    )
+    .await?;
+    let booked = CarrierShipment {
+        tracking_number: shipment.tracking_number.clone(),
+        carrier_reference: shipment.carrier_reference.clone(),
+    };
+    let pdf = carrier.label(&request, &booked).await?;
+    store_pdf(s3_client, bucket, &shipment.label_storage_key, pdf.clone()).await?;
+
+    Ok((shipment.tracking_number, pdf))
+}
+
+// Call off a parcel the carrier has not picked up yet, so it can be booked again
+pub async fn cancel_shipment(
+    db: &DatabaseConnection,
+    carriers: &ShippingCarriers,
+    order_id: Uuid,
+    user_id: Uuid,
+    role: &UserRole,
+    seller_order_id: Option<Uuid>,
+) -> Result<()> {
+    ensure_shipper(role)?;
+    let (_, seller_order) = find_seller_order(db, order_id, user_id, role, seller_order_id).await?;
+
+    let shipment = open_shipment(db, seller_order.id)
+        .await?
+        .ok_or_else(|| AppError::not_found("This order has not been shipped yet"))?;
+
+    if parse_status(&shipment.status)? != ShipmentStatus::Created {
+        return Err(AppError::bad_request("The carrier already has this parcel"));
+    }
+
+    carriers.get(&shipment.carrier)?.cancel(&lookup(&shipment)).await?;
+
+    let now = Utc::now();
+    let txn = db.begin().await?;
+
+    let mut active = shipment.into_active_model();
+    active.status = Set(ShipmentStatus::Canceled.to_string());
+    active.canceled_at = Set(Some(now));
+    active.updated_at = Set(now);
+    active.update(&txn).await?;
+
+    let mut active = seller_order.into_active_model();
+    active.tracking_number = Set(None);
+    active.shipping_provider = Set(None);
+    active.updated_at = Set(now);
+    active.update(&txn).await?;
+
+    txn.commit().await?;
+
+    Ok(())
+}
+
+// Bring the sub-order along with its parcel: it is shipped once the carrier has it and
+// delivered when the carrier says so. Cash on delivery orders are delivered when the buyer
+// gives their code, so the carrier's word is only recorded.
+async fn follow_parcel<C: ConnectionTrait>(
+    db: &C,
+    seller_order_id: Uuid,
+    carrier_name: &str,
+    status: ShipmentStatus,
+) -> Result<()> {
+    if matches!(status, ShipmentStatus::Created | ShipmentStatus::Returned | ShipmentStatus::Canceled) {
+        return Ok(());
+    }
+
+    let mut seller_order = seller_order::Entity::find_by_id(seller_order_id)
+        .lock_exclusive()
+        .one(db)
+        .await?
+        .ok_or_else(|| AppError::not_found("Sub-order not found"))?;
+
+    if seller_order.status == OrderStatus::Processing.to_string() {
+        let change = StatusChange {
+            to: OrderStatus::Shipped,
+            actor: OrderActor::System,
+            actor_id: None,
+            note: Some(format!("Picked up by {}", carrier_name)),
+        };
+        seller_order = order_status::transition_seller_order(db, seller_order, change).await?;
+    }
+
+    if status == ShipmentStatus::Delivered
+        && seller_order.status == OrderStatus::Shipped.to_string()
+        && seller_order.delivery_code.is_none()
+    {
+        let change = StatusChange {
+            to: OrderStatus::Delivered,
+            actor: OrderActor::System,
+            actor_id: None,
+            note: Some(format!("Delivered by {}", carrier_name)),
+        };
+        order_status::transition_seller_order(db, seller_order, change).await?;
+    }
+
+    Ok(())
+}
+
+// Record the tracking events we do not have yet and move the parcel and its sub-order along.
+// Returns how many events were new.
+async fn apply_events(
+    db: &DatabaseConnection,
+    carrier: &dyn ShippingCarrier,
+    shipment_id: Uuid,
+    events: Vec<TrackingEvent>,
+    source: TrackingSource,
+    raw_payload: Option<serde_json::Value>,
+) -> Result<usize> {
+    let txn = db.begin().await?;
+
+    let shipment = shipment::Entity::find_by_id(shipment_id)
+        .lock_exclusive()
+        .one(&txn)
+        .await?
+        .ok_or_else(|| AppError::not_found("Shipment not found"))?;
+
+    // Late events for a parcel that was called off are ignored
+    if shipment.status == ShipmentStatus::Canceled.to_string() {
+        return Ok(0);
+    }
+
+    let known: Vec<(String, DateTime<Utc>)> = shipment_event::Entity::find()
+        .filter(shipment_event::Column::ShipmentId.eq(shipment.id))
+        .all(&txn)
+        .await?
+        .into_iter()
+        .map(|event| (event.status, event.occurred_at))
+        .collect();
+
+    let now = Utc::now();
+    let mut new_events: Vec<TrackingEvent> = Vec::new();
+    for event in events {
+        let key = (event.status.to_string(), event.occurred_at);
+        if known.contains(&key)
+            || new_events
+                .iter()
+                .any(|seen| seen.status == event.status && seen.occurred_at == event.occurred_at)
+        {
+            continue;
+        }
+
+        shipment_event::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            shipment_id: Set(shipment.id),
+            status: Set(event.status.to_string()),
+            description: Set(event.description.clone()),
+            location: Set(event.location.clone()),
+            occurred_at: Set(event.occurred_at),
+            source: Set(source.to_string()),
+            raw_payload: Set(raw_payload.clone()),
+            created_at: Set(now),
+        }
+        .insert(&txn)
+        .await?;
+        new_events.push(event);
+    }
+
+    // The parcel is wherever its most recent event puts it
+    let latest = new_events
+        .iter()
+        .filter(|event| shipment.last_event_at.is_none_or(|at| event.occurred_at >= at))
+        .max_by_key(|event| event.occurred_at);
+
+    if let Some(latest) = latest {
+        let status = latest.status;
+        let seller_order_id = shipment.seller_order_id;
+
+        let mut active = shipment.into_active_model();
+        active.status = Set(status.to_string());
+        active.last_event_at = Set(Some(latest.occurred_at));
+        active.updated_at = Set(now);
+        active.update(&txn).await?;
+
+        follow_parcel(&txn, seller_order_id, carrier.name(), status).await?;
+    }
+
+    txn.commit().await?;
+
+    Ok(new_events.len())
+}
+
+// A carrier pushing tracking events. The token the carrier was given for the shipment, on the URL
+// or in the body, must match the tracking number it reports on.
+pub async fn handle_webhook(
+    db: &DatabaseConnection,
+    carriers: &ShippingCarriers,
+    carrier_code: &str,
+    url_token: Option<String>,
+    payload: serde_json::Value,
+) -> Result<TrackingWebhookAck> {
+    let carrier = carriers.get(carrier_code)?;
+    let update = carrier
+        .parse_webhook(&payload)
+        .ok_or_else(|| AppError::bad_request("Unrecognized tracking event"))?;
+
+    let token = url_token
+        .or(update.token)
+        .ok_or_else(|| AppError::auth("Missing webhook token"))?;
+
+    let shipment = shipment::Entity::find()
+        .filter(shipment::Column::Carrier.eq(carrier.code()))
+        .filter(shipment::Column::CallbackToken.eq(token))
+        .filter(shipment::Column::TrackingNumber.eq(update.tracking_number))
+        .one(db)
+        .await?
+        .ok_or_else(|| AppError::auth("Invalid webhook token"))?;
+
+    let recorded = apply_events(
+        db,
+        carrier.as_ref(),
+        shipment.id,
+        update.events,
+        TrackingSource::Webhook,
+        Some(payload),
+    )
+    .await?;
+
+    Ok(TrackingWebhookAck { recorded })
+}
+
+// Ask carriers about parcels still on the way, least recently checked first.
+// Returns how many new tracking events were recorded.
+pub async fn track_shipments(db: &DatabaseConnection, carriers: &ShippingCarriers) -> Result<usize> {
+    let open = [
+        ShipmentStatus::Created,
+        ShipmentStatus::InTransit,
+        ShipmentStatus::OutForDelivery,
+        ShipmentStatus::Failed,
+    ];
+
+    let mut recorded = 0;
+    for carrier in carriers.tracked() {
+        let shipments = shipment::Entity::find()
+            .filter(shipment::Column::Carrier.eq(carrier.code()))
+            .filter(shipment::Column::Status.is_in(open.iter().map(|status| status.to_string())))
+            .order_by_with_nulls(shipment::Column::LastCheckedAt, Order::Asc, NullOrdering::First)
+            .limit(TRACKING_BATCH_SIZE)
+            .all(db)
+            .await?;
+
+        for shipment in shipments {
+            match carrier.track(&lookup(&shipment)).await {
+                Ok(events) => {
+                    recorded += apply_events(
+                        db,
+                        carrier.as_ref(),
+                        shipment.id,
+                        events,
+                        TrackingSource::TrackingJob,
+                        None,
+                    )
+                    .await?;
+                }
+                Err(e) => tracing::warn!(
+                    "Failed to track {} shipment {}: {:?}",
+                    carrier.code(),
+                    shipment.tracking_number,
+                    e
+                ),
+            }
+
+            shipment::Entity::update_many()
+                .col_expr(shipment::Column::LastCheckedAt, Expr::value(Utc::now()))
+                .filter(shipment::Column::Id.eq(shipment.id))
+                .exec(db)
+                .await?;
+        }
+    }
+
+    Ok(recorded)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entities::order_status_history;
+    use sea_orm::{DatabaseBackend, MockDatabase, Transaction, Value};
+    use std::collections::BTreeMap;
+
+    fn seller_order(status: OrderStatus, delivery_code: Option<&str>) -> seller_order::Model {
+        let now = Utc::now();
+        seller_order::Model {
+            id: Uuid::new_v4(),
+            order_id: Uuid::new_v4(),
+            seller_id: Uuid::new_v4(),
+            status: status.to_string(),
+            subtotal: 5000.into(),
+            discount_amount: 0.into(),
+            shipping_amount: 0.into(),
+            tax_amount: 0.into(),
+            total_amount: 5000.into(),
+            fulfillment_status: "unfulfilled".to_string(),
+            shipping_method: ShippingMethod::Delivery.to_string(),
+            shipping_zone: None,
+            shipping_rate_id: None,
+            tracking_number: None,
+            shipping_provider: None,
+            shipped_at: None,
+            delivered_at: None,
+            payout_status: "pending".to_string(),
+            payout_amount: 0.into(),
+            paid_out_at: None,
+            cancellation_reason: None,
+            cancellation_note: None,
+            canceled_by: None,
+            canceled_at: None,
+            delivery_code: delivery_code.map(str::to_string),
+            delivery_code_attempts: 0,
+            cod_collected_amount: None,
+            cod_collected_at: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    fn with_status(seller_order: &seller_order::Model, status: OrderStatus) -> seller_order::Model {
+        seller_order::Model {
+            status: status.to_string(),
+            ..seller_order.clone()
+        }
+    }
+
+    // The parent order, already at the status the sub-order moves to so it is not rolled up again
+    fn order(seller_order: &seller_order::Model, status: OrderStatus) -> order::Model {
+        let now = Utc::now();
+        order::Model {
+            id: seller_order.order_id,
+            user_id: Uuid::new_v4(),
+            subtotal: 5000.into(),
+            discount_amount: 0.into(),
+            shipping_amount: 0.into(),
+            tax_amount: 0.into(),
+            total_amount: 5000.into(),
+            discount_code_id: None,
+            status: status.to_string(),
+            payment_status: PaymentStatus::Paid.to_string(),
+            payment_method: PaymentMethod::Mtn.to_string(),
+            shipping_address: serde_json::json!({}),
+            idempotency_key: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    fn history(seller_order: &seller_order::Model) -> order_status_history::Model {
+        order_status_history::Model {
+            id: Uuid::new_v4(),
+            order_id: seller_order.order_id,
+            seller_order_id: Some(seller_order.id),
+            from_status: None,
+            to_status: seller_order.status.clone(),
+            actor_id: None,
+            actor_role: OrderActor::System.to_string(),
+            note: None,
+            created_at: Utc::now(),
+        }
+    }
+
+    // What a transition to the given status reads and writes, for a sub-order not paid online
+    fn transition(mock: MockDatabase, seller_order: &seller_order::Model, to: OrderStatus) -> MockDatabase {
+        let updated = with_status(seller_order, to.clone());
+        let mock = mock.append_query_results([[updated.clone()]]);
+        let mock = if to == OrderStatus::Delivered {
+            mock.append_query_results([[BTreeMap::from([("found", Value::from(false))])]])
+        } else {
+            mock
+        };
+        mock.append_query_results([[history(&updated)]])
+            .append_query_results([[order(&updated, to)]])
+            .append_query_results([[updated]])
+    }
+
+    // The statuses the sub-order was moved to, in order
+    fn seller_order_updates(log: Vec<Transaction>) -> Vec<String> {
+        log.iter()
+            .flat_map(|txn| txn.statements().to_vec())
+            .filter(|stmt| stmt.sql.starts_with("UPDATE \"seller_orders\""))
+            .filter_map(|stmt| {
+                stmt.values.unwrap().0.into_iter().find_map(|value| match value {
+                    Value::String(Some(status)) if status.parse::<OrderStatus>().is_ok() => Some(*status),
+                    _ => None,
+                })
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn parcels_delivered_by_the_carrier_deliver_the_sub_order() {
+        let shipped = seller_order(OrderStatus::Shipped, None);
+        let mock = MockDatabase::new(DatabaseBackend::Postgres).append_query_results([[shipped.clone()]]);
+        let db = transition(mock, &shipped, OrderStatus::Delivered).into_connection();
+
+        follow_parcel(&db, shipped.id, "Mock carrier", ShipmentStatus::Delivered).await.unwrap();
+
+        assert_eq!(seller_order_updates(db.into_transaction_log()), vec![OrderStatus::Delivered.to_string()]);
+    }
+
+    #[tokio::test]
+    async fn cash_on_delivery_parcels_wait_for_the_buyers_code() {
+        let processing = seller_order(OrderStatus::Processing, Some("123456"));
+        let mock = MockDatabase::new(DatabaseBackend::Postgres).append_query_results([[processing.clone()]]);
+        let db = transition(mock, &processing, OrderStatus::Shipped).into_connection();
+
+        follow_parcel(&db, processing.id, "Mock carrier", ShipmentStatus::Delivered).await.unwrap();
+
+        // Picked up, but only the buyer's code marks it delivered
+        assert_eq!(seller_order_updates(db.into_transaction_log()), vec![OrderStatus::Shipped.to_string()]);
+    }
+
+    fn shipment(seller_order: &seller_order::Model) -> shipment::Model {
+        let now = Utc::now();
+        shipment::Model {
+            id: Uuid::new_v4(),
+            seller_order_id: seller_order.id,
+            carrier: "mock".to_string(),
+            tracking_number: "MOCK1".to_string(),
+            carrier_reference: None,
+            status: ShipmentStatus::Created.to_string(),
+            weight_grams: 1000,
+            label_storage_key: String::new(),
+            callback_token: "secret".to_string(),
+            created_by: None,
+            last_event_at: None,
+            last_checked_at: None,
+            canceled_at: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    fn event(shipment: &shipment::Model, status: ShipmentStatus, occurred_at: DateTime<Utc>) -> shipment_event::Model {
+        shipment_event::Model {
+            id: Uuid::new_v4(),
+            shipment_id: shipment.id,
+            status: status.to_string(),
+            description: None,
+            location: None,
+            occurred_at,
+            source: TrackingSource::Webhook.to_string(),
+            raw_payload: None,
+            created_at: Utc::now(),
+        }
+    }
+
+    fn picked_up_payload(occurred_at: DateTime<Utc>) -> serde_json::Value {
+        serde_json::json!({
+            "tracking_number": "MOCK1",
+            "status": "in_transit",
+            "location": "Douala",
+            "occurred_at": occurred_at.to_rfc3339(),
+        })
+    }
+
+    #[tokio::test]
+    async fn tracking_webhooks_ship_the_sub_order() {
+        let carriers = ShippingCarriers::mock();
+        let processing = seller_order(OrderStatus::Processing, None);
+        let created = shipment(&processing);
+        let at = Utc::now();
+
+        let mock = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([[created.clone()]])
+            .append_query_results([[created.clone()]])
+            .append_query_results([Vec::<shipment_event::Model>::new()])
+            .append_query_results([[event(&created, ShipmentStatus::InTransit, at)]])
+            .append_query_results([[created.clone()]])
+            .append_query_results([[processing.clone()]]);
+        let db = transition(mock, &processing, OrderStatus::Shipped).into_connection();
+
+        let ack = handle_webhook(&db, &carriers, "mock", Some("secret".to_string()), picked_up_payload(at))
+            .await
+            .unwrap();
+
+        assert_eq!(ack.recorded, 1);
+        assert_eq!(seller_order_updates(db.into_transaction_log()), vec![OrderStatus::Shipped.to_string()]);
+    }
+
+    #[tokio::test]
+    async fn repeated_tracking_events_are_recorded_once() {
+        let carriers = ShippingCarriers::mock();
+        let shipped = seller_order(OrderStatus::Shipped, None);
+        let created = shipment(&shipped);
+        let at = Utc::now();
+
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([[created.clone()]])
+            .append_query_results([[created.clone()]])
+            .append_query_results([[event(&created, ShipmentStatus::InTransit, at)]])
+            .into_connection();
+
+        let ack = handle_webhook(&db, &carriers, "mock", Some("secret".to_string()), picked_up_payload(at))
+            .await
+            .unwrap();
+
+        assert_eq!(ack.recorded, 0);
+        let log = db.into_transaction_log();
+        assert!(!log
+            .iter()
+            .flat_map(|txn| txn.statements().to_vec())
+            .any(|stmt| stmt.sql.starts_with("INSERT") || stmt.sql.starts_with("UPDATE")));
+    }
+
+    #[tokio::test]
+    async fn tracking_webhooks_need_the_shipments_token() {
+        let carriers = ShippingCarriers::mock();
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([Vec::<shipment::Model>::new()])
+            .into_connection();
+
+        let result = handle_webhook(&db, &carriers, "mock", Some("wrong".to_string()), picked_up_payload(Utc::now())).await;
+
+        assert!(matches!(result, Err(AppError::Auth(_))));
+    }
+}
@@ -0,0 +1,427 @@
+use bigdecimal::BigDecimal;
+use chrono::Utc;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, Condition, ConnectionTrait, DatabaseConnection, EntityTrait, IntoActiveModel,
+    QueryFilter, QueryOrder, Set,
+};
+use std::str::FromStr;
+use uuid::Uuid;
+
+use crate::entities::{seller_order, seller_shipping_setting, shipping_rate, user};
+use crate::errors::{AppError, Result};
+use crate::models::money::{Currency, Money};
+use crate::models::shipping::{
+    CreateShippingRateRequest, SellerShippingQuote, ShippingChoice, ShippingMethod, ShippingOption, ShippingRate,
+    ShippingRateQuery, ShippingSettings, ShippingZone, UpdateShippingRateRequest, UpdateShippingSettingsRequest,
+};
+use crate::models::user::UserRole;
+use crate::utils::validation;
+
+// Sellers who have not set up delivery hand parcels to their own couriers
+const DEFAULT_CARRIER: &str = "manual";
+
+fn to_money(value: &BigDecimal) -> Result<Money> {
+    Ok(Money::from_decimal(value, Currency::XAF)?)
+}
+
+fn to_rate(model: shipping_rate::Model) -> Result<ShippingRate> {
+    Ok(ShippingRate {
+        id: model.id,
+        seller_id: model.seller_id,
+        name: model.name,
+        carrier: model.carrier,
+        zone: ShippingZone::from_str(&model.zone).map_err(AppError::internal)?,
+        min_weight_grams: model.min_weight_grams,
+        max_weight_grams: model.max_weight_grams,
+        price: to_money(&model.price)?,
+        free_above: model.free_above.as_ref().map(to_money).transpose()?,
+        delivery_days: model.delivery_days,
+        is_active: model.is_active,
+        created_at: model.created_at,
+    })
+}
+
+fn ensure_xaf(field: &str, amount: Money) -> Result<Money> {
+    if amount.currency() != Currency::XAF {
+        return Err(AppError::validation(format!("{}: Shipping is charged in XAF", field)));
+    }
+    if amount.is_negative() {
+        return Err(AppError::validation(format!("{}: The amount cannot be negative", field)));
+    }
+    Ok(amount)
+}
+
+fn ensure_free_above(free_above: Money) -> Result<Money> {
+    let free_above = ensure_xaf("free_above", free_above)?;
+    if free_above.is_zero() {
+        return Err(AppError::validation("free_above: Leave it empty rather than zero"));
+    }
+    Ok(free_above)
+}
+
+fn ensure_bracket(min_weight_grams: i32, max_weight_grams: Option<i32>) -> Result<()> {
+    if max_weight_grams.is_some_and(|max| max <= min_weight_grams) {
+        return Err(AppError::validation("max_weight_grams: The bracket must end above its minimum weight"));
+    }
+    Ok(())
+}
+
+// Whose rates a user manages: sellers their own, admins the marketplace defaults
+pub fn rate_owner(user_id: Uuid, role: &UserRole) -> Result<Option<Uuid>> {
+    match role {
+        UserRole::Seller => Ok(Some(user_id)),
+        UserRole::Admin => Ok(None),
+        _ => Err(AppError::forbidden("Only sellers and admins can manage shipping rates")),
+    }
+}
+
+fn owned_by(owner: Option<Uuid>) -> Condition {
+    match owner {
+        Some(seller_id) => Condition::all().add(shipping_rate::Column::SellerId.eq(seller_id)),
+        None => Condition::all().add(shipping_rate::Column::SellerId.is_null()),
+    }
+}
+
+async fn find_rate(db: &DatabaseConnection, owner: Option<Uuid>, rate_id: Uuid) -> Result<shipping_rate::Model> {
+    shipping_rate::Entity::find_by_id(rate_id)
+        .filter(owned_by(owner))
+        .one(db)
+        .await?
+        .ok_or_else(|| AppError::not_found("Shipping rate not found"))
+}
+
+// A seller's rates, or the marketplace defaults, by zone and weight
+pub async fn get_rates(db: &DatabaseConnection, owner: Option<Uuid>, query: ShippingRateQuery) -> Result<Vec<ShippingRate>> {
+    let owner = if query.defaults == Some(true) { None } else { owner };
+
+    let mut select = shipping_rate::Entity::find().filter(owned_by(owner));
+    if let Some(zone) = query.zone {
+        select = select.filter(shipping_rate::Column::Zone.eq(zone.to_string()));
+    }
+
+    select
+        .order_by_asc(shipping_rate::Column::Zone)
+        .order_by_asc(shipping_rate::Column::MinWeightGrams)
+        .order_by_asc(shipping_rate::Column::Price)
+        .all(db)
+        .await?
+        .into_iter()
+        .map(to_rate)
+        .collect()
+}
+
+pub async fn create_rate(db: &DatabaseConnection, owner: Option<Uuid>, payload: CreateShippingRateRequest) -> Result<ShippingRate> {
+    validation::validate(&payload)?;
+
+    let min_weight_grams = payload.min_weight_grams.unwrap_or(0);
+    ensure_bracket(min_weight_grams, payload.max_weight_grams)?;
+
+    let now = Utc::now();
+    let rate = shipping_rate::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        seller_id: Set(owner),
+        name: Set(payload.name.trim().to_string()),
+        carrier: Set(payload.carrier.map(|c| c.trim().to_lowercase()).unwrap_or_else(|| DEFAULT_CARRIER.to_string())),
+        zone: Set(payload.zone.to_string()),
+        min_weight_grams: Set(min_weight_grams),
+        max_weight_grams: Set(payload.max_weight_grams),
+        price: Set(ensure_xaf("price", payload.price)?.to_decimal()),
+        free_above: Set(payload.free_above.map(ensure_free_above).transpose()?.map(|m| m.to_decimal())),
+        delivery_days: Set(payload.delivery_days),
+        is_active: Set(true),
+        created_at: Set(now),
+        updated_at: Set(now),
+    }
+    .insert(db)
+    .await?;
+
+    to_rate(rate)
+}
+
+pub async fn update_rate(
+    db: &DatabaseConnection,
+    owner: Option<Uuid>,
+    rate_id: Uuid,
+    payload: UpdateShippingRateRequest,
+) -> Result<ShippingRate> {
+    validation::validate(&payload)?;
+
+    let rate = find_rate(db, owner, rate_id).await?;
+    let min_weight_grams = payload.min_weight_grams.unwrap_or(rate.min_weight_grams);
+    let max_weight_grams = payload.max_weight_grams.or(rate.max_weight_grams);
+    ensure_bracket(min_weight_grams, max_weight_grams)?;
+
+    let mut active = rate.into_active_model();
+    if let Some(name) = payload.name {
+        active.name = Set(name.trim().to_string());
+    }
+    if let Some(carrier) = payload.carrier {
+        active.carrier = Set(carrier.trim().to_lowercase());
+    }
+    if let Some(price) = payload.price {
+        active.price = Set(ensure_xaf("price", price)?.to_decimal());
+    }
+    if let Some(free_above) = payload.free_above {
+        active.free_above = Set(Some(ensure_free_above(free_above)?.to_decimal()));
+    }
+    if let Some(delivery_days) = payload.delivery_days {
+        active.delivery_days = Set(Some(delivery_days));
+    }
+    if let Some(is_active) = payload.is_active {
+        active.is_active = Set(is_active);
+    }
+    active.min_weight_grams = Set(min_weight_grams);
+    active.max_weight_grams = Set(max_weight_grams);
+    active.updated_at = Set(Utc::now());
+
+    to_rate(active.update(db).await?)
+}
+
+// Rates no order used are deleted; the others are switched off so past orders keep them
+pub async fn remove_rate(db: &DatabaseConnection, owner: Option<Uuid>, rate_id: Uuid) -> Result<()> {
+    let rate = find_rate(db, owner, rate_id).await?;
+
+    let used = seller_order::Entity::find()
+        .filter(seller_order::Column::ShippingRateId.eq(rate.id))
+        .one(db)
+        .await?
+        .is_some();
+
+    if !used {
+        shipping_rate::Entity::delete_by_id(rate.id).exec(db).await?;
+    } else if rate.is_active {
+        let mut active = rate.into_active_model();
+        active.is_active = Set(false);
+        active.updated_at = Set(Utc::now());
+        active.update(db).await?;
+    }
+
+    Ok(())
+}
+
+pub async fn get_settings(db: &DatabaseConnection, seller_id: Uuid) -> Result<ShippingSettings> {
+    let settings = seller_shipping_setting::Entity::find_by_id(seller_id).one(db).await?;
+
+    Ok(ShippingSettings {
+        seller_id,
+        ship_from_city: settings.as_ref().and_then(|s| s.ship_from_city.clone()),
+        pickup_enabled: settings.as_ref().is_some_and(|s| s.pickup_enabled),
+        pickup_address: settings.and_then(|s| s.pickup_address),
+    })
+}
+
+pub async fn update_settings(
+    db: &DatabaseConnection,
+    seller_id: Uuid,
+    payload: UpdateShippingSettingsRequest,
+) -> Result<ShippingSettings> {
+    validation::validate(&payload)?;
+
+    let existing = seller_shipping_setting::Entity::find_by_id(seller_id).one(db).await?;
+    let ship_from_city = payload
+        .ship_from_city
+        .map(|city| city.trim().to_string())
+        .or_else(|| existing.as_ref().and_then(|s| s.ship_from_city.clone()));
+    let pickup_enabled = payload
+        .pickup_enabled
+        .unwrap_or_else(|| existing.as_ref().is_some_and(|s| s.pickup_enabled));
+    let pickup_address = payload
+        .pickup_address
+        .map(|address| address.trim().to_string())
+        .or_else(|| existing.as_ref().and_then(|s| s.pickup_address.clone()));
+
+    if pickup_enabled && pickup_address.is_none() {
+        return Err(AppError::validation("pickup_address: Tell buyers where to collect their orders"));
+    }
+
+    let now = Utc::now();
+    match existing {
+        Some(settings) => {
+            let mut active = settings.into_active_model();
+            active.ship_from_city = Set(ship_from_city);
+            active.pickup_enabled = Set(pickup_enabled);
+            active.pickup_address = Set(pickup_address);
+            active.updated_at = Set(now);
+            active.update(db).await?;
+        }
+        None => {
+            seller_shipping_setting::ActiveModel {
+                seller_id: Set(seller_id),
+                ship_from_city: Set(ship_from_city),
+                pickup_enabled: Set(pickup_enabled),
+                pickup_address: Set(pickup_address),
+                created_at: Set(now),
+                updated_at: Set(now),
+            }
+            .insert(db)
+            .await?;
+        }
+    }
+
+    get_settings(db, seller_id).await
+}
+
+// Where the buyer wants their order delivered
+pub struct Destination {
+    pub city: String,
+    pub country: String,
+}
+
+impl Destination {
+    pub fn new(city: &str, country: Option<&str>) -> Self {
+        Self {
+            city: city.trim().to_string(),
+            country: country.map(str::trim).filter(|c| !c.is_empty()).unwrap_or("Cameroon").to_string(),
+        }
+    }
+}
+
+// Compare place names without case, accents or punctuation, so "Yaoundé" matches "YAOUNDE"
+fn normalize_place(name: &str) -> String {
+    name.chars()
+        .filter_map(|c| match c.to_lowercase().next().unwrap_or(c) {
+            'à' | 'â' | 'ä' => Some('a'),
+            'é' | 'è' | 'ê' | 'ë' => Some('e'),
+            'î' | 'ï' => Some('i'),
+            'ô' | 'ö' => Some('o'),
+            'ù' | 'û' | 'ü' => Some('u'),
+            'ç' => Some('c'),
+            c if c.is_alphanumeric() => Some(c),
+            _ => None,
+        })
+        .collect()
+}
+
+fn is_cameroon(country: &str) -> bool {
+    matches!(normalize_place(country).as_str(), "cameroon" | "cameroun" | "cm" | "cmr")
+}
+
+// Work out the zone of a delivery from the city the seller ships from
+pub fn classify_zone(origin: Option<&str>, destination: &Destination) -> ShippingZone {
+    if !is_cameroon(&destination.country) {
+        return ShippingZone::International;
+    }
+
+    let to = normalize_place(&destination.city);
+    let Some(from) = origin.map(normalize_place).filter(|from| !from.is_empty()) else {
+        return ShippingZone::OtherRegions;
+    };
+
+    let metropoles = ["douala", "yaounde"];
+    if from == to {
+        ShippingZone::SameCity
+    } else if metropoles.contains(&from.as_str()) && metropoles.contains(&to.as_str()) {
+        ShippingZone::DoualaYaounde
+    } else {
+        ShippingZone::OtherRegions
+    }
+}
+
+// Quote delivery of a seller's items: their total weight and their price before discount codes.
+// The seller's own rates for the zone are used, or else the marketplace defaults; options are
+// sorted cheapest first and pickup in store, when offered, comes last.
+pub async fn quote_seller<C: ConnectionTrait>(
+    db: &C,
+    seller_id: Uuid,
+    weight_grams: i64,
+    goods: Money,
+    destination: &Destination,
+) -> Result<SellerShippingQuote> {
+    let settings = seller_shipping_setting::Entity::find_by_id(seller_id).one(db).await?;
+    let origin = match settings.as_ref().and_then(|s| s.ship_from_city.clone()) {
+        Some(city) => Some(city),
+        None => user::Entity::find_by_id(seller_id)
+            .one(db)
+            .await?
+            .and_then(|seller| seller.address_city),
+    };
+    let zone = classify_zone(origin.as_deref(), destination);
+
+    let rates = shipping_rate::Entity::find()
+        .filter(shipping_rate::Column::Zone.eq(zone.to_string()))
+        .filter(shipping_rate::Column::IsActive.eq(true))
+        .filter(
+            Condition::any()
+                .add(shipping_rate::Column::SellerId.eq(seller_id))
+                .add(shipping_rate::Column::SellerId.is_null()),
+        )
+        .all(db)
+        .await?;
+    let own_rates = rates.iter().any(|rate| rate.seller_id.is_some());
+
+    let mut options = Vec::new();
+    for rate in rates {
+        if own_rates && rate.seller_id.is_none() {
+            continue;
+        }
+        let in_bracket = weight_grams >= rate.min_weight_grams as i64
+            && rate.max_weight_grams.is_none_or(|max| weight_grams <= max as i64);
+        if !in_bracket {
+            continue;
+        }
+
+        let free_shipping = match &rate.free_above {
+            Some(free_above) => goods >= to_money(free_above)?,
+            None => false,
+        };
+        options.push(ShippingOption {
+            method: ShippingMethod::Delivery,
+            rate_id: Some(rate.id),
+            name: rate.name,
+            carrier: Some(rate.carrier),
+            price: if free_shipping { Money::zero(Currency::XAF) } else { to_money(&rate.price)? },
+            free_shipping,
+            delivery_days: rate.delivery_days,
+            pickup_address: None,
+        });
+    }
+    options.sort_by_key(|option| (option.price.amount(), option.delivery_days.unwrap_or(i32::MAX)));
+
+    if let Some(settings) = settings.filter(|s| s.pickup_enabled) {
+        options.push(ShippingOption {
+            method: ShippingMethod::Pickup,
+            rate_id: None,
+            name: "Pickup in store".to_string(),
+            carrier: None,
+            price: Money::zero(Currency::XAF),
+            free_shipping: false,
+            delivery_days: None,
+            pickup_address: settings.pickup_address,
+        });
+    }
+
+    Ok(SellerShippingQuote {
+        seller_id,
+        zone,
+        weight_grams,
+        options,
+    })
+}
+
+// The option the buyer picked for a seller, or the cheapest delivery when they did not pick one
+pub fn choose_option(quote: &SellerShippingQuote, choice: Option<&ShippingChoice>, destination: &Destination) -> Result<ShippingOption> {
+    let option = match choice {
+        Some(choice) if choice.pickup => quote
+            .options
+            .iter()
+            .find(|option| option.method == ShippingMethod::Pickup)
+            .ok_or_else(|| AppError::bad_request("A seller in your cart does not offer pickup in store"))?,
+        Some(ShippingChoice { rate_id: Some(rate_id), .. }) => quote
+            .options
+            .iter()
+            .find(|option| option.rate_id == Some(*rate_id))
+            .ok_or_else(|| AppError::bad_request("The chosen shipping option is not available for this delivery"))?,
+        _ => quote
+            .options
+            .iter()
+            .find(|option| option.method == ShippingMethod::Delivery)
+            .ok_or_else(|| {
+                AppError::bad_request(format!(
+                    "A seller in your cart does not deliver to {}; choose pickup or remove their items",
+                    destination.city
+                ))
+            })?,
+    };
+
+    Ok(option.clone())
+}
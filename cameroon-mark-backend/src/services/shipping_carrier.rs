@@ -0,0 +1,477 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use rand::{distributions::Alphanumeric, thread_rng, Rng};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use uuid::Uuid;
+
+use crate::config::ShippingConfig;
+use crate::errors::{AppError, Result};
+use crate::models::money::Money;
+use crate::models::order::ShippingAddress;
+use crate::models::shipment::ShipmentStatus;
+use crate::utils::pdf::{PdfWriter, MARGIN};
+
+// Carrier used for sellers' own couriers, and when a rate names a carrier we do not know
+pub const MANUAL_CARRIER: &str = "manual";
+
+// What we ask a carrier to pick up and deliver
+#[derive(Debug, Clone)]
+pub struct ShipmentRequest {
+    // Our shipment id, sent to the carrier as the external reference
+    pub shipment_id: Uuid,
+    pub order_id: Uuid,
+    pub seller_order_id: Uuid,
+    pub sender_name: String,
+    pub sender_city: Option<String>,
+    pub sender_phone: Option<String>,
+    pub recipient: ShippingAddress,
+    pub weight_grams: i32,
+    // Cash the courier collects from the buyer
+    pub cash_on_delivery: Option<Money>,
+    // Typed in by sellers who use their own couriers
+    pub tracking_number: Option<String>,
+    // Where the carrier pushes tracking events for this shipment
+    pub callback_url: String,
+}
+
+// The carrier's answer to a shipment request
+#[derive(Debug, Clone)]
+pub struct CarrierShipment {
+    pub tracking_number: String,
+    // Id the carrier knows the shipment by, when it differs from the tracking number
+    pub carrier_reference: Option<String>,
+}
+
+// Everything a carrier needs to look a shipment up again
+#[derive(Debug, Clone)]
+pub struct ShipmentLookup {
+    pub tracking_number: String,
+    pub carrier_reference: Option<String>,
+}
+
+// One step of a parcel's journey as the carrier reports it
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrackingEvent {
+    pub status: ShipmentStatus,
+    pub description: Option<String>,
+    pub location: Option<String>,
+    pub occurred_at: DateTime<Utc>,
+}
+
+// What a carrier's webhook says about a shipment
+#[derive(Debug, Clone)]
+pub struct TrackingUpdate {
+    pub tracking_number: String,
+    // Secret identifying the shipment, for carriers that send it in the body
+    pub token: Option<String>,
+    pub events: Vec<TrackingEvent>,
+}
+
+// A way of getting parcels to buyers: a courier company's API, or the seller's own delivery
+#[async_trait]
+pub trait ShippingCarrier: Send + Sync {
+    // Stored on shipping rates and shipments, e.g. "manual"
+    fn code(&self) -> &'static str;
+
+    fn name(&self) -> &'static str;
+
+    // Book the shipment with the carrier and get its tracking number
+    async fn create_shipment(&self, request: &ShipmentRequest) -> Result<CarrierShipment>;
+
+    // The label to stick on the parcel, as a PDF
+    async fn label(&self, request: &ShipmentRequest, shipment: &CarrierShipment) -> Result<Vec<u8>>;
+
+    // Call the pickup off. Parcels the carrier already has cannot be canceled.
+    async fn cancel(&self, lookup: &ShipmentLookup) -> Result<()>;
+
+    // Whether the carrier can be asked for tracking events. Otherwise the seller reports progress.
+    fn supports_tracking(&self) -> bool {
+        false
+    }
+
+    // Every tracking event the carrier has for a shipment
+    async fn track(&self, _lookup: &ShipmentLookup) -> Result<Vec<TrackingEvent>> {
+        Err(AppError::bad_request(format!("{} shipments are tracked by the seller", self.name())))
+    }
+
+    // Read a webhook call. None when the body is not a tracking event from this carrier.
+    fn parse_webhook(&self, _payload: &serde_json::Value) -> Option<TrackingUpdate> {
+        None
+    }
+}
+
+// An A4 label with the sender, recipient and tracking number, for carriers without their own
+pub fn render_label(carrier_name: &str, request: &ShipmentRequest, tracking_number: &str) -> Result<Vec<u8>> {
+    let mut pdf = PdfWriter::new(&format!("Shipping label {}", tracking_number))?;
+
+    pdf.text(carrier_name, MARGIN, 16.0, true);
+    pdf.advance(10.0);
+    pdf.text("Tracking number", MARGIN, 9.0, false);
+    pdf.advance(8.0);
+    pdf.text(tracking_number, MARGIN, 22.0, true);
+    pdf.advance(10.0);
+    pdf.rule();
+    pdf.advance(8.0);
+
+    let recipient = &request.recipient;
+    pdf.text("Deliver to", MARGIN, 9.0, true);
+    pdf.advance(7.0);
+    let mut lines = vec![recipient.name.clone(), recipient.address_1.clone()];
+    lines.extend(recipient.address_2.clone().filter(|a| !a.is_empty()));
+    lines.push(match &recipient.postal_code {
+        Some(code) if !code.is_empty() => format!("{} {}", code, recipient.city),
+        _ => recipient.city.clone(),
+    });
+    lines.push(recipient.country.clone());
+    lines.extend(recipient.phone.clone());
+    for line in lines {
+        pdf.text(&line, MARGIN, 14.0, false);
+        pdf.advance(7.0);
+    }
+    pdf.advance(4.0);
+    pdf.rule();
+    pdf.advance(8.0);
+
+    pdf.text("From", MARGIN, 9.0, true);
+    pdf.advance(5.0);
+    let from = [
+        Some(request.sender_name.clone()),
+        request.sender_city.clone(),
+        request.sender_phone.clone(),
+    ];
+    for line in from.into_iter().flatten() {
+        pdf.text(&line, MARGIN, 10.0, false);
+        pdf.advance(5.0);
+    }
+    pdf.advance(6.0);
+
+    pdf.text(&format!("Order {}", request.order_id), MARGIN, 9.0, false);
+    pdf.advance(5.0);
+    pdf.text(&format!("Weight {:.2} kg", request.weight_grams as f64 / 1000.0), MARGIN, 9.0, false);
+    pdf.advance(5.0);
+    if let Some(amount) = request.cash_on_delivery {
+        pdf.advance(4.0);
+        pdf.text(&format!("Cash on delivery: collect {}", amount), MARGIN, 14.0, true);
+        pdf.advance(6.0);
+        pdf.text("Hand over only when the buyer gives their delivery code", MARGIN, 9.0, false);
+    }
+
+    pdf.finish()
+}
+
+fn random_code(length: usize) -> String {
+    thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(length)
+        .map(char::from)
+        .collect::<String>()
+        .to_uppercase()
+}
+
+// The seller delivers with their own couriers and reports progress on the order themselves
+pub struct ManualCarrier;
+
+#[async_trait]
+impl ShippingCarrier for ManualCarrier {
+    fn code(&self) -> &'static str {
+        MANUAL_CARRIER
+    }
+
+    fn name(&self) -> &'static str {
+        "Own courier"
+    }
+
+    async fn create_shipment(&self, request: &ShipmentRequest) -> Result<CarrierShipment> {
+        let tracking_number = match &request.tracking_number {
+            Some(number) => number.trim().to_string(),
+            None => format!("CM{}{}", Utc::now().format("%y%m%d"), random_code(6)),
+        };
+
+        Ok(CarrierShipment {
+            tracking_number,
+            carrier_reference: None,
+        })
+    }
+
+    async fn label(&self, request: &ShipmentRequest, shipment: &CarrierShipment) -> Result<Vec<u8>> {
+        render_label(self.name(), request, &shipment.tracking_number)
+    }
+
+    async fn cancel(&self, _lookup: &ShipmentLookup) -> Result<()> {
+        Ok(())
+    }
+}
+
+// In-memory carrier for tests and local development. Parcels can be moved along by hand to stand in for the courier.
+pub struct MockCarrier {
+    shipments: Mutex<HashMap<String, Vec<TrackingEvent>>>,
+}
+
+impl MockCarrier {
+    pub fn new() -> Self {
+        Self {
+            shipments: Mutex::new(HashMap::new()),
+        }
+    }
+
+    // Record a tracking event as if the courier had scanned the parcel
+    pub fn advance(&self, tracking_number: &str, status: ShipmentStatus) -> Result<()> {
+        let mut shipments = self.shipments.lock().unwrap_or_else(|e| e.into_inner());
+        let events = shipments
+            .get_mut(tracking_number)
+            .ok_or_else(|| AppError::external_service(format!("Mock: unknown shipment {}", tracking_number)))?;
+        events.push(TrackingEvent {
+            status,
+            description: None,
+            location: None,
+            occurred_at: Utc::now(),
+        });
+        Ok(())
+    }
+}
+
+impl Default for MockCarrier {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn parse_event(value: &serde_json::Value) -> Option<TrackingEvent> {
+    Some(TrackingEvent {
+        status: value["status"].as_str()?.parse().ok()?,
+        description: value["description"].as_str().map(str::to_string),
+        location: value["location"].as_str().map(str::to_string),
+        occurred_at: value["occurred_at"]
+            .as_str()
+            .and_then(|at| DateTime::parse_from_rfc3339(at).ok())
+            .map(|at| at.with_timezone(&Utc))
+            .unwrap_or_else(Utc::now),
+    })
+}
+
+#[async_trait]
+impl ShippingCarrier for MockCarrier {
+    fn code(&self) -> &'static str {
+        "mock"
+    }
+
+    fn name(&self) -> &'static str {
+        "Mock carrier"
+    }
+
+    async fn create_shipment(&self, request: &ShipmentRequest) -> Result<CarrierShipment> {
+        let tracking_number = format!("MOCK{}", request.shipment_id.simple().to_string()[..12].to_uppercase());
+        self.shipments
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(tracking_number.clone(), Vec::new());
+
+        Ok(CarrierShipment {
+            tracking_number,
+            carrier_reference: Some(request.shipment_id.to_string()),
+        })
+    }
+
+    async fn label(&self, request: &ShipmentRequest, shipment: &CarrierShipment) -> Result<Vec<u8>> {
+        render_label(self.name(), request, &shipment.tracking_number)
+    }
+
+    async fn cancel(&self, lookup: &ShipmentLookup) -> Result<()> {
+        let mut shipments = self.shipments.lock().unwrap_or_else(|e| e.into_inner());
+        match shipments.get(&lookup.tracking_number) {
+            Some(events) if !events.is_empty() => {
+                Err(AppError::bad_request("The carrier already has this parcel"))
+            }
+            _ => {
+                shipments.remove(&lookup.tracking_number);
+                Ok(())
+            }
+        }
+    }
+
+    fn supports_tracking(&self) -> bool {
+        true
+    }
+
+    async fn track(&self, lookup: &ShipmentLookup) -> Result<Vec<TrackingEvent>> {
+        self.shipments
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(&lookup.tracking_number)
+            .cloned()
+            .ok_or_else(|| AppError::external_service(format!("Mock: unknown shipment {}", lookup.tracking_number)))
+    }
+
+    // {"tracking_number", "token", "events": [{"status", "description", "location", "occurred_at"}]},
+    // or a single event at the top level
+    fn parse_webhook(&self, payload: &serde_json::Value) -> Option<TrackingUpdate> {
+        let events = match payload["events"].as_array() {
+            Some(events) => events.iter().map(parse_event).collect::<Option<Vec<_>>>()?,
+            None => vec![parse_event(payload)?],
+        };
+
+        Some(TrackingUpdate {
+            tracking_number: payload["tracking_number"].as_str()?.to_string(),
+            token: payload["token"].as_str().map(str::to_string),
+            events,
+        })
+    }
+}
+
+// The carriers sellers can ship with, by code
+pub struct ShippingCarriers {
+    carriers: HashMap<&'static str, Arc<dyn ShippingCarrier>>,
+    // Public URL of this API, where carriers push tracking events
+    callback_base_url: String,
+}
+
+impl ShippingCarriers {
+    pub fn new(carriers: Vec<Arc<dyn ShippingCarrier>>, callback_base_url: &str) -> Self {
+        Self {
+            carriers: carriers.into_iter().map(|carrier| (carrier.code(), carrier)).collect(),
+            callback_base_url: callback_base_url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    // Sellers' own couriers, plus the mock carrier when enabled for local development
+    pub fn from_config(config: &ShippingConfig) -> Self {
+        let mut carriers: Vec<Arc<dyn ShippingCarrier>> = vec![Arc::new(ManualCarrier)];
+        if config.mock_carrier {
+            carriers.push(Arc::new(MockCarrier::new()));
+        }
+        Self::new(carriers, &config.callback_base_url)
+    }
+
+    // Sellers' own couriers and a mock carrier, for tests
+    pub fn mock() -> Self {
+        Self::new(
+            vec![Arc::new(ManualCarrier), Arc::new(MockCarrier::new())],
+            "http://localhost:8080",
+        )
+    }
+
+    pub fn get(&self, code: &str) -> Result<Arc<dyn ShippingCarrier>> {
+        self.carriers
+            .get(code)
+            .cloned()
+            .ok_or_else(|| AppError::bad_request(format!("Unknown carrier: {}", code)))
+    }
+
+    // Carriers whose shipments the tracking job looks up
+    pub fn tracked(&self) -> Vec<Arc<dyn ShippingCarrier>> {
+        self.carriers
+            .values()
+            .filter(|carrier| carrier.supports_tracking())
+            .cloned()
+            .collect()
+    }
+
+    pub fn callback_url(&self, carrier: &str, token: &str) -> String {
+        format!("{}/api/shipping/webhooks/{}?token={}", self.callback_base_url, carrier, token)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request() -> ShipmentRequest {
+        ShipmentRequest {
+            shipment_id: Uuid::new_v4(),
+            order_id: Uuid::new_v4(),
+            seller_order_id: Uuid::new_v4(),
+            sender_name: "Boutique Akwa".to_string(),
+            sender_city: Some("Douala".to_string()),
+            sender_phone: None,
+            recipient: ShippingAddress {
+                name: "Awa Ngono".to_string(),
+                address_1: "Rue 1.234".to_string(),
+                address_2: None,
+                city: "Yaounde".to_string(),
+                postal_code: None,
+                country: "CM".to_string(),
+                phone: Some("+237677123456".to_string()),
+            },
+            weight_grams: 1500,
+            cash_on_delivery: None,
+            tracking_number: None,
+            callback_url: "http://localhost:8080/api/shipping/webhooks/mock?token=t".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn mock_parcels_move_when_advanced() {
+        let carrier = MockCarrier::new();
+        let shipment = carrier.create_shipment(&request()).await.unwrap();
+        let lookup = ShipmentLookup {
+            tracking_number: shipment.tracking_number.clone(),
+            carrier_reference: shipment.carrier_reference.clone(),
+        };
+        assert!(carrier.track(&lookup).await.unwrap().is_empty());
+
+        carrier.advance(&shipment.tracking_number, ShipmentStatus::InTransit).unwrap();
+        carrier.advance(&shipment.tracking_number, ShipmentStatus::Delivered).unwrap();
+
+        let statuses: Vec<ShipmentStatus> = carrier.track(&lookup).await.unwrap().into_iter().map(|e| e.status).collect();
+        assert_eq!(statuses, vec![ShipmentStatus::InTransit, ShipmentStatus::Delivered]);
+        assert!(carrier.advance("MOCKUNKNOWN", ShipmentStatus::InTransit).is_err());
+    }
+
+    #[tokio::test]
+    async fn mock_parcels_cannot_be_canceled_once_picked_up() {
+        let carrier = MockCarrier::new();
+        let waiting = carrier.create_shipment(&request()).await.unwrap();
+        let picked_up = carrier.create_shipment(&request()).await.unwrap();
+        carrier.advance(&picked_up.tracking_number, ShipmentStatus::InTransit).unwrap();
+
+        let lookup = |shipment: &CarrierShipment| ShipmentLookup {
+            tracking_number: shipment.tracking_number.clone(),
+            carrier_reference: shipment.carrier_reference.clone(),
+        };
+        assert!(carrier.cancel(&lookup(&waiting)).await.is_ok());
+        assert!(carrier.track(&lookup(&waiting)).await.is_err());
+        assert!(carrier.cancel(&lookup(&picked_up)).await.is_err());
+    }
+
+    #[test]
+    fn mock_webhooks_are_parsed() {
+        let carrier = MockCarrier::new();
+
+        let update = carrier
+            .parse_webhook(&serde_json::json!({
+                "tracking_number": "MOCK1",
+                "token": "secret",
+                "events": [
+                    {"status": "in_transit", "location": "Douala", "occurred_at": "2026-10-01T08:00:00Z"},
+                    {"status": "delivered", "occurred_at": "2026-10-02T15:30:00Z"}
+                ]
+            }))
+            .unwrap();
+        assert_eq!(update.tracking_number, "MOCK1");
+        assert_eq!(update.token.as_deref(), Some("secret"));
+        assert_eq!(update.events.len(), 2);
+        assert_eq!(update.events[0].location.as_deref(), Some("Douala"));
+
+        let single = carrier
+            .parse_webhook(&serde_json::json!({"tracking_number": "MOCK1", "status": "out_for_delivery"}))
+            .unwrap();
+        assert_eq!(single.events[0].status, ShipmentStatus::OutForDelivery);
+
+        assert!(carrier.parse_webhook(&serde_json::json!({"tracking_number": "MOCK1", "status": "lost"})).is_none());
+    }
+
+    #[test]
+    fn mock_carriers_are_tracked() {
+        let carriers = ShippingCarriers::mock();
+
+        let tracked: Vec<&str> = carriers.tracked().iter().map(|carrier| carrier.code()).collect();
+        assert_eq!(tracked, vec!["mock"]);
+        assert!(carriers.get(MANUAL_CARRIER).is_ok());
+        assert!(carriers.get("dhl").is_err());
+        assert_eq!(
+            carriers.callback_url("mock", "t"),
+            "http://localhost:8080/api/shipping/webhooks/mock?token=t"
+        );
+    }
+}
@@ -0,0 +1,454 @@
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use sea_orm::{ConnectionTrait, DatabaseBackend, DatabaseConnection, EntityTrait, Statement};
+use std::str::FromStr;
+use uuid::Uuid;
+
+use crate::entities::{seller_tax_profile, user};
+use crate::errors::{AppError, Result};
+use crate::models::financial::{BalanceSheet, CashFlow, FinancialStatement, IncomeStatement, StatementLine};
+use crate::models::ledger::{LedgerAccountKind, LedgerTransactionKind};
+use crate::models::money::{Currency, Money};
+use crate::models::user::UserRole;
+use crate::utils::pdf::{PdfWriter, MARGIN, PAGE_WIDTH};
+
+fn to_money(value: &BigDecimal) -> Result<Money> {
+    Ok(Money::from_decimal(value, Currency::XAF)?)
+}
+
+fn percent(part: Money, whole: Money) -> f64 {
+    if whole.is_positive() {
+        part.amount() as f64 / whole.amount() as f64 * 100.0
+    } else {
+        0.0
+    }
+}
+
+fn negate(amount: Money) -> Result<Money> {
+    Ok(Money::zero(amount.currency()).checked_sub(amount)?)
+}
+
+// Balances of the seller's ledger accounts just before a point in time
+async fn balances_at(db: &DatabaseConnection, seller_id: Uuid, at: DateTime<Utc>) -> Result<Vec<(LedgerAccountKind, Money)>> {
+    let rows = db
+        .query_all(Statement::from_sql_and_values(
+            DatabaseBackend::Postgres,
+            r#"
+            SELECT a.kind, COALESCE(SUM(e.amount), 0) AS balance
+            FROM ledger_accounts a
+            LEFT JOIN ledger_entries e ON e.account_id = a.id AND e.created_at < $2
+            WHERE a.owner_id = $1
+            GROUP BY a.kind
+            "#,
+            [seller_id.into(), at.into()],
+        ))
+        .await?;
+
+    let mut balances = Vec::with_capacity(rows.len());
+    for row in rows {
+        let kind: String = row.try_get("", "kind")?;
+        let kind = LedgerAccountKind::from_str(&kind).map_err(AppError::internal)?;
+        balances.push((kind, to_money(&row.try_get("", "balance")?)?));
+    }
+    Ok(balances)
+}
+
+fn balance_of(balances: &[(LedgerAccountKind, Money)], kind: LedgerAccountKind) -> Money {
+    balances
+        .iter()
+        .find(|(k, _)| *k == kind)
+        .map(|(_, balance)| *balance)
+        .unwrap_or(Money::zero(Currency::XAF))
+}
+
+fn total_of(balances: &[(LedgerAccountKind, Money)]) -> Result<Money> {
+    Ok(Money::sum(balances.iter().map(|(_, balance)| *balance), Currency::XAF)?)
+}
+
+// Who can see whose statements: sellers their own, admins any seller's
+pub fn statement_seller(viewer_id: Uuid, role: &UserRole, seller_id: Option<Uuid>) -> Result<Uuid> {
+    match role {
+        UserRole::Seller => match seller_id {
+            Some(id) if id != viewer_id => Err(AppError::forbidden("You can only see your own statements")),
+            _ => Ok(viewer_id),
+        },
+        UserRole::Admin => seller_id.ok_or_else(|| AppError::validation("seller_id: Choose the seller")),
+        _ => Err(AppError::forbidden("Only sellers and admins can access financial records")),
+    }
+}
+
+// Build a seller's income statement, balance sheet and cash flow for a period from their
+// delivered sub-orders, refunds, expenses and ledger. Days are UTC and both ends are included.
+pub async fn generate_financial_statement(
+    db: &DatabaseConnection,
+    seller_id: Uuid,
+    start_date: NaiveDate,
+    end_date: NaiveDate,
+) -> Result<FinancialStatement> {
+    if end_date < start_date {
+        return Err(AppError::bad_request("The end date must not be before the start date"));
+    }
+
+    let seller = user::Entity::find_by_id(seller_id)
+        .one(db)
+        .await?
+        .ok_or_else(|| AppError::not_found("Seller not found"))?;
+    let legal_name = seller_tax_profile::Entity::find_by_id(seller_id)
+        .one(db)
+        .await?
+        .and_then(|profile| profile.legal_name);
+
+    let start = start_date.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc();
+    let end = (end_date + Duration::days(1)).and_hms_opt(0, 0, 0).unwrap_or_default().and_utc();
+    let zero = Money::zero(Currency::XAF);
+
+    // Goods and delivery on sub-orders delivered in the period, before VAT
+    let row = db
+        .query_one(Statement::from_sql_and_values(
+            DatabaseBackend::Postgres,
+            r#"
+            SELECT COUNT(*) AS orders_delivered,
+                   COALESCE(SUM(items.taxable), 0) AS sales,
+                   COALESCE(SUM(so.shipping_amount), 0) AS delivery,
+                   COALESCE(SUM(so.tax_amount), 0) AS vat
+            FROM seller_orders so
+            LEFT JOIN (
+                SELECT seller_order_id, SUM(taxable_amount) AS taxable FROM order_items GROUP BY seller_order_id
+            ) items ON items.seller_order_id = so.id
+            WHERE so.seller_id = $1 AND so.delivered_at >= $2 AND so.delivered_at < $3
+            "#,
+            [seller_id.into(), start.into(), end.into()],
+        ))
+        .await?
+        .ok_or_else(|| AppError::internal("Statement query returned no row"))?;
+    let orders_delivered: i64 = row.try_get("", "orders_delivered")?;
+    let sales = to_money(&row.try_get("", "sales")?)?;
+    let delivery = to_money(&row.try_get("", "delivery")?)?;
+    let vat = to_money(&row.try_get("", "vat")?)?;
+
+    // Refunds recorded in the period on delivered sub-orders, each carrying its share of the VAT
+    let row = db
+        .query_one(Statement::from_sql_and_values(
+            DatabaseBackend::Postgres,
+            r#"
+            SELECT COALESCE(SUM(ri.amount), 0) AS refunded,
+                   COALESCE(SUM(ROUND(ri.amount * so.tax_amount / NULLIF(so.total_amount, 0), 0)), 0) AS refunded_vat
+            FROM refund_items ri
+            JOIN refunds r ON r.id = ri.refund_id
+            JOIN seller_orders so ON so.id = ri.seller_order_id
+            WHERE so.seller_id = $1 AND so.delivered_at IS NOT NULL
+              AND r.created_at >= $2 AND r.created_at < $3
+            "#,
+            [seller_id.into(), start.into(), end.into()],
+        ))
+        .await?
+        .ok_or_else(|| AppError::internal("Statement query returned no row"))?;
+    let refunded = to_money(&row.try_get("", "refunded")?)?;
+    let refunded_vat = to_money(&row.try_get("", "refunded_vat")?)?;
+    let refunds = refunded.checked_sub(refunded_vat)?;
+
+    // Movements on the seller's accounts in the period, by what caused them
+    let rows = db
+        .query_all(Statement::from_sql_and_values(
+            DatabaseBackend::Postgres,
+            r#"
+            SELECT t.kind, SUM(e.amount) AS amount
+            FROM ledger_entries e
+            JOIN ledger_accounts a ON a.id = e.account_id
+            JOIN ledger_transactions t ON t.id = e.transaction_id
+            WHERE a.owner_id = $1 AND e.created_at >= $2 AND e.created_at < $3
+            GROUP BY t.kind
+            "#,
+            [seller_id.into(), start.into(), end.into()],
+        ))
+        .await?;
+    let (mut payments_received, mut fees_paid, mut refunds_paid, mut payouts) = (zero, zero, zero, zero);
+    for row in rows {
+        let kind: String = row.try_get("", "kind")?;
+        let amount = to_money(&row.try_get("", "amount")?)?;
+        match LedgerTransactionKind::from_str(&kind).map_err(AppError::internal)? {
+            LedgerTransactionKind::PaymentCaptured => payments_received = payments_received.checked_add(amount)?,
+            LedgerTransactionKind::DeliveryReleased | LedgerTransactionKind::CashCollected => {
+                fees_paid = fees_paid.checked_sub(amount)?
+            }
+            LedgerTransactionKind::Refund => refunds_paid = refunds_paid.checked_sub(amount)?,
+            LedgerTransactionKind::WithdrawalPaid => payouts = payouts.checked_sub(amount)?,
+            // Only move money between the seller's own accounts
+            LedgerTransactionKind::WithdrawalRequested | LedgerTransactionKind::WithdrawalReturned => {}
+        }
+    }
+
+    // The marketplace's fees, less what it gave back on refunds
+    let row = db
+        .query_one(Statement::from_sql_and_values(
+            DatabaseBackend::Postgres,
+            r#"
+            SELECT COALESCE(SUM(e.amount), 0) AS fees
+            FROM ledger_entries e
+            JOIN ledger_accounts a ON a.id = e.account_id
+            JOIN ledger_transactions t ON t.id = e.transaction_id
+            JOIN seller_orders so ON so.id = t.seller_order_id
+            WHERE a.kind = $4 AND so.seller_id = $1 AND e.created_at >= $2 AND e.created_at < $3
+            "#,
+            [
+                seller_id.into(),
+                start.into(),
+                end.into(),
+                LedgerAccountKind::PlatformCommission.to_string().into(),
+            ],
+        ))
+        .await?
+        .ok_or_else(|| AppError::internal("Statement query returned no row"))?;
+    let marketplace_fees = to_money(&row.try_get("", "fees")?)?;
+
+    let row = db
+        .query_one(Statement::from_sql_and_values(
+            DatabaseBackend::Postgres,
+            r#"
+            SELECT COALESCE(SUM(cod_collected_amount), 0) AS collected
+            FROM seller_orders
+            WHERE seller_id = $1 AND cod_collected_at >= $2 AND cod_collected_at < $3
+            "#,
+            [seller_id.into(), start.into(), end.into()],
+        ))
+        .await?
+        .ok_or_else(|| AppError::internal("Statement query returned no row"))?;
+    let cash_on_delivery_collected = to_money(&row.try_get("", "collected")?)?;
+
+    let rows = db
+        .query_all(Statement::from_sql_and_values(
+            DatabaseBackend::Postgres,
+            r#"
+            SELECT category, SUM(amount) AS amount
+            FROM expenses
+            WHERE user_id = $1 AND date >= $2 AND date <= $3
+            GROUP BY category
+            ORDER BY category
+            "#,
+            [seller_id.into(), start_date.into(), end_date.into()],
+        ))
+        .await?;
+    let mut operating_expenses = Vec::with_capacity(rows.len());
+    for row in rows {
+        let category: String = row.try_get("", "category")?;
+        operating_expenses.push(StatementLine::new(category, to_money(&row.try_get("", "amount")?)?));
+    }
+    let total_operating_expenses = Money::sum(operating_expenses.iter().filter_map(|line| line.amount), Currency::XAF)?;
+
+    let revenue = sales.checked_add(delivery)?.checked_sub(refunds)?;
+    let gross_profit = revenue.checked_sub(marketplace_fees)?;
+    let net_income = gross_profit.checked_sub(total_operating_expenses)?;
+
+    let opening = balances_at(db, seller_id, start).await?;
+    let closing = balances_at(db, seller_id, end).await?;
+
+    let assets = vec![
+        StatementLine::unavailable("Cash", "Cash held outside the marketplace is not tracked"),
+        StatementLine::new("Sales held in escrow", balance_of(&closing, LedgerAccountKind::SellerEscrow)),
+        StatementLine::new("Wallet balance", balance_of(&closing, LedgerAccountKind::SellerWallet)),
+        StatementLine::new("Payouts in progress", balance_of(&closing, LedgerAccountKind::SellerWithdrawals)),
+        StatementLine::unavailable("Inventory", "What stock cost to buy is not tracked"),
+    ];
+    let liabilities = vec![
+        StatementLine::unavailable("VAT payable", "VAT already paid to the tax administration is not tracked"),
+        StatementLine::unavailable("Loans and other payables", "Not tracked"),
+    ];
+    let equity = vec![StatementLine::unavailable(
+        "Owner's equity",
+        "Needs the assets and liabilities that are not tracked",
+    )];
+    let total_assets = Money::sum(assets.iter().filter_map(|line| line.amount), Currency::XAF)?;
+    let total_liabilities = Money::sum(liabilities.iter().filter_map(|line| line.amount), Currency::XAF)?;
+
+    Ok(FinancialStatement {
+        seller_id,
+        seller_name: legal_name.unwrap_or(seller.name),
+        period_start: start_date,
+        period_end: end_date,
+        income_statement: IncomeStatement {
+            orders_delivered,
+            sales,
+            delivery,
+            refunds,
+            revenue,
+            marketplace_fees,
+            gross_profit,
+            gross_margin: percent(gross_profit, revenue),
+            operating_expenses,
+            total_operating_expenses,
+            net_income,
+            net_margin: percent(net_income, revenue),
+            vat_collected: vat.checked_sub(refunded_vat)?,
+        },
+        balance_sheet: BalanceSheet {
+            as_of: end_date,
+            assets,
+            total_assets,
+            liabilities,
+            total_liabilities,
+            equity,
+        },
+        cash_flow: CashFlow {
+            opening_balance: total_of(&opening)?,
+            payments_received,
+            fees_paid,
+            refunds_paid,
+            payouts,
+            closing_balance: total_of(&closing)?,
+            cash_on_delivery_collected,
+            expenses_paid: total_operating_expenses,
+            net_cash_flow: payments_received
+                .checked_sub(fees_paid)?
+                .checked_sub(refunds_paid)?
+                .checked_add(cash_on_delivery_collected)?
+                .checked_sub(total_operating_expenses)?,
+        },
+    })
+}
+
+// One line of the statements as (section, label, amount, note)
+type StatementRow = (&'static str, String, Option<Money>, Option<String>);
+
+// Every line of the statements in order
+fn statement_rows(statement: &FinancialStatement) -> Result<Vec<StatementRow>> {
+    let income = &statement.income_statement;
+    let balance = &statement.balance_sheet;
+    let cash = &statement.cash_flow;
+    let mut rows = Vec::new();
+
+    let mut push = |section: &'static str, label: &str, amount: Money| rows.push((section, label.to_string(), Some(amount), None));
+    push("Income statement", "Sales (excluding VAT)", income.sales);
+    push("Income statement", "Delivery", income.delivery);
+    push("Income statement", "Refunds", negate(income.refunds)?);
+    push("Income statement", "Revenue", income.revenue);
+    push("Income statement", "Marketplace fees", negate(income.marketplace_fees)?);
+    push("Income statement", "Gross profit", income.gross_profit);
+    for line in &income.operating_expenses {
+        if let Some(amount) = line.amount {
+            push("Income statement", &format!("Expenses: {}", line.label), negate(amount)?);
+        }
+    }
+    push("Income statement", "Total operating expenses", negate(income.total_operating_expenses)?);
+    push("Income statement", "Net income", income.net_income);
+    push("Income statement", "VAT collected", income.vat_collected);
+
+    push("Cash flow", "Opening balance held by the marketplace", cash.opening_balance);
+    push("Cash flow", "Payments received", cash.payments_received);
+    push("Cash flow", "Fees paid", negate(cash.fees_paid)?);
+    push("Cash flow", "Refunds paid", negate(cash.refunds_paid)?);
+    push("Cash flow", "Payouts", negate(cash.payouts)?);
+    push("Cash flow", "Closing balance held by the marketplace", cash.closing_balance);
+    push("Cash flow", "Cash collected on delivery", cash.cash_on_delivery_collected);
+    push("Cash flow", "Expenses paid", negate(cash.expenses_paid)?);
+    push("Cash flow", "Net cash flow", cash.net_cash_flow);
+
+    for (section, lines) in [
+        ("Assets", &balance.assets),
+        ("Liabilities", &balance.liabilities),
+        ("Equity", &balance.equity),
+    ] {
+        for line in lines {
+            rows.push((section, line.label.clone(), line.amount, line.note.clone()));
+        }
+    }
+    rows.push(("Assets", "Total assets".to_string(), Some(balance.total_assets), None));
+    rows.push(("Liabilities", "Total liabilities".to_string(), Some(balance.total_liabilities), None));
+
+    Ok(rows)
+}
+
+fn csv_field(value: &str) -> String {
+    // Text that spreadsheets would run as a formula is kept as text
+    let value = if value.starts_with(['=', '+', '-', '@', '\t', '\r']) {
+        format!("'{}", value)
+    } else {
+        value.to_string()
+    };
+
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value
+    }
+}
+
+// One row per line with the amount in whole francs, for spreadsheets and accounting software
+pub fn statement_csv(statement: &FinancialStatement) -> Result<String> {
+    let mut csv = String::from("section,line,amount_xaf,note\n");
+    for (section, label, amount, note) in statement_rows(statement)? {
+        csv.push_str(&format!(
+            "{},{},{},{}\n",
+            csv_field(section),
+            csv_field(&label),
+            amount.map(|amount| amount.whole_units().to_string()).unwrap_or_default(),
+            csv_field(note.as_deref().unwrap_or("")),
+        ));
+    }
+    Ok(csv)
+}
+
+pub fn statement_pdf(statement: &FinancialStatement) -> Result<Vec<u8>> {
+    let right = PAGE_WIDTH - MARGIN;
+    let mut pdf = PdfWriter::new(&format!("Financial statements {}", statement.seller_name))?;
+
+    pdf.text("FINANCIAL STATEMENTS", MARGIN, 16.0, true);
+    pdf.advance(8.0);
+    pdf.text(&statement.seller_name, MARGIN, 10.0, true);
+    pdf.advance(5.0);
+    pdf.text(
+        &format!(
+            "{} to {}",
+            statement.period_start.format("%d/%m/%Y"),
+            statement.period_end.format("%d/%m/%Y")
+        ),
+        MARGIN,
+        9.0,
+        false,
+    );
+    pdf.advance(10.0);
+
+    let mut current = "";
+    for (section, label, amount, note) in statement_rows(statement)? {
+        if section != current {
+            if !current.is_empty() {
+                pdf.advance(4.0);
+            }
+            pdf.text(section, MARGIN, 10.0, true);
+            pdf.advance(2.0);
+            pdf.rule();
+            pdf.advance(4.0);
+            current = section;
+        }
+        let total = label.starts_with("Total") || matches!(label.as_str(), "Revenue" | "Gross profit" | "Net income" | "Net cash flow");
+        pdf.text(&label, MARGIN, 9.0, total);
+        match amount {
+            Some(amount) => pdf.text_right(&amount.to_string(), right, 9.0, total),
+            None => pdf.text_right("Not available", right, 9.0, false),
+        }
+        pdf.advance(5.0);
+        if let Some(note) = note {
+            pdf.text(&note, MARGIN + 4.0, 7.5, false);
+            pdf.advance(4.5);
+        }
+    }
+
+    pdf.advance(6.0);
+    pdf.text("All amounts in CFA francs (XAF). Days are UTC.", MARGIN, 8.0, false);
+    pdf.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_fields_are_quoted_and_never_formulas() {
+        assert_eq!(csv_field("Sales"), "Sales");
+        assert_eq!(csv_field("Fees, commission"), "\"Fees, commission\"");
+        assert_eq!(csv_field("The \"best\" shop"), "\"The \"\"best\"\" shop\"");
+        assert_eq!(csv_field("=HYPERLINK(\"http://x\")"), "\"'=HYPERLINK(\"\"http://x\"\")\"");
+        assert_eq!(csv_field("+237 shop"), "'+237 shop");
+        assert_eq!(csv_field("-1"), "'-1");
+        assert_eq!(csv_field("@SUM(A1)"), "'@SUM(A1)");
+    }
+}
@@ -0,0 +1,264 @@
+use bigdecimal::{BigDecimal, ToPrimitive};
+use chrono::{DateTime, Utc};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, Condition, ConnectionTrait, DatabaseConnection, EntityTrait, IntoActiveModel,
+    QueryFilter, QueryOrder, Set,
+};
+use uuid::Uuid;
+
+use crate::entities::{category, order_item, seller_tax_profile, tax_rule};
+use crate::errors::{AppError, Result};
+use crate::models::money::{Currency, Money};
+use crate::models::tax::{CreateTaxRuleRequest, TaxRule, TaxRuleQuery, UpdateTaxRuleRequest};
+use crate::services::category::to_rate;
+use crate::utils::validation;
+
+fn to_rule(model: tax_rule::Model) -> TaxRule {
+    TaxRule {
+        id: model.id,
+        name: model.name,
+        category_id: model.category_id,
+        rate: model.rate.to_f64().unwrap_or_default(),
+        prices_include_tax: model.prices_include_tax,
+        starts_at: model.starts_at,
+        ends_at: model.ends_at,
+        created_at: model.created_at,
+    }
+}
+
+fn ensure_period(starts_at: DateTime<Utc>, ends_at: Option<DateTime<Utc>>) -> Result<()> {
+    if ends_at.is_some_and(|ends_at| ends_at <= starts_at) {
+        return Err(AppError::validation("ends_at: A rule must end after it starts"));
+    }
+    Ok(())
+}
+
+// Sellers charge VAT unless they said they are not registered for it
+pub async fn vat_registered<C: ConnectionTrait>(db: &C, seller_id: Uuid) -> Result<bool> {
+    Ok(seller_tax_profile::Entity::find_by_id(seller_id)
+        .one(db)
+        .await?
+        .is_none_or(|profile| profile.vat_registered))
+}
+
+// Tax rules, most recent first
+pub async fn get_rules(db: &DatabaseConnection, query: TaxRuleQuery) -> Result<Vec<TaxRule>> {
+    let mut select = tax_rule::Entity::find();
+    if let Some(category_id) = query.category_id {
+        select = select.filter(tax_rule::Column::CategoryId.eq(category_id));
+    }
+    if query.active == Some(true) {
+        select = select.filter(in_force(Utc::now()));
+    }
+
+    Ok(select
+        .order_by_desc(tax_rule::Column::StartsAt)
+        .all(db)
+        .await?
+        .into_iter()
+        .map(to_rule)
+        .collect())
+}
+
+pub async fn create_rule(db: &DatabaseConnection, admin_id: Uuid, payload: CreateTaxRuleRequest) -> Result<TaxRule> {
+    validation::validate(&payload)?;
+
+    if let Some(category_id) = payload.category_id {
+        category::Entity::find_by_id(category_id)
+            .one(db)
+            .await?
+            .ok_or_else(|| AppError::not_found("Category not found"))?;
+    }
+
+    let now = Utc::now();
+    let starts_at = payload.starts_at.unwrap_or(now);
+    ensure_period(starts_at, payload.ends_at)?;
+
+    let rule = tax_rule::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        name: Set(payload.name),
+        category_id: Set(payload.category_id),
+        rate: Set(to_rate(payload.rate)?),
+        prices_include_tax: Set(payload.prices_include_tax),
+        starts_at: Set(starts_at),
+        ends_at: Set(payload.ends_at),
+        created_by: Set(Some(admin_id)),
+        created_at: Set(now),
+        updated_at: Set(now),
+    }
+    .insert(db)
+    .await?;
+
+    Ok(to_rule(rule))
+}
+
+pub async fn update_rule(db: &DatabaseConnection, rule_id: Uuid, payload: UpdateTaxRuleRequest) -> Result<TaxRule> {
+    validation::validate(&payload)?;
+
+    let rule = tax_rule::Entity::find_by_id(rule_id)
+        .one(db)
+        .await?
+        .ok_or_else(|| AppError::not_found("Tax rule not found"))?;
+
+    let starts_at = payload.starts_at.unwrap_or(rule.starts_at);
+    let ends_at = payload.ends_at.or(rule.ends_at);
+    ensure_period(starts_at, ends_at)?;
+
+    let mut active = rule.into_active_model();
+    if let Some(name) = payload.name {
+        active.name = Set(name);
+    }
+    if let Some(rate) = payload.rate {
+        active.rate = Set(to_rate(rate)?);
+    }
+    if let Some(prices_include_tax) = payload.prices_include_tax {
+        active.prices_include_tax = Set(prices_include_tax);
+    }
+    active.starts_at = Set(starts_at);
+    active.ends_at = Set(ends_at);
+    active.updated_at = Set(Utc::now());
+
+    Ok(to_rule(active.update(db).await?))
+}
+
+// Rules that were never used are deleted; the others stop applying now so past orders keep them
+pub async fn end_rule(db: &DatabaseConnection, rule_id: Uuid) -> Result<()> {
+    let rule = tax_rule::Entity::find_by_id(rule_id)
+        .one(db)
+        .await?
+        .ok_or_else(|| AppError::not_found("Tax rule not found"))?;
+
+    let used = order_item::Entity::find()
+        .filter(order_item::Column::TaxRuleId.eq(rule.id))
+        .one(db)
+        .await?
+        .is_some();
+
+    let now = Utc::now();
+    if !used || rule.starts_at > now {
+        tax_rule::Entity::delete_by_id(rule.id).exec(db).await?;
+        return Ok(());
+    }
+
+    if rule.ends_at.is_none_or(|ends_at| ends_at > now) {
+        let mut active = rule.into_active_model();
+        active.ends_at = Set(Some(now));
+        active.updated_at = Set(now);
+        active.update(db).await?;
+    }
+
+    Ok(())
+}
+
+fn in_force(at: DateTime<Utc>) -> Condition {
+    Condition::all()
+        .add(tax_rule::Column::StartsAt.lte(at))
+        .add(
+            Condition::any()
+                .add(tax_rule::Column::EndsAt.is_null())
+                .add(tax_rule::Column::EndsAt.gt(at)),
+        )
+}
+
+// One item of a seller's sub-order, as priced at checkout
+pub struct TaxLine {
+    pub category_id: Uuid,
+    pub line_total: Money,
+}
+
+// The VAT on one item
+pub struct ItemTax {
+    pub rule_id: Option<Uuid>,
+    pub rate: BigDecimal,
+    pub prices_include_tax: bool,
+    // The item's price after discount, without VAT
+    pub taxable_amount: Money,
+    pub tax: Money,
+}
+
+impl ItemTax {
+    // VAT the buyer pays on top of the price
+    pub fn added(&self) -> Money {
+        if self.prices_include_tax {
+            Money::zero(self.tax.currency())
+        } else {
+            self.tax
+        }
+    }
+}
+
+// Work out the VAT on a seller's items. Each item is taxed on its price after its share of the
+// seller's discount, at the rate of the rule for its category or else the default rule. Sellers
+// who are not registered for VAT, and items no rule covers, are not taxed.
+pub async fn compute_taxes<C: ConnectionTrait>(
+    db: &C,
+    seller_id: Uuid,
+    lines: &[TaxLine],
+    discount: Money,
+    at: DateTime<Utc>,
+) -> Result<Vec<ItemTax>> {
+    if lines.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let rules = if vat_registered(db, seller_id).await? {
+        tax_rule::Entity::find()
+            .filter(in_force(at))
+            .filter(
+                Condition::any()
+                    .add(tax_rule::Column::CategoryId.is_null())
+                    .add(tax_rule::Column::CategoryId.is_in(lines.iter().map(|line| line.category_id))),
+            )
+            .all(db)
+            .await?
+    } else {
+        Vec::new()
+    };
+
+    let zero = Money::zero(Currency::XAF);
+    let totals: Vec<Money> = lines.iter().map(|line| line.line_total).collect();
+    let discounts = if discount.is_positive() && totals.iter().any(|total| total.is_positive()) {
+        discount.allocate(&totals)?
+    } else {
+        vec![zero; totals.len()]
+    };
+
+    let mut taxes = Vec::with_capacity(lines.len());
+    for (line, discount) in lines.iter().zip(discounts) {
+        let base = line.line_total.checked_sub(discount)?.max(zero)?;
+
+        let rule = rules
+            .iter()
+            .filter(|rule| rule.category_id.is_none_or(|id| id == line.category_id))
+            .max_by_key(|rule| (rule.category_id.is_some(), rule.starts_at));
+
+        taxes.push(match rule {
+            Some(rule) if rule.prices_include_tax => {
+                let tax = base.mul_rate(&(&rule.rate / (BigDecimal::from(1) + &rule.rate)))?;
+                ItemTax {
+                    rule_id: Some(rule.id),
+                    rate: rule.rate.clone(),
+                    prices_include_tax: true,
+                    taxable_amount: base.checked_sub(tax)?,
+                    tax,
+                }
+            }
+            Some(rule) => ItemTax {
+                rule_id: Some(rule.id),
+                rate: rule.rate.clone(),
+                prices_include_tax: false,
+                taxable_amount: base,
+                tax: base.mul_rate(&rule.rate)?,
+            },
+            None => ItemTax {
+                rule_id: None,
+                rate: BigDecimal::from(0),
+                prices_include_tax: false,
+                taxable_amount: base,
+                tax: zero,
+            },
+        });
+    }
+
+    Ok(taxes)
+}
@@ -0,0 +1,351 @@
+use chrono::Utc;
+use rand::distributions::Alphanumeric;
+use rand::{thread_rng, Rng};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, PaginatorTrait, QueryFilter,
+    QueryOrder, Set, SqlErr,
+};
+use uuid::Uuid;
+
+use crate::{
+    entities::{saved_item, user, wishlist},
+    errors::{AppError, Result},
+    models::cart::{AddToCartRequest, CartOwner},
+    models::saved_item::SavedItem,
+    models::wishlist::{
+        AddWishlistItemRequest, CreateWishlistRequest, MoveWishlistItemRequest, SharedWishlist,
+        UpdateWishlistRequest, Wishlist, WishlistToCartRequest, WishlistWithItems,
+    },
+    services::{cart, saved_item as saved_item_service},
+};
+
+// Name of the list the classic saved-items endpoints work on
+const DEFAULT_WISHLIST_NAME: &str = "Saved items";
+
+fn generate_share_slug() -> String {
+    thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(12)
+        .map(char::from)
+        .collect::<String>()
+        .to_lowercase()
+}
+
+async fn item_count(db: &DatabaseConnection, wishlist_id: Uuid) -> Result<u64> {
+    Ok(saved_item::Entity::find()
+        .filter(saved_item::Column::WishlistId.eq(wishlist_id))
+        .count(db)
+        .await?)
+}
+
+// Load a wishlist, making sure it belongs to the user
+async fn find_owned(db: &DatabaseConnection, user_id: Uuid, wishlist_id: Uuid) -> Result<wishlist::Model> {
+    wishlist::Entity::find_by_id(wishlist_id)
+        .filter(wishlist::Column::UserId.eq(user_id))
+        .one(db)
+        .await?
+        .ok_or_else(|| AppError::not_found("Wishlist not found"))
+}
+
+async fn ensure_name_available(
+    db: &DatabaseConnection,
+    user_id: Uuid,
+    name: &str,
+    except: Option<Uuid>,
+) -> Result<()> {
+    let mut query = wishlist::Entity::find()
+        .filter(wishlist::Column::UserId.eq(user_id))
+        .filter(wishlist::Column::Name.eq(name));
+
+    if let Some(id) = except {
+        query = query.filter(wishlist::Column::Id.ne(id));
+    }
+
+    if query.one(db).await?.is_some() {
+        return Err(AppError::bad_request("A wishlist with this name already exists"));
+    }
+
+    Ok(())
+}
+
+fn is_unique_violation(err: &DbErr) -> bool {
+    matches!(err.sql_err(), Some(SqlErr::UniqueConstraintViolation(_)))
+}
+
+async fn insert_wishlist(
+    db: &DatabaseConnection,
+    user_id: Uuid,
+    name: String,
+    is_public: bool,
+    is_default: bool,
+) -> std::result::Result<wishlist::Model, DbErr> {
+    let now = Utc::now();
+    let wishlist = wishlist::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        user_id: Set(user_id),
+        name: Set(name),
+        is_public: Set(is_public),
+        is_default: Set(is_default),
+        share_slug: Set(generate_share_slug()),
+        created_at: Set(now),
+        updated_at: Set(now),
+    };
+
+    wishlist.insert(db).await
+}
+
+// Get the user's default list, creating it on first use
+pub async fn get_or_create_default(db: &DatabaseConnection, user_id: Uuid) -> Result<wishlist::Model> {
+    let find_default = || {
+        wishlist::Entity::find()
+            .filter(wishlist::Column::UserId.eq(user_id))
+            .filter(wishlist::Column::IsDefault.eq(true))
+            .one(db)
+    };
+
+    if let Some(wishlist) = find_default().await? {
+        return Ok(wishlist);
+    }
+
+    match insert_wishlist(db, user_id, DEFAULT_WISHLIST_NAME.to_string(), false, true).await {
+        Ok(wishlist) => Ok(wishlist),
+        // Another request created it first
+        Err(err) if is_unique_violation(&err) => find_default().await?.ok_or_else(|| err.into()),
+        Err(err) => Err(err.into()),
+    }
+}
+
+// Get all of a user's wishlists
+pub async fn get_wishlists(db: &DatabaseConnection, user_id: Uuid) -> Result<Vec<Wishlist>> {
+    get_or_create_default(db, user_id).await?;
+
+    let wishlists = wishlist::Entity::find()
+        .filter(wishlist::Column::UserId.eq(user_id))
+        .order_by_desc(wishlist::Column::IsDefault)
+        .order_by_asc(wishlist::Column::CreatedAt)
+        .all(db)
+        .await?;
+
+    let mut result = Vec::with_capacity(wishlists.len());
+    for wishlist in wishlists {
+        let count = item_count(db, wishlist.id).await?;
+        result.push(Wishlist::from_model(wishlist, count));
+    }
+
+    Ok(result)
+}
+
+// Get one of the user's wishlists with its items
+pub async fn get_wishlist(db: &DatabaseConnection, user_id: Uuid, wishlist_id: Uuid) -> Result<WishlistWithItems> {
+    let wishlist = find_owned(db, user_id, wishlist_id).await?;
+
+    let items = saved_item_service::get_items_with_products(
+        db,
+        saved_item::Entity::find().filter(saved_item::Column::WishlistId.eq(wishlist.id)),
+    )
+    .await?;
+
+    Ok(WishlistWithItems {
+        wishlist: Wishlist::from_model(wishlist, items.len() as u64),
+        items,
+    })
+}
+
+// Create a new named wishlist
+pub async fn create_wishlist(db: &DatabaseConnection, user_id: Uuid, payload: CreateWishlistRequest) -> Result<Wishlist> {
+    // Make sure the default list exists so a new list is never taken as the default,
+    // and so a first list cannot take its name
+    get_or_create_default(db, user_id).await?;
+
+    let name = payload.name.trim().to_string();
+    ensure_name_available(db, user_id, &name, None).await?;
+
+    let wishlist = insert_wishlist(db, user_id, name, payload.is_public.unwrap_or(false), false)
+        .await
+        .map_err(|err| {
+            if is_unique_violation(&err) {
+                AppError::bad_request("A wishlist with this name already exists")
+            } else {
+                err.into()
+            }
+        })?;
+    Ok(Wishlist::from_model(wishlist, 0))
+}
+
+// Rename a wishlist or change its visibility
+pub async fn update_wishlist(
+    db: &DatabaseConnection,
+    user_id: Uuid,
+    wishlist_id: Uuid,
+    payload: UpdateWishlistRequest,
+) -> Result<Wishlist> {
+    let wishlist = find_owned(db, user_id, wishlist_id).await?;
+    let mut wishlist: wishlist::ActiveModel = wishlist.into();
+
+    if let Some(name) = payload.name {
+        let name = name.trim().to_string();
+        ensure_name_available(db, user_id, &name, Some(wishlist_id)).await?;
+        wishlist.name = Set(name);
+    }
+    if let Some(is_public) = payload.is_public {
+        wishlist.is_public = Set(is_public);
+    }
+    wishlist.updated_at = Set(Utc::now());
+
+    let wishlist = wishlist.update(db).await?;
+    let count = item_count(db, wishlist.id).await?;
+    Ok(Wishlist::from_model(wishlist, count))
+}
+
+// Delete a wishlist and its items (the default list cannot be deleted)
+pub async fn delete_wishlist(db: &DatabaseConnection, user_id: Uuid, wishlist_id: Uuid) -> Result<()> {
+    let wishlist = find_owned(db, user_id, wishlist_id).await?;
+
+    if wishlist.is_default {
+        return Err(AppError::bad_request("The default wishlist cannot be deleted"));
+    }
+
+    wishlist::Entity::delete_by_id(wishlist.id).exec(db).await?;
+
+    Ok(())
+}
+
+// Replace the share slug so links handed out earlier stop working
+pub async fn regenerate_share_link(db: &DatabaseConnection, user_id: Uuid, wishlist_id: Uuid) -> Result<Wishlist> {
+    let wishlist = find_owned(db, user_id, wishlist_id).await?;
+    let mut wishlist: wishlist::ActiveModel = wishlist.into();
+    wishlist.share_slug = Set(generate_share_slug());
+    wishlist.updated_at = Set(Utc::now());
+
+    let wishlist = wishlist.update(db).await?;
+    let count = item_count(db, wishlist.id).await?;
+    Ok(Wishlist::from_model(wishlist, count))
+}
+
+// Add a product to a wishlist
+pub async fn add_item(
+    db: &DatabaseConnection,
+    user_id: Uuid,
+    wishlist_id: Uuid,
+    payload: AddWishlistItemRequest,
+) -> Result<SavedItem> {
+    let wishlist = find_owned(db, user_id, wishlist_id).await?;
+
+    saved_item_service::save_to_list(
+        db,
+        user_id,
+        wishlist.id,
+        payload.product_id,
+        payload.alerts_enabled.unwrap_or(false),
+    )
+    .await
+}
+
+// Remove a product from a wishlist
+pub async fn remove_item(db: &DatabaseConnection, user_id: Uuid, wishlist_id: Uuid, product_id: Uuid) -> Result<()> {
+    let wishlist = find_owned(db, user_id, wishlist_id).await?;
+
+    let result = saved_item::Entity::delete_many()
+        .filter(saved_item::Column::WishlistId.eq(wishlist.id))
+        .filter(saved_item::Column::ProductId.eq(product_id))
+        .exec(db)
+        .await?;
+
+    if result.rows_affected == 0 {
+        return Err(AppError::not_found("Item not found in wishlist"));
+    }
+
+    Ok(())
+}
+
+// Move a product from one of the user's wishlists to another
+pub async fn move_item(
+    db: &DatabaseConnection,
+    user_id: Uuid,
+    wishlist_id: Uuid,
+    product_id: Uuid,
+    payload: MoveWishlistItemRequest,
+) -> Result<SavedItem> {
+    let source = find_owned(db, user_id, wishlist_id).await?;
+    let target = find_owned(db, user_id, payload.target_wishlist_id).await?;
+
+    if source.id == target.id {
+        return Err(AppError::bad_request("Item is already in this wishlist"));
+    }
+
+    let item = saved_item::Entity::find()
+        .filter(saved_item::Column::WishlistId.eq(source.id))
+        .filter(saved_item::Column::ProductId.eq(product_id))
+        .one(db)
+        .await?
+        .ok_or_else(|| AppError::not_found("Item not found in wishlist"))?;
+
+    let already_in_target = saved_item::Entity::find()
+        .filter(saved_item::Column::WishlistId.eq(target.id))
+        .filter(saved_item::Column::ProductId.eq(product_id))
+        .one(db)
+        .await?;
+
+    if already_in_target.is_some() {
+        return Err(AppError::bad_request("Product is already in the target wishlist"));
+    }
+
+    // Keep the saved price and alert settings when moving
+    let mut item: saved_item::ActiveModel = item.into();
+    item.wishlist_id = Set(target.id);
+
+    let item = item.update(db).await?;
+    Ok(SavedItem::from(item))
+}
+
+// Put a product from a wishlist into the cart
+pub async fn add_item_to_cart(
+    db: &DatabaseConnection,
+    user_id: Uuid,
+    wishlist_id: Uuid,
+    product_id: Uuid,
+    payload: WishlistToCartRequest,
+) -> Result<()> {
+    let wishlist = find_owned(db, user_id, wishlist_id).await?;
+
+    saved_item::Entity::find()
+        .filter(saved_item::Column::WishlistId.eq(wishlist.id))
+        .filter(saved_item::Column::ProductId.eq(product_id))
+        .one(db)
+        .await?
+        .ok_or_else(|| AppError::not_found("Item not found in wishlist"))?;
+
+    cart::add_to_cart(
+        db,
+        CartOwner::User(user_id),
+        AddToCartRequest {
+            product_id,
+            quantity: payload.quantity.unwrap_or(1),
+        },
+    )
+    .await
+}
+
+// Open a public wishlist through its share link
+pub async fn get_shared_wishlist(db: &DatabaseConnection, share_slug: &str) -> Result<SharedWishlist> {
+    let (wishlist, owner) = wishlist::Entity::find()
+        .filter(wishlist::Column::ShareSlug.eq(share_slug))
+        .filter(wishlist::Column::IsPublic.eq(true))
+        .find_also_related(user::Entity)
+        .one(db)
+        .await?
+        .ok_or_else(|| AppError::not_found("Wishlist not found"))?;
+
+    let items = saved_item_service::get_items_with_products(
+        db,
+        saved_item::Entity::find().filter(saved_item::Column::WishlistId.eq(wishlist.id)),
+    )
+    .await?;
+
+    Ok(SharedWishlist {
+        name: wishlist.name,
+        owner_name: owner.map(|u| u.name).unwrap_or_default(),
+        items,
+        updated_at: wishlist.updated_at,
+    })
+}
@@ -0,0 +1,421 @@
+use bigdecimal::BigDecimal;
+use chrono::Utc;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, EntityTrait, IntoActiveModel, PaginatorTrait,
+    QueryFilter, QueryOrder, QuerySelect, Set, TransactionTrait,
+};
+use std::str::FromStr;
+use uuid::Uuid;
+
+use crate::entities::withdrawal;
+use crate::errors::{AppError, Result};
+use crate::models::ledger::{
+    CreateWithdrawalRequest, LedgerAccountKind, LedgerTransactionKind, MarkWithdrawalPaidRequest,
+    RejectWithdrawalRequest, Withdrawal, WithdrawalQuery, WithdrawalStatus,
+};
+use crate::models::money::{Currency, Money};
+use crate::models::notification::NotificationKind;
+use crate::models::order::PaymentMethod;
+use crate::services::ledger::{self, NewTransaction, Posting};
+use crate::services::notification;
+use crate::services::order::normalize_phone;
+use crate::services::payment_gateway::{GatewayPaymentStatus, PaymentGateways, PaymentLookup, TransferRequest};
+use crate::utils::validation;
+
+// Smaller amounts cost more in provider fees than they are worth
+pub const MIN_WITHDRAWAL: Money = Money::xaf(5_000);
+
+fn to_money(value: &BigDecimal) -> Result<Money> {
+    Ok(Money::from_decimal(value, Currency::XAF)?)
+}
+
+fn to_withdrawal(model: withdrawal::Model) -> Result<Withdrawal> {
+    Ok(Withdrawal {
+        id: model.id,
+        seller_id: model.seller_id,
+        amount: to_money(&model.amount)?,
+        method: PaymentMethod::from_str(&model.method).map_err(AppError::internal)?,
+        phone_number: model.phone_number,
+        status: WithdrawalStatus::from_str(&model.status).map_err(AppError::internal)?,
+        provider_reference: model.provider_reference,
+        failure_reason: model.failure_reason,
+        reviewed_at: model.reviewed_at,
+        paid_at: model.paid_at,
+        created_at: model.created_at,
+    })
+}
+
+fn transaction(kind: LedgerTransactionKind, withdrawal: &withdrawal::Model, description: String) -> NewTransaction {
+    NewTransaction {
+        kind,
+        order_id: None,
+        seller_order_id: None,
+        withdrawal_id: Some(withdrawal.id),
+        refund_id: None,
+        description,
+    }
+}
+
+async fn find_locked<C: ConnectionTrait>(db: &C, withdrawal_id: Uuid) -> Result<withdrawal::Model> {
+    withdrawal::Entity::find_by_id(withdrawal_id)
+        .lock_exclusive()
+        .one(db)
+        .await?
+        .ok_or_else(|| AppError::not_found("Withdrawal not found"))
+}
+
+// Ask for part of the wallet to be paid to a Mobile Money account. The amount leaves the
+// wallet straight away so it cannot be asked for twice.
+pub async fn request_withdrawal(
+    db: &DatabaseConnection,
+    seller_id: Uuid,
+    payload: CreateWithdrawalRequest,
+) -> Result<Withdrawal> {
+    validation::validate(&payload)?;
+
+    if !matches!(payload.method, PaymentMethod::Mtn | PaymentMethod::Orange) {
+        return Err(AppError::bad_request("Withdrawals are paid to MTN or Orange Mobile Money"));
+    }
+    if payload.amount.currency() != Currency::XAF {
+        return Err(AppError::bad_request("Withdrawals are paid in XAF"));
+    }
+    if payload.amount.checked_sub(MIN_WITHDRAWAL)?.is_negative() {
+        return Err(AppError::bad_request(format!("The minimum withdrawal is {}", MIN_WITHDRAWAL)));
+    }
+    let phone_number = normalize_phone(&payload.phone_number)
+        .filter(|phone| phone.starts_with("+2376"))
+        .ok_or_else(|| AppError::validation("Enter a Cameroon mobile number, e.g. 6XX XX XX XX"))?;
+
+    let txn = db.begin().await?;
+
+    let available = ledger::lock_seller_balance(&txn, seller_id, LedgerAccountKind::SellerWallet).await?;
+    if available.checked_sub(payload.amount)?.is_negative() {
+        return Err(AppError::bad_request(format!("Only {} is available to withdraw", available.max(Money::zero(Currency::XAF))?)));
+    }
+
+    let now = Utc::now();
+    let created = withdrawal::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        seller_id: Set(seller_id),
+        amount: Set(payload.amount.to_decimal()),
+        currency: Set(Currency::XAF.to_string()),
+        method: Set(payload.method.to_string()),
+        phone_number: Set(phone_number),
+        status: Set(WithdrawalStatus::Pending.to_string()),
+        provider_reference: Set(None),
+        failure_reason: Set(None),
+        reviewed_by: Set(None),
+        reviewed_at: Set(None),
+        paid_at: Set(None),
+        created_at: Set(now),
+        updated_at: Set(now),
+    }
+    .insert(&txn)
+    .await?;
+
+    ledger::post(
+        &txn,
+        transaction(
+            LedgerTransactionKind::WithdrawalRequested,
+            &created,
+            format!("Withdrawal to {}", created.phone_number),
+        ),
+        vec![
+            Posting::seller(seller_id, LedgerAccountKind::SellerWallet, Money::zero(Currency::XAF).checked_sub(payload.amount)?),
+            Posting::seller(seller_id, LedgerAccountKind::SellerWithdrawals, payload.amount),
+        ],
+    )
+    .await?;
+
+    txn.commit().await?;
+
+    to_withdrawal(created)
+}
+
+// A seller's own withdrawals, or everyone's for admins, newest first
+pub async fn get_withdrawals(
+    db: &DatabaseConnection,
+    seller_id: Option<Uuid>,
+    query: WithdrawalQuery,
+) -> Result<(Vec<Withdrawal>, u64)> {
+    let page = query.page.unwrap_or(1).max(1);
+    let per_page = query.per_page.unwrap_or(20).clamp(1, 50);
+
+    let mut select = withdrawal::Entity::find();
+    if let Some(seller_id) = seller_id {
+        select = select.filter(withdrawal::Column::SellerId.eq(seller_id));
+    }
+    if let Some(status) = query.status {
+        select = select.filter(withdrawal::Column::Status.eq(status.to_string()));
+    }
+
+    let paginator = select.order_by_desc(withdrawal::Column::CreatedAt).paginate(db, per_page);
+    let total = paginator.num_items().await?;
+    let withdrawals = paginator
+        .fetch_page(page - 1)
+        .await?
+        .into_iter()
+        .map(to_withdrawal)
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok((withdrawals, total))
+}
+
+// The money reached the seller: it leaves the marketplace's cash
+async fn complete<C: ConnectionTrait>(db: &C, withdrawal: withdrawal::Model, provider_reference: String) -> Result<withdrawal::Model> {
+    let amount = to_money(&withdrawal.amount)?;
+    let now = Utc::now();
+
+    ledger::post(
+        db,
+        transaction(
+            LedgerTransactionKind::WithdrawalPaid,
+            &withdrawal,
+            format!("Withdrawal paid to {}", withdrawal.phone_number),
+        ),
+        vec![
+            Posting::seller(withdrawal.seller_id, LedgerAccountKind::SellerWithdrawals, Money::zero(Currency::XAF).checked_sub(amount)?),
+            Posting::platform(LedgerAccountKind::PlatformCash, amount),
+        ],
+    )
+    .await?;
+
+    let mut active = withdrawal.into_active_model();
+    active.status = Set(WithdrawalStatus::Paid.to_string());
+    active.provider_reference = Set(Some(provider_reference));
+    active.paid_at = Set(Some(now));
+    active.updated_at = Set(now);
+    let paid = active.update(db).await?;
+
+    notification::notify(
+        db,
+        paid.seller_id,
+        NotificationKind::WithdrawalUpdate,
+        "Withdrawal paid",
+        format!("{} was sent to {}.", amount, paid.phone_number),
+        Some("/wallet".to_string()),
+    )
+    .await?;
+
+    Ok(paid)
+}
+
+// Put the amount back in the seller's wallet after a rejection or a failed transfer
+async fn return_funds<C: ConnectionTrait>(
+    db: &C,
+    withdrawal: withdrawal::Model,
+    status: WithdrawalStatus,
+    reason: String,
+) -> Result<withdrawal::Model> {
+    let amount = to_money(&withdrawal.amount)?;
+
+    ledger::post(
+        db,
+        transaction(
+            LedgerTransactionKind::WithdrawalReturned,
+            &withdrawal,
+            format!("Withdrawal {}: {}", status, reason),
+        ),
+        vec![
+            Posting::seller(withdrawal.seller_id, LedgerAccountKind::SellerWithdrawals, Money::zero(Currency::XAF).checked_sub(amount)?),
+            Posting::seller(withdrawal.seller_id, LedgerAccountKind::SellerWallet, amount),
+        ],
+    )
+    .await?;
+
+    let title = match status {
+        WithdrawalStatus::Rejected => "Withdrawal rejected",
+        _ => "Withdrawal failed",
+    };
+
+    let mut active = withdrawal.into_active_model();
+    active.status = Set(status.to_string());
+    active.failure_reason = Set(Some(reason.clone()));
+    active.updated_at = Set(Utc::now());
+    let returned = active.update(db).await?;
+
+    notification::notify(
+        db,
+        returned.seller_id,
+        NotificationKind::WithdrawalUpdate,
+        title,
+        format!("{} is back in your wallet: {}", amount, reason),
+        Some("/wallet".to_string()),
+    )
+    .await?;
+
+    Ok(returned)
+}
+
+// Record where a transfer sent through the provider stands. Only withdrawals still processing change.
+async fn apply_transfer_status(
+    db: &DatabaseConnection,
+    withdrawal_id: Uuid,
+    provider_reference: String,
+    status: GatewayPaymentStatus,
+) -> Result<withdrawal::Model> {
+    let txn = db.begin().await?;
+    let withdrawal = find_locked(&txn, withdrawal_id).await?;
+
+    if withdrawal.status != WithdrawalStatus::Processing.to_string() {
+        return Ok(withdrawal);
+    }
+
+    let updated = match status {
+        GatewayPaymentStatus::Successful => complete(&txn, withdrawal, provider_reference).await?,
+        GatewayPaymentStatus::Failed { reason } => {
+            return_funds(&txn, withdrawal, WithdrawalStatus::Failed, reason).await?
+        }
+        GatewayPaymentStatus::Pending => {
+            let mut active = withdrawal.into_active_model();
+            active.provider_reference = Set(Some(provider_reference));
+            active.updated_at = Set(Utc::now());
+            active.update(&txn).await?
+        }
+    };
+
+    txn.commit().await?;
+
+    Ok(updated)
+}
+
+// Approve a pending withdrawal (admin only). Providers that can send transfers are asked to
+// pay it now; otherwise it waits to be paid by hand and marked paid.
+pub async fn approve_withdrawal(
+    db: &DatabaseConnection,
+    gateways: &PaymentGateways,
+    admin_id: Uuid,
+    withdrawal_id: Uuid,
+) -> Result<Withdrawal> {
+    let txn = db.begin().await?;
+    let withdrawal = find_locked(&txn, withdrawal_id).await?;
+
+    if withdrawal.status != WithdrawalStatus::Pending.to_string() {
+        return Err(AppError::bad_request(format!("Withdrawal is already {}", withdrawal.status)));
+    }
+
+    let method = PaymentMethod::from_str(&withdrawal.method).map_err(AppError::internal)?;
+    let gateway = gateways.for_method(&method)?;
+    let automatic = gateway.supports_transfers();
+
+    // Marked processing before calling the provider so a second approval cannot send it again
+    let now = Utc::now();
+    let mut active = withdrawal.into_active_model();
+    active.status = Set(if automatic { WithdrawalStatus::Processing } else { WithdrawalStatus::Approved }.to_string());
+    active.reviewed_by = Set(Some(admin_id));
+    active.reviewed_at = Set(Some(now));
+    active.updated_at = Set(now);
+    let approved = active.update(&txn).await?;
+
+    txn.commit().await?;
+
+    if !automatic {
+        return to_withdrawal(approved);
+    }
+
+    let request = TransferRequest {
+        withdrawal_id: approved.id,
+        amount: to_money(&approved.amount)?,
+        phone_number: approved.phone_number.clone(),
+        description: "Cameroon Mark seller payout".to_string(),
+    };
+
+    let updated = match gateway.transfer(&request).await {
+        Ok(initiation) => apply_transfer_status(db, approved.id, initiation.provider_reference, initiation.status).await?,
+        // The provider may have paid before the error reached us (a timeout, say), so the money only
+        // goes back to the wallet once a lookup under the same reference says the transfer failed
+        Err(e) => {
+            tracing::error!(
+                "{} transfer for withdrawal {} did not complete, leaving it to the transfer check: {:?}",
+                gateway.name(),
+                approved.id,
+                e
+            );
+            apply_transfer_status(db, approved.id, approved.id.to_string(), GatewayPaymentStatus::Pending).await?
+        }
+    };
+
+    to_withdrawal(updated)
+}
+
+// Turn down a withdrawal that was not sent yet (admin only)
+pub async fn reject_withdrawal(
+    db: &DatabaseConnection,
+    admin_id: Uuid,
+    withdrawal_id: Uuid,
+    payload: RejectWithdrawalRequest,
+) -> Result<Withdrawal> {
+    validation::validate(&payload)?;
+
+    let txn = db.begin().await?;
+    let withdrawal = find_locked(&txn, withdrawal_id).await?;
+
+    let status = WithdrawalStatus::from_str(&withdrawal.status).map_err(AppError::internal)?;
+    if !matches!(status, WithdrawalStatus::Pending | WithdrawalStatus::Approved) {
+        return Err(AppError::bad_request(format!("A {} withdrawal cannot be rejected", status)));
+    }
+
+    let mut active = withdrawal.into_active_model();
+    active.reviewed_by = Set(Some(admin_id));
+    active.reviewed_at = Set(Some(Utc::now()));
+    let withdrawal = active.update(&txn).await?;
+
+    let rejected = return_funds(&txn, withdrawal, WithdrawalStatus::Rejected, payload.reason).await?;
+    txn.commit().await?;
+
+    to_withdrawal(rejected)
+}
+
+// Record an approved withdrawal paid by hand from the provider's merchant portal (admin only)
+pub async fn mark_withdrawal_paid(
+    db: &DatabaseConnection,
+    withdrawal_id: Uuid,
+    payload: MarkWithdrawalPaidRequest,
+) -> Result<Withdrawal> {
+    validation::validate(&payload)?;
+
+    let txn = db.begin().await?;
+    let withdrawal = find_locked(&txn, withdrawal_id).await?;
+
+    if withdrawal.status != WithdrawalStatus::Approved.to_string() {
+        return Err(AppError::bad_request("Only approved withdrawals can be marked paid"));
+    }
+
+    let paid = complete(&txn, withdrawal, payload.provider_reference).await?;
+    txn.commit().await?;
+
+    to_withdrawal(paid)
+}
+
+// Look up transfers still waiting on the provider. Returns how many reached a final status.
+pub async fn check_processing_withdrawals(db: &DatabaseConnection, gateways: &PaymentGateways) -> Result<u64> {
+    let processing = withdrawal::Entity::find()
+        .filter(withdrawal::Column::Status.eq(WithdrawalStatus::Processing.to_string()))
+        .order_by_asc(withdrawal::Column::ReviewedAt)
+        .all(db)
+        .await?;
+
+    let mut settled = 0;
+    for withdrawal in processing {
+        let method = PaymentMethod::from_str(&withdrawal.method).map_err(AppError::internal)?;
+        let gateway = gateways.for_method(&method)?;
+        let provider_reference = withdrawal.provider_reference.clone().unwrap_or_else(|| withdrawal.id.to_string());
+
+        let lookup = PaymentLookup {
+            payment_id: withdrawal.id,
+            provider_reference: provider_reference.clone(),
+            amount: to_money(&withdrawal.amount)?,
+        };
+
+        match gateway.check_transfer(&lookup).await {
+            Ok(GatewayPaymentStatus::Pending) => {}
+            Ok(status) => {
+                apply_transfer_status(db, withdrawal.id, provider_reference, status).await?;
+                settled += 1;
+            }
+            Err(e) => tracing::warn!("Could not check transfer for withdrawal {}: {:?}", withdrawal.id, e),
+        }
+    }
+
+    Ok(settled)
+}
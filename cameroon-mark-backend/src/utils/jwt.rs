@@ -17,6 +17,17 @@ pub struct Claims {
     pub iat: i64,         // Issued at
 }
 
+// Claims of the signed token identifying an anonymous cart
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CartClaims {
+    pub sub: Uuid,        // Guest cart ID
+    pub token_type: String,
+    pub exp: i64,
+    pub iat: i64,
+}
+
+const CART_TOKEN_TYPE: &str = "guest_cart";
+
 // Generate a JWT token for a user
 pub fn generate_token(user: &User) -> Result<String> {
     let now = Utc::now();
@@ -50,3 +61,36 @@ pub fn verify_token(token: &str) -> Result<Claims> {
 
     Ok(token_data.claims)
 }
+
+// Generate a signed token for a guest cart
+pub fn generate_cart_token(guest_cart_id: Uuid, expires_at: chrono::DateTime<Utc>) -> Result<String> {
+    let claims = CartClaims {
+        sub: guest_cart_id,
+        token_type: CART_TOKEN_TYPE.to_string(),
+        exp: expires_at.timestamp(),
+        iat: Utc::now().timestamp(),
+    };
+
+    encode(
+        &Header::default(),
+        &claims,
+        &EncodingKey::from_secret(config::get_config().jwt.secret.as_bytes()),
+    )
+    .map_err(|_| AppError::internal("Failed to generate cart token"))
+}
+
+// Verify a guest cart token and return the cart ID
+pub fn verify_cart_token(token: &str) -> Result<Uuid> {
+    let token_data = decode::<CartClaims>(
+        token,
+        &DecodingKey::from_secret(config::get_config().jwt.secret.as_bytes()),
+        &Validation::default(),
+    )
+    .map_err(|_| AppError::auth("Invalid cart token"))?;
+
+    if token_data.claims.token_type != CART_TOKEN_TYPE {
+        return Err(AppError::auth("Invalid cart token"));
+    }
+
+    Ok(token_data.claims.sub)
+}
@@ -1,3 +1,4 @@
 pub mod jwt;
 pub mod password;
+pub mod pdf;
 pub mod validation;
@@ -0,0 +1,125 @@
+use aws_sdk_s3::Client as S3Client;
+use printpdf::{BuiltinFont, IndirectFontRef, Line, Mm, PdfDocument, PdfDocumentReference, PdfLayerReference, Point};
+use std::sync::Arc;
+
+use crate::errors::{AppError, Result};
+
+// A4 portrait, in millimetres
+pub const PAGE_WIDTH: f32 = 210.0;
+pub const PAGE_HEIGHT: f32 = 297.0;
+pub const MARGIN: f32 = 18.0;
+
+// Rough Helvetica advance widths, enough to right-align amounts
+pub fn text_width(text: &str, size: f32) -> f32 {
+    let em: f32 = text
+        .chars()
+        .map(|c| match c {
+            ' ' | '.' | ',' | '-' => 0.28,
+            '0'..='9' => 0.556,
+            'A'..='Z' => 0.667,
+            _ => 0.5,
+        })
+        .sum();
+    // Font size is in points, the page in millimetres
+    em * size * 0.3528
+}
+
+// Writes a document top to bottom, starting a new page when the current one is full
+pub struct PdfWriter {
+    doc: PdfDocumentReference,
+    layer: PdfLayerReference,
+    regular: IndirectFontRef,
+    bold: IndirectFontRef,
+    y: f32,
+}
+
+impl PdfWriter {
+    pub fn new(title: &str) -> Result<Self> {
+        let (doc, page, layer) = PdfDocument::new(title, Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), "Layer 1");
+        let regular = doc
+            .add_builtin_font(BuiltinFont::Helvetica)
+            .map_err(|e| AppError::internal(format!("Failed to create PDF: {}", e)))?;
+        let bold = doc
+            .add_builtin_font(BuiltinFont::HelveticaBold)
+            .map_err(|e| AppError::internal(format!("Failed to create PDF: {}", e)))?;
+        let layer = doc.get_page(page).get_layer(layer);
+
+        Ok(Self {
+            doc,
+            layer,
+            regular,
+            bold,
+            y: PAGE_HEIGHT - MARGIN,
+        })
+    }
+
+    pub fn text(&self, text: &str, x: f32, size: f32, bold: bool) {
+        let font = if bold { &self.bold } else { &self.regular };
+        self.layer.use_text(text, size, Mm(x), Mm(self.y), font);
+    }
+
+    pub fn text_right(&self, text: &str, right: f32, size: f32, bold: bool) {
+        self.text(text, right - text_width(text, size), size, bold);
+    }
+
+    pub fn rule(&self) {
+        let y = self.y + 2.0;
+        self.layer.set_outline_thickness(0.5);
+        self.layer.add_line(Line {
+            points: vec![
+                (Point::new(Mm(MARGIN), Mm(y)), false),
+                (Point::new(Mm(PAGE_WIDTH - MARGIN), Mm(y)), false),
+            ],
+            is_closed: false,
+        });
+    }
+
+    pub fn advance(&mut self, step: f32) {
+        self.y -= step;
+        if self.y < MARGIN {
+            let (page, layer) = self.doc.add_page(Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), "Layer 1");
+            self.layer = self.doc.get_page(page).get_layer(layer);
+            self.y = PAGE_HEIGHT - MARGIN;
+        }
+    }
+
+    pub fn finish(self) -> Result<Vec<u8>> {
+        self.doc
+            .save_to_bytes()
+            .map_err(|e| AppError::internal(format!("Failed to create PDF: {}", e)))
+    }
+}
+
+// Fetch a stored document from MinIO
+pub async fn load_pdf(s3_client: &Arc<S3Client>, bucket: &str, key: &str) -> Result<Vec<u8>> {
+    let object = s3_client
+        .get_object()
+        .bucket(bucket)
+        .key(key)
+        .send()
+        .await
+        .map_err(|e| AppError::internal(format!("Failed to download file from MinIO: {}", e)))?;
+
+    let body = object
+        .body
+        .collect()
+        .await
+        .map_err(|e| AppError::internal(format!("Failed to read file from MinIO: {}", e)))?;
+
+    Ok(body.into_bytes().to_vec())
+}
+
+// Keep a generated document in MinIO
+pub async fn store_pdf(s3_client: &Arc<S3Client>, bucket: &str, key: &str, pdf: Vec<u8>) -> Result<()> {
+    s3_client
+        .put_object()
+        .bucket(bucket)
+        .key(key)
+        .body(pdf.into())
+        .content_type("application/pdf")
+        .send()
+        .await
+        .map_err(|e| AppError::internal(format!("Failed to upload file to MinIO: {}", e)))?;
+
+    Ok(())
+}